@@ -25,7 +25,7 @@ extern crate proc_macro;
 use self::proc_macro::TokenStream;
 use syn::{Attribute, Data, DeriveInput, Fields, Ident, Type};
 
-#[proc_macro_derive(SocketWrapper, attributes(sink, stream))]
+#[proc_macro_derive(SocketWrapper, attributes(draft, sink, stream))]
 pub fn socket_derive(input: TokenStream) -> TokenStream {
     let input: DeriveInput = syn::parse(input).unwrap();
 
@@ -73,7 +73,67 @@ pub fn socket_derive(input: TokenStream) -> TokenStream {
         quote! {}
     };
 
-    let kind = Ident::new(&format!("{}", name).to_uppercase(), name.span());
+    let draft = has_attr(&input.attrs, "draft");
+
+    let kind = if draft {
+        quote! { async_zmq_types::draft::DraftKind::#name.classic() }
+    } else {
+        let kind = Ident::new(&format!("{}", name).to_uppercase(), name.span());
+        quote! { #kind }
+    };
+
+    let create = if draft {
+        quote! {
+            fn create(_: Option<&zmq::Context>) -> zmq::Result<zmq::Socket> {
+                async_zmq_types::draft::DraftKind::#name.create()
+            }
+
+            fn is_draft() -> bool {
+                true
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // The draft options each draft socket accepts
+    let markers: &[&str] = match format!("{}", name).to_lowercase().as_str() {
+        "server" if draft => &["HelloMsg", "DisconnectMsg"],
+        "client" if draft => &["HelloMsg", "HiccupMsg"],
+        "peer" if draft => &["HelloMsg", "DisconnectMsg", "HiccupMsg"],
+        "radio" if draft => &["MulticastLoop"],
+        "dish" if draft => &["MulticastLoop", "Groups"],
+        _ => &[],
+    };
+
+    let draft_markers = markers.iter().map(|marker| {
+        let marker = Ident::new(marker, name.span());
+        quote! {
+            impl async_zmq_types::draft::#marker for #name {}
+        }
+    });
+
+    // Draft sockets aren't created in a caller's context, so their builders don't take one
+    let builder = if draft {
+        quote! {
+            impl async_zmq_types::draft::DraftSocket for #name {}
+
+            impl #name {
+                /// Start a builder for this draft socket type
+                pub fn builder() -> async_zmq_types::SocketBuilder<Self> {
+                    async_zmq_types::SocketBuilder::draft()
+                }
+            }
+        }
+    } else {
+        quote! {
+            impl async_zmq_types::HasBuilder for #name {}
+        }
+    };
+
+    let draft_marker = quote! {
+        #(#draft_markers)*
+    };
 
     let as_socket = quote! {
         impl crate::prelude::IntoInnerSocket for #name {
@@ -83,9 +143,15 @@ pub fn socket_derive(input: TokenStream) -> TokenStream {
                 self.inner
             }
 
+            fn socket_mut(&mut self) -> &mut Self::Socket {
+                &mut self.inner
+            }
+
             fn kind() -> SocketType {
                 #kind
             }
+
+            #create
         }
     };
 
@@ -97,9 +163,39 @@ pub fn socket_derive(input: TokenStream) -> TokenStream {
         quote! {}
     };
 
+    // Sending sockets can be closed the same way their sinks are, through an empty sink
     let sink = if has_attr(&input.attrs, "sink") {
         quote! {
             impl crate::prelude::SinkSocket for #name {}
+
+            impl crate::prelude::GracefulShutdown for #name {
+                type Error = crate::error::Error;
+                type Future = <crate::async_types::MultipartSink<Self> as crate::prelude::GracefulShutdown>::Future;
+
+                fn graceful_shutdown(self, timeout: Option<std::time::Duration>) -> Self::Future {
+                    let sink = crate::prelude::SinkSocket::sink(self, 1);
+                    crate::prelude::GracefulShutdown::graceful_shutdown(sink, timeout)
+                }
+            }
+
+            impl #name {
+                /// Wait for multiparts this socket is still sending, then close it
+                ///
+                /// This waits as long as the socket's ZMQ_LINGER option allows, see
+                /// `GracefulShutdown`.
+                pub fn close(self) -> <Self as crate::prelude::GracefulShutdown>::Future {
+                    crate::prelude::GracefulShutdown::graceful_shutdown(self, None)
+                }
+
+                /// Wait for multiparts this socket is still sending and close it, waiting at most
+                /// `timeout`
+                pub fn shutdown(
+                    self,
+                    timeout: std::time::Duration,
+                ) -> <Self as crate::prelude::GracefulShutdown>::Future {
+                    crate::prelude::GracefulShutdown::graceful_shutdown(self, Some(timeout))
+                }
+            }
         }
     } else {
         quote! {}
@@ -109,6 +205,8 @@ pub fn socket_derive(input: TokenStream) -> TokenStream {
         #from_sock
         #sub
         #pair
+        #draft_marker
+        #builder
         #from_parts
         #as_socket
         #stream
@@ -0,0 +1,313 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains `LoadBalancer`, the least-recently-used worker queue from the ZeroMQ
+//! guide's load balancing broker, which the backends build their `broker` modules on.
+
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use futures::{future::poll_fn, task::AtomicWaker, Sink, SinkExt, Stream, TryStream};
+use log::warn;
+
+use crate::message::{Envelope, Multipart, PeerId};
+
+/// The body a worker sends to tell a `LoadBalancer` it's ready for its first request
+pub const READY: &str = "READY";
+
+/* ----------------------------------TYPES----------------------------------- */
+
+/// A snapshot of a `LoadBalancer`'s queues and counters
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LoadBalancerStats {
+    /// Workers waiting for a request
+    pub idle_workers: usize,
+    /// Requests waiting for a worker
+    pub queued_requests: usize,
+    /// READY messages received from workers
+    pub workers_registered: u64,
+    /// Requests received from clients
+    pub requests_received: u64,
+    /// Requests handed to workers
+    pub requests_dispatched: u64,
+    /// Replies handed back to clients
+    pub replies_forwarded: u64,
+}
+
+/// A stream of a `LoadBalancer`'s statistics
+///
+/// The stream yields the current statistics when it's first polled, and then the latest
+/// statistics whenever they've changed since it was last polled, so a slow reader skips
+/// snapshots rather than falling behind. It ends once the load balancer stops.
+pub struct StatsStream {
+    shared: Arc<StatsShared>,
+}
+
+/// A load balancer between clients connected to a ROUTER frontend, and workers connected to a
+/// ROUTER backend
+///
+/// Workers are usually REQ sockets. They start by sending `READY`, and from then on each reply
+/// they send tells the load balancer they're ready for another request. Requests are handed to
+/// the worker that has been idle the longest, and are queued while every worker is busy. Once
+/// the queue holds `queue_limit` requests, the load balancer stops reading from the frontend, and
+/// further requests wait in ZeroMQ's queues instead.
+///
+/// Workers see each request as an `Envelope` routed through the client that sent it, so
+/// `Envelope::reply` creates a reply the load balancer can route back.
+///
+/// The backends' `broker::LoadBalancer` types are the usual way to create one.
+pub struct LoadBalancer<F, B> {
+    frontend: F,
+    backend: B,
+    idle: VecDeque<PeerId>,
+    queue: VecDeque<Envelope>,
+    queue_limit: Option<usize>,
+    stats: LoadBalancerStats,
+    watchers: Vec<Arc<StatsShared>>,
+}
+
+struct StatsShared {
+    state: Mutex<StatsState>,
+    waker: AtomicWaker,
+}
+
+struct StatsState {
+    stats: LoadBalancerStats,
+    changed: bool,
+    done: bool,
+}
+
+enum Event<E> {
+    Frontend(Option<Result<Multipart, E>>),
+    Backend(Option<Result<Multipart, E>>),
+}
+
+/* ----------------------------------impls----------------------------------- */
+
+impl StatsShared {
+    fn update(&self, stats: LoadBalancerStats, done: bool) {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.changed |= state.stats != stats;
+            state.stats = stats;
+            state.done = done;
+        }
+
+        self.waker.wake();
+    }
+}
+
+impl Stream for StatsStream {
+    type Item = LoadBalancerStats;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        self.shared.waker.register(cx.waker());
+
+        let mut state = self.shared.state.lock().unwrap();
+
+        if state.changed {
+            state.changed = false;
+            Poll::Ready(Some(state.stats))
+        } else if state.done {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+impl<F, B, E> LoadBalancer<F, B>
+where
+    F: TryStream<Ok = Multipart, Error = E> + Sink<Multipart, Error = E> + Unpin,
+    B: TryStream<Ok = Multipart, Error = E> + Sink<Multipart, Error = E> + Unpin,
+{
+    /// Create a load balancer between a ROUTER frontend and a ROUTER backend
+    pub fn new(frontend: F, backend: B) -> Self {
+        LoadBalancer {
+            frontend,
+            backend,
+            idle: VecDeque::new(),
+            queue: VecDeque::new(),
+            queue_limit: None,
+            stats: LoadBalancerStats::default(),
+            watchers: Vec::new(),
+        }
+    }
+
+    /// Limit how many requests can wait for a worker
+    ///
+    /// By default the queue is unbounded. A limit of zero only reads requests from the frontend
+    /// while a worker is idle.
+    pub fn queue_limit(mut self, limit: usize) -> Self {
+        self.queue_limit = Some(limit);
+        self
+    }
+
+    /// Watch the load balancer's statistics
+    pub fn stats(&mut self) -> StatsStream {
+        let shared = Arc::new(StatsShared {
+            state: Mutex::new(StatsState {
+                stats: self.stats,
+                changed: true,
+                done: false,
+            }),
+            waker: AtomicWaker::new(),
+        });
+
+        self.watchers.push(Arc::clone(&shared));
+
+        StatsStream { shared }
+    }
+
+    /// Balance requests until a socket fails or closes
+    pub async fn run(mut self) -> Result<LoadBalancerStats, E> {
+        loop {
+            let event = poll_fn(|cx| self.poll_event(cx)).await;
+
+            match event {
+                Event::Frontend(Some(multipart)) => self.request(multipart?),
+                Event::Backend(Some(multipart)) => self.reply(multipart?).await?,
+                Event::Frontend(None) | Event::Backend(None) => {
+                    self.publish(true);
+                    return Ok(self.stats);
+                }
+            }
+
+            self.dispatch().await?;
+            self.publish(false);
+        }
+    }
+
+    fn request(&mut self, multipart: Multipart) {
+        match Envelope::from_multipart(multipart) {
+            Ok(ref envelope) if envelope.peer().is_none() => {
+                warn!("Dropping request without a client id");
+            }
+            Ok(envelope) => {
+                self.stats.requests_received += 1;
+                self.queue.push_back(envelope);
+            }
+            Err(e) => warn!("Dropping malformed request, {}", e),
+        }
+    }
+
+    async fn reply(&mut self, multipart: Multipart) -> Result<(), E> {
+        let (mut routes, body) = match Envelope::from_multipart(multipart) {
+            Ok(envelope) => envelope.into_parts(),
+            Err(e) => {
+                warn!("Dropping malformed message from worker, {}", e);
+                return Ok(());
+            }
+        };
+
+        let worker = match routes.pop() {
+            Some(worker) if routes.is_empty() => worker,
+            _ => {
+                warn!("Dropping message from worker with unexpected routes");
+                return Ok(());
+            }
+        };
+
+        let ready = body.len() == 1 && body.get(0).map(|msg| &msg[..]) == Some(READY.as_bytes());
+
+        // Whatever the worker sent, it's waiting for its next request now
+        if !self.idle.contains(&worker) {
+            self.idle.push_back(worker);
+        }
+
+        if ready {
+            self.stats.workers_registered += 1;
+            return Ok(());
+        }
+
+        match Envelope::from_multipart(body) {
+            Ok(ref envelope) if envelope.peer().is_none() => {
+                warn!("Dropping reply without a client id");
+            }
+            Ok(envelope) => {
+                self.stats.replies_forwarded += 1;
+                self.frontend.send(envelope.into_multipart()).await?;
+            }
+            Err(e) => warn!("Dropping malformed reply, {}", e),
+        }
+
+        Ok(())
+    }
+
+    async fn dispatch(&mut self) -> Result<(), E> {
+        while !self.idle.is_empty() && !self.queue.is_empty() {
+            let worker = self.idle.pop_front().unwrap();
+            let request = self.queue.pop_front().unwrap();
+
+            self.stats.requests_dispatched += 1;
+
+            let routed = Envelope::new(vec![worker], request.into_multipart());
+            self.backend.send(routed.into_multipart()).await?;
+        }
+
+        Ok(())
+    }
+
+    fn accepting_requests(&self) -> bool {
+        match self.queue_limit {
+            Some(limit) => self.queue.len() < limit + self.idle.len(),
+            None => true,
+        }
+    }
+
+    fn poll_event(&mut self, cx: &mut Context) -> Poll<Event<E>> {
+        // Replies come first, they free up workers for the requests that are waiting
+        if let Poll::Ready(res) = Pin::new(&mut self.backend).try_poll_next(cx) {
+            return Poll::Ready(Event::Backend(res));
+        }
+
+        if self.accepting_requests() {
+            if let Poll::Ready(res) = Pin::new(&mut self.frontend).try_poll_next(cx) {
+                return Poll::Ready(Event::Frontend(res));
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+impl<F, B> LoadBalancer<F, B> {
+    fn publish(&mut self, done: bool) {
+        self.stats.idle_workers = self.idle.len();
+        self.stats.queued_requests = self.queue.len();
+
+        // Forget watchers whose streams have been dropped
+        self.watchers.retain(|shared| Arc::strong_count(shared) > 1);
+
+        for shared in &self.watchers {
+            shared.update(self.stats, done);
+        }
+    }
+}
+
+impl<F, B> Drop for LoadBalancer<F, B> {
+    fn drop(&mut self) {
+        // Streams end once the load balancer stops, whether it returned or was dropped
+        self.publish(true);
+    }
+}
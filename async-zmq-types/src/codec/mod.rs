@@ -0,0 +1,477 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains the `Decoder` and `Encoder` traits, for turning `Multipart`s into typed
+//! values and back, along with the stream and sink adapters that use them.
+
+use std::{
+    error::Error as StdError,
+    fmt,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use failure::Fail;
+use futures::{ready, Future, Sink, Stream, TryFutureExt, TryStream};
+
+use crate::{GracefulShutdown, Multipart, ShutdownReport};
+
+mod router;
+
+pub use self::router::{EnvelopeCodec, RouterCodec, RouterSink, RouterStream};
+
+/* ----------------------------------TYPES----------------------------------- */
+
+/// The error produced by `FramedStream` and `CodecSink`
+///
+/// It holds either an error from the underlying socket, or an error from the codec.
+#[derive(Debug)]
+pub enum CodecError<E, C> {
+    Socket(E),
+    Codec(C),
+}
+
+impl<E, C> fmt::Display for CodecError<E, C>
+where
+    E: fmt::Display,
+    C: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CodecError::Socket(ref e) => write!(f, "Socket error, {}", e),
+            CodecError::Codec(ref e) => write!(f, "Codec error, {}", e),
+        }
+    }
+}
+
+impl<E, C> Fail for CodecError<E, C>
+where
+    E: Fail,
+    C: Fail,
+{
+    fn cause(&self) -> Option<&dyn Fail> {
+        match *self {
+            CodecError::Socket(ref e) => Some(e),
+            CodecError::Codec(ref e) => Some(e),
+        }
+    }
+}
+
+/// The error produced by the built-in serde codecs
+///
+/// These codecs expect every `Multipart` to contain a single frame.
+#[derive(Debug)]
+pub enum SerdeError<E> {
+    Frames(usize),
+    Format(E),
+}
+
+impl<E> fmt::Display for SerdeError<E>
+where
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SerdeError::Frames(count) => write!(f, "Expected a single frame, got {}", count),
+            SerdeError::Format(ref e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl<E> StdError for SerdeError<E>
+where
+    E: StdError + 'static,
+{
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            SerdeError::Frames(_) => None,
+            SerdeError::Format(ref e) => Some(e),
+        }
+    }
+}
+
+#[cfg(any(feature = "bincode", feature = "json", feature = "msgpack"))]
+fn single_frame<E>(mut multipart: Multipart) -> Result<zmq::Message, SerdeError<E>> {
+    if multipart.len() != 1 {
+        return Err(SerdeError::Frames(multipart.len()));
+    }
+
+    multipart.pop_front().ok_or(SerdeError::Frames(0))
+}
+
+/// Generate a codec that sends each value as a single frame in a serde format
+#[cfg(any(feature = "bincode", feature = "json", feature = "msgpack"))]
+macro_rules! serde_codec {
+    (
+        $name:ident,
+        $format:literal,
+        $feature:literal,
+        decode: $decode:path => $decode_error:ty,
+        encode: $encode:path => $encode_error:ty $(,)?
+    ) => {
+        #[doc = concat!("A codec that sends each value as a single ", $format, " frame")]
+        ///
+        #[doc = concat!("This is available with the `", $feature, "` feature.")]
+        pub struct $name<T> {
+            phantom: PhantomData<fn() -> T>,
+        }
+
+        impl<T> $name<T> {
+            pub fn new() -> Self {
+                $name {
+                    phantom: PhantomData,
+                }
+            }
+        }
+
+        impl<T> Default for $name<T> {
+            fn default() -> Self {
+                $name::new()
+            }
+        }
+
+        impl<T> Clone for $name<T> {
+            fn clone(&self) -> Self {
+                $name::new()
+            }
+        }
+
+        impl<T> Decoder for $name<T>
+        where
+            T: serde::de::DeserializeOwned,
+        {
+            type Item = T;
+            type Error = SerdeError<$decode_error>;
+
+            fn decode(&mut self, multipart: Multipart) -> Result<T, Self::Error> {
+                let msg = single_frame(multipart)?;
+
+                $decode(&msg).map_err(SerdeError::Format)
+            }
+        }
+
+        impl<T> Encoder<T> for $name<T>
+        where
+            T: serde::Serialize,
+        {
+            type Error = SerdeError<$encode_error>;
+
+            fn encode(&mut self, item: T) -> Result<Multipart, Self::Error> {
+                let bytes = $encode(&item).map_err(SerdeError::Format)?;
+
+                Ok(zmq::Message::from(bytes).into())
+            }
+        }
+    };
+}
+
+#[cfg(feature = "bincode")]
+serde_codec!(
+    BincodeCodec,
+    "bincode",
+    "bincode",
+    decode: bincode::deserialize => bincode::Error,
+    encode: bincode::serialize => bincode::Error,
+);
+
+#[cfg(feature = "json")]
+serde_codec!(
+    JsonCodec,
+    "JSON",
+    "json",
+    decode: serde_json::from_slice => serde_json::Error,
+    encode: serde_json::to_vec => serde_json::Error,
+);
+
+#[cfg(feature = "msgpack")]
+serde_codec!(
+    MsgPackCodec,
+    "MessagePack",
+    "msgpack",
+    decode: rmp_serde::from_slice => rmp_serde::decode::Error,
+    encode: rmp_serde::to_vec => rmp_serde::encode::Error,
+);
+
+/// A stream that decodes each `Multipart` from the underlying stream
+pub struct FramedStream<S, D>
+where
+    S: TryStream<Ok = Multipart>,
+    D: Decoder,
+{
+    stream: S,
+    decoder: D,
+}
+
+impl<S, D> FramedStream<S, D>
+where
+    S: TryStream<Ok = Multipart>,
+    D: Decoder,
+{
+    /// Wrap a stream with a Decoder
+    pub fn new(stream: S, decoder: D) -> Self {
+        FramedStream { stream, decoder }
+    }
+
+    /// Give up the decoder, returning the original stream
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
+
+impl<S, D> Stream for FramedStream<S, D>
+where
+    S: TryStream<Ok = Multipart> + Unpin,
+    D: Decoder + Unpin,
+{
+    type Item = Result<D::Item, CodecError<S::Error, D::Error>>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        let res = match ready!(Pin::new(&mut this.stream).try_poll_next(cx)) {
+            Some(Ok(multipart)) => Some(this.decoder.decode(multipart).map_err(CodecError::Codec)),
+            Some(Err(e)) => Some(Err(CodecError::Socket(e))),
+            None => None,
+        };
+
+        Poll::Ready(res)
+    }
+}
+
+impl<S, D> Unpin for FramedStream<S, D>
+where
+    S: TryStream<Ok = Multipart> + Unpin,
+    D: Decoder + Unpin,
+{
+}
+
+/// A sink that encodes each item before sending it to the underlying sink
+pub struct CodecSink<S, E, T>
+where
+    S: Sink<Multipart>,
+    E: Encoder<T>,
+{
+    sink: S,
+    encoder: E,
+    phantom: PhantomData<fn(T)>,
+}
+
+impl<S, E, T> CodecSink<S, E, T>
+where
+    S: Sink<Multipart>,
+    E: Encoder<T>,
+{
+    /// Wrap a sink with an Encoder
+    pub fn new(sink: S, encoder: E) -> Self {
+        CodecSink {
+            sink,
+            encoder,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Give up the encoder, returning the original sink
+    pub fn into_inner(self) -> S {
+        self.sink
+    }
+}
+
+impl<S, E, T> CodecSink<S, E, T>
+where
+    S: Sink<Multipart> + GracefulShutdown<Error = <S as Sink<Multipart>>::Error>,
+    E: Encoder<T>,
+{
+    /// Flush the sink's pending multiparts, waiting as long as the socket's ZMQ_LINGER allows
+    pub fn close(
+        self,
+    ) -> impl Future<Output = Result<ShutdownReport, CodecError<<S as Sink<Multipart>>::Error, E::Error>>>
+    {
+        self.sink
+            .graceful_shutdown(None)
+            .map_err(CodecError::Socket)
+    }
+
+    /// Flush the sink's pending multiparts, waiting at most `timeout`
+    pub fn shutdown(
+        self,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<ShutdownReport, CodecError<<S as Sink<Multipart>>::Error, E::Error>>>
+    {
+        self.sink
+            .graceful_shutdown(Some(timeout))
+            .map_err(CodecError::Socket)
+    }
+}
+
+impl<S, E, T> Sink<T> for CodecSink<S, E, T>
+where
+    S: Sink<Multipart> + Unpin,
+    E: Encoder<T> + Unpin,
+{
+    type Error = CodecError<S::Error, E::Error>;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.sink)
+            .poll_ready(cx)
+            .map_err(CodecError::Socket)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        let this = &mut *self;
+        let multipart = this.encoder.encode(item).map_err(CodecError::Codec)?;
+
+        Pin::new(&mut this.sink)
+            .start_send(multipart)
+            .map_err(CodecError::Socket)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.sink)
+            .poll_flush(cx)
+            .map_err(CodecError::Socket)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.sink)
+            .poll_close(cx)
+            .map_err(CodecError::Socket)
+    }
+}
+
+impl<S, E, T> Unpin for CodecSink<S, E, T>
+where
+    S: Sink<Multipart> + Unpin,
+    E: Encoder<T> + Unpin,
+{
+}
+
+/* ----------------------------------TRAITS---------------------------------- */
+
+/// Turn a received `Multipart` into a typed value
+pub trait Decoder {
+    /// The type produced from each `Multipart`
+    type Item;
+
+    /// The error produced when a `Multipart` can't be decoded
+    type Error;
+
+    fn decode(&mut self, multipart: Multipart) -> Result<Self::Item, Self::Error>;
+}
+
+/// Turn a typed value into a `Multipart` to be sent
+pub trait Encoder<T> {
+    /// The error produced when a value can't be encoded
+    type Error;
+
+    fn encode(&mut self, item: T) -> Result<Multipart, Self::Error>;
+}
+
+/// This trait is provided to decode every `Multipart` a stream produces.
+pub trait Framed: TryStream<Ok = Multipart> + Sized {
+    /// Decode the stream's multiparts with the given decoder
+    ///
+    /// ### Example, using a Pull wrapper type
+    /// ```rust
+    /// use std::sync::Arc;
+    ///
+    /// use async_zmq_types::{
+    ///     codec::{CodecError, Decoder},
+    ///     Multipart,
+    /// };
+    /// use futures::TryStreamExt;
+    /// use tokio_zmq::{prelude::*, Error, Pull};
+    ///
+    /// struct Lines;
+    ///
+    /// impl Decoder for Lines {
+    ///     type Item = Vec<String>;
+    ///     type Error = std::str::Utf8Error;
+    ///
+    ///     fn decode(&mut self, multipart: Multipart) -> Result<Self::Item, Self::Error> {
+    ///         multipart
+    ///             .iter()
+    ///             .map(|msg| std::str::from_utf8(msg).map(String::from))
+    ///             .collect()
+    ///     }
+    /// }
+    ///
+    /// async fn run(context: Arc<zmq::Context>) -> Result<(), CodecError<Error, std::str::Utf8Error>> {
+    ///     let pull: Pull = Pull::builder(context)
+    ///         .bind("tcp://*:5580")
+    ///         .build()
+    ///         .await
+    ///         .map_err(CodecError::Socket)?;
+    ///
+    ///     pull.stream()
+    ///         .framed(Lines)
+    ///         .try_for_each(|lines| async move {
+    ///             println!("{:?}", lines);
+    ///             Ok(())
+    ///         })
+    ///         .await
+    /// }
+    ///
+    /// fn main() {
+    ///     let context = Arc::new(zmq::Context::new());
+    ///     let fut = run(context);
+    ///
+    ///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+    ///     # let _ = fut;
+    /// }
+    /// ```
+    fn framed<D>(self, decoder: D) -> FramedStream<Self, D>
+    where
+        D: Decoder;
+}
+
+/// This trait is provided to encode every item before it is sent to a sink.
+pub trait WithCodec: Sink<Multipart> + Sized {
+    /// Encode the sink's items with the given encoder
+    fn with_codec<E, T>(self, encoder: E) -> CodecSink<Self, E, T>
+    where
+        E: Encoder<T>;
+}
+
+/* ----------------------------------impls----------------------------------- */
+
+impl<T> Framed for T
+where
+    T: TryStream<Ok = Multipart>,
+{
+    fn framed<D>(self, decoder: D) -> FramedStream<Self, D>
+    where
+        D: Decoder,
+    {
+        FramedStream::new(self, decoder)
+    }
+}
+
+impl<T> WithCodec for T
+where
+    T: Sink<Multipart>,
+{
+    fn with_codec<E, U>(self, encoder: E) -> CodecSink<Self, E, U>
+    where
+        E: Encoder<U>,
+    {
+        CodecSink::new(self, encoder)
+    }
+}
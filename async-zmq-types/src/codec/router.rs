@@ -0,0 +1,133 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains the codecs that split ROUTER messages by peer.
+
+use crate::{
+    codec::{CodecSink, Decoder, Encoder, FramedStream},
+    message::{Envelope, EnvelopeError, Multipart, PeerId},
+};
+
+/// A stream of `(PeerId, Multipart)` pairs received by a ROUTER socket
+pub type RouterStream<S> = FramedStream<S, RouterCodec>;
+
+/// A sink of `(PeerId, Multipart)` pairs for a ROUTER socket to send
+pub type RouterSink<S> = CodecSink<S, RouterCodec, (PeerId, Multipart)>;
+
+/// Split a ROUTER socket's messages into the peer that sent them and the body
+///
+/// Messages are expected to follow REQ framing, the peer's id, an empty delimiter frame, then
+/// the body. This is what ROUTER sockets receive from REQ sockets, and from DEALER sockets that
+/// send an empty frame before each message. Messages that have passed through more than one
+/// ROUTER carry more than one id, use `EnvelopeCodec` to keep all of them.
+///
+/// ### Example, using a Router wrapper type
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use async_zmq_types::{
+///     codec::{CodecError, RouterCodec},
+///     EnvelopeError,
+/// };
+/// use futures::{StreamExt, TryStreamExt};
+/// use tokio_zmq::{prelude::*, Error, Router};
+///
+/// async fn echo(context: Arc<zmq::Context>) -> Result<(), CodecError<Error, EnvelopeError>> {
+///     let router: Router = Router::builder(context)
+///         .bind("tcp://*:5581")
+///         .build()
+///         .await
+///         .map_err(CodecError::Socket)?;
+///
+///     let (sink, stream) = router.sink_stream(25).split();
+///
+///     stream
+///         .framed(RouterCodec)
+///         .map_ok(|(peer, body)| {
+///             println!("Message from {:?}", peer);
+///             (peer, body)
+///         })
+///         .forward(sink.with_codec(RouterCodec))
+///         .await
+/// }
+///
+/// fn main() {
+///     let context = Arc::new(zmq::Context::new());
+///     let fut = echo(context);
+///
+///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+///     # let _ = fut;
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RouterCodec;
+
+impl Decoder for RouterCodec {
+    type Item = (PeerId, Multipart);
+    type Error = EnvelopeError;
+
+    fn decode(&mut self, mut multipart: Multipart) -> Result<Self::Item, Self::Error> {
+        let peer = match multipart.pop_front() {
+            Some(ref frame) if !frame.is_empty() => PeerId::from(&frame[..]),
+            _ => return Err(EnvelopeError::MissingPeerId),
+        };
+
+        match multipart.pop_front() {
+            Some(ref frame) if frame.is_empty() => Ok((peer, multipart)),
+            _ => Err(EnvelopeError::MissingDelimiter),
+        }
+    }
+}
+
+impl Encoder<(PeerId, Multipart)> for RouterCodec {
+    type Error = EnvelopeError;
+
+    fn encode(&mut self, (peer, mut body): (PeerId, Multipart)) -> Result<Multipart, Self::Error> {
+        // ROUTER sockets treat an empty first frame as a body, not an id
+        if peer.as_bytes().is_empty() {
+            return Err(EnvelopeError::MissingPeerId);
+        }
+
+        body.push_front(zmq::Message::new());
+        body.push_front(peer.into());
+
+        Ok(body)
+    }
+}
+
+/// Parse every message into an `Envelope`, keeping the full stack of routing ids
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EnvelopeCodec;
+
+impl Decoder for EnvelopeCodec {
+    type Item = Envelope;
+    type Error = EnvelopeError;
+
+    fn decode(&mut self, multipart: Multipart) -> Result<Self::Item, Self::Error> {
+        Envelope::from_multipart(multipart)
+    }
+}
+
+impl Encoder<Envelope> for EnvelopeCodec {
+    type Error = EnvelopeError;
+
+    fn encode(&mut self, envelope: Envelope) -> Result<Multipart, Self::Error> {
+        Ok(envelope.into_multipart())
+    }
+}
@@ -19,42 +19,261 @@
 
 //! This module contains `SocketBuilder` and related types.
 
-use std::{marker::PhantomData, sync::Arc};
+// failure's derive expands into impls nested inside of a const block
+#![allow(non_local_definitions)]
+
+use std::{
+    fmt, io,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use failure::Fail;
+
+#[cfg(feature = "draft")]
+use crate::draft::DraftSocket;
+use crate::{
+    endpoint::{Endpoint, EndpointError},
+    options::SocketOptions,
+    HasBuilder, IntoInnerSocket, Pair, Sub, UnPair,
+};
+
+/// The reasons building a socket can fail
+#[derive(Debug, Fail)]
+pub enum BuildError {
+    #[fail(display = "Error in ZeroMQ, {}", _0)]
+    /// ZeroMQ rejected an option, or couldn't bind or connect
+    Zmq(#[cause] zmq::Error),
+
+    #[fail(display = "Invalid endpoint, {}", _0)]
+    /// An endpoint couldn't be parsed, or can't be used in the direction it was given
+    Endpoint(#[cause] EndpointError),
+
+    #[fail(display = "Couldn't bind ipc://{}, {}", path, cause)]
+    /// The file of an ipc endpoint was in the way, or its permissions couldn't be set
+    Ipc {
+        path: String,
+        #[cause]
+        cause: io::Error,
+    },
+}
+
+impl From<zmq::Error> for BuildError {
+    fn from(e: zmq::Error) -> Self {
+        BuildError::Zmq(e)
+    }
+}
 
-use zmq;
+impl From<EndpointError> for BuildError {
+    fn from(e: EndpointError) -> Self {
+        BuildError::Endpoint(e)
+    }
+}
 
-use crate::{IntoInnerSocket, Pair, Sub, UnPair};
+fn parse_all<F>(endpoints: &[String], check: F) -> Result<Vec<Endpoint>, EndpointError>
+where
+    F: Fn(&Endpoint) -> Result<(), EndpointError>,
+{
+    endpoints
+        .iter()
+        .map(|endpoint| {
+            let endpoint = endpoint.parse()?;
+            check(&endpoint)?;
+            Ok(endpoint)
+        })
+        .collect()
+}
 
-fn bind_all(sock: zmq::Socket, binds: &[&str]) -> zmq::Result<zmq::Socket> {
-    for bind in binds {
-        sock.bind(bind)?;
+fn ipc_error(path: &Path) -> impl FnOnce(io::Error) -> BuildError + '_ {
+    move |cause| BuildError::Ipc {
+        path: path.display().to_string(),
+        cause,
     }
-    Ok(sock)
 }
 
-fn connect_all(sock: zmq::Socket, connects: &[&str]) -> zmq::Result<zmq::Socket> {
-    for connect in connects {
-        sock.connect(connect)?;
+/// Remove the file a previous process left behind at an ipc endpoint
+///
+/// ZeroMQ unlinks whatever is at the path before binding, so this also refuses to bind over a
+/// file that isn't a socket, or a socket something is still listening on.
+#[cfg(unix)]
+fn clear_stale_ipc(path: &Path) -> io::Result<()> {
+    use std::{
+        fs,
+        os::unix::{fs::FileTypeExt, net::UnixStream},
+    };
+
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    if !metadata.file_type().is_socket() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "a file that isn't a socket is in the way",
+        ));
+    }
+
+    match UnixStream::connect(path) {
+        Ok(_) => Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            "another socket is listening",
+        )),
+        Err(ref e) if e.kind() == io::ErrorKind::ConnectionRefused => fs::remove_file(path),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(not(unix))]
+fn clear_stale_ipc(_: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_ipc_permissions(path: &Path, mode: u32) -> io::Result<()> {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn set_ipc_permissions(_: &Path, _: u32) -> io::Result<()> {
+    Ok(())
+}
+
+fn bind(
+    sock: &zmq::Socket,
+    endpoint: &Endpoint,
+    ipc_permissions: Option<u32>,
+) -> Result<String, BuildError> {
+    let path = match *endpoint {
+        // Abstract sockets have no file
+        Endpoint::Ipc(ref path) if !path.to_string_lossy().starts_with('@') => Some(path),
+        _ => None,
+    };
+
+    // ZeroMQ makes up a fresh path for `*`
+    if let Some(path) = path.filter(|_| !endpoint.is_ephemeral()) {
+        clear_stale_ipc(path).map_err(ipc_error(path))?;
+    }
+
+    sock.bind(&endpoint.to_string())?;
+    let last = sock.get_last_endpoint()?;
+
+    if let (Some(path), Some(mode)) = (path, ipc_permissions) {
+        let path: PathBuf = match last {
+            Ok(ref last) => last.trim_start_matches("ipc://").into(),
+            Err(_) => path.to_owned(),
+        };
+
+        set_ipc_permissions(&path, mode).map_err(ipc_error(&path))?;
+    }
+
+    Ok(last.unwrap_or_else(|bytes| String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+/// Bind a socket that has already been built to `endpoint`, returning the endpoint ZeroMQ
+/// resolved it to
+///
+/// The endpoint is checked, and a stale ipc file is cleared, the same way they are while building
+/// a socket. `ipc_permissions` should be the socket's `SocketOptions::ipc_permissions`.
+pub fn bind_endpoint(
+    sock: &zmq::Socket,
+    endpoint: &str,
+    ipc_permissions: Option<u32>,
+) -> Result<String, BuildError> {
+    let endpoint: Endpoint = endpoint.parse()?;
+    endpoint.check_bind()?;
+
+    bind(sock, &endpoint, ipc_permissions)
+}
+
+/// Connect a socket that has already been built to `endpoint`
+///
+/// The endpoint is checked the same way it is while building a socket.
+pub fn connect_endpoint(sock: &zmq::Socket, endpoint: &str) -> Result<(), BuildError> {
+    let endpoint: Endpoint = endpoint.parse()?;
+    endpoint.check_connect()?;
+
+    sock.connect(&endpoint.to_string())?;
+    Ok(())
+}
+
+// Only the draft socket types' builders go without a context, and none of them are PAIR or SUB
+fn classic_context(ctx: Option<Arc<zmq::Context>>) -> Arc<zmq::Context> {
+    ctx.expect("Builders of classic socket types have a context")
+}
+
+/// Create a socket, apply its options, and bind and connect it
+///
+/// Every endpoint is parsed before the socket is created, so an invalid one doesn't leave the
+/// socket half bound.
+fn build_socket<F>(
+    create: F,
+    binds: &[String],
+    connects: &[String],
+    options: &SocketOptions,
+) -> Result<zmq::Socket, BuildError>
+where
+    F: FnOnce() -> zmq::Result<zmq::Socket>,
+{
+    let binds = parse_all(binds, Endpoint::check_bind)?;
+    let connects = parse_all(connects, Endpoint::check_connect)?;
+
+    let sock = create()?;
+    options.apply(&sock)?;
+
+    for endpoint in &binds {
+        bind(&sock, endpoint, options.ipc_permissions)?;
     }
+    for endpoint in &connects {
+        sock.connect(&endpoint.to_string())?;
+    }
+
     Ok(sock)
 }
 
 /// The root struct for a Socket builder
 ///
-/// This struct contains a context and an identity.
-pub struct SocketBuilder<'a, T>
+/// This struct contains a context and the socket's options. Builders own their configuration, so
+/// they can be cloned and sent between threads to build many sockets with the same settings.
+///
+/// ### Example, using the Dealer wrapper type
+/// ```rust
+/// use std::{sync::Arc, time::Duration};
+///
+/// use tokio_zmq::{prelude::*, Dealer};
+///
+/// let context = Arc::new(zmq::Context::new());
+///
+/// let builder = Dealer::builder(context)
+///     .sndhwm(1000)
+///     .linger(Some(Duration::from_millis(500)))
+///     .reconnect_ivl(Duration::from_millis(100))
+///     .immediate(true);
+///
+/// // The builder can be reused for as many sockets as needed
+/// let first = builder.clone().connect("inproc://builder-example").do_build();
+/// assert!(first.is_ok());
+///
+/// // Options that ZeroMQ rejects are reported when the socket is built
+/// let invalid = builder.sndhwm(-1).connect("inproc://builder-example").do_build();
+/// assert!(invalid.is_err());
+/// ```
+pub struct SocketBuilder<T>
 where
     T: IntoInnerSocket,
 {
-    ctx: Arc<zmq::Context>,
-    identity: Option<&'a [u8]>,
-    custom: Box<dyn Fn(&zmq::Socket)>,
-    _type: PhantomData<T>,
+    ctx: Option<Arc<zmq::Context>>,
+    pub(crate) options: SocketOptions,
+    _type: PhantomData<fn() -> T>,
 }
 
-impl<'a, T> SocketBuilder<'a, T>
+impl<T> SocketBuilder<T>
 where
-    T: IntoInnerSocket,
+    T: HasBuilder,
 {
     /// Create a new Socket builder
     ///
@@ -62,47 +281,50 @@ where
     /// for their socket builder (except PAIR sockets).
     pub fn new(ctx: Arc<zmq::Context>) -> Self {
         SocketBuilder {
-            ctx,
-            identity: None,
-            custom: Box::new(|_| {}),
+            ctx: Some(ctx),
+            options: SocketOptions::default(),
             _type: PhantomData,
         }
     }
+}
 
-    /// Give the socket a custom identity
-    pub fn identity(self, identity: &'a [u8]) -> Self {
-        SocketBuilder {
-            identity: Some(identity),
-            ..self
-        }
-    }
-
-    /// Provide a function for configuring the underlying ZeroMQ socket
-    ///
-    /// Note: Only the last call to customize will apply to a given socket.
+#[cfg(feature = "draft")]
+impl<T> SocketBuilder<T>
+where
+    T: IntoInnerSocket + DraftSocket,
+{
+    /// Create a builder for one of the draft socket types
     ///
-    /// Documentation can be found [here](https://docs.rs/zmq/0.9.1/zmq/struct.Socket.html)
-    pub fn customize<F>(self, f: F) -> Self
-    where
-        F: Fn(&zmq::Socket) + 'static,
-    {
+    /// Draft sockets aren't created in a caller's context, see the `draft` module.
+    pub fn draft() -> Self {
         SocketBuilder {
-            custom: Box::new(f),
-            ..self
+            ctx: None,
+            options: SocketOptions::default(),
+            _type: PhantomData,
         }
     }
+}
+
+impl<T> SocketBuilder<T>
+where
+    T: IntoInnerSocket,
+{
+    socket_options!();
 
     /// Bind the socket to an address
     ///
     /// Since this is just part of the builder, and the socket doesn't exist yet, we store the
-    /// address for later retrieval.
-    pub fn bind(self, addr: &'a str) -> SockConfig<'a, T> {
+    /// address for later retrieval. The address can be a string or an `Endpoint`, and is parsed
+    /// when the socket is built.
+    pub fn bind<E>(self, addr: E) -> SockConfig<T>
+    where
+        E: Into<String>,
+    {
         SockConfig {
             ctx: self.ctx,
-            bind: vec![addr],
+            bind: vec![addr.into()],
             connect: Vec::new(),
-            identity: self.identity,
-            customize: self.custom,
+            options: self.options,
             _type: self._type,
         }
     }
@@ -110,251 +332,317 @@ where
     /// Connect the socket to an address
     ///
     /// Since this is just part of the builder, and the socket doesn't exist yet, we store the
-    /// address for later retrieval.
-    pub fn connect(self, addr: &'a str) -> SockConfig<'a, T> {
+    /// address for later retrieval. The address can be a string or an `Endpoint`, and is parsed
+    /// when the socket is built.
+    pub fn connect<E>(self, addr: E) -> SockConfig<T>
+    where
+        E: Into<String>,
+    {
         SockConfig {
             ctx: self.ctx,
             bind: Vec::new(),
-            connect: vec![addr],
-            identity: self.identity,
-            customize: self.custom,
+            connect: vec![addr.into()],
+            options: self.options,
             _type: self._type,
         }
     }
 }
 
+impl<T> SocketBuilder<T>
+where
+    T: IntoInnerSocket + Pair,
+{
+    /// Bind or Connect the socket to an address
+    ///
+    /// This method indicates that the resulting socket will be a PAIR socket.
+    pub fn pair<E>(self, addr: E, bind: bool) -> PairConfig
+    where
+        E: Into<String>,
+    {
+        PairConfig {
+            ctx: classic_context(self.ctx),
+            addr: addr.into(),
+            bind,
+            options: self.options,
+        }
+    }
+}
+
+impl<T> Clone for SocketBuilder<T>
+where
+    T: IntoInnerSocket,
+{
+    fn clone(&self) -> Self {
+        SocketBuilder {
+            ctx: self.ctx.clone(),
+            options: self.options.clone(),
+            _type: PhantomData,
+        }
+    }
+}
+
+impl<T> fmt::Debug for SocketBuilder<T>
+where
+    T: IntoInnerSocket,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SocketBuilder")
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
 /// The final builder step for some socket types
 ///
 /// This contains all the information required to contstruct a valid socket, except in the case of
 /// SUB, which needs an additional `filter` parameter.
-pub struct SockConfig<'a, T>
+pub struct SockConfig<T>
 where
     T: IntoInnerSocket,
 {
-    pub ctx: Arc<zmq::Context>,
-    pub bind: Vec<&'a str>,
-    pub connect: Vec<&'a str>,
-    pub identity: Option<&'a [u8]>,
-    pub customize: Box<dyn Fn(&zmq::Socket)>,
-    _type: PhantomData<T>,
+    /// The context the socket is created in, which the draft socket types don't have
+    pub ctx: Option<Arc<zmq::Context>>,
+    pub bind: Vec<String>,
+    pub connect: Vec<String>,
+    pub options: SocketOptions,
+    _type: PhantomData<fn() -> T>,
+}
+
+impl<T> SockConfig<T>
+where
+    T: IntoInnerSocket,
+{
+    pub(crate) fn from_parts(
+        ctx: Arc<zmq::Context>,
+        bind: Vec<String>,
+        connect: Vec<String>,
+        options: SocketOptions,
+    ) -> Self {
+        SockConfig {
+            ctx: Some(ctx),
+            bind,
+            connect,
+            options,
+            _type: PhantomData,
+        }
+    }
 }
 
-impl<'a, T> SockConfig<'a, T>
+impl<T> SockConfig<T>
 where
     T: UnPair + IntoInnerSocket,
 {
     /// Bind the `SockConfig` to an address, returning a `SockConfig`
     ///
     /// This allows for a single socket to be bound to multiple addresses.
-    pub fn bind(mut self, addr: &'a str) -> Self {
-        self.bind.push(addr);
+    pub fn bind<E>(mut self, addr: E) -> Self
+    where
+        E: Into<String>,
+    {
+        self.bind.push(addr.into());
         self
     }
 
     /// Connect the `SockConfig` to an address, returning a `SockConfig`
     ///
     /// This allows for a single socket to be connected to multiple addresses.
-    pub fn connect(mut self, addr: &'a str) -> Self {
-        self.connect.push(addr);
-        self
-    }
-
-    /// Provide a function for configuring the underlying ZeroMQ socket
-    ///
-    /// Note: Only the last call to customize will apply to a given socket.
-    ///
-    /// Documentation can be found [here](https://docs.rs/zmq/0.9.1/zmq/struct.Socket.html)
-    pub fn customize<F>(self, f: F) -> Self
+    pub fn connect<E>(mut self, addr: E) -> Self
     where
-        F: Fn(&zmq::Socket) + 'static,
+        E: Into<String>,
     {
-        SockConfig {
-            customize: Box::new(f),
-            ..self
-        }
+        self.connect.push(addr.into());
+        self
     }
 
-    pub fn do_build(self) -> Result<zmq::Socket, zmq::Error> {
-        let SockConfig {
-            ctx,
-            bind,
-            connect,
-            identity,
-            customize,
-            _type,
-        } = self;
+    socket_options!();
 
-        let sock = ctx.socket(T::kind())?;
-        if let Some(identity) = identity {
-            sock.set_identity(identity)?;
-        }
-        customize(&sock);
-        let sock = bind_all(sock, &bind)?;
-        let sock = connect_all(sock, &connect)?;
+    /// Create the configured ZeroMQ socket
+    ///
+    /// Any endpoint that can't be parsed, and any socket option that ZeroMQ rejects, is returned
+    /// as an error.
+    pub fn do_build(self) -> Result<zmq::Socket, BuildError> {
+        let ctx = self.ctx.as_deref();
 
-        Ok(sock)
+        build_socket(|| T::create(ctx), &self.bind, &self.connect, &self.options)
     }
 }
 
-impl<'a, T> SockConfig<'a, T>
+impl<T> SockConfig<T>
 where
     T: IntoInnerSocket + Pair,
 {
     /// Bind or Connect the socket to an address
     ///
     /// This method indicates that the resulting socket will be a PAIR socket.
-    pub fn pair(self, addr: &'a str, bind: bool) -> PairConfig<'a> {
+    pub fn pair<E>(self, addr: E, bind: bool) -> PairConfig
+    where
+        E: Into<String>,
+    {
         PairConfig {
-            ctx: self.ctx,
-            addr,
+            ctx: classic_context(self.ctx),
+            addr: addr.into(),
             bind,
-            customize: self.customize,
-            identity: self.identity,
+            options: self.options,
         }
     }
 }
 
-impl<'a, T> SockConfig<'a, T>
+impl<T> SockConfig<T>
 where
     T: IntoInnerSocket + Sub,
 {
     /// Continue the building process into a SubConfig, for the SUB socket type which requires
     /// setting a subscription filter.
-    pub fn filter(self, pattern: &'a [u8]) -> SubConfig<'a> {
+    pub fn filter(self, pattern: &[u8]) -> SubConfig {
         SubConfig {
-            ctx: self.ctx,
+            ctx: classic_context(self.ctx),
             bind: self.bind,
             connect: self.connect,
-            customize: self.customize,
-            filter: vec![pattern],
-            identity: self.identity,
+            filter: vec![pattern.to_vec()],
+            options: self.options,
+        }
+    }
+}
+
+impl<T> Clone for SockConfig<T>
+where
+    T: IntoInnerSocket,
+{
+    fn clone(&self) -> Self {
+        SockConfig {
+            ctx: self.ctx.clone(),
+            bind: self.bind.clone(),
+            connect: self.connect.clone(),
+            options: self.options.clone(),
+            _type: PhantomData,
         }
     }
 }
 
+impl<T> fmt::Debug for SockConfig<T>
+where
+    T: IntoInnerSocket,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SockConfig")
+            .field("bind", &self.bind)
+            .field("connect", &self.connect)
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
 /// The final builder step for the Sub socket type.
 ///
 /// This contains all the information required to contstruct a valid SUB socket
-pub struct SubConfig<'a> {
+#[derive(Clone)]
+pub struct SubConfig {
     pub ctx: Arc<zmq::Context>,
-    pub bind: Vec<&'a str>,
-    pub connect: Vec<&'a str>,
-    pub customize: Box<dyn Fn(&zmq::Socket)>,
-    pub filter: Vec<&'a [u8]>,
-    pub identity: Option<&'a [u8]>,
+    pub bind: Vec<String>,
+    pub connect: Vec<String>,
+    pub filter: Vec<Vec<u8>>,
+    pub options: SocketOptions,
 }
 
-impl<'a> SubConfig<'a> {
-    /// Continue the building process into a SubConfig, for the SUB socket type which requires
-    /// setting a subscription filter.
-    pub fn filter(mut self, pattern: &'a [u8]) -> SubConfig<'a> {
-        self.filter.push(pattern);
-
-        SubConfig {
-            ctx: self.ctx,
-            bind: self.bind,
-            connect: self.connect,
-            customize: self.customize,
-            filter: self.filter,
-            identity: self.identity,
-        }
+impl SubConfig {
+    /// Add another subscription filter to the SUB socket
+    pub fn filter(mut self, pattern: &[u8]) -> SubConfig {
+        self.filter.push(pattern.to_vec());
+        self
     }
 
-    /// Provide a function for configuring the underlying ZeroMQ socket
-    ///
-    /// Note: Only the last call to customize will apply to a given socket.
-    ///
-    /// Documentation can be found [here](https://docs.rs/zmq/0.9.1/zmq/struct.Socket.html)
-    pub fn customize<F>(self, f: F) -> Self
-    where
-        F: Fn(&zmq::Socket) + 'static,
-    {
-        SubConfig {
-            customize: Box::new(f),
-            ..self
-        }
-    }
+    socket_options!();
 
     /// Finalize the `SubConfig` into a `Sub` if the creation is successful, or into an Error
     /// if something went wrong.
-    pub fn do_build(self) -> Result<zmq::Socket, zmq::Error> {
+    pub fn do_build(self) -> Result<zmq::Socket, BuildError> {
         let SubConfig {
             ctx,
             bind,
             connect,
-            customize,
             filter,
-            identity,
+            options,
         } = self;
 
-        let sock = ctx.socket(zmq::SUB)?;
-        if let Some(identity) = identity {
-            sock.set_identity(identity)?;
-        }
-        customize(&sock);
-        let sock = bind_all(sock, &bind)?;
-        let sock = connect_all(sock, &connect)?;
+        let sock = build_socket(|| ctx.socket(zmq::SUB), &bind, &connect, &options)?;
         for pattern in filter {
-            sock.set_subscribe(pattern)?;
+            sock.set_subscribe(&pattern)?;
         }
 
         Ok(sock)
     }
 }
 
+impl fmt::Debug for SubConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SubConfig")
+            .field("bind", &self.bind)
+            .field("connect", &self.connect)
+            .field("filter", &self.filter)
+            .field("options", &self.options)
+            .finish()
+    }
+}
+
 /// The final builder step for the Pair socket type.
 ///
 /// This contains all the information required to contstruct a valid PAIR socket
-pub struct PairConfig<'a> {
+#[derive(Clone)]
+pub struct PairConfig {
     ctx: Arc<zmq::Context>,
-    addr: &'a str,
+    addr: String,
     bind: bool,
-    customize: Box<dyn Fn(&zmq::Socket)>,
-    identity: Option<&'a [u8]>,
+    pub options: SocketOptions,
 }
 
-impl<'a> PairConfig<'a> {
+impl PairConfig {
+    pub(crate) fn from_parts(
+        ctx: Arc<zmq::Context>,
+        addr: String,
+        bind: bool,
+        options: SocketOptions,
+    ) -> Self {
+        PairConfig {
+            ctx,
+            addr,
+            bind,
+            options,
+        }
+    }
+
     /// Construct a raw `Socket` type from the given `PairConfig`
     ///
     /// This build takes the same arguments as the `SockConfig`'s build method for convenience, but
     /// this should not be called with `zmq::SocketType`s other than `zmq::PAIR`. The `Pair`
     /// wrapper uses this builder, so it is better to use the Pair wrapper than directly building a
     /// PAIR socket.
-    pub fn do_build(self) -> Result<zmq::Socket, zmq::Error> {
+    pub fn do_build(self) -> Result<zmq::Socket, BuildError> {
         let PairConfig {
             ctx,
             addr,
             bind,
-            customize,
-            identity,
+            options,
         } = self;
 
-        let sock = ctx.socket(zmq::PAIR)?;
-        if let Some(identity) = identity {
-            sock.set_identity(identity)?;
-        }
-        customize(&sock);
-        if bind {
-            sock.bind(addr)?;
+        let endpoints = [addr];
+        let (binds, connects) = if bind {
+            (&endpoints[..], &[][..])
         } else {
-            sock.connect(addr)?;
-        }
+            (&[][..], &endpoints[..])
+        };
 
-        Ok(sock)
+        build_socket(|| ctx.socket(zmq::PAIR), binds, connects, &options)
     }
 
-    /// Provide a function for configuring the underlying ZeroMQ socket
-    ///
-    /// Note: Only the last call to customize will apply to a given socket.
-    ///
-    /// Documentation can be found [here](https://docs.rs/zmq/0.9.1/zmq/struct.Socket.html)
-    pub fn customize<F>(self, f: F) -> Self
-    where
-        F: Fn(&zmq::Socket) + 'static,
-    {
-        PairConfig {
-            customize: Box::new(f),
-            ..self
-        }
+    socket_options!();
+}
+
+impl fmt::Debug for PairConfig {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PairConfig")
+            .field("addr", &self.addr)
+            .field("bind", &self.bind)
+            .field("options", &self.options)
+            .finish()
     }
 }
@@ -0,0 +1,280 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains the key types used to configure CURVE security on a socket.
+
+// failure's derive expands into impls nested inside of a const block
+#![allow(non_local_definitions)]
+
+use std::fmt;
+
+use failure::Fail;
+
+use crate::raw;
+
+const KEY_LEN: usize = 32;
+const Z85_KEY_LEN: usize = 40;
+const Z85_CHARS: &[u8] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ.-:+=^!/*?&<>()[]{}@%$#";
+
+/// The reasons a CURVE key can be rejected
+#[derive(Clone, Debug, Eq, Fail, PartialEq)]
+pub enum CurveError {
+    #[fail(display = "CURVE keys are 32 bytes, got {}", _0)]
+    /// A binary key had the wrong length
+    KeyLength(usize),
+
+    #[fail(display = "Z85 CURVE keys are 40 characters, got {}", _0)]
+    /// A Z85 key had the wrong length
+    Z85Length(usize),
+
+    #[fail(display = "Invalid character in Z85 CURVE key")]
+    /// A Z85 key contained a character outside of the Z85 alphabet
+    Z85Char,
+
+    #[fail(display = "CURVE public key doesn't belong to the secret key")]
+    /// A keypair's public key isn't the one derived from its secret key
+    Mismatch,
+
+    #[fail(display = "ZeroMQ was built without CURVE support")]
+    /// The keypair couldn't be checked, since ZeroMQ can't derive public keys
+    Unsupported,
+
+    #[fail(display = "ZeroMQ couldn't derive the CURVE public key, {}", _0)]
+    /// The keypair couldn't be checked, since deriving its public key failed
+    Zmq(#[cause] zmq::Error),
+}
+
+fn key_from_bytes(bytes: &[u8]) -> Result<[u8; KEY_LEN], CurveError> {
+    if bytes.len() != KEY_LEN {
+        return Err(CurveError::KeyLength(bytes.len()));
+    }
+
+    let mut key = [0; KEY_LEN];
+    key.copy_from_slice(bytes);
+    Ok(key)
+}
+
+fn key_from_z85(text: &str) -> Result<[u8; KEY_LEN], CurveError> {
+    if text.len() != Z85_KEY_LEN {
+        return Err(CurveError::Z85Length(text.len()));
+    }
+
+    // ZeroMQ silently accepts characters outside of the alphabet, and decodes them to garbage
+    if !text.bytes().all(|b| Z85_CHARS.contains(&b)) {
+        return Err(CurveError::Z85Char);
+    }
+
+    let bytes = zmq::z85_decode(text).map_err(|_| CurveError::Z85Char)?;
+    key_from_bytes(&bytes)
+}
+
+fn key_to_z85(key: &[u8; KEY_LEN]) -> String {
+    zmq::z85_encode(key).expect("CURVE keys are a multiple of 4 bytes")
+}
+
+/// A CURVE public key
+///
+/// Clients need the server's public key before they can connect.
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
+pub struct CurvePublicKey([u8; KEY_LEN]);
+
+impl CurvePublicKey {
+    /// Load a public key from its 32 byte binary form
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CurveError> {
+        key_from_bytes(bytes).map(CurvePublicKey)
+    }
+
+    /// Load a public key from its 40 character Z85 form
+    pub fn from_z85(text: &str) -> Result<Self, CurveError> {
+        key_from_z85(text).map(CurvePublicKey)
+    }
+
+    /// The binary form of the key
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// The Z85 form of the key
+    pub fn to_z85(&self) -> String {
+        key_to_z85(&self.0)
+    }
+}
+
+impl fmt::Debug for CurvePublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CurvePublicKey({})", self.to_z85())
+    }
+}
+
+/// A CURVE secret key
+///
+/// The key is never included in `Debug` output.
+#[derive(Clone, Eq, PartialEq)]
+pub struct CurveSecretKey([u8; KEY_LEN]);
+
+impl CurveSecretKey {
+    /// Load a secret key from its 32 byte binary form
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CurveError> {
+        key_from_bytes(bytes).map(CurveSecretKey)
+    }
+
+    /// Load a secret key from its 40 character Z85 form
+    pub fn from_z85(text: &str) -> Result<Self, CurveError> {
+        key_from_z85(text).map(CurveSecretKey)
+    }
+
+    /// The binary form of the key
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// The Z85 form of the key
+    pub fn to_z85(&self) -> String {
+        key_to_z85(&self.0)
+    }
+}
+
+impl fmt::Debug for CurveSecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CurveSecretKey(..)")
+    }
+}
+
+/// A CURVE public and secret key
+///
+/// ### Example
+/// ```rust
+/// use async_zmq_types::CurveKeyPair;
+///
+/// let keys = CurveKeyPair::generate().unwrap();
+///
+/// // Keys can be stored as Z85 text and loaded again later
+/// let public = keys.public().to_z85();
+/// let secret = keys.secret().to_z85();
+///
+/// let loaded = CurveKeyPair::from_z85(&public, &secret).unwrap();
+/// assert_eq!(keys.public(), loaded.public());
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CurveKeyPair {
+    public: CurvePublicKey,
+    secret: CurveSecretKey,
+}
+
+impl CurveKeyPair {
+    /// Generate a new keypair
+    ///
+    /// This fails with `ENOTSUP` if ZeroMQ was built without CURVE support.
+    pub fn generate() -> zmq::Result<Self> {
+        let pair = zmq::CurveKeyPair::new()?;
+
+        Ok(CurveKeyPair {
+            public: CurvePublicKey(pair.public_key),
+            secret: CurveSecretKey(pair.secret_key),
+        })
+    }
+
+    /// Create a keypair from already-loaded keys
+    ///
+    /// The public key must be the one derived from the secret key, since a mismatched pair would
+    /// otherwise only show up as handshakes that silently fail.
+    pub fn new(public: CurvePublicKey, secret: CurveSecretKey) -> Result<Self, CurveError> {
+        let derived = match raw::curve_public(&secret.to_z85()) {
+            Ok(derived) => derived,
+            Err(zmq::Error::ENOTSUP) => return Err(CurveError::Unsupported),
+            Err(zmq::Error::EINVAL) => return Err(CurveError::Z85Char),
+            Err(e) => return Err(CurveError::Zmq(e)),
+        };
+
+        if derived != public.to_z85() {
+            return Err(CurveError::Mismatch);
+        }
+
+        Ok(CurveKeyPair { public, secret })
+    }
+
+    /// Load a keypair from the 32 byte binary form of each key
+    pub fn from_bytes(public: &[u8], secret: &[u8]) -> Result<Self, CurveError> {
+        CurveKeyPair::new(
+            CurvePublicKey::from_bytes(public)?,
+            CurveSecretKey::from_bytes(secret)?,
+        )
+    }
+
+    /// Load a keypair from the 40 character Z85 form of each key
+    pub fn from_z85(public: &str, secret: &str) -> Result<Self, CurveError> {
+        CurveKeyPair::new(
+            CurvePublicKey::from_z85(public)?,
+            CurveSecretKey::from_z85(secret)?,
+        )
+    }
+
+    pub fn public(&self) -> &CurvePublicKey {
+        &self.public
+    }
+
+    pub fn secret(&self) -> &CurveSecretKey {
+        &self.secret
+    }
+}
+
+/// The side of a CURVE connection a socket takes
+///
+/// This is independent of whether the socket binds or connects, although servers usually bind.
+#[derive(Clone, Debug)]
+pub enum CurveRole {
+    /// Accept connections from any client that knows this keypair's public key
+    Server(CurveKeyPair),
+    /// Connect to the server with the given public key, authenticating with this keypair
+    Client {
+        keys: CurveKeyPair,
+        server: CurvePublicKey,
+    },
+}
+
+impl CurveRole {
+    /// Configure the socket for this role
+    ///
+    /// This fails with `ENOTSUP` if ZeroMQ was built without CURVE support, rather than letting
+    /// the socket fall back to an unencrypted connection.
+    pub fn apply(&self, sock: &zmq::Socket) -> zmq::Result<()> {
+        if zmq::has("curve") != Some(true) {
+            return Err(zmq::Error::ENOTSUP);
+        }
+
+        match *self {
+            CurveRole::Server(ref keys) => {
+                sock.set_curve_server(true)?;
+                sock.set_curve_publickey(keys.public.as_bytes())?;
+                sock.set_curve_secretkey(keys.secret.as_bytes())?;
+            }
+            CurveRole::Client {
+                ref keys,
+                ref server,
+            } => {
+                sock.set_curve_serverkey(server.as_bytes())?;
+                sock.set_curve_publickey(keys.public.as_bytes())?;
+                sock.set_curve_secretkey(keys.secret.as_bytes())?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,454 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains the types for libzmq's draft sockets, SERVER and CLIENT, RADIO and DISH,
+//! SCATTER and GATHER, CHANNEL, and PEER.
+//!
+//! Draft sockets are thread-safe, and only send and receive single frames. Instead of routing
+//! frames, each message carries its SERVER or PEER routing id, or its RADIO group, so a
+//! `Multipart` read from one always holds a single message. `RoutedMessage` and `GroupMessage`
+//! pair that message with its routing id or group.
+//!
+//! The draft API is only available when libzmq was built with it, and the zmq crate doesn't wrap
+//! it. The zmq crate also doesn't give out the pointer of a `zmq::Context`, so draft sockets
+//! can't be created in a caller's context. Instead, their builders take no context, and every
+//! draft socket is created in one context of their own, which lives for the rest of the process.
+//! That has a few consequences:
+//!
+//! - Draft sockets reach each other over inproc, but can't reach classic sockets over inproc
+//! - A ZAP handler never sees them, since it's bound in a caller's context
+//! - They can't be monitored, since a monitor's PAIR socket is created in a caller's context
+//! - Options set on a caller's context, such as its I/O threads, don't apply to them
+//! - Terminating a caller's context doesn't close them, or wait for them
+//!
+//! ### Example
+//! ```rust
+//! use async_zmq_types::draft::{GroupMessage, Group, RoutedMessage};
+//!
+//! let group = Group::new("weather").unwrap();
+//! let update = GroupMessage::new(group, zmq::Message::from("sunny"));
+//! assert_eq!(update.group.as_str(), "weather");
+//!
+//! // Messages that weren't received from a SERVER have no routing id
+//! let reply = RoutedMessage::from_multipart(zmq::Message::from("hi").into());
+//! assert!(reply.is_err());
+//! ```
+
+// failure's derive expands into impls nested inside of a const block
+#![allow(non_local_definitions)]
+
+use std::{fmt, num::NonZeroU32, os::raw::c_int};
+
+use failure::Fail;
+
+use crate::{
+    config::{SockConfig, SocketBuilder},
+    message::Multipart,
+    raw, IntoInnerSocket,
+};
+
+/// The longest group name libzmq accepts, in bytes
+pub const GROUP_MAX_LENGTH: usize = 255;
+
+/* ----------------------------------TYPES----------------------------------- */
+
+/// The reasons a `Multipart` or a group name can't be used with a draft socket
+#[derive(Clone, Debug, Eq, Fail, PartialEq)]
+pub enum DraftError {
+    #[fail(display = "Draft sockets send single frames, got {}", _0)]
+    /// The multipart didn't hold exactly one frame
+    Frames(usize),
+
+    #[fail(display = "Message has no routing id")]
+    /// The message wasn't received from a SERVER or PEER socket
+    RoutingId,
+
+    #[fail(display = "Message has no group")]
+    /// The message wasn't received from a DISH socket
+    NoGroup,
+
+    #[fail(display = "Invalid group name, {}", _0)]
+    /// The group name was too long, or contained a nul byte
+    Group(String),
+}
+
+/// The draft socket types
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DraftKind {
+    Server,
+    Client,
+    Radio,
+    Dish,
+    Scatter,
+    Gather,
+    Channel,
+    Peer,
+}
+
+/// The id a SERVER or PEER socket gives each of its peers
+///
+/// Ids are never zero. A SERVER tags every message it receives with the id of the client that
+/// sent it, and a reply goes back to the client whose id it carries.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct RoutingId(NonZeroU32);
+
+/// The name of a RADIO and DISH group, at most `GROUP_MAX_LENGTH` bytes
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Group(String);
+
+/// A message to or from the peer with the given routing id
+#[derive(Debug)]
+pub struct RoutedMessage {
+    pub routing_id: RoutingId,
+    pub body: zmq::Message,
+}
+
+/// A message to or from a RADIO and DISH group
+#[derive(Debug)]
+pub struct GroupMessage {
+    pub group: Group,
+    pub body: zmq::Message,
+}
+
+/// A poller holding a single draft socket
+///
+/// Draft sockets have no ZMQ_FD, so backends that wait on file descriptors wait on the poller's
+/// instead. Like ZMQ_FD, it only signals that the socket's events may have changed, and is reset
+/// by `drain`.
+pub struct Poller {
+    inner: raw::RawPoller,
+}
+
+/* ---------------------------------MARKERS---------------------------------- */
+
+// The SocketWrapper derive implements these for the draft wrapper types whose sockets accept
+// the options, which gives their builders the matching methods.
+
+/// Marks the draft wrapper types, whose builders are made without a context
+pub trait DraftSocket {}
+
+/// Marks the SERVER, CLIENT, and PEER wrapper types
+pub trait HelloMsg {}
+
+/// Marks the SERVER and PEER wrapper types
+pub trait DisconnectMsg {}
+
+/// Marks the CLIENT and PEER wrapper types
+pub trait HiccupMsg {}
+
+/// Marks the RADIO and DISH wrapper types
+pub trait MulticastLoop {}
+
+/// Marks the DISH wrapper types
+pub trait Groups {}
+
+/* ----------------------------------impls----------------------------------- */
+
+impl DraftKind {
+    /// The socket type as libzmq numbers it
+    pub fn as_raw(self) -> c_int {
+        match self {
+            DraftKind::Server => 12,
+            DraftKind::Client => 13,
+            DraftKind::Radio => 14,
+            DraftKind::Dish => 15,
+            DraftKind::Gather => 16,
+            DraftKind::Scatter => 17,
+            DraftKind::Peer => 19,
+            DraftKind::Channel => 20,
+        }
+    }
+
+    /// The classic socket type this one behaves most like
+    ///
+    /// This is what the draft wrapper types return from `IntoInnerSocket::kind`, so proxies know
+    /// which ways they can forward.
+    pub fn classic(self) -> zmq::SocketType {
+        match self {
+            DraftKind::Server | DraftKind::Peer => zmq::ROUTER,
+            DraftKind::Client => zmq::DEALER,
+            DraftKind::Radio => zmq::PUB,
+            DraftKind::Dish => zmq::SUB,
+            DraftKind::Scatter => zmq::PUSH,
+            DraftKind::Gather => zmq::PULL,
+            DraftKind::Channel => zmq::PAIR,
+        }
+    }
+
+    /// Create a socket of this type, in the context draft sockets share
+    pub fn create(self) -> zmq::Result<zmq::Socket> {
+        raw::socket(self.as_raw())
+    }
+}
+
+impl RoutingId {
+    /// Create a routing id, returning `None` for zero
+    pub fn new(id: u32) -> Option<Self> {
+        NonZeroU32::new(id).map(RoutingId)
+    }
+
+    pub fn get(self) -> u32 {
+        self.0.get()
+    }
+
+    /// The routing id a message was received with
+    pub fn of(msg: &mut zmq::Message) -> Option<Self> {
+        RoutingId::new(raw::routing_id(msg))
+    }
+
+    /// Address a message to the peer with this routing id
+    pub fn apply(self, msg: &mut zmq::Message) {
+        // libzmq only rejects zero, which a RoutingId can't be
+        raw::set_routing_id(msg, self.get()).expect("Routing ids are never zero");
+    }
+}
+
+impl fmt::Display for RoutingId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Group {
+    /// Check a group name
+    pub fn new(name: &str) -> Result<Self, DraftError> {
+        if name.len() > GROUP_MAX_LENGTH || name.contains('\0') {
+            return Err(DraftError::Group(name.to_owned()));
+        }
+
+        Ok(Group(name.to_owned()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The group a message was received with
+    pub fn of(msg: &mut zmq::Message) -> Option<Self> {
+        raw::group(msg).map(Group)
+    }
+
+    /// Send a message to this group
+    pub fn apply(&self, msg: &mut zmq::Message) {
+        // libzmq only rejects names that are too long, which a Group can't be
+        raw::set_group(msg, &self.0).expect("Group names are checked when they're created");
+    }
+}
+
+impl fmt::Display for Group {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+fn single(mut multipart: Multipart) -> Result<zmq::Message, DraftError> {
+    match multipart.len() {
+        1 => Ok(multipart.pop_front().unwrap()),
+        len => Err(DraftError::Frames(len)),
+    }
+}
+
+impl RoutedMessage {
+    pub fn new(routing_id: RoutingId, body: zmq::Message) -> Self {
+        RoutedMessage { routing_id, body }
+    }
+
+    /// Create a reply to this message, routed back to its sender
+    pub fn reply(&self, body: zmq::Message) -> Self {
+        RoutedMessage::new(self.routing_id, body)
+    }
+
+    pub fn from_multipart(multipart: Multipart) -> Result<Self, DraftError> {
+        let mut body = single(multipart)?;
+        let routing_id = RoutingId::of(&mut body).ok_or(DraftError::RoutingId)?;
+
+        Ok(RoutedMessage { routing_id, body })
+    }
+
+    pub fn into_multipart(self) -> Multipart {
+        let mut body = self.body;
+        self.routing_id.apply(&mut body);
+
+        body.into()
+    }
+}
+
+impl From<RoutedMessage> for Multipart {
+    fn from(msg: RoutedMessage) -> Self {
+        msg.into_multipart()
+    }
+}
+
+impl GroupMessage {
+    pub fn new(group: Group, body: zmq::Message) -> Self {
+        GroupMessage { group, body }
+    }
+
+    pub fn from_multipart(multipart: Multipart) -> Result<Self, DraftError> {
+        let mut body = single(multipart)?;
+        let group = Group::of(&mut body).ok_or(DraftError::NoGroup)?;
+
+        Ok(GroupMessage { group, body })
+    }
+
+    pub fn into_multipart(self) -> Multipart {
+        let mut body = self.body;
+        self.group.apply(&mut body);
+
+        body.into()
+    }
+}
+
+impl From<GroupMessage> for Multipart {
+    fn from(msg: GroupMessage) -> Self {
+        msg.into_multipart()
+    }
+}
+
+/// Join a RADIO group on a DISH socket
+pub fn join(sock: &zmq::Socket, group: &Group) -> zmq::Result<()> {
+    raw::join(sock, group.as_str())
+}
+
+/// Leave a RADIO group on a DISH socket
+pub fn leave(sock: &zmq::Socket, group: &Group) -> zmq::Result<()> {
+    raw::leave(sock, group.as_str())
+}
+
+/// Connect a PEER socket, returning the routing id its messages to the new peer take
+pub fn connect_peer(sock: &zmq::Socket, endpoint: &str) -> zmq::Result<RoutingId> {
+    let routing_id = raw::connect_peer(sock, endpoint)?;
+
+    Ok(RoutingId::new(routing_id).expect("libzmq reports failed connections as errors"))
+}
+
+impl Poller {
+    pub fn new(sock: &zmq::Socket) -> zmq::Result<Self> {
+        Ok(Poller {
+            inner: raw::RawPoller::new(sock)?,
+        })
+    }
+
+    /// Reset the poller's file descriptor, after which it signals the next change in events
+    pub fn drain(&self) {
+        self.inner.drain();
+    }
+}
+
+#[cfg(unix)]
+impl std::os::unix::io::AsRawFd for Poller {
+    fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+        // The poller always has a file descriptor once it holds a thread-safe socket
+        self.inner
+            .fd()
+            .expect("Pollers of draft sockets have a file descriptor")
+    }
+}
+
+impl fmt::Debug for Poller {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Poller").finish()
+    }
+}
+
+/* ---------------------------------BUILDERS--------------------------------- */
+
+macro_rules! hello_msg {
+    () => {
+        /// Send a message to each peer when it connects (ZMQ_HELLO_MSG)
+        pub fn hello_msg(mut self, msg: &[u8]) -> Self {
+            self.options.hello_msg = Some(msg.to_vec());
+            self
+        }
+    };
+}
+
+macro_rules! disconnect_msg {
+    () => {
+        /// Receive a message from ZeroMQ when a peer disconnects (ZMQ_DISCONNECT_MSG)
+        ///
+        /// The message carries the routing id of the peer that went away.
+        pub fn disconnect_msg(mut self, msg: &[u8]) -> Self {
+            self.options.disconnect_msg = Some(msg.to_vec());
+            self
+        }
+    };
+}
+
+macro_rules! hiccup_msg {
+    () => {
+        /// Receive a message from ZeroMQ when the connection is lost and re-established
+        /// (ZMQ_HICCUP_MSG)
+        pub fn hiccup_msg(mut self, msg: &[u8]) -> Self {
+            self.options.hiccup_msg = Some(msg.to_vec());
+            self
+        }
+    };
+}
+
+macro_rules! multicast_loop {
+    () => {
+        /// Deliver multicast messages back to the host that sent them (ZMQ_MULTICAST_LOOP)
+        ///
+        /// This only affects the udp transport, and is on by default.
+        pub fn multicast_loop(mut self, multicast_loop: bool) -> Self {
+            self.options.multicast_loop = Some(multicast_loop);
+            self
+        }
+    };
+}
+
+macro_rules! join {
+    () => {
+        /// Join a RADIO group once the DISH socket is built
+        pub fn join(mut self, group: &$crate::draft::Group) -> Self {
+            self.options.groups.push(group.as_str().to_owned());
+            self
+        }
+    };
+}
+
+/// Add the draft options to the builders of the wrapper types that accept them
+macro_rules! draft_options {
+    ($($marker:ident => $option:ident;)*) => {
+        $(
+            impl<T> SocketBuilder<T>
+            where
+                T: IntoInnerSocket + $marker,
+            {
+                $option!();
+            }
+
+            impl<T> SockConfig<T>
+            where
+                T: IntoInnerSocket + $marker,
+            {
+                $option!();
+            }
+        )*
+    };
+}
+
+draft_options! {
+    HelloMsg => hello_msg;
+    DisconnectMsg => disconnect_msg;
+    HiccupMsg => hiccup_msg;
+    MulticastLoop => multicast_loop;
+    Groups => join;
+}
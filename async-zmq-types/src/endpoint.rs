@@ -0,0 +1,477 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains `Endpoint`, a parsed ZeroMQ address.
+//!
+//! Builders still accept endpoints as strings, and parse them when the socket is built, so a
+//! typo is reported as an `EndpointError` naming the problem rather than as ZeroMQ's `EINVAL`.
+
+// failure's derive expands into impls nested inside of a const block
+#![allow(non_local_definitions)]
+
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    path::PathBuf,
+    str::FromStr,
+};
+
+use failure::Fail;
+
+/// Transports ZeroMQ supports that `Endpoint` passes through without parsing
+const OTHER_TRANSPORTS: &[&str] = &["pgm", "epgm", "norm", "tipc", "vmci", "udp", "wss"];
+
+/* ----------------------------------TYPES----------------------------------- */
+
+/// The reasons an endpoint can be rejected
+#[derive(Clone, Debug, Eq, Fail, PartialEq)]
+pub enum EndpointError {
+    #[fail(display = "Endpoint {:?} has no transport, such as tcp://", _0)]
+    /// The endpoint didn't start with a transport
+    MissingTransport(String),
+
+    #[fail(display = "Unknown transport {:?}", _0)]
+    /// The transport isn't one ZeroMQ supports
+    Transport(String),
+
+    #[fail(display = "{} endpoint has no address", _0)]
+    /// Nothing followed the transport
+    Empty(String),
+
+    #[fail(display = "Invalid host or interface {:?}", _0)]
+    /// The host wasn't `*`, an IP address, or a name
+    Host(String),
+
+    #[fail(display = "Address {:?} has no port", _0)]
+    /// A tcp or ws address didn't end with a port
+    MissingPort(String),
+
+    #[fail(display = "Invalid port {:?}", _0)]
+    /// The port wasn't `*` or a number up to 65535
+    Port(String),
+
+    #[fail(display = "Can't connect to {}, it has a wildcard", _0)]
+    /// A connect endpoint used `*` or port 0, which only make sense when binding
+    Wildcard(String),
+
+    #[fail(display = "Can't bind to {}, only connects have a source address", _0)]
+    /// A bind endpoint had a source address
+    SourceOnBind(String),
+}
+
+/// A ZeroMQ endpoint
+///
+/// Endpoints are parsed with `str::parse`, and print as the address ZeroMQ expects, so an
+/// endpoint can be passed anywhere a builder takes one.
+///
+/// ### Example
+/// ```rust
+/// use std::net::Ipv4Addr;
+///
+/// use async_zmq_types::{Endpoint, EndpointError};
+///
+/// let endpoint: Endpoint = "tcp://127.0.0.1:5555".parse().unwrap();
+/// assert_eq!(endpoint, Endpoint::tcp(Ipv4Addr::LOCALHOST, 5555));
+/// assert_eq!(endpoint.to_string(), "tcp://127.0.0.1:5555");
+///
+/// // Port 0 and `*` both ask ZeroMQ to pick a port
+/// let ephemeral: Endpoint = "tcp://*:0".parse().unwrap();
+/// assert!(ephemeral.is_ephemeral());
+/// assert_eq!(ephemeral.check_connect(), Err(EndpointError::Wildcard("tcp://*:*".to_owned())));
+///
+/// assert_eq!(
+///     "tpc://localhost:5555".parse::<Endpoint>(),
+///     Err(EndpointError::Transport("tpc".to_owned()))
+/// );
+/// ```
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Endpoint {
+    /// A `tcp://` endpoint
+    Tcp(TcpEndpoint),
+    /// An `ipc://` endpoint, the path of a unix domain socket
+    ///
+    /// Paths starting with `@` are abstract sockets on Linux, and a path of `*` asks ZeroMQ to
+    /// make one up when binding.
+    Ipc(PathBuf),
+    /// An `inproc://` endpoint, a name shared by the sockets of one context
+    Inproc(String),
+    /// A `ws://` endpoint, for libzmq built with WebSocket support
+    Ws(WsEndpoint),
+    /// An endpoint for a transport this type doesn't model, such as `pgm://` or `udp://`
+    Other { transport: String, address: String },
+}
+
+/// The address of a `tcp://` endpoint
+///
+/// When binding, `host` is the interface to listen on. When connecting, it's the peer, and the
+/// connection can be made from a particular `source` address.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TcpEndpoint {
+    pub source: Option<TcpSource>,
+    pub host: Host,
+    pub port: Port,
+}
+
+/// The local address a tcp connection is made from, written before a `;` in the endpoint
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TcpSource {
+    pub host: Host,
+    pub port: Option<Port>,
+}
+
+/// The address of a `ws://` endpoint
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct WsEndpoint {
+    pub host: Host,
+    pub port: Port,
+    /// The path, starting with `/`, or empty
+    pub path: String,
+}
+
+/// A host or interface in a tcp or ws address
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Host {
+    /// `*`, every interface
+    Any,
+    /// An IP address, IPv6 addresses are written in brackets
+    Ip(IpAddr),
+    /// A hostname, or the name of an interface such as `eth0`
+    Name(String),
+}
+
+/// A port in a tcp or ws address
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Port {
+    /// `*` or `0`, a port ZeroMQ picks when binding
+    Any,
+    /// A particular port
+    Number(u16),
+}
+
+/* ----------------------------------impls----------------------------------- */
+
+impl Endpoint {
+    /// A tcp endpoint for a host and port
+    pub fn tcp<H, P>(host: H, port: P) -> Self
+    where
+        H: Into<Host>,
+        P: Into<Port>,
+    {
+        Endpoint::Tcp(TcpEndpoint {
+            source: None,
+            host: host.into(),
+            port: port.into(),
+        })
+    }
+
+    /// An ipc endpoint for a path
+    pub fn ipc<P>(path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Endpoint::Ipc(path.into())
+    }
+
+    /// An inproc endpoint for a name
+    pub fn inproc<S>(name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        Endpoint::Inproc(name.into())
+    }
+
+    /// The transport, such as `"tcp"`
+    pub fn transport(&self) -> &str {
+        match *self {
+            Endpoint::Tcp(_) => "tcp",
+            Endpoint::Ipc(_) => "ipc",
+            Endpoint::Inproc(_) => "inproc",
+            Endpoint::Ws(_) => "ws",
+            Endpoint::Other { ref transport, .. } => transport,
+        }
+    }
+
+    /// Whether ZeroMQ picks part of the address when the endpoint is bound
+    ///
+    /// Binding returns the endpoint ZeroMQ picked, which is also the one to unbind.
+    pub fn is_ephemeral(&self) -> bool {
+        match *self {
+            Endpoint::Tcp(ref tcp) => tcp.port == Port::Any,
+            Endpoint::Ipc(ref path) => path.as_os_str() == "*",
+            Endpoint::Ws(ref ws) => ws.port == Port::Any,
+            Endpoint::Inproc(_) | Endpoint::Other { .. } => false,
+        }
+    }
+
+    /// Check that the endpoint can be bound
+    pub fn check_bind(&self) -> Result<(), EndpointError> {
+        match *self {
+            Endpoint::Tcp(TcpEndpoint {
+                source: Some(_), ..
+            }) => Err(EndpointError::SourceOnBind(self.to_string())),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check that the endpoint can be connected to
+    pub fn check_connect(&self) -> Result<(), EndpointError> {
+        let wildcard = match *self {
+            Endpoint::Tcp(ref tcp) => tcp.host == Host::Any || tcp.port == Port::Any,
+            Endpoint::Ws(ref ws) => ws.host == Host::Any || ws.port == Port::Any,
+            Endpoint::Ipc(_) => self.is_ephemeral(),
+            Endpoint::Inproc(_) | Endpoint::Other { .. } => false,
+        };
+
+        if wildcard {
+            Err(EndpointError::Wildcard(self.to_string()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn parse_host(host: &str) -> Result<Host, EndpointError> {
+    if host == "*" {
+        return Ok(Host::Any);
+    }
+
+    if host.starts_with('[') && host.ends_with(']') {
+        return host[1..host.len() - 1]
+            .parse::<Ipv6Addr>()
+            .map(|ip| Host::Ip(ip.into()))
+            .map_err(|_| EndpointError::Host(host.to_owned()));
+    }
+
+    if let Ok(ip) = host.parse::<Ipv4Addr>() {
+        return Ok(Host::Ip(ip.into()));
+    }
+
+    let valid = !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.' || c == '_');
+
+    if valid {
+        Ok(Host::Name(host.to_owned()))
+    } else {
+        Err(EndpointError::Host(host.to_owned()))
+    }
+}
+
+fn parse_port(port: &str) -> Result<Port, EndpointError> {
+    if port == "*" {
+        return Ok(Port::Any);
+    }
+
+    // u16's parser accepts a leading +
+    if !port.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(EndpointError::Port(port.to_owned()));
+    }
+
+    port.parse::<u16>()
+        .map(Port::from)
+        .map_err(|_| EndpointError::Port(port.to_owned()))
+}
+
+/// Split `host:port`, leaving the colons of a bracketed IPv6 address alone
+fn split_port(address: &str) -> (&str, Option<&str>) {
+    let host_end = if address.starts_with('[') {
+        address.find(']').map(|i| i + 1).unwrap_or(address.len())
+    } else {
+        0
+    };
+
+    match address[host_end..].rfind(':') {
+        Some(i) => (&address[..host_end + i], Some(&address[host_end + i + 1..])),
+        None => (address, None),
+    }
+}
+
+fn parse_address(address: &str) -> Result<(Host, Port), EndpointError> {
+    match split_port(address) {
+        (host, Some(port)) => Ok((parse_host(host)?, parse_port(port)?)),
+        (_, None) => Err(EndpointError::MissingPort(address.to_owned())),
+    }
+}
+
+fn parse_source(source: &str) -> Result<TcpSource, EndpointError> {
+    let (host, port) = split_port(source);
+
+    Ok(TcpSource {
+        host: parse_host(host)?,
+        port: port.map(parse_port).transpose()?,
+    })
+}
+
+fn parse_tcp(address: &str) -> Result<TcpEndpoint, EndpointError> {
+    let (source, address) = match address.find(';') {
+        Some(i) => (Some(&address[..i]), &address[i + 1..]),
+        None => (None, address),
+    };
+
+    let (host, port) = parse_address(address)?;
+
+    Ok(TcpEndpoint {
+        source: source.map(parse_source).transpose()?,
+        host,
+        port,
+    })
+}
+
+fn parse_ws(address: &str) -> Result<WsEndpoint, EndpointError> {
+    let (address, path) = match address.find('/') {
+        Some(i) => (&address[..i], &address[i..]),
+        None => (address, ""),
+    };
+
+    let (host, port) = parse_address(address)?;
+
+    Ok(WsEndpoint {
+        host,
+        port,
+        path: path.to_owned(),
+    })
+}
+
+impl FromStr for Endpoint {
+    type Err = EndpointError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (transport, address) = match s.find("://") {
+            Some(i) => (&s[..i], &s[i + 3..]),
+            None => return Err(EndpointError::MissingTransport(s.to_owned())),
+        };
+
+        let known = matches!(transport, "tcp" | "ipc" | "inproc" | "ws")
+            || OTHER_TRANSPORTS.contains(&transport);
+
+        if !known {
+            return Err(EndpointError::Transport(transport.to_owned()));
+        }
+
+        if address.is_empty() {
+            return Err(EndpointError::Empty(transport.to_owned()));
+        }
+
+        match transport {
+            "tcp" => parse_tcp(address).map(Endpoint::Tcp),
+            "ipc" => Ok(Endpoint::Ipc(PathBuf::from(address))),
+            "inproc" => Ok(Endpoint::Inproc(address.to_owned())),
+            "ws" => parse_ws(address).map(Endpoint::Ws),
+            transport => Ok(Endpoint::Other {
+                transport: transport.to_owned(),
+                address: address.to_owned(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Endpoint::Tcp(ref tcp) => write!(f, "tcp://{}", tcp),
+            Endpoint::Ipc(ref path) => write!(f, "ipc://{}", path.display()),
+            Endpoint::Inproc(ref name) => write!(f, "inproc://{}", name),
+            Endpoint::Ws(ref ws) => write!(f, "ws://{}:{}{}", ws.host, ws.port, ws.path),
+            Endpoint::Other {
+                ref transport,
+                ref address,
+            } => write!(f, "{}://{}", transport, address),
+        }
+    }
+}
+
+impl fmt::Display for TcpEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref source) = self.source {
+            write!(f, "{}", source.host)?;
+            if let Some(port) = source.port {
+                write!(f, ":{}", port)?;
+            }
+            write!(f, ";")?;
+        }
+
+        write!(f, "{}:{}", self.host, self.port)
+    }
+}
+
+impl fmt::Display for Host {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Host::Any => write!(f, "*"),
+            Host::Ip(IpAddr::V4(ref ip)) => write!(f, "{}", ip),
+            Host::Ip(IpAddr::V6(ref ip)) => write!(f, "[{}]", ip),
+            Host::Name(ref name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl fmt::Display for Port {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Port::Any => write!(f, "*"),
+            Port::Number(port) => write!(f, "{}", port),
+        }
+    }
+}
+
+impl From<Endpoint> for String {
+    fn from(endpoint: Endpoint) -> Self {
+        endpoint.to_string()
+    }
+}
+
+impl<'a> From<&'a Endpoint> for String {
+    fn from(endpoint: &'a Endpoint) -> Self {
+        endpoint.to_string()
+    }
+}
+
+impl From<SocketAddr> for Endpoint {
+    fn from(addr: SocketAddr) -> Self {
+        Endpoint::tcp(addr.ip(), addr.port())
+    }
+}
+
+impl From<IpAddr> for Host {
+    fn from(ip: IpAddr) -> Self {
+        Host::Ip(ip)
+    }
+}
+
+impl From<Ipv4Addr> for Host {
+    fn from(ip: Ipv4Addr) -> Self {
+        Host::Ip(ip.into())
+    }
+}
+
+impl From<Ipv6Addr> for Host {
+    fn from(ip: Ipv6Addr) -> Self {
+        Host::Ip(ip.into())
+    }
+}
+
+impl From<u16> for Port {
+    fn from(port: u16) -> Self {
+        match port {
+            0 => Port::Any,
+            port => Port::Number(port),
+        }
+    }
+}
@@ -19,22 +19,68 @@
 
 //! Provide useful types and traits for working with ZMQ Asynchronously.
 
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-use futures::{Future, Sink, Stream};
+use futures::{Future, Sink, TryFuture, TryStream};
 
+#[macro_use]
+mod options;
+pub mod broker;
+pub mod codec;
 mod config;
+mod curve;
+#[cfg(feature = "draft")]
+pub mod draft;
+pub mod endpoint;
+pub mod mdp;
 mod message;
+pub mod monitor;
+pub mod proxy;
+mod raw;
+mod socket_config;
 mod stream;
+pub mod subscription;
+pub mod zap;
 
 pub use crate::{
-    config::{PairConfig, SockConfig, SocketBuilder, SubConfig},
-    message::Multipart,
+    config::{
+        bind_endpoint, connect_endpoint, BuildError, PairConfig, SockConfig, SocketBuilder,
+        SubConfig,
+    },
+    curve::{CurveError, CurveKeyPair, CurvePublicKey, CurveRole, CurveSecretKey},
+    endpoint::{Endpoint, EndpointError},
+    message::{shared_message, Envelope, EnvelopeError, Multipart, PeerId},
+    monitor::SocketEvent,
+    options::{Customize, PlainRole, SocketOptions},
+    raw::unbind,
+    socket_config::{ConfigError, SocketConfig, SocketKind},
     stream::{ControlledStream, EndingStream},
 };
 
 /* ----------------------------------TYPES----------------------------------- */
 
+/// What happened to the multiparts a sink was still holding when it was shut down
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ShutdownReport {
+    undelivered: usize,
+}
+
+impl ShutdownReport {
+    pub fn new(undelivered: usize) -> Self {
+        ShutdownReport { undelivered }
+    }
+
+    /// How many multiparts were thrown away instead of being handed to ZeroMQ
+    pub fn undelivered(&self) -> usize {
+        self.undelivered
+    }
+
+    /// Whether every multipart was handed to ZeroMQ
+    pub fn is_complete(&self) -> bool {
+        self.undelivered == 0
+    }
+}
+
 /* ----------------------------------TRAITS---------------------------------- */
 
 pub trait IntoSocket<T, U>: Sized
@@ -47,35 +93,63 @@ where
 /// Define all actions possible on a socket
 ///
 /// This should be generic enough to implement over any executor. On Tokio, this might consist of
-/// a Socket with an EventedFd, on Futures, it might just be a Socket.
+/// a Socket with an `AsyncFd`, on Futures, it might just be a Socket.
 pub trait InnerSocket<T>: Sized
 where
     T: IntoInnerSocket + From<Self>,
 {
     /// The future that sends a multipart to a ZMQ socket
-    type Request: Future<Item = T>;
+    type Request: TryFuture<Ok = T>;
 
     /// The future that receives a multipart from a ZMQ socket
-    type Response: Future<Item = (Multipart, T)>;
+    type Response: TryFuture<Ok = (Multipart, T)>;
 
     /// A Stream of multiparts received from a ZMQ socket
-    type Stream: Stream<Item = Multipart> + IntoSocket<T, Self>;
+    type Stream: TryStream<Ok = Multipart> + IntoSocket<T, Self>;
 
     /// A Sink that sends multiparts to a ZMQ socket
-    type Sink: Sink<SinkItem = Multipart> + IntoSocket<T, Self>;
+    type Sink: Sink<Multipart> + IntoSocket<T, Self>;
 
     /// A Sink and Stream that sends and receives multiparts from a ZMQ socket
-    type SinkStream: Stream<Item = Multipart> + Sink<SinkItem = Multipart> + IntoSocket<T, Self>;
+    type SinkStream: TryStream<Ok = Multipart> + Sink<Multipart> + IntoSocket<T, Self>;
+
+    /// The future that sends a multipart to a borrowed ZMQ socket
+    type RequestMut<'a>: TryFuture<Ok = ()>
+    where
+        Self: 'a;
+
+    /// The future that receives a multipart from a borrowed ZMQ socket
+    type ResponseMut<'a>: TryFuture<Ok = Multipart>
+    where
+        Self: 'a;
+
+    /// The future that binds a ZMQ socket, resolving to the endpoint it was bound to
+    type Bind: TryFuture<Ok = String>;
+
+    /// The future that unbinds, connects, or disconnects a ZMQ socket
+    type Endpoint: TryFuture<Ok = ()>;
 
     fn send(self, multipart: Multipart) -> Self::Request;
 
     fn recv(self) -> Self::Response;
 
+    fn send_mut(&mut self, multipart: Multipart) -> Self::RequestMut<'_>;
+
+    fn recv_mut(&mut self) -> Self::ResponseMut<'_>;
+
     fn stream(self) -> Self::Stream;
 
     fn sink(self, buffer_size: usize) -> Self::Sink;
 
     fn sink_stream(self, buffer_size: usize) -> Self::SinkStream;
+
+    fn bind(&self, endpoint: &str) -> Self::Bind;
+
+    fn unbind(&self, endpoint: &str) -> Self::Endpoint;
+
+    fn connect(&self, endpoint: &str) -> Self::Endpoint;
+
+    fn disconnect(&self, endpoint: &str) -> Self::Endpoint;
 }
 
 /// The `IntoInnerSocket` trait is implemented for all wrapper types. This makes implementing other traits a
@@ -89,7 +163,77 @@ where
     /// Any type implementing `IntoInnerSocket` must have a way of returning an InnerSocket.
     fn socket(self) -> Self::Socket;
 
+    /// Borrow the InnerSocket without giving up the wrapper type.
+    fn socket_mut(&mut self) -> &mut Self::Socket;
+
     fn kind() -> zmq::SocketType;
+
+    /// Create a ZeroMQ socket of this type in `ctx`
+    ///
+    /// The draft socket types override this, since the zmq crate can't create them. They're
+    /// built without a context, and their `kind` is the classic type they behave most like.
+    fn create(ctx: Option<&zmq::Context>) -> zmq::Result<zmq::Socket> {
+        ctx.ok_or(zmq::Error::EINVAL)?.socket(Self::kind())
+    }
+
+    /// Whether this is one of the draft socket types
+    #[cfg(feature = "draft")]
+    fn is_draft() -> bool {
+        false
+    }
+
+    /// Bind the socket to another endpoint, resolving to the endpoint it was bound to
+    ///
+    /// Binding to port 0, or to `*` as a port, picks a free port, and the endpoint the future
+    /// resolves to says which one. That's also the endpoint to pass to `unbind`.
+    ///
+    /// ### Example, using the Rep wrapper type
+    /// ```rust
+    /// use std::sync::Arc;
+    ///
+    /// use tokio_zmq::{prelude::*, Error, Rep};
+    ///
+    /// async fn serve(context: Arc<zmq::Context>) -> Result<(), Error> {
+    ///     let mut rep: Rep = Rep::builder(context)
+    ///         .bind("inproc://rep")
+    ///         .build()
+    ///         .await?;
+    ///
+    ///     let endpoint = rep.bind("tcp://127.0.0.1:0").await?;
+    ///     println!("Also listening on {}", endpoint);
+    ///
+    ///     loop {
+    ///         let multipart = rep.recv_mut().await?;
+    ///         rep.send_mut(multipart).await?;
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let context = Arc::new(zmq::Context::new());
+    ///     let fut = serve(context);
+    ///
+    ///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+    ///     # let _ = fut;
+    /// }
+    /// ```
+    fn bind(&mut self, endpoint: &str) -> <Self::Socket as InnerSocket<Self>>::Bind {
+        self.socket_mut().bind(endpoint)
+    }
+
+    /// Stop accepting connections on an endpoint the socket was bound to
+    fn unbind(&mut self, endpoint: &str) -> <Self::Socket as InnerSocket<Self>>::Endpoint {
+        self.socket_mut().unbind(endpoint)
+    }
+
+    /// Connect the socket to another endpoint
+    fn connect(&mut self, endpoint: &str) -> <Self::Socket as InnerSocket<Self>>::Endpoint {
+        self.socket_mut().connect(endpoint)
+    }
+
+    /// Disconnect the socket from an endpoint it was connected to
+    fn disconnect(&mut self, endpoint: &str) -> <Self::Socket as InnerSocket<Self>>::Endpoint {
+        self.socket_mut().disconnect(endpoint)
+    }
 }
 
 /// The `ControlHandler` trait is used to impose stopping rules for streams that otherwise would
@@ -121,37 +265,32 @@ pub trait StreamSocket: IntoInnerSocket {
     ///
     /// ### Example, using the Rep wrapper type
     /// ```rust
-    /// extern crate futures;
-    /// extern crate tokio;
-    /// extern crate tokio_zmq;
-    /// extern crate zmq;
-    ///
     /// use std::sync::Arc;
     ///
-    /// use futures::Future;
-    /// use tokio_zmq::{prelude::*, async_types::MultipartStream, Error, Multipart, Rep};
+    /// use tokio_zmq::{prelude::*, Error, Multipart, Rep};
+    ///
+    /// async fn recv_one(context: Arc<zmq::Context>) -> Result<Multipart, Error> {
+    ///     let rep: Rep = Rep::builder(context)
+    ///         .connect("tcp://localhost:5568")
+    ///         .build()
+    ///         .await?;
+    ///
+    ///     let (multipart, _rep) = rep.recv().await?;
+    ///
+    ///     for msg in &multipart {
+    ///         if let Some(msg) = msg.as_str() {
+    ///             println!("Message: {}", msg);
+    ///         }
+    ///     }
+    ///
+    ///     Ok(multipart)
+    /// }
     ///
     /// fn main() {
     ///     let context = Arc::new(zmq::Context::new());
+    ///     let fut = recv_one(context);
     ///
-    ///     let fut = Rep::builder(context)
-    ///         .connect("tcp://localhost:5568")
-    ///         .build()
-    ///         .and_then(|rep| {
-    ///             rep.recv().and_then(|(multipart, _)| {
-    ///                 for msg in &multipart {
-    ///                     if let Some(msg) = msg.as_str() {
-    ///                         println!("Message: {}", msg);
-    ///                     }
-    ///                 }
-    ///                 Ok(multipart)
-    ///             })
-    ///         });
-    ///
-    ///     // tokio::run(fut.map(|_| ()).or_else(|e| {
-    ///     //     println!("Error: {}", e);
-    ///     //     Ok(())
-    ///     // }));
+    ///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
     ///     # let _ = fut;
     /// }
     /// ```
@@ -159,41 +298,74 @@ pub trait StreamSocket: IntoInnerSocket {
         self.socket().recv()
     }
 
-    /// Receive a stream of multipart messages from the socket.
+    /// Receive a single multipart message from the socket without giving up ownership of it.
     ///
-    /// ### Example, using a Sub wrapper type
+    /// ### Example, using the Rep wrapper type
     /// ```rust
-    /// extern crate zmq;
-    /// extern crate futures;
-    /// extern crate tokio;
-    /// extern crate tokio_zmq;
-    ///
     /// use std::sync::Arc;
     ///
-    /// use futures::{Future, Stream};
-    /// use tokio_zmq::{prelude::*, async_types::MultipartStream, Error, Multipart, Sub};
+    /// use tokio_zmq::{prelude::*, Error, Rep};
+    ///
+    /// async fn echo(context: Arc<zmq::Context>) -> Result<(), Error> {
+    ///     let mut rep: Rep = Rep::builder(context)
+    ///         .connect("tcp://localhost:5568")
+    ///         .build()
+    ///         .await?;
+    ///
+    ///     loop {
+    ///         let multipart = rep.recv_mut().await?;
+    ///         rep.send_mut(multipart).await?;
+    ///     }
+    /// }
     ///
     /// fn main() {
     ///     let context = Arc::new(zmq::Context::new());
-    ///     let fut = Sub::builder(context)
+    ///     let fut = echo(context);
+    ///
+    ///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+    ///     # let _ = fut;
+    /// }
+    /// ```
+    fn recv_mut(
+        &mut self,
+    ) -> <<Self as IntoInnerSocket>::Socket as InnerSocket<Self>>::ResponseMut<'_> {
+        self.socket_mut().recv_mut()
+    }
+
+    /// Receive a stream of multipart messages from the socket.
+    ///
+    /// ### Example, using a Sub wrapper type
+    /// ```rust
+    /// use std::sync::Arc;
+    ///
+    /// use futures::TryStreamExt;
+    /// use tokio_zmq::{prelude::*, Error, Sub};
+    ///
+    /// async fn print_all(context: Arc<zmq::Context>) -> Result<(), Error> {
+    ///     let sub: Sub = Sub::builder(context)
     ///         .connect("tcp://localhost:5569")
     ///         .filter(b"")
     ///         .build()
-    ///         .and_then(|sub| {
-    ///             sub.stream().for_each(|multipart| {
-    ///                 for msg in multipart {
-    ///                     if let Some(msg) = msg.as_str() {
-    ///                         println!("Message: {}", msg);
-    ///                     }
+    ///         .await?;
+    ///
+    ///     sub.stream()
+    ///         .try_for_each(|multipart| async move {
+    ///             for msg in multipart {
+    ///                 if let Some(msg) = msg.as_str() {
+    ///                     println!("Message: {}", msg);
     ///                 }
-    ///                 Ok(())
-    ///             })
-    ///         });
-    ///
-    ///     // tokio::run(fut.map(|_| ()).or_else(|e| {
-    ///     //     println!("Error: {}", e);
-    ///     //     Ok(())
-    ///     // }));
+    ///             }
+    ///             Ok(())
+    ///         })
+    ///         .await
+    /// }
+    ///
+    /// fn main() {
+    ///     let context = Arc::new(zmq::Context::new());
+    ///     let fut = print_all(context);
+    ///
+    ///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+    ///     # let _ = fut;
     /// }
     /// ```
     fn stream(self) -> <<Self as IntoInnerSocket>::Socket as InnerSocket<Self>>::Stream {
@@ -208,28 +380,26 @@ pub trait SinkSocket: IntoInnerSocket {
     ///
     /// ### Example, using a Pub wrapper type
     /// ```rust
-    /// extern crate zmq;
-    /// extern crate futures;
-    /// extern crate tokio;
-    /// extern crate tokio_zmq;
-    ///
     /// use std::sync::Arc;
     ///
-    /// use futures::Future;
-    /// use tokio_zmq::{prelude::*, async_types::MultipartStream, Error, Pub};
+    /// use tokio_zmq::{prelude::*, Error, Pub};
     ///
-    /// fn main() {
-    ///     let context = Arc::new(zmq::Context::new());
-    ///     let msg = zmq::Message::from_slice(b"Hello");
-    ///     let fut = Pub::builder(context)
+    /// async fn send_one(context: Arc<zmq::Context>) -> Result<Pub, Error> {
+    ///     let msg = zmq::Message::from("Hello");
+    ///     let zpub: Pub = Pub::builder(context)
     ///         .connect("tcp://localhost:5569")
     ///         .build()
-    ///         .and_then(|zpub| zpub.send(msg.into()));
+    ///         .await?;
     ///
-    ///     // tokio::run(fut.map(|_| ()).or_else(|e| {
-    ///     //     println!("Error: {}", e);
-    ///     //     Ok(())
-    ///     // }));
+    ///     zpub.send(msg.into()).await
+    /// }
+    ///
+    /// fn main() {
+    ///     let context = Arc::new(zmq::Context::new());
+    ///     let fut = send_one(context);
+    ///
+    ///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+    ///     # let _ = fut;
     /// }
     /// ```
     fn send(
@@ -239,6 +409,44 @@ pub trait SinkSocket: IntoInnerSocket {
         self.socket().send(multipart)
     }
 
+    /// Send a single multipart message to the socket without giving up ownership of it.
+    ///
+    /// ### Example, using a Req wrapper type
+    /// ```rust
+    /// use std::sync::Arc;
+    ///
+    /// use tokio_zmq::{prelude::*, Error, Req};
+    ///
+    /// async fn ping(context: Arc<zmq::Context>) -> Result<(), Error> {
+    ///     let mut req: Req = Req::builder(context)
+    ///         .connect("tcp://localhost:5568")
+    ///         .build()
+    ///         .await?;
+    ///
+    ///     for i in 0..10 {
+    ///         req.send_mut(zmq::Message::from(&format!("ping {}", i)).into())
+    ///             .await?;
+    ///         let _pong = req.recv_mut().await?;
+    ///     }
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// fn main() {
+    ///     let context = Arc::new(zmq::Context::new());
+    ///     let fut = ping(context);
+    ///
+    ///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+    ///     # let _ = fut;
+    /// }
+    /// ```
+    fn send_mut(
+        &mut self,
+        multipart: Multipart,
+    ) -> <<Self as IntoInnerSocket>::Socket as InnerSocket<Self>>::RequestMut<'_> {
+        self.socket_mut().send_mut(multipart)
+    }
+
     /// Send a stream of multipart messages to the socket.
     ///
     /// It takes a buffer_size argument, which will determine how many `Multipart`s can be
@@ -246,33 +454,29 @@ pub trait SinkSocket: IntoInnerSocket {
     ///
     /// ### Example, using a Pub wrapper type
     /// ```rust
-    /// extern crate zmq;
-    /// extern crate futures;
-    /// extern crate tokio;
-    /// extern crate tokio_zmq;
-    ///
     /// use std::sync::Arc;
     ///
-    /// use futures::{Future, Stream, stream::iter_ok};
-    /// use tokio_zmq::{prelude::*, async_types::MultipartStream, Error, Multipart, Pub};
+    /// use futures::{stream, StreamExt};
+    /// use tokio_zmq::{prelude::*, Error, Multipart, Pub};
     ///
-    /// fn main() {
-    ///     let context = Arc::new(zmq::Context::new());
-    ///     let fut = Pub::builder(context)
+    /// async fn send_five(context: Arc<zmq::Context>) -> Result<(), Error> {
+    ///     let zpub: Pub = Pub::builder(context)
     ///         .connect("tcp://localhost:5570")
     ///         .build()
-    ///         .and_then(|zpub| {
-    ///             iter_ok(0..5)
-    ///                 .map(|i| {
-    ///                     zmq::Message::from_slice(format!("i: {}", i).as_bytes()).into()
-    ///                 })
-    ///                 .forward(zpub.sink(25))
-    ///         });
-    ///
-    ///     // tokio::run(fut.map(|_| ()).or_else(|e| {
-    ///     //     println!("Error: {}", e);
-    ///     //     Ok(())
-    ///     // }));
+    ///         .await?;
+    ///
+    ///     stream::iter(0..5)
+    ///         .map(|i| Ok(zmq::Message::from(&format!("i: {}", i)).into()))
+    ///         .forward(zpub.sink(25))
+    ///         .await
+    /// }
+    ///
+    /// fn main() {
+    ///     let context = Arc::new(zmq::Context::new());
+    ///     let fut = send_five(context);
+    ///
+    ///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+    ///     # let _ = fut;
     /// }
     /// ```
     fn sink(
@@ -292,30 +496,28 @@ pub trait SinkStreamSocket: IntoInnerSocket {
     ///
     /// ### Example, using a Rep wrapper type
     /// ```rust
-    /// extern crate futures;
-    /// extern crate tokio_zmq;
-    /// extern crate zmq;
-    ///
     /// use std::sync::Arc;
     ///
-    /// use futures::{Future, Stream};
-    /// use tokio_zmq::{prelude::*, Rep};
+    /// use futures::StreamExt;
+    /// use tokio_zmq::{prelude::*, Error, Rep};
     ///
-    /// fn main() {
-    ///     let ctx = Arc::new(zmq::Context::new());
-    ///     let fut = Rep::builder(ctx)
+    /// async fn echo(context: Arc<zmq::Context>) -> Result<(), Error> {
+    ///     let rep: Rep = Rep::builder(context)
     ///         .bind("tcp://*:5571")
     ///         .build()
-    ///         .and_then(|rep| {
-    ///             let (sink, stream) = rep.sink_stream(25).split();
+    ///         .await?;
     ///
-    ///             stream.forward(sink)
-    ///         });
+    ///     let (sink, stream) = rep.sink_stream(25).split();
     ///
-    ///     // tokio::run(fut.map(|_| ()).or_else(|e| {
-    ///     //     println!("Error: {}", e);
-    ///     //     Ok(())
-    ///     // }));
+    ///     stream.forward(sink).await
+    /// }
+    ///
+    /// fn main() {
+    ///     let context = Arc::new(zmq::Context::new());
+    ///     let fut = echo(context);
+    ///
+    ///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+    ///     # let _ = fut;
     /// }
     /// ```
     fn sink_stream(
@@ -324,20 +526,35 @@ pub trait SinkStreamSocket: IntoInnerSocket {
     ) -> <<Self as IntoInnerSocket>::Socket as InnerSocket<Self>>::SinkStream;
 }
 
+/// This trait is implemented by sinks that can flush their pending multiparts before closing their
+/// socket.
+///
+/// The backends' sinks and the socket types that can send provide `close()` and
+/// `shutdown(timeout)` methods built on this trait, and wrappers like `CodecSink` forward to it.
+pub trait GracefulShutdown: Sized {
+    type Error;
+
+    type Future: Future<Output = Result<ShutdownReport, Self::Error>>;
+
+    /// Flush pending multiparts, then drop the socket
+    ///
+    /// With no `timeout`, this waits as long as the socket's ZMQ_LINGER option allows, which is
+    /// forever when it's -1. Multiparts that couldn't be handed to ZeroMQ in time are dropped and
+    /// counted in the report. Multiparts ZeroMQ has already accepted are still subject to
+    /// ZMQ_LINGER once the socket is closed.
+    fn graceful_shutdown(self, timeout: Option<Duration>) -> Self::Future;
+}
+
 /// This trait is provided to allow for ending a stream based on a Multipart message it receives.
-pub trait WithEndHandler: Stream<Item = Multipart> + Sized {
+pub trait WithEndHandler: TryStream<Ok = Multipart> + Sized {
     /// Add an EndHandler to a stream.
     ///
     /// ### Example, using a Sub wrapper type
     /// ```rust
-    /// extern crate futures;
-    /// extern crate tokio_zmq;
-    /// extern crate zmq;
-    ///
     /// use std::sync::Arc;
     ///
-    /// use futures::{Future, Stream};
-    /// use tokio_zmq::{prelude::*, Sub, Multipart};
+    /// use futures::TryStreamExt;
+    /// use tokio_zmq::{prelude::*, Error, Multipart, Sub};
     ///
     /// struct End(u32);
     ///
@@ -349,22 +566,25 @@ pub trait WithEndHandler: Stream<Item = Multipart> + Sized {
     ///     }
     /// }
     ///
-    /// fn main() {
-    ///     let ctx = Arc::new(zmq::Context::new());
-    ///     let fut = Sub::builder(ctx)
+    /// async fn run(context: Arc<zmq::Context>) -> Result<(), Error> {
+    ///     let sub: Sub = Sub::builder(context)
     ///         .bind("tcp://*:5571")
     ///         .filter(b"")
     ///         .build()
-    ///         .and_then(|sub| {
-    ///             sub.stream()
-    ///                 .with_end_handler(End(0))
-    ///                 .for_each(|_| Ok(()))
-    ///         });
-    ///
-    ///     // tokio::run(fut.map(|_| ()).or_else(|e| {
-    ///     //     println!("Error: {}", e);
-    ///     //     Ok(())
-    ///     // }));
+    ///         .await?;
+    ///
+    ///     sub.stream()
+    ///         .with_end_handler(End(0))
+    ///         .try_for_each(|_| async { Ok(()) })
+    ///         .await
+    /// }
+    ///
+    /// fn main() {
+    ///     let context = Arc::new(zmq::Context::new());
+    ///     let fut = run(context);
+    ///
+    ///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+    ///     # let _ = fut;
     /// }
     /// ```
     fn with_end_handler<E>(self, end_handler: E) -> EndingStream<E, Self, Self::Error>
@@ -374,20 +594,16 @@ pub trait WithEndHandler: Stream<Item = Multipart> + Sized {
 
 /// This trait is implemented by all Streams with Item = Multipart and Error = Error, it provides
 /// the ability to control when the stream stops based on the content of another stream.
-pub trait Controllable: Stream<Item = Multipart> + Sized {
+pub trait Controllable: TryStream<Ok = Multipart> + Sized {
     /// Add a controller stream to a given stream. This allows the controller stream to decide when
     /// the controlled stream should stop.
     ///
     /// ### Example, using a controlled Pull wrapper type and a controller Sub wrapper type
     /// ```rust
-    /// extern crate futures;
-    /// extern crate tokio_zmq;
-    /// extern crate zmq;
-    ///
     /// use std::sync::Arc;
     ///
-    /// use futures::{Future, Stream};
-    /// use tokio_zmq::{prelude::*, Pull, Sub, Multipart};
+    /// use futures::{try_join, TryStreamExt};
+    /// use tokio_zmq::{prelude::*, Error, Multipart, Pull, Sub};
     ///
     /// struct End;
     ///
@@ -397,29 +613,30 @@ pub trait Controllable: Stream<Item = Multipart> + Sized {
     ///     }
     /// }
     ///
-    /// fn main() {
-    ///     let ctx = Arc::new(zmq::Context::new());
-    ///     let init_pull = Pull::builder(Arc::clone(&ctx))
+    /// async fn run(context: Arc<zmq::Context>) -> Result<(), Error> {
+    ///     let init_pull = Pull::builder(Arc::clone(&context))
     ///         .bind("tcp://*:5572")
     ///         .build();
     ///
-    ///     let init_sub = Sub::builder(ctx)
+    ///     let init_sub = Sub::builder(context)
     ///         .bind("tcp://*:5573")
     ///         .filter(b"")
     ///         .build();
     ///
-    ///     let fut = init_pull
-    ///         .join(init_sub)
-    ///         .and_then(|(pull, sub)| {
-    ///             pull.stream()
-    ///                 .controlled(sub.stream(), End)
-    ///                 .for_each(|_| Ok(()))
-    ///         });
-    ///
-    ///     // tokio::run(fut.map(|_| ()).or_else(|e| {
-    ///     //     println!("Error: {}", e);
-    ///     //     Ok(())
-    ///     // }));
+    ///     let (pull, sub): (Pull, Sub) = try_join!(init_pull, init_sub)?;
+    ///
+    ///     pull.stream()
+    ///         .controlled(sub.stream(), End)
+    ///         .try_for_each(|_| async { Ok(()) })
+    ///         .await
+    /// }
+    ///
+    /// fn main() {
+    ///     let context = Arc::new(zmq::Context::new());
+    ///     let fut = run(context);
+    ///
+    ///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+    ///     # let _ = fut;
     /// }
     /// ```
     fn controlled<H, S>(
@@ -429,8 +646,7 @@ pub trait Controllable: Stream<Item = Multipart> + Sized {
     ) -> ControlledStream<H, S, Self, Self::Error>
     where
         H: ControlHandler,
-        S: Stream<Item = Multipart>,
-        Self: Stream<Item = Multipart, Error = S::Error>;
+        S: TryStream<Ok = Multipart, Error = Self::Error>;
 }
 
 pub trait UnPair {}
@@ -439,14 +655,17 @@ pub trait Sub {}
 pub trait UnSub {}
 
 pub trait Build<T, E> {
-    type Result: Future<Item = T, Error = E>;
+    type Result: Future<Output = Result<T, E>>;
 
     fn build(self) -> Self::Result;
 }
 
-/// This trait is implemented by all socket types to allow custom builders to be created
+/// This trait is implemented by the classic socket types to allow custom builders to be created
+///
+/// The draft socket types aren't created in a caller's context, so their builders are made by an
+/// inherent `builder()` that doesn't take one.
 pub trait HasBuilder: IntoInnerSocket {
-    fn builder(ctx: Arc<zmq::Context>) -> SocketBuilder<'static, Self>
+    fn builder(ctx: Arc<zmq::Context>) -> SocketBuilder<Self>
     where
         Self: Sized,
     {
@@ -456,8 +675,6 @@ pub trait HasBuilder: IntoInnerSocket {
 
 /* ----------------------------------impls----------------------------------- */
 
-impl<T> HasBuilder for T where T: IntoInnerSocket {}
-
 impl<T> SinkStreamSocket for T
 where
     T: StreamSocket + SinkSocket,
@@ -472,7 +689,7 @@ where
 
 impl<T> WithEndHandler for T
 where
-    T: Stream<Item = Multipart>,
+    T: TryStream<Ok = Multipart>,
 {
     fn with_end_handler<E>(self, end_handler: E) -> EndingStream<E, Self, Self::Error>
     where
@@ -484,7 +701,7 @@ where
 
 impl<T> Controllable for T
 where
-    T: Stream<Item = Multipart>,
+    T: TryStream<Ok = Multipart>,
 {
     fn controlled<H, S>(
         self,
@@ -493,7 +710,7 @@ where
     ) -> ControlledStream<H, S, Self, Self::Error>
     where
         H: ControlHandler,
-        S: Stream<Item = Multipart, Error = T::Error>,
+        S: TryStream<Ok = Multipart, Error = T::Error>,
     {
         ControlledStream::new(self, control_stream, handler)
     }
@@ -0,0 +1,244 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains the messages of the Majordomo Protocol, MDP/0.2, as described in
+//! [ZeroMQ RFC 18](https://rfc.zeromq.org/spec/18/).
+//!
+//! Clients talk to a broker with `ClientMessage`s, and workers talk to it with `WorkerMessage`s.
+//! Neither includes the routing id a ROUTER socket adds to the messages it receives.
+
+// failure's derive expands into impls nested inside of a const block
+#![allow(non_local_definitions)]
+
+use failure::Fail;
+
+use crate::message::{Envelope, EnvelopeError, Multipart, PeerId};
+
+/// The first frame of every message between a client and a broker
+pub const CLIENT: &str = "MDPC02";
+
+/// The first frame of every message between a worker and a broker
+pub const WORKER: &str = "MDPW02";
+
+/// The reasons a `Multipart` can fail to parse as a Majordomo message
+#[derive(Clone, Debug, Eq, Fail, PartialEq)]
+pub enum MdpError {
+    #[fail(display = "Message is not a Majordomo message")]
+    /// The first frame was not the expected protocol header
+    Protocol,
+
+    #[fail(display = "Unknown Majordomo command {}", _0)]
+    /// The command frame held a command this side of the protocol doesn't accept
+    Command(u8),
+
+    #[fail(display = "Majordomo message is missing frames")]
+    /// The message ended before all of its command's frames were read
+    MissingFrames,
+
+    #[fail(display = "Majordomo service names must be UTF-8")]
+    /// The service frame wasn't valid UTF-8
+    Service,
+
+    #[fail(display = "Invalid client address, {}", _0)]
+    /// A worker command's client address couldn't be parsed
+    Envelope(#[cause] EnvelopeError),
+}
+
+/// The commands clients and brokers exchange
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ClientCommand {
+    /// A request from a client
+    Request,
+    /// A reply that will be followed by more replies
+    Partial,
+    /// The last reply to a request
+    Final,
+}
+
+/// A message between a client and a broker
+#[derive(Debug)]
+pub struct ClientMessage {
+    pub command: ClientCommand,
+    pub service: String,
+    pub body: Multipart,
+}
+
+/// A message between a worker and a broker
+#[derive(Debug)]
+pub enum WorkerMessage {
+    /// A worker is ready to handle requests for `service`
+    Ready { service: String },
+    /// A request from the client at `client`
+    Request {
+        client: Vec<PeerId>,
+        body: Multipart,
+    },
+    /// A reply that will be followed by more replies
+    Partial {
+        client: Vec<PeerId>,
+        body: Multipart,
+    },
+    /// The last reply to a request
+    Final {
+        client: Vec<PeerId>,
+        body: Multipart,
+    },
+    /// The sender is still alive
+    Heartbeat,
+    /// The sender is going away, or the receiver should reconnect
+    Disconnect,
+}
+
+impl ClientCommand {
+    fn from_byte(byte: u8) -> Result<Self, MdpError> {
+        match byte {
+            0x01 => Ok(ClientCommand::Request),
+            0x02 => Ok(ClientCommand::Partial),
+            0x03 => Ok(ClientCommand::Final),
+            byte => Err(MdpError::Command(byte)),
+        }
+    }
+
+    fn as_byte(self) -> u8 {
+        match self {
+            ClientCommand::Request => 0x01,
+            ClientCommand::Partial => 0x02,
+            ClientCommand::Final => 0x03,
+        }
+    }
+}
+
+fn header(multipart: &mut Multipart, protocol: &str) -> Result<u8, MdpError> {
+    match multipart.pop_front() {
+        Some(ref frame) if &frame[..] == protocol.as_bytes() => (),
+        Some(_) => return Err(MdpError::Protocol),
+        None => return Err(MdpError::MissingFrames),
+    }
+
+    match multipart.pop_front() {
+        Some(ref frame) if frame.len() == 1 => Ok(frame[0]),
+        Some(_) => Err(MdpError::Protocol),
+        None => Err(MdpError::MissingFrames),
+    }
+}
+
+fn service(multipart: &mut Multipart) -> Result<String, MdpError> {
+    let frame = multipart.pop_front().ok_or(MdpError::MissingFrames)?;
+
+    frame.as_str().map(String::from).ok_or(MdpError::Service)
+}
+
+fn with_header(protocol: &str, command: u8, mut multipart: Multipart) -> Multipart {
+    multipart.push_front(zmq::Message::from(&[command][..]));
+    multipart.push_front(zmq::Message::from(protocol));
+    multipart
+}
+
+impl ClientMessage {
+    /// Create a request for `service`
+    pub fn request(service: &str, body: Multipart) -> Self {
+        ClientMessage {
+            command: ClientCommand::Request,
+            service: service.to_owned(),
+            body,
+        }
+    }
+
+    pub fn from_multipart(mut multipart: Multipart) -> Result<Self, MdpError> {
+        let command = ClientCommand::from_byte(header(&mut multipart, CLIENT)?)?;
+        let service = service(&mut multipart)?;
+
+        Ok(ClientMessage {
+            command,
+            service,
+            body: multipart,
+        })
+    }
+
+    pub fn into_multipart(self) -> Multipart {
+        let mut multipart = self.body;
+        multipart.push_front(zmq::Message::from(self.service.as_str()));
+
+        with_header(CLIENT, self.command.as_byte(), multipart)
+    }
+}
+
+impl From<ClientMessage> for Multipart {
+    fn from(msg: ClientMessage) -> Self {
+        msg.into_multipart()
+    }
+}
+
+impl WorkerMessage {
+    pub fn from_multipart(mut multipart: Multipart) -> Result<Self, MdpError> {
+        let command = header(&mut multipart, WORKER)?;
+
+        let routed = |multipart| {
+            Envelope::from_multipart(multipart)
+                .map(Envelope::into_parts)
+                .map_err(MdpError::Envelope)
+        };
+
+        match command {
+            0x01 => Ok(WorkerMessage::Ready {
+                service: service(&mut multipart)?,
+            }),
+            0x02 => {
+                let (client, body) = routed(multipart)?;
+                Ok(WorkerMessage::Request { client, body })
+            }
+            0x03 => {
+                let (client, body) = routed(multipart)?;
+                Ok(WorkerMessage::Partial { client, body })
+            }
+            0x04 => {
+                let (client, body) = routed(multipart)?;
+                Ok(WorkerMessage::Final { client, body })
+            }
+            0x05 => Ok(WorkerMessage::Heartbeat),
+            0x06 => Ok(WorkerMessage::Disconnect),
+            byte => Err(MdpError::Command(byte)),
+        }
+    }
+
+    pub fn into_multipart(self) -> Multipart {
+        let (command, multipart) = match self {
+            WorkerMessage::Ready { service } => (0x01, zmq::Message::from(service.as_str()).into()),
+            WorkerMessage::Request { client, body } => {
+                (0x02, Envelope::new(client, body).into_multipart())
+            }
+            WorkerMessage::Partial { client, body } => {
+                (0x03, Envelope::new(client, body).into_multipart())
+            }
+            WorkerMessage::Final { client, body } => {
+                (0x04, Envelope::new(client, body).into_multipart())
+            }
+            WorkerMessage::Heartbeat => (0x05, Multipart::new()),
+            WorkerMessage::Disconnect => (0x06, Multipart::new()),
+        };
+
+        with_header(WORKER, command, multipart)
+    }
+}
+
+impl From<WorkerMessage> for Multipart {
+    fn from(msg: WorkerMessage) -> Self {
+        msg.into_multipart()
+    }
+}
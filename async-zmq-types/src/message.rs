@@ -19,16 +19,88 @@
 
 //! This module contains the Multipart type, which is a wrapper around a `VecDeque`. The Multipart
 //! type implements `From<zmq::Message>` for easy creation.
+//!
+//! It also contains the `Envelope` type, which splits a `Multipart` into the routing frames
+//! ROUTER sockets add and the body of the message.
+//!
+//! Frames can be built from `Bytes` or `Arc<[u8]>` without copying, and sent without giving up
+//! ownership of them when ZeroMQ isn't ready to take them.
+
+// failure's derive expands into impls nested inside of a const block
+#![allow(non_local_definitions)]
 
 use std::{
     collections::{
         vec_deque::{Drain, IntoIter, Iter, IterMut},
         VecDeque,
     },
+    fmt,
+    iter::FromIterator,
+    mem,
     ops::RangeBounds,
+    os::raw::c_void,
+    sync::Arc,
 };
 
-use zmq;
+use bytes::Bytes;
+use failure::Fail;
+
+// zmq::Message is a struct with a single zmq_msg_t field. With equal sizes, that field must sit
+// at offset zero, so a pointer to one is a pointer to the other.
+const _: () = assert!(mem::size_of::<zmq::Message>() == mem::size_of::<zmq_sys::zmq_msg_t>());
+
+/// Get the zmq_msg_t a message wraps
+///
+/// This is what zmq's own `msg_ptr` does, which the zmq crate doesn't export.
+pub(crate) fn msg_ptr(msg: &mut zmq::Message) -> *mut zmq_sys::zmq_msg_t {
+    msg as *mut zmq::Message as *mut zmq_sys::zmq_msg_t
+}
+
+unsafe extern "C" fn drop_shared<T>(_data: *mut c_void, hint: *mut c_void) {
+    drop(Box::from_raw(hint as *mut T));
+}
+
+/// Create a message that borrows its contents from `data` instead of copying them
+///
+/// The message keeps `data` alive until ZeroMQ is done with it, which may be after the message
+/// has been sent and dropped, and on one of ZeroMQ's I/O threads. Cloning a `Bytes` or an
+/// `Arc<[u8]>` for each message is enough to send the same buffer to many sockets.
+pub fn shared_message<T>(data: T) -> zmq::Message
+where
+    T: AsRef<[u8]> + Send + 'static,
+{
+    let len = data.as_ref().len();
+    if len == 0 {
+        return zmq::Message::new();
+    }
+
+    let owner = Box::into_raw(Box::new(data));
+
+    unsafe {
+        // The boxed owner doesn't move, so the slice it points to stays valid until drop_shared
+        let bytes = (*owner).as_ref().as_ptr();
+
+        let mut msg = zmq_sys::zmq_msg_t::default();
+        let rc = zmq_sys::zmq_msg_init_data(
+            &mut msg,
+            bytes as *mut c_void,
+            len,
+            drop_shared::<T> as *mut zmq_sys::zmq_free_fn,
+            owner as *mut c_void,
+        );
+
+        if rc == -1 {
+            drop(Box::from_raw(owner));
+            panic!("Failed to initialize message, {}", last_error());
+        }
+
+        mem::transmute::<zmq_sys::zmq_msg_t, zmq::Message>(msg)
+    }
+}
+
+fn last_error() -> zmq::Error {
+    zmq::Error::from_raw(unsafe { zmq_sys::zmq_errno() })
+}
 
 /// This type is used for receiving and sending messages in Multipart groups. An application could
 /// make using this easier by implementing traits as follows:
@@ -91,7 +163,7 @@ use zmq;
 ///     let multipart2: Multipart = envelope.into();
 /// }
 /// ```
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Multipart {
     inner: VecDeque<zmq::Message>,
 }
@@ -129,7 +201,7 @@ impl Multipart {
         self.inner.is_empty()
     }
 
-    pub fn iter(&self) -> Iter<zmq::Message> {
+    pub fn iter(&self) -> Iter<'_, zmq::Message> {
         self.inner.iter()
     }
 
@@ -137,23 +209,61 @@ impl Multipart {
         self.inner.len()
     }
 
-    pub fn iter_mut(&mut self) -> IterMut<zmq::Message> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, zmq::Message> {
         self.inner.iter_mut()
     }
 
-    pub fn drain<R>(&mut self, range: R) -> Drain<zmq::Message>
+    pub fn drain<R>(&mut self, range: R) -> Drain<'_, zmq::Message>
     where
         R: RangeBounds<usize>,
     {
         self.inner.drain(range)
     }
-}
 
-impl Default for Multipart {
-    fn default() -> Self {
-        Multipart {
-            inner: VecDeque::new(),
+    /// Add a frame to the back of the multipart without copying `data`
+    pub fn push_back_shared<T>(&mut self, data: T)
+    where
+        T: AsRef<[u8]> + Send + 'static,
+    {
+        self.inner.push_back(shared_message(data))
+    }
+
+    /// Add a frame to the front of the multipart without copying `data`
+    pub fn push_front_shared<T>(&mut self, data: T)
+    where
+        T: AsRef<[u8]> + Send + 'static,
+    {
+        self.inner.push_front(shared_message(data))
+    }
+
+    /// Send the first frame of the multipart, removing it only once ZeroMQ has accepted it
+    ///
+    /// `SNDMORE` is added to `flags` if more frames follow. If sending fails, including with
+    /// `EAGAIN` when `DONTWAIT` is set, the frame is left where it was so the send can be retried
+    /// without having copied it beforehand. Sending from an empty multipart does nothing.
+    pub fn send_front(&mut self, sock: &mut zmq::Socket, flags: i32) -> zmq::Result<()> {
+        let flags = if self.inner.len() > 1 {
+            flags | zmq::SNDMORE
+        } else {
+            flags
+        };
+
+        let msg = match self.inner.front_mut() {
+            Some(msg) => msg,
+            None => return Ok(()),
+        };
+
+        // zmq_msg_send only takes ownership of the message's contents if it succeeds, so on
+        // failure the frame is still intact
+        let rc = unsafe { zmq_sys::zmq_msg_send(msg_ptr(msg), sock.as_mut_ptr(), flags) };
+
+        if rc == -1 {
+            return Err(last_error());
         }
+
+        // What's left behind is an empty message
+        self.inner.pop_front();
+        Ok(())
     }
 }
 
@@ -171,6 +281,29 @@ impl From<Vec<zmq::Message>> for Multipart {
     }
 }
 
+impl From<Bytes> for Multipart {
+    fn from(data: Bytes) -> Self {
+        shared_message(data).into()
+    }
+}
+
+impl From<Arc<[u8]>> for Multipart {
+    fn from(data: Arc<[u8]>) -> Self {
+        shared_message(data).into()
+    }
+}
+
+impl FromIterator<zmq::Message> for Multipart {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = zmq::Message>,
+    {
+        Multipart {
+            inner: iter.into_iter().collect(),
+        }
+    }
+}
+
 impl<'a> IntoIterator for &'a Multipart {
     type Item = &'a zmq::Message;
     type IntoIter = Iter<'a, zmq::Message>;
@@ -197,3 +330,181 @@ impl<'a> IntoIterator for &'a mut Multipart {
         self.iter_mut()
     }
 }
+
+/// The reasons a `Multipart` can fail to parse as an `Envelope`
+#[derive(Clone, Debug, Eq, Fail, PartialEq)]
+pub enum EnvelopeError {
+    #[fail(display = "Envelope has no routing id")]
+    /// The multipart was empty, or started with an empty frame where a routing id was expected
+    MissingPeerId,
+
+    #[fail(display = "Envelope has no empty delimiter frame")]
+    /// The routing frames were not followed by an empty delimiter frame
+    MissingDelimiter,
+}
+
+/// The identity a ROUTER socket assigns to one of its peers
+///
+/// ROUTER sockets prefix every message they receive with the identity of the peer that sent it,
+/// and use the first frame of every message they send to pick the peer to send it to.
+#[derive(Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct PeerId(Vec<u8>);
+
+impl PeerId {
+    pub fn new(id: Vec<u8>) -> Self {
+        PeerId(id)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl fmt::Debug for PeerId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PeerId(")?;
+        for byte in &self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        write!(f, ")")
+    }
+}
+
+impl From<Vec<u8>> for PeerId {
+    fn from(id: Vec<u8>) -> Self {
+        PeerId(id)
+    }
+}
+
+impl<'a> From<&'a [u8]> for PeerId {
+    fn from(id: &'a [u8]) -> Self {
+        PeerId(id.to_vec())
+    }
+}
+
+impl<'a> From<&'a str> for PeerId {
+    fn from(id: &'a str) -> Self {
+        PeerId(id.as_bytes().to_vec())
+    }
+}
+
+impl From<PeerId> for zmq::Message {
+    fn from(id: PeerId) -> Self {
+        id.0.into()
+    }
+}
+
+/// A `Multipart` split according to ZeroMQ's REQ/REP envelope rules
+///
+/// An envelope is a stack of routing ids, followed by an empty delimiter frame, followed by the
+/// body of the message. Every ROUTER a message passes through pushes the id of the peer it came
+/// from onto the stack, so the first route is the peer closest to the socket that received it.
+///
+/// ```rust
+/// use async_zmq_types::{Envelope, Multipart, PeerId};
+///
+/// let multipart: Multipart = vec![
+///     zmq::Message::from("client"),
+///     zmq::Message::from(""),
+///     zmq::Message::from("Hello"),
+/// ]
+/// .into();
+///
+/// let envelope = Envelope::from_multipart(multipart).unwrap();
+/// assert_eq!(envelope.peer(), Some(&PeerId::from("client")));
+/// assert_eq!(envelope.body().get(0).and_then(|msg| msg.as_str()), Some("Hello"));
+///
+/// // Replies keep the routes, and swap out the body
+/// let reply = envelope.reply(zmq::Message::from("World").into()).into_multipart();
+/// assert_eq!(reply.len(), 3);
+/// ```
+#[derive(Debug, Default)]
+pub struct Envelope {
+    routes: Vec<PeerId>,
+    body: Multipart,
+}
+
+impl Envelope {
+    /// Create an envelope addressed through the given routes
+    pub fn new(routes: Vec<PeerId>, body: Multipart) -> Self {
+        Envelope { routes, body }
+    }
+
+    /// Split a multipart into its routing ids and body
+    ///
+    /// Every frame before the first empty frame is a routing id. An envelope with no routing ids
+    /// is valid, it's what a REP socket sees after stripping its peer's id, and what a DEALER
+    /// must send to talk to a REP socket. Multiparts without an empty delimiter frame are
+    /// rejected.
+    pub fn from_multipart(mut multipart: Multipart) -> Result<Self, EnvelopeError> {
+        let mut routes = Vec::new();
+
+        loop {
+            match multipart.pop_front() {
+                Some(ref frame) if frame.is_empty() => break,
+                Some(frame) => routes.push(PeerId::from(&frame[..])),
+                None => return Err(EnvelopeError::MissingDelimiter),
+            }
+        }
+
+        Ok(Envelope {
+            routes,
+            body: multipart,
+        })
+    }
+
+    /// Reassemble the routing ids, delimiter, and body into a single multipart
+    pub fn into_multipart(self) -> Multipart {
+        let mut multipart = self.body;
+        multipart.push_front(zmq::Message::new());
+
+        for route in self.routes.into_iter().rev() {
+            multipart.push_front(route.into());
+        }
+
+        multipart
+    }
+
+    /// Create an envelope with the same routes and a new body
+    pub fn reply(&self, body: Multipart) -> Self {
+        Envelope {
+            routes: self.routes.clone(),
+            body,
+        }
+    }
+
+    /// The peer closest to the socket that received this envelope
+    pub fn peer(&self) -> Option<&PeerId> {
+        self.routes.first()
+    }
+
+    pub fn routes(&self) -> &[PeerId] {
+        &self.routes
+    }
+
+    pub fn routes_mut(&mut self) -> &mut Vec<PeerId> {
+        &mut self.routes
+    }
+
+    pub fn body(&self) -> &Multipart {
+        &self.body
+    }
+
+    pub fn body_mut(&mut self) -> &mut Multipart {
+        &mut self.body
+    }
+
+    pub fn into_parts(self) -> (Vec<PeerId>, Multipart) {
+        (self.routes, self.body)
+    }
+}
+
+impl From<Envelope> for Multipart {
+    fn from(envelope: Envelope) -> Self {
+        envelope.into_multipart()
+    }
+}
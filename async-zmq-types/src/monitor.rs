@@ -0,0 +1,181 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains `SocketEvent`, the typed form of the events ZeroMQ's socket monitor emits.
+
+use std::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use crate::message::Multipart;
+
+static MONITOR_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+/// An event reported by a socket monitor
+///
+/// Each event carries the endpoint it happened on. Depending on the event, ZeroMQ also reports
+/// the file descriptor of the underlying connection, or the `errno` of the failure, which can be
+/// turned into a `zmq::Error` with `zmq::Error::from_raw`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SocketEvent {
+    /// A connection to a remote peer was established
+    Connected { endpoint: String, fd: i32 },
+    /// A connection attempt is in progress
+    ConnectDelayed { endpoint: String },
+    /// A connection attempt failed, and will be retried after `interval`
+    ConnectRetried {
+        endpoint: String,
+        interval: Duration,
+    },
+    /// The socket is bound and listening
+    Listening { endpoint: String, fd: i32 },
+    /// The socket could not bind to the endpoint
+    BindFailed { endpoint: String, errno: i32 },
+    /// A connection from a remote peer was accepted
+    Accepted { endpoint: String, fd: i32 },
+    /// A connection from a remote peer could not be accepted
+    AcceptFailed { endpoint: String, errno: i32 },
+    /// A connection was closed
+    Closed { endpoint: String, fd: i32 },
+    /// A connection could not be closed cleanly
+    CloseFailed { endpoint: String, errno: i32 },
+    /// A peer disconnected unexpectedly
+    Disconnected { endpoint: String, fd: i32 },
+    /// The monitor was stopped, no more events will be reported
+    MonitorStopped { endpoint: String },
+    /// The ZMTP handshake failed for a reason ZeroMQ doesn't detail
+    HandshakeFailedNoDetail { endpoint: String, errno: i32 },
+    /// The ZMTP handshake succeeded, including any security mechanism
+    HandshakeSucceeded { endpoint: String },
+    /// The ZMTP handshake failed because of a protocol error, `code` is one of ZeroMQ's
+    /// `ZMQ_PROTOCOL_ERROR_*` values
+    HandshakeFailedProtocol { endpoint: String, code: u32 },
+    /// The ZMTP handshake failed because the peer was not authenticated, `status` is the ZAP
+    /// status code
+    HandshakeFailedAuth { endpoint: String, status: u32 },
+    /// An event this version of Async ZMQ Types doesn't know about
+    Unknown {
+        endpoint: String,
+        event: u16,
+        value: u32,
+    },
+}
+
+impl SocketEvent {
+    /// Parse an event from the multipart a socket monitor sends
+    ///
+    /// The first frame holds the event number and its value, and the second holds the endpoint.
+    /// Returns `None` if the multipart isn't shaped like a monitor event.
+    pub fn from_multipart(multipart: &Multipart) -> Option<Self> {
+        let header = multipart.get(0)?;
+        if header.len() != 6 {
+            return None;
+        }
+
+        let event = u16::from_ne_bytes([header[0], header[1]]);
+        let value = u32::from_ne_bytes([header[2], header[3], header[4], header[5]]);
+        let endpoint = String::from_utf8_lossy(multipart.get(1)?).into_owned();
+
+        let fd = value as i32;
+        let errno = value as i32;
+
+        let event = match event {
+            0x0001 => SocketEvent::Connected { endpoint, fd },
+            0x0002 => SocketEvent::ConnectDelayed { endpoint },
+            0x0004 => SocketEvent::ConnectRetried {
+                endpoint,
+                interval: Duration::from_millis(u64::from(value)),
+            },
+            0x0008 => SocketEvent::Listening { endpoint, fd },
+            0x0010 => SocketEvent::BindFailed { endpoint, errno },
+            0x0020 => SocketEvent::Accepted { endpoint, fd },
+            0x0040 => SocketEvent::AcceptFailed { endpoint, errno },
+            0x0080 => SocketEvent::Closed { endpoint, fd },
+            0x0100 => SocketEvent::CloseFailed { endpoint, errno },
+            0x0200 => SocketEvent::Disconnected { endpoint, fd },
+            0x0400 => SocketEvent::MonitorStopped { endpoint },
+            0x0800 => SocketEvent::HandshakeFailedNoDetail { endpoint, errno },
+            0x1000 => SocketEvent::HandshakeSucceeded { endpoint },
+            0x2000 => SocketEvent::HandshakeFailedProtocol {
+                endpoint,
+                code: value,
+            },
+            0x4000 => SocketEvent::HandshakeFailedAuth {
+                endpoint,
+                status: value,
+            },
+            event => SocketEvent::Unknown {
+                endpoint,
+                event,
+                value,
+            },
+        };
+
+        Some(event)
+    }
+
+    /// The endpoint the event happened on
+    pub fn endpoint(&self) -> &str {
+        match *self {
+            SocketEvent::Connected { ref endpoint, .. }
+            | SocketEvent::ConnectDelayed { ref endpoint }
+            | SocketEvent::ConnectRetried { ref endpoint, .. }
+            | SocketEvent::Listening { ref endpoint, .. }
+            | SocketEvent::BindFailed { ref endpoint, .. }
+            | SocketEvent::Accepted { ref endpoint, .. }
+            | SocketEvent::AcceptFailed { ref endpoint, .. }
+            | SocketEvent::Closed { ref endpoint, .. }
+            | SocketEvent::CloseFailed { ref endpoint, .. }
+            | SocketEvent::Disconnected { ref endpoint, .. }
+            | SocketEvent::MonitorStopped { ref endpoint }
+            | SocketEvent::HandshakeFailedNoDetail { ref endpoint, .. }
+            | SocketEvent::HandshakeSucceeded { ref endpoint }
+            | SocketEvent::HandshakeFailedProtocol { ref endpoint, .. }
+            | SocketEvent::HandshakeFailedAuth { ref endpoint, .. }
+            | SocketEvent::Unknown { ref endpoint, .. } => endpoint,
+        }
+    }
+}
+
+/// Start monitoring `sock`, returning the PAIR socket its events will be received on
+///
+/// The monitor publishes on a new inproc endpoint, so the PAIR socket is created from `ctx`,
+/// which must be the context `sock` was created with. Every event is monitored.
+pub fn monitor_socket(ctx: &zmq::Context, sock: &zmq::Socket) -> zmq::Result<zmq::Socket> {
+    let endpoint = monitor_endpoint();
+    sock.monitor(&endpoint, zmq::SocketEvent::ALL as i32)?;
+
+    connect_monitor(ctx, &endpoint)
+}
+
+/// Create a unique inproc endpoint for a socket monitor to publish on
+pub fn monitor_endpoint() -> String {
+    let id = MONITOR_COUNT.fetch_add(1, Ordering::Relaxed);
+
+    format!("inproc://async-zmq-monitor-{}", id)
+}
+
+/// Connect a PAIR socket to a socket monitor's endpoint
+pub fn connect_monitor(ctx: &zmq::Context, endpoint: &str) -> zmq::Result<zmq::Socket> {
+    let pair = ctx.socket(zmq::PAIR)?;
+    pair.connect(endpoint)?;
+
+    Ok(pair)
+}
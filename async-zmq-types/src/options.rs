@@ -0,0 +1,592 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains `SocketOptions`, the typed ZeroMQ socket options shared by every builder.
+
+use std::{fmt, os::raw::c_int, sync::Arc, time::Duration};
+
+use serde::Deserialize;
+
+use crate::{curve::CurveRole, raw::set_option};
+
+/// A function for configuring options that `SocketOptions` doesn't cover
+pub type Customize = Arc<dyn Fn(&zmq::Socket) -> zmq::Result<()> + Send + Sync>;
+
+/// The side of a PLAIN connection a socket takes
+///
+/// PLAIN sends the password in clear text, so it should only be used on trusted networks.
+#[derive(Clone)]
+pub enum PlainRole {
+    /// Ask connecting clients for a username and password, checked by the ZAP handler
+    Server,
+    /// Connect with the given username and password
+    Client { username: String, password: String },
+}
+
+impl PlainRole {
+    /// Configure the socket for this role
+    pub fn apply(&self, sock: &zmq::Socket) -> zmq::Result<()> {
+        match *self {
+            PlainRole::Server => sock.set_plain_server(true),
+            PlainRole::Client {
+                ref username,
+                ref password,
+            } => {
+                sock.set_plain_username(Some(username))?;
+                sock.set_plain_password(Some(password))
+            }
+        }
+    }
+}
+
+impl fmt::Debug for PlainRole {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PlainRole::Server => write!(f, "Server"),
+            PlainRole::Client { ref username, .. } => f
+                .debug_struct("Client")
+                .field("username", username)
+                .finish(),
+        }
+    }
+}
+
+/// The socket options that will be applied to a socket when it is built
+///
+/// Any option left as `None` keeps ZeroMQ's default. Options are applied before the socket binds
+/// or connects, since some of them (such as IMMEDIATE and CONFLATE) only affect connections made
+/// after they are set.
+///
+/// `SocketOptions` can be deserialized from a map keyed by the option names below, using ZeroMQ's
+/// own units (milliseconds for intervals, seconds for the TCP keepalive timers). The identity and
+/// `customize` function can't be set this way; see `SocketConfig` for the former.
+///
+/// `outbound_buffer` and `inbound_buffer` aren't ZeroMQ options. They size the buffers a backend
+/// keeps outside of ZeroMQ, and are ignored by backends that don't have any. `ipc_permissions`
+/// isn't one either, it's applied to the files of ipc endpoints when the socket is bound.
+///
+/// With the `draft` feature, the draft sockets' options are here too. `groups` are the groups a
+/// DISH socket joins once it's built.
+#[derive(Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SocketOptions {
+    #[serde(skip)]
+    pub identity: Option<Vec<u8>>,
+    pub sndhwm: Option<i32>,
+    pub rcvhwm: Option<i32>,
+    pub sndbuf: Option<i32>,
+    pub rcvbuf: Option<i32>,
+    pub linger: Option<i32>,
+    pub reconnect_ivl: Option<i32>,
+    pub reconnect_ivl_max: Option<i32>,
+    pub connect_timeout: Option<i32>,
+    pub handshake_ivl: Option<i32>,
+    pub heartbeat_ivl: Option<i32>,
+    pub heartbeat_ttl: Option<i32>,
+    pub heartbeat_timeout: Option<i32>,
+    pub tcp_keepalive: Option<i32>,
+    pub tcp_keepalive_cnt: Option<i32>,
+    pub tcp_keepalive_idle: Option<i32>,
+    pub tcp_keepalive_intvl: Option<i32>,
+    pub backlog: Option<i32>,
+    pub maxmsgsize: Option<i64>,
+    pub immediate: Option<bool>,
+    pub ipv6: Option<bool>,
+    pub conflate: Option<bool>,
+    pub router_mandatory: Option<bool>,
+    pub xpub_verbose: Option<bool>,
+    pub xpub_manual: Option<bool>,
+    pub xpub_welcome_msg: Option<Vec<u8>>,
+    pub zap_domain: Option<String>,
+    pub outbound_buffer: Option<usize>,
+    pub inbound_buffer: Option<usize>,
+    pub ipc_permissions: Option<u32>,
+    #[cfg(feature = "draft")]
+    pub hello_msg: Option<Vec<u8>>,
+    #[cfg(feature = "draft")]
+    pub disconnect_msg: Option<Vec<u8>>,
+    #[cfg(feature = "draft")]
+    pub hiccup_msg: Option<Vec<u8>>,
+    #[cfg(feature = "draft")]
+    pub multicast_loop: Option<bool>,
+    #[cfg(feature = "draft")]
+    pub groups: Vec<String>,
+    #[serde(skip)]
+    pub plain: Option<PlainRole>,
+    #[serde(skip)]
+    pub curve: Option<CurveRole>,
+    #[serde(skip)]
+    pub customize: Option<Customize>,
+}
+
+impl SocketOptions {
+    /// Apply all the configured options to the given socket
+    ///
+    /// The `customize` function, if any, runs last so it can override anything set here.
+    pub fn apply(&self, sock: &zmq::Socket) -> zmq::Result<()> {
+        if let Some(ref identity) = self.identity {
+            sock.set_identity(identity)?;
+        }
+        if let Some(hwm) = self.sndhwm {
+            sock.set_sndhwm(hwm)?;
+        }
+        if let Some(hwm) = self.rcvhwm {
+            sock.set_rcvhwm(hwm)?;
+        }
+        if let Some(size) = self.sndbuf {
+            sock.set_sndbuf(size)?;
+        }
+        if let Some(size) = self.rcvbuf {
+            sock.set_rcvbuf(size)?;
+        }
+        if let Some(linger) = self.linger {
+            sock.set_linger(linger)?;
+        }
+        if let Some(ivl) = self.reconnect_ivl {
+            sock.set_reconnect_ivl(ivl)?;
+        }
+        if let Some(ivl) = self.reconnect_ivl_max {
+            sock.set_reconnect_ivl_max(ivl)?;
+        }
+        if let Some(timeout) = self.connect_timeout {
+            sock.set_connect_timeout(timeout)?;
+        }
+        if let Some(ivl) = self.handshake_ivl {
+            sock.set_handshake_ivl(ivl)?;
+        }
+        if let Some(ivl) = self.heartbeat_ivl {
+            sock.set_heartbeat_ivl(ivl)?;
+        }
+        if let Some(ttl) = self.heartbeat_ttl {
+            sock.set_heartbeat_ttl(ttl)?;
+        }
+        if let Some(timeout) = self.heartbeat_timeout {
+            sock.set_heartbeat_timeout(timeout)?;
+        }
+        if let Some(keepalive) = self.tcp_keepalive {
+            sock.set_tcp_keepalive(keepalive)?;
+        }
+        if let Some(cnt) = self.tcp_keepalive_cnt {
+            sock.set_tcp_keepalive_cnt(cnt)?;
+        }
+        if let Some(idle) = self.tcp_keepalive_idle {
+            sock.set_tcp_keepalive_idle(idle)?;
+        }
+        if let Some(intvl) = self.tcp_keepalive_intvl {
+            sock.set_tcp_keepalive_intvl(intvl)?;
+        }
+        if let Some(backlog) = self.backlog {
+            sock.set_backlog(backlog)?;
+        }
+        if let Some(size) = self.maxmsgsize {
+            sock.set_maxmsgsize(size)?;
+        }
+        if let Some(immediate) = self.immediate {
+            sock.set_immediate(immediate)?;
+        }
+        if let Some(ipv6) = self.ipv6 {
+            sock.set_ipv6(ipv6)?;
+        }
+        if let Some(conflate) = self.conflate {
+            sock.set_conflate(conflate)?;
+        }
+        if let Some(mandatory) = self.router_mandatory {
+            sock.set_router_mandatory(mandatory)?;
+        }
+        if let Some(verbose) = self.xpub_verbose {
+            sock.set_xpub_verbose(verbose)?;
+        }
+        if let Some(manual) = self.xpub_manual {
+            let manual: c_int = if manual { 1 } else { 0 };
+            set_option(sock, ZMQ_XPUB_MANUAL, &manual.to_ne_bytes())?;
+        }
+        if let Some(ref welcome) = self.xpub_welcome_msg {
+            set_option(sock, ZMQ_XPUB_WELCOME_MSG, welcome)?;
+        }
+        if let Some(ref domain) = self.zap_domain {
+            sock.set_zap_domain(domain)?;
+        }
+        #[cfg(feature = "draft")]
+        self.apply_draft(sock)?;
+        if let Some(ref plain) = self.plain {
+            plain.apply(sock)?;
+        }
+        if let Some(ref curve) = self.curve {
+            curve.apply(sock)?;
+        }
+        if let Some(ref customize) = self.customize {
+            customize(sock)?;
+        }
+
+        Ok(())
+    }
+
+    #[cfg(feature = "draft")]
+    fn apply_draft(&self, sock: &zmq::Socket) -> zmq::Result<()> {
+        if let Some(ref hello) = self.hello_msg {
+            set_option(sock, ZMQ_HELLO_MSG, hello)?;
+        }
+        if let Some(ref disconnect) = self.disconnect_msg {
+            set_option(sock, ZMQ_DISCONNECT_MSG, disconnect)?;
+        }
+        if let Some(ref hiccup) = self.hiccup_msg {
+            set_option(sock, ZMQ_HICCUP_MSG, hiccup)?;
+        }
+        if let Some(multicast_loop) = self.multicast_loop {
+            let multicast_loop: c_int = if multicast_loop { 1 } else { 0 };
+            set_option(sock, ZMQ_MULTICAST_LOOP, &multicast_loop.to_ne_bytes())?;
+        }
+        for group in &self.groups {
+            crate::raw::join(sock, group)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for SocketOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut debug = f.debug_struct("SocketOptions");
+        debug
+            .field("identity", &self.identity)
+            .field("sndhwm", &self.sndhwm)
+            .field("rcvhwm", &self.rcvhwm)
+            .field("sndbuf", &self.sndbuf)
+            .field("rcvbuf", &self.rcvbuf)
+            .field("linger", &self.linger)
+            .field("reconnect_ivl", &self.reconnect_ivl)
+            .field("reconnect_ivl_max", &self.reconnect_ivl_max)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("handshake_ivl", &self.handshake_ivl)
+            .field("heartbeat_ivl", &self.heartbeat_ivl)
+            .field("heartbeat_ttl", &self.heartbeat_ttl)
+            .field("heartbeat_timeout", &self.heartbeat_timeout)
+            .field("tcp_keepalive", &self.tcp_keepalive)
+            .field("tcp_keepalive_cnt", &self.tcp_keepalive_cnt)
+            .field("tcp_keepalive_idle", &self.tcp_keepalive_idle)
+            .field("tcp_keepalive_intvl", &self.tcp_keepalive_intvl)
+            .field("backlog", &self.backlog)
+            .field("maxmsgsize", &self.maxmsgsize)
+            .field("immediate", &self.immediate)
+            .field("ipv6", &self.ipv6)
+            .field("conflate", &self.conflate)
+            .field("router_mandatory", &self.router_mandatory)
+            .field("xpub_verbose", &self.xpub_verbose)
+            .field("xpub_manual", &self.xpub_manual)
+            .field("xpub_welcome_msg", &self.xpub_welcome_msg)
+            .field("zap_domain", &self.zap_domain)
+            .field("outbound_buffer", &self.outbound_buffer)
+            .field("inbound_buffer", &self.inbound_buffer)
+            .field(
+                "ipc_permissions",
+                &self.ipc_permissions.map(|mode| format!("{:o}", mode)),
+            )
+            .field("plain", &self.plain)
+            .field("curve", &self.curve);
+        #[cfg(feature = "draft")]
+        debug
+            .field("hello_msg", &self.hello_msg)
+            .field("disconnect_msg", &self.disconnect_msg)
+            .field("hiccup_msg", &self.hiccup_msg)
+            .field("multicast_loop", &self.multicast_loop)
+            .field("groups", &self.groups);
+        debug
+            .field(
+                "customize",
+                &self.customize.as_ref().map(|_| "Fn(&zmq::Socket)"),
+            )
+            .finish()
+    }
+}
+
+// Options the zmq crate doesn't have setters for, or only takes as strings
+const ZMQ_XPUB_MANUAL: c_int = 71;
+const ZMQ_XPUB_WELCOME_MSG: c_int = 72;
+#[cfg(feature = "draft")]
+const ZMQ_MULTICAST_LOOP: c_int = 96;
+#[cfg(feature = "draft")]
+const ZMQ_HELLO_MSG: c_int = 110;
+#[cfg(feature = "draft")]
+const ZMQ_DISCONNECT_MSG: c_int = 111;
+#[cfg(feature = "draft")]
+const ZMQ_HICCUP_MSG: c_int = 114;
+
+/// Convert a duration into the milliseconds ZeroMQ expects, saturating at `i32::MAX`
+pub(crate) fn millis(duration: Duration) -> i32 {
+    duration.as_millis().min(i32::MAX as u128) as i32
+}
+
+/// Convert a duration into the seconds ZeroMQ expects, saturating at `i32::MAX`
+pub(crate) fn secs(duration: Duration) -> i32 {
+    duration.as_secs().min(i32::MAX as u64) as i32
+}
+
+/// Generate the typed option setters for a builder type with an `options: SocketOptions` field
+macro_rules! socket_options {
+    () => {
+        /// Give the socket a custom identity (ZMQ_IDENTITY)
+        pub fn identity(mut self, identity: &[u8]) -> Self {
+            self.options.identity = Some(identity.to_vec());
+            self
+        }
+
+        /// Set the high water mark for outbound messages (ZMQ_SNDHWM)
+        pub fn sndhwm(mut self, hwm: i32) -> Self {
+            self.options.sndhwm = Some(hwm);
+            self
+        }
+
+        /// Set the high water mark for inbound messages (ZMQ_RCVHWM)
+        pub fn rcvhwm(mut self, hwm: i32) -> Self {
+            self.options.rcvhwm = Some(hwm);
+            self
+        }
+
+        /// Set the kernel transmit buffer size in bytes (ZMQ_SNDBUF)
+        pub fn sndbuf(mut self, size: i32) -> Self {
+            self.options.sndbuf = Some(size);
+            self
+        }
+
+        /// Set the kernel receive buffer size in bytes (ZMQ_RCVBUF)
+        pub fn rcvbuf(mut self, size: i32) -> Self {
+            self.options.rcvbuf = Some(size);
+            self
+        }
+
+        /// Set how long pending messages are kept after the socket is closed (ZMQ_LINGER)
+        ///
+        /// `None` keeps pending messages until they have been delivered.
+        pub fn linger(mut self, linger: Option<std::time::Duration>) -> Self {
+            self.options.linger = Some(linger.map(crate::options::millis).unwrap_or(-1));
+            self
+        }
+
+        /// Set the initial reconnection interval (ZMQ_RECONNECT_IVL)
+        pub fn reconnect_ivl(mut self, ivl: std::time::Duration) -> Self {
+            self.options.reconnect_ivl = Some(crate::options::millis(ivl));
+            self
+        }
+
+        /// Set the maximum reconnection interval (ZMQ_RECONNECT_IVL_MAX)
+        pub fn reconnect_ivl_max(mut self, ivl: std::time::Duration) -> Self {
+            self.options.reconnect_ivl_max = Some(crate::options::millis(ivl));
+            self
+        }
+
+        /// Set how long a connect may take before it is abandoned (ZMQ_CONNECT_TIMEOUT)
+        pub fn connect_timeout(mut self, timeout: std::time::Duration) -> Self {
+            self.options.connect_timeout = Some(crate::options::millis(timeout));
+            self
+        }
+
+        /// Set the maximum time allowed for the ZMTP handshake (ZMQ_HANDSHAKE_IVL)
+        pub fn handshake_ivl(mut self, ivl: std::time::Duration) -> Self {
+            self.options.handshake_ivl = Some(crate::options::millis(ivl));
+            self
+        }
+
+        /// Send a ZMTP heartbeat at the given interval (ZMQ_HEARTBEAT_IVL)
+        pub fn heartbeat_ivl(mut self, ivl: std::time::Duration) -> Self {
+            self.options.heartbeat_ivl = Some(crate::options::millis(ivl));
+            self
+        }
+
+        /// Ask the remote peer to time out after the given duration (ZMQ_HEARTBEAT_TTL)
+        pub fn heartbeat_ttl(mut self, ttl: std::time::Duration) -> Self {
+            self.options.heartbeat_ttl = Some(crate::options::millis(ttl));
+            self
+        }
+
+        /// Close the connection if no traffic arrives after a heartbeat (ZMQ_HEARTBEAT_TIMEOUT)
+        pub fn heartbeat_timeout(mut self, timeout: std::time::Duration) -> Self {
+            self.options.heartbeat_timeout = Some(crate::options::millis(timeout));
+            self
+        }
+
+        /// Enable or disable TCP keepalive (ZMQ_TCP_KEEPALIVE)
+        pub fn tcp_keepalive(mut self, keepalive: bool) -> Self {
+            self.options.tcp_keepalive = Some(if keepalive { 1 } else { 0 });
+            self
+        }
+
+        /// Set the number of unanswered keepalive probes before dropping (ZMQ_TCP_KEEPALIVE_CNT)
+        pub fn tcp_keepalive_cnt(mut self, cnt: i32) -> Self {
+            self.options.tcp_keepalive_cnt = Some(cnt);
+            self
+        }
+
+        /// Set how long a connection is idle before probing, in seconds (ZMQ_TCP_KEEPALIVE_IDLE)
+        pub fn tcp_keepalive_idle(mut self, idle: std::time::Duration) -> Self {
+            self.options.tcp_keepalive_idle = Some(crate::options::secs(idle));
+            self
+        }
+
+        /// Set the interval between keepalive probes, in seconds (ZMQ_TCP_KEEPALIVE_INTVL)
+        pub fn tcp_keepalive_intvl(mut self, intvl: std::time::Duration) -> Self {
+            self.options.tcp_keepalive_intvl = Some(crate::options::secs(intvl));
+            self
+        }
+
+        /// Set the maximum length of the queue of pending connections (ZMQ_BACKLOG)
+        pub fn backlog(mut self, backlog: i32) -> Self {
+            self.options.backlog = Some(backlog);
+            self
+        }
+
+        /// Drop peers that send messages larger than the given size in bytes (ZMQ_MAXMSGSIZE)
+        pub fn maxmsgsize(mut self, size: i64) -> Self {
+            self.options.maxmsgsize = Some(size);
+            self
+        }
+
+        /// Only queue messages to completed connections (ZMQ_IMMEDIATE)
+        pub fn immediate(mut self, immediate: bool) -> Self {
+            self.options.immediate = Some(immediate);
+            self
+        }
+
+        /// Enable IPv6 on the socket (ZMQ_IPV6)
+        pub fn ipv6(mut self, ipv6: bool) -> Self {
+            self.options.ipv6 = Some(ipv6);
+            self
+        }
+
+        /// Keep only the last message in the inbound and outbound queues (ZMQ_CONFLATE)
+        pub fn conflate(mut self, conflate: bool) -> Self {
+            self.options.conflate = Some(conflate);
+            self
+        }
+
+        /// Report unroutable messages as errors instead of dropping them (ZMQ_ROUTER_MANDATORY)
+        pub fn router_mandatory(mut self, mandatory: bool) -> Self {
+            self.options.router_mandatory = Some(mandatory);
+            self
+        }
+
+        /// Pass every subscription to an XPUB socket's reader, not only new topics (ZMQ_XPUB_VERBOSE)
+        pub fn xpub_verbose(mut self, verbose: bool) -> Self {
+            self.options.xpub_verbose = Some(verbose);
+            self
+        }
+
+        /// Leave it to an XPUB socket's reader to apply subscriptions (ZMQ_XPUB_MANUAL)
+        ///
+        /// Subscriptions are passed on as they arrive, and only take effect once the reader
+        /// subscribes the socket to them.
+        pub fn xpub_manual(mut self, manual: bool) -> Self {
+            self.options.xpub_manual = Some(manual);
+            self
+        }
+
+        /// Send a message to each subscriber that connects to an XPUB socket
+        /// (ZMQ_XPUB_WELCOME_MSG)
+        ///
+        /// Subscribers only receive the message if they subscribe to it.
+        pub fn xpub_welcome_msg(mut self, msg: &[u8]) -> Self {
+            self.options.xpub_welcome_msg = Some(msg.to_vec());
+            self
+        }
+
+        /// Set the domain the ZAP handler authenticates this socket's peers in (ZMQ_ZAP_DOMAIN)
+        ///
+        /// NULL sockets are only authenticated when a domain is set.
+        pub fn zap_domain(mut self, domain: &str) -> Self {
+            self.options.zap_domain = Some(domain.to_owned());
+            self
+        }
+
+        /// Set how many outgoing multiparts the backend may hold for the socket before sends wait
+        ///
+        /// This is only used by backends that buffer outside of ZeroMQ, such as futures-zmq.
+        pub fn outbound_buffer(mut self, size: usize) -> Self {
+            self.options.outbound_buffer = Some(size);
+            self
+        }
+
+        /// Set how many incoming multiparts the backend may read ahead of the socket's consumer
+        ///
+        /// This is only used by backends that buffer outside of ZeroMQ, such as futures-zmq.
+        pub fn inbound_buffer(mut self, size: usize) -> Self {
+            self.options.inbound_buffer = Some(size);
+            self
+        }
+
+        /// Set the permissions of the files the socket's ipc endpoints bind, such as `0o660`
+        ///
+        /// This only applies on unix, and not to abstract sockets, which have no file.
+        pub fn ipc_permissions(mut self, mode: u32) -> Self {
+            self.options.ipc_permissions = Some(mode);
+            self
+        }
+
+        /// Make the socket a PLAIN server (ZMQ_PLAIN_SERVER)
+        ///
+        /// Usernames and passwords are checked by the ZAP handler.
+        pub fn plain_server(mut self) -> Self {
+            self.options.plain = Some($crate::PlainRole::Server);
+            self
+        }
+
+        /// Make the socket a PLAIN client with the given credentials
+        /// (ZMQ_PLAIN_USERNAME, ZMQ_PLAIN_PASSWORD)
+        pub fn plain_client(mut self, username: &str, password: &str) -> Self {
+            self.options.plain = Some($crate::PlainRole::Client {
+                username: username.to_owned(),
+                password: password.to_owned(),
+            });
+            self
+        }
+
+        /// Make the socket a CURVE server with the given keypair (ZMQ_CURVE_SERVER)
+        ///
+        /// Only clients that know the keypair's public key can connect.
+        pub fn curve_server(mut self, keys: $crate::CurveKeyPair) -> Self {
+            self.options.curve = Some($crate::CurveRole::Server(keys));
+            self
+        }
+
+        /// Make the socket a CURVE client of the server with the given public key
+        /// (ZMQ_CURVE_SERVERKEY)
+        pub fn curve_client(
+            mut self,
+            keys: $crate::CurveKeyPair,
+            server: $crate::CurvePublicKey,
+        ) -> Self {
+            self.options.curve = Some($crate::CurveRole::Client { keys, server });
+            self
+        }
+
+        /// Provide a function for configuring the underlying ZeroMQ socket
+        ///
+        /// This runs after the typed options have been applied, and any error it returns is
+        /// reported when the socket is built.
+        ///
+        /// Note: Only the last call to customize will apply to a given socket.
+        ///
+        /// Documentation can be found [here](https://docs.rs/zmq/0.9.1/zmq/struct.Socket.html)
+        pub fn customize<F>(mut self, f: F) -> Self
+        where
+            F: Fn(&zmq::Socket) -> zmq::Result<()> + Send + Sync + 'static,
+        {
+            self.options.customize = Some(std::sync::Arc::new(f));
+            self
+        }
+    };
+}
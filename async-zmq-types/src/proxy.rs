@@ -0,0 +1,392 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains `Proxy`, an asynchronous take on `zmq_proxy_steerable`, which the backends
+//! build their `proxy` functions on.
+
+// failure's derive expands into impls nested inside of a const block
+#![allow(non_local_definitions)]
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use failure::Fail;
+use futures::{future::poll_fn, Sink, SinkExt, TryStream};
+use log::warn;
+
+use crate::Multipart;
+
+/* ----------------------------------TYPES----------------------------------- */
+
+/// The reasons a proxy can't be created
+#[derive(Clone, Debug, Fail, PartialEq)]
+pub enum ProxyError {
+    #[fail(
+        display = "A {:?} frontend and a {:?} backend can't forward in either direction",
+        frontend, backend
+    )]
+    /// Neither socket can receive what the other sends, so the proxy would never do anything
+    NoDirection {
+        frontend: zmq::SocketType,
+        backend: zmq::SocketType,
+    },
+}
+
+/// The commands a proxy's control socket accepts
+///
+/// These are the same commands `zmq_proxy_steerable` accepts, sent as a single frame.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ProxyCommand {
+    /// Stop forwarding multiparts until the proxy is resumed
+    Pause,
+    /// Start forwarding multiparts again
+    Resume,
+    /// Stop the proxy
+    Terminate,
+    /// Reply with the proxy's `ProxyStatistics`
+    Statistics,
+}
+
+/// Counts of the messages and bytes a proxy has passed through each of its sockets
+///
+/// Like `zmq_proxy_steerable`, each frame of a multipart counts as a message.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ProxyStatistics {
+    pub frontend_messages_in: u64,
+    pub frontend_bytes_in: u64,
+    pub frontend_messages_out: u64,
+    pub frontend_bytes_out: u64,
+    pub backend_messages_in: u64,
+    pub backend_bytes_in: u64,
+    pub backend_messages_out: u64,
+    pub backend_bytes_out: u64,
+}
+
+/// A proxy between a frontend and a backend
+///
+/// Multiparts are forwarded in each direction the sockets allow, so a PULL frontend and a PUSH
+/// backend only forward from the frontend, while ROUTER and DEALER, or XSUB and XPUB, forward
+/// both ways. Each forwarded multipart is also copied to the capture sink, if there is one.
+///
+/// The backends' `proxy` and `proxy_steerable` functions are the usual way to create one.
+pub struct Proxy<F, B, C, K> {
+    frontend: F,
+    backend: B,
+    forward: bool,
+    reverse: bool,
+    capture: Option<C>,
+    control: Option<K>,
+    reply_always: bool,
+}
+
+enum Event<E> {
+    Frontend(Option<Result<Multipart, E>>),
+    Backend(Option<Result<Multipart, E>>),
+    Control(Option<Result<Multipart, E>>),
+}
+
+/* ----------------------------------impls----------------------------------- */
+
+impl ProxyCommand {
+    /// Parse a command, returning `None` if the multipart isn't one
+    pub fn from_multipart(multipart: &Multipart) -> Option<Self> {
+        if multipart.len() != 1 {
+            return None;
+        }
+
+        match multipart.get(0).map(|msg| &msg[..]) {
+            Some(b"PAUSE") => Some(ProxyCommand::Pause),
+            Some(b"RESUME") => Some(ProxyCommand::Resume),
+            Some(b"TERMINATE") => Some(ProxyCommand::Terminate),
+            Some(b"STATISTICS") => Some(ProxyCommand::Statistics),
+            _ => None,
+        }
+    }
+
+    /// The command as it's sent to a control socket
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ProxyCommand::Pause => "PAUSE",
+            ProxyCommand::Resume => "RESUME",
+            ProxyCommand::Terminate => "TERMINATE",
+            ProxyCommand::Statistics => "STATISTICS",
+        }
+    }
+}
+
+impl From<ProxyCommand> for Multipart {
+    fn from(command: ProxyCommand) -> Self {
+        zmq::Message::from(command.as_str()).into()
+    }
+}
+
+impl ProxyStatistics {
+    /// Parse the reply to a STATISTICS command, eight frames each holding a native-endian u64
+    pub fn from_multipart(multipart: &Multipart) -> Option<Self> {
+        if multipart.len() != 8 {
+            return None;
+        }
+
+        let mut values = [0; 8];
+        for (value, msg) in values.iter_mut().zip(multipart.iter()) {
+            let mut bytes = [0; 8];
+            if msg.len() != bytes.len() {
+                return None;
+            }
+            bytes.copy_from_slice(msg);
+            *value = u64::from_ne_bytes(bytes);
+        }
+
+        Some(ProxyStatistics {
+            frontend_messages_in: values[0],
+            frontend_bytes_in: values[1],
+            frontend_messages_out: values[2],
+            frontend_bytes_out: values[3],
+            backend_messages_in: values[4],
+            backend_bytes_in: values[5],
+            backend_messages_out: values[6],
+            backend_bytes_out: values[7],
+        })
+    }
+
+    /// The reply to a STATISTICS command
+    pub fn to_multipart(&self) -> Multipart {
+        [
+            self.frontend_messages_in,
+            self.frontend_bytes_in,
+            self.frontend_messages_out,
+            self.frontend_bytes_out,
+            self.backend_messages_in,
+            self.backend_bytes_in,
+            self.backend_messages_out,
+            self.backend_bytes_out,
+        ]
+        .iter()
+        .map(|value| zmq::Message::from(&value.to_ne_bytes()[..]))
+        .collect()
+    }
+}
+
+fn count(multipart: &Multipart, messages: &mut u64, bytes: &mut u64) {
+    *messages += multipart.len() as u64;
+    *bytes += multipart.iter().map(|msg| msg.len() as u64).sum::<u64>();
+}
+
+fn copy(multipart: &Multipart) -> Multipart {
+    multipart
+        .iter()
+        .map(|msg| zmq::Message::from(&msg[..]))
+        .collect()
+}
+
+fn can_recv(kind: zmq::SocketType) -> bool {
+    !matches!(kind, zmq::PUB | zmq::PUSH)
+}
+
+fn can_send(kind: zmq::SocketType) -> bool {
+    !matches!(kind, zmq::SUB | zmq::PULL)
+}
+
+impl<F, B, C, K, E> Proxy<F, B, C, K>
+where
+    F: TryStream<Ok = Multipart, Error = E> + Sink<Multipart, Error = E> + Unpin,
+    B: TryStream<Ok = Multipart, Error = E> + Sink<Multipart, Error = E> + Unpin,
+    C: Sink<Multipart, Error = E> + Unpin,
+    K: TryStream<Ok = Multipart, Error = E> + Sink<Multipart, Error = E> + Unpin,
+{
+    /// Create a proxy between two sockets of the given kinds
+    ///
+    /// This fails if the sockets can't forward in either direction, such as two PULL sockets,
+    /// since a proxy between them would never do anything.
+    pub fn new(
+        frontend: F,
+        frontend_kind: zmq::SocketType,
+        backend: B,
+        backend_kind: zmq::SocketType,
+    ) -> Result<Self, ProxyError> {
+        let forward = can_recv(frontend_kind) && can_send(backend_kind);
+        let reverse = can_recv(backend_kind) && can_send(frontend_kind);
+
+        if !forward && !reverse {
+            return Err(ProxyError::NoDirection {
+                frontend: frontend_kind,
+                backend: backend_kind,
+            });
+        }
+
+        Ok(Proxy {
+            frontend,
+            backend,
+            forward,
+            reverse,
+            capture: None,
+            control: None,
+            reply_always: false,
+        })
+    }
+
+    /// Copy every forwarded multipart to `capture`
+    pub fn capture(mut self, capture: C) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// Take commands from `control`
+    ///
+    /// STATISTICS is answered with the proxy's statistics. If `kind` is REP, which has to reply
+    /// to every request, other commands are answered with an empty frame.
+    pub fn control(mut self, control: K, kind: zmq::SocketType) -> Self {
+        self.control = Some(control);
+        self.reply_always = kind == zmq::REP;
+        self
+    }
+
+    /// Forward multiparts until a socket fails or closes, or the proxy is terminated
+    pub async fn run(mut self) -> Result<ProxyStatistics, E> {
+        let mut stats = ProxyStatistics::default();
+        let mut paused = false;
+        let mut frontend_first = true;
+
+        loop {
+            let event = poll_fn(|cx| self.poll_event(cx, paused, &mut frontend_first)).await;
+
+            match event {
+                Event::Frontend(Some(multipart)) => {
+                    let multipart = multipart?;
+                    count(
+                        &multipart,
+                        &mut stats.frontend_messages_in,
+                        &mut stats.frontend_bytes_in,
+                    );
+
+                    if let Some(ref mut capture) = self.capture {
+                        capture.send(copy(&multipart)).await?;
+                    }
+
+                    count(
+                        &multipart,
+                        &mut stats.backend_messages_out,
+                        &mut stats.backend_bytes_out,
+                    );
+                    self.backend.send(multipart).await?;
+                }
+                Event::Backend(Some(multipart)) => {
+                    let multipart = multipart?;
+                    count(
+                        &multipart,
+                        &mut stats.backend_messages_in,
+                        &mut stats.backend_bytes_in,
+                    );
+
+                    if let Some(ref mut capture) = self.capture {
+                        capture.send(copy(&multipart)).await?;
+                    }
+
+                    count(
+                        &multipart,
+                        &mut stats.frontend_messages_out,
+                        &mut stats.frontend_bytes_out,
+                    );
+                    self.frontend.send(multipart).await?;
+                }
+                Event::Frontend(None) | Event::Backend(None) => return Ok(stats),
+                Event::Control(Some(multipart)) => {
+                    let command = ProxyCommand::from_multipart(&multipart?);
+
+                    let reply = match command {
+                        Some(ProxyCommand::Pause) => {
+                            paused = true;
+                            None
+                        }
+                        Some(ProxyCommand::Resume) => {
+                            paused = false;
+                            None
+                        }
+                        Some(ProxyCommand::Terminate) => None,
+                        Some(ProxyCommand::Statistics) => Some(stats.to_multipart()),
+                        None => {
+                            warn!("Invalid command sent to proxy");
+                            None
+                        }
+                    };
+
+                    let reply = match reply {
+                        Some(reply) => Some(reply),
+                        None if self.reply_always => Some(zmq::Message::new().into()),
+                        None => None,
+                    };
+
+                    if let (Some(reply), Some(control)) = (reply, self.control.as_mut()) {
+                        control.send(reply).await?;
+                    }
+
+                    if command == Some(ProxyCommand::Terminate) {
+                        return Ok(stats);
+                    }
+                }
+                Event::Control(None) => {
+                    // Nothing could resume the proxy once its control socket is gone
+                    self.control = None;
+                    paused = false;
+                }
+            }
+        }
+    }
+
+    fn poll_event(
+        &mut self,
+        cx: &mut Context,
+        paused: bool,
+        frontend_first: &mut bool,
+    ) -> Poll<Event<E>> {
+        if let Some(ref mut control) = self.control {
+            if let Poll::Ready(res) = Pin::new(control).try_poll_next(cx) {
+                return Poll::Ready(Event::Control(res));
+            }
+        }
+
+        if paused {
+            return Poll::Pending;
+        }
+
+        // Take turns, so a busy socket can't starve the other
+        let order = if *frontend_first {
+            [true, false]
+        } else {
+            [false, true]
+        };
+        *frontend_first = !*frontend_first;
+
+        for frontend in &order {
+            if *frontend && self.forward {
+                if let Poll::Ready(res) = Pin::new(&mut self.frontend).try_poll_next(cx) {
+                    return Poll::Ready(Event::Frontend(res));
+                }
+            } else if !*frontend && self.reverse {
+                if let Poll::Ready(res) = Pin::new(&mut self.backend).try_poll_next(cx) {
+                    return Poll::Ready(Event::Backend(res));
+                }
+            }
+        }
+
+        Poll::Pending
+    }
+}
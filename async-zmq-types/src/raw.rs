@@ -0,0 +1,279 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Calls into libzmq for the few things the zmq crate doesn't wrap.
+
+use std::{
+    ffi::{CStr, CString},
+    mem,
+    os::raw::{c_char, c_int, c_void},
+};
+
+// A PollItem is the zmq_pollitem_t that zmq_poll takes, which starts with the socket pointer
+const _: () = assert!(mem::size_of::<zmq::PollItem>() == mem::size_of::<zmq_sys::zmq_pollitem_t>());
+
+fn as_raw(sock: &zmq::Socket) -> *mut c_void {
+    let item = sock.as_poll_item(zmq::PollEvents::empty());
+
+    unsafe { (*(&item as *const zmq::PollItem as *const zmq_sys::zmq_pollitem_t)).socket }
+}
+
+fn check(rc: c_int) -> zmq::Result<()> {
+    if rc == -1 {
+        Err(zmq::Error::from_raw(unsafe { zmq_sys::zmq_errno() }))
+    } else {
+        Ok(())
+    }
+}
+
+/// Set an option by handing its raw value to `zmq_setsockopt`
+pub(crate) fn set_option(sock: &zmq::Socket, option: c_int, value: &[u8]) -> zmq::Result<()> {
+    check(unsafe {
+        zmq_sys::zmq_setsockopt(
+            as_raw(sock),
+            option,
+            value.as_ptr() as *const c_void,
+            value.len(),
+        )
+    })
+}
+
+/// Stop a socket accepting connections on an endpoint it was bound to
+///
+/// Endpoints bound with a wildcard port have to be unbound with the port ZeroMQ picked, which
+/// the socket's ZMQ_LAST_ENDPOINT holds right after binding.
+pub fn unbind(sock: &zmq::Socket, endpoint: &str) -> zmq::Result<()> {
+    let endpoint = CString::new(endpoint).map_err(|_| zmq::Error::EINVAL)?;
+
+    check(unsafe { zmq_sys::zmq_unbind(as_raw(sock), endpoint.as_ptr()) })
+}
+
+// zmq-sys doesn't declare zmq_curve_public, although every libzmq since 4.2 has it
+extern "C" {
+    fn zmq_curve_public(z85_public_key: *mut c_char, z85_secret_key: *const c_char) -> c_int;
+}
+
+/// Derive the Z85 public key of a Z85 CURVE secret key
+///
+/// This fails with `ENOTSUP` if ZeroMQ was built without CURVE support.
+pub(crate) fn curve_public(z85_secret: &str) -> zmq::Result<String> {
+    let secret = CString::new(z85_secret).map_err(|_| zmq::Error::EINVAL)?;
+    let mut public = [0 as c_char; 41];
+
+    check(unsafe { zmq_curve_public(public.as_mut_ptr(), secret.as_ptr()) })?;
+
+    let public = unsafe { CStr::from_ptr(public.as_ptr()) };
+    Ok(public.to_string_lossy().into_owned())
+}
+
+#[cfg(feature = "draft")]
+pub(crate) use self::draft::*;
+
+#[cfg(feature = "draft")]
+mod draft {
+    use std::{
+        ffi::{CStr, CString},
+        os::raw::{c_char, c_int, c_long, c_short, c_void},
+        sync::{Mutex, PoisonError},
+    };
+
+    use super::{as_raw, check};
+    use crate::message::msg_ptr;
+
+    // The draft API is only exported by libzmq builds with ZMQ_BUILD_DRAFT_API, and zmq-sys
+    // doesn't declare it
+    extern "C" {
+        fn zmq_msg_set_routing_id(msg: *mut zmq_sys::zmq_msg_t, routing_id: u32) -> c_int;
+        fn zmq_msg_routing_id(msg: *mut zmq_sys::zmq_msg_t) -> u32;
+        fn zmq_msg_set_group(msg: *mut zmq_sys::zmq_msg_t, group: *const c_char) -> c_int;
+        fn zmq_msg_group(msg: *mut zmq_sys::zmq_msg_t) -> *const c_char;
+        fn zmq_join(s: *mut c_void, group: *const c_char) -> c_int;
+        fn zmq_leave(s: *mut c_void, group: *const c_char) -> c_int;
+        fn zmq_connect_peer(s: *mut c_void, addr: *const c_char) -> u32;
+        fn zmq_poller_new() -> *mut c_void;
+        fn zmq_poller_destroy(poller: *mut *mut c_void) -> c_int;
+        fn zmq_poller_add(
+            poller: *mut c_void,
+            s: *mut c_void,
+            user_data: *mut c_void,
+            events: c_short,
+        ) -> c_int;
+        fn zmq_poller_wait(poller: *mut c_void, event: *mut PollerEvent, timeout: c_long) -> c_int;
+        #[cfg(unix)]
+        fn zmq_poller_fd(poller: *mut c_void, fd: *mut c_int) -> c_int;
+    }
+
+    #[repr(C)]
+    struct PollerEvent {
+        socket: *mut c_void,
+        #[cfg(unix)]
+        fd: c_int,
+        #[cfg(windows)]
+        fd: usize,
+        user_data: *mut c_void,
+        events: c_short,
+    }
+
+    // The context every draft socket is created in
+    //
+    // zmq::Context doesn't hand out its pointer, and zmq::Socket::from_raw doesn't keep a context
+    // alive, so draft sockets share a context of their own. It's never terminated, so closing a
+    // draft socket never waits on it, and zmq_ctx_term on any other context never waits on a
+    // draft socket.
+    #[derive(Clone, Copy)]
+    struct DraftContext(*mut c_void);
+
+    // libzmq contexts are thread-safe
+    unsafe impl Send for DraftContext {}
+
+    static CONTEXT: Mutex<Option<DraftContext>> = Mutex::new(None);
+
+    fn context() -> zmq::Result<*mut c_void> {
+        let mut context = CONTEXT.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(DraftContext(ctx)) = *context {
+            return Ok(ctx);
+        }
+
+        let ctx = unsafe { zmq_sys::zmq_ctx_new() };
+        if ctx.is_null() {
+            return Err(zmq::Error::from_raw(unsafe { zmq_sys::zmq_errno() }));
+        }
+
+        *context = Some(DraftContext(ctx));
+        Ok(ctx)
+    }
+
+    fn c_string(s: &str) -> zmq::Result<CString> {
+        CString::new(s).map_err(|_| zmq::Error::EINVAL)
+    }
+
+    /// Create a socket of a type the zmq crate doesn't know, in the draft sockets' context
+    pub(crate) fn socket(kind: c_int) -> zmq::Result<zmq::Socket> {
+        let sock = unsafe { zmq_sys::zmq_socket(context()?, kind) };
+
+        if sock.is_null() {
+            return Err(zmq::Error::from_raw(unsafe { zmq_sys::zmq_errno() }));
+        }
+
+        Ok(unsafe { zmq::Socket::from_raw(sock) })
+    }
+
+    pub(crate) fn routing_id(msg: &mut zmq::Message) -> u32 {
+        unsafe { zmq_msg_routing_id(msg_ptr(msg)) }
+    }
+
+    pub(crate) fn set_routing_id(msg: &mut zmq::Message, routing_id: u32) -> zmq::Result<()> {
+        check(unsafe { zmq_msg_set_routing_id(msg_ptr(msg), routing_id) })
+    }
+
+    pub(crate) fn group(msg: &mut zmq::Message) -> Option<String> {
+        let group = unsafe { zmq_msg_group(msg_ptr(msg)) };
+
+        if group.is_null() {
+            return None;
+        }
+
+        // Messages without a group have an empty one
+        let group = unsafe { CStr::from_ptr(group) };
+        if group.to_bytes().is_empty() {
+            return None;
+        }
+
+        Some(group.to_string_lossy().into_owned())
+    }
+
+    pub(crate) fn set_group(msg: &mut zmq::Message, group: &str) -> zmq::Result<()> {
+        let group = c_string(group)?;
+
+        check(unsafe { zmq_msg_set_group(msg_ptr(msg), group.as_ptr()) })
+    }
+
+    pub(crate) fn join(sock: &zmq::Socket, group: &str) -> zmq::Result<()> {
+        let group = c_string(group)?;
+
+        check(unsafe { zmq_join(as_raw(sock), group.as_ptr()) })
+    }
+
+    pub(crate) fn leave(sock: &zmq::Socket, group: &str) -> zmq::Result<()> {
+        let group = c_string(group)?;
+
+        check(unsafe { zmq_leave(as_raw(sock), group.as_ptr()) })
+    }
+
+    pub(crate) fn connect_peer(sock: &zmq::Socket, endpoint: &str) -> zmq::Result<u32> {
+        let endpoint = c_string(endpoint)?;
+
+        match unsafe { zmq_connect_peer(as_raw(sock), endpoint.as_ptr()) } {
+            0 => Err(zmq::Error::from_raw(unsafe { zmq_sys::zmq_errno() })),
+            routing_id => Ok(routing_id),
+        }
+    }
+
+    /// A zmq_poller holding a single socket
+    pub(crate) struct RawPoller(*mut c_void);
+
+    // The poller is only used through `&mut self` or from the thread that owns it
+    unsafe impl Send for RawPoller {}
+
+    impl RawPoller {
+        pub(crate) fn new(sock: &zmq::Socket) -> zmq::Result<Self> {
+            let poller = unsafe { zmq_poller_new() };
+
+            if poller.is_null() {
+                return Err(zmq::Error::from_raw(unsafe { zmq_sys::zmq_errno() }));
+            }
+
+            let poller = RawPoller(poller);
+            let events = (zmq::POLLIN | zmq::POLLOUT).bits();
+            check(unsafe { zmq_poller_add(poller.0, as_raw(sock), std::ptr::null_mut(), events) })?;
+
+            Ok(poller)
+        }
+
+        #[cfg(unix)]
+        pub(crate) fn fd(&self) -> zmq::Result<c_int> {
+            let mut fd = -1;
+            check(unsafe { zmq_poller_fd(self.0, &mut fd) })?;
+
+            Ok(fd)
+        }
+
+        /// Wait on the poller without blocking, which resets its file descriptor
+        pub(crate) fn drain(&self) {
+            let mut event = PollerEvent {
+                socket: std::ptr::null_mut(),
+                fd: Default::default(),
+                user_data: std::ptr::null_mut(),
+                events: 0,
+            };
+
+            // EAGAIN only means the socket has nothing to report
+            let _ = unsafe { zmq_poller_wait(self.0, &mut event, 0) };
+        }
+    }
+
+    impl Drop for RawPoller {
+        fn drop(&mut self) {
+            unsafe {
+                zmq_poller_destroy(&mut self.0);
+            }
+        }
+    }
+}
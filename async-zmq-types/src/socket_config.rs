@@ -0,0 +1,317 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains `SocketConfig`, a socket description that can be read from a config file.
+
+// failure's derive expands into impls nested inside of a const block
+#![allow(non_local_definitions)]
+
+use std::{fmt, sync::Arc};
+
+use failure::Fail;
+use serde::Deserialize;
+
+use crate::{
+    config::{PairConfig, SockConfig, SubConfig},
+    endpoint::{Endpoint, EndpointError},
+    options::SocketOptions,
+    IntoInnerSocket, UnPair,
+};
+
+/// The kinds of socket a `SocketConfig` can describe
+///
+/// In config files these are written in lowercase, such as `kind = "dealer"`.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SocketKind {
+    Dealer,
+    Pair,
+    Pub,
+    Pull,
+    Push,
+    Rep,
+    Req,
+    Router,
+    Sub,
+    Xpub,
+    Xsub,
+}
+
+impl From<SocketKind> for zmq::SocketType {
+    fn from(kind: SocketKind) -> Self {
+        match kind {
+            SocketKind::Dealer => zmq::DEALER,
+            SocketKind::Pair => zmq::PAIR,
+            SocketKind::Pub => zmq::PUB,
+            SocketKind::Pull => zmq::PULL,
+            SocketKind::Push => zmq::PUSH,
+            SocketKind::Rep => zmq::REP,
+            SocketKind::Req => zmq::REQ,
+            SocketKind::Router => zmq::ROUTER,
+            SocketKind::Sub => zmq::SUB,
+            SocketKind::Xpub => zmq::XPUB,
+            SocketKind::Xsub => zmq::XSUB,
+        }
+    }
+}
+
+impl fmt::Display for SocketKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", zmq::SocketType::from(*self))
+    }
+}
+
+/// The reasons a `SocketConfig` can be rejected
+#[derive(Clone, Debug, Fail)]
+pub enum ConfigError {
+    #[fail(
+        display = "Config describes a {} socket, but a {:?} was requested",
+        found, expected
+    )]
+    /// The config's kind doesn't match the socket type it is being built into
+    KindMismatch {
+        expected: zmq::SocketType,
+        found: SocketKind,
+    },
+
+    #[fail(display = "{} socket has no bind or connect endpoints", _0)]
+    /// The socket would never bind or connect to anything
+    NoEndpoints(SocketKind),
+
+    #[fail(display = "SUB without filter, use \"\" to subscribe to every message")]
+    /// A SUB socket must have at least one subscription
+    SubWithoutFilter,
+
+    #[fail(
+        display = "Subscriptions given for a {} socket, only SUB can subscribe",
+        _0
+    )]
+    /// Subscriptions were given for a socket that isn't SUB
+    UnexpectedSubscriptions(SocketKind),
+
+    #[fail(
+        display = "PAIR with {} endpoints, exactly one bind or connect is required",
+        _0
+    )]
+    /// A PAIR socket must bind or connect to exactly one endpoint
+    PairEndpoints(usize),
+
+    #[fail(display = "SUB sockets must be converted with `sub_config`")]
+    /// A SUB socket was converted into a `SockConfig`, which would drop its subscriptions
+    SubConfigRequired,
+
+    #[fail(display = "Invalid endpoint, {}", _0)]
+    /// A bind or connect endpoint was invalid
+    Endpoint(#[cause] EndpointError),
+
+    #[cfg(feature = "draft")]
+    #[fail(display = "Draft sockets can't be built from a config")]
+    /// A config was converted into a `SockConfig` for a draft socket type
+    DraftSocket,
+}
+
+/// A socket description that can be deserialized with serde
+///
+/// This allows a socket's topology and options to live in a config file rather than in code.
+/// Once loaded, the `SocketConfig` is converted into a `SockConfig`, `SubConfig`, or `PairConfig`,
+/// which can be built with the `Build` trait just like one created through `SocketBuilder`.
+///
+/// Every field except `kind` is optional. Options are given in ZeroMQ's units, see
+/// `SocketOptions` for the list.
+///
+/// ### Example, using the Rep wrapper type
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use async_zmq_types::SocketConfig;
+/// use tokio_zmq::{prelude::*, Rep};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), failure::Error> {
+/// let config: SocketConfig = toml::from_str(r#"
+///     kind = "rep"
+///     bind = ["inproc://socket-config-example"]
+///     identity = "replier"
+///
+///     [options]
+///     sndhwm = 1000
+///     linger = 0
+/// "#)?;
+///
+/// let context = Arc::new(zmq::Context::new());
+/// let rep: Rep = config.sock_config(context)?.build().await?;
+/// # let _ = rep;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ### Example, rejecting an invalid config
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use async_zmq_types::{ConfigError, SocketConfig};
+///
+/// let config: SocketConfig = toml::from_str(r#"
+///     kind = "sub"
+///     connect = ["tcp://localhost:5556"]
+/// "#).unwrap();
+///
+/// let context = Arc::new(zmq::Context::new());
+/// match config.sub_config(context) {
+///     Err(ConfigError::SubWithoutFilter) => (),
+///     other => panic!("Unexpected result: {:?}", other),
+/// }
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SocketConfig {
+    pub kind: SocketKind,
+    #[serde(default)]
+    pub bind: Vec<String>,
+    #[serde(default)]
+    pub connect: Vec<String>,
+    #[serde(default)]
+    pub identity: Option<String>,
+    #[serde(default)]
+    pub subscriptions: Vec<String>,
+    #[serde(default)]
+    pub options: SocketOptions,
+}
+
+impl SocketConfig {
+    /// Check that the config describes a socket that can be built
+    ///
+    /// This is called by each of the conversion methods, but can be used on its own to reject a
+    /// config file early.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let endpoints = self.bind.len() + self.connect.len();
+
+        if endpoints == 0 {
+            return Err(ConfigError::NoEndpoints(self.kind));
+        }
+
+        let check = |endpoint: &String, check: fn(&Endpoint) -> Result<(), EndpointError>| {
+            endpoint
+                .parse()
+                .and_then(|endpoint| check(&endpoint))
+                .map_err(ConfigError::Endpoint)
+        };
+
+        for endpoint in &self.bind {
+            check(endpoint, Endpoint::check_bind)?;
+        }
+        for endpoint in &self.connect {
+            check(endpoint, Endpoint::check_connect)?;
+        }
+
+        match self.kind {
+            SocketKind::Sub if self.subscriptions.is_empty() => Err(ConfigError::SubWithoutFilter),
+            SocketKind::Sub => Ok(()),
+            kind if !self.subscriptions.is_empty() => {
+                Err(ConfigError::UnexpectedSubscriptions(kind))
+            }
+            SocketKind::Pair if endpoints != 1 => Err(ConfigError::PairEndpoints(endpoints)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Convert the config into a `SockConfig` for the given wrapper type
+    ///
+    /// The config's kind must match the wrapper type. SUB and PAIR sockets have their own
+    /// conversions, `sub_config` and `pair_config`, and the draft socket types can't be
+    /// described by a config.
+    pub fn sock_config<T>(self, ctx: Arc<zmq::Context>) -> Result<SockConfig<T>, ConfigError>
+    where
+        T: IntoInnerSocket + UnPair,
+    {
+        #[cfg(feature = "draft")]
+        if T::is_draft() {
+            return Err(ConfigError::DraftSocket);
+        }
+        self.check_kind(T::kind())?;
+        if self.kind == SocketKind::Sub {
+            return Err(ConfigError::SubConfigRequired);
+        }
+        self.validate()?;
+
+        let options = self.options_with_identity();
+        Ok(SockConfig::from_parts(
+            ctx,
+            self.bind,
+            self.connect,
+            options,
+        ))
+    }
+
+    /// Convert the config into a `SubConfig`
+    pub fn sub_config(self, ctx: Arc<zmq::Context>) -> Result<SubConfig, ConfigError> {
+        self.check_kind(zmq::SUB)?;
+        self.validate()?;
+
+        let options = self.options_with_identity();
+        Ok(SubConfig {
+            ctx,
+            bind: self.bind,
+            connect: self.connect,
+            filter: self
+                .subscriptions
+                .into_iter()
+                .map(String::into_bytes)
+                .collect(),
+            options,
+        })
+    }
+
+    /// Convert the config into a `PairConfig`
+    pub fn pair_config(self, ctx: Arc<zmq::Context>) -> Result<PairConfig, ConfigError> {
+        self.check_kind(zmq::PAIR)?;
+        self.validate()?;
+
+        let options = self.options_with_identity();
+        let (addr, bind) = match (
+            self.bind.into_iter().next(),
+            self.connect.into_iter().next(),
+        ) {
+            (Some(addr), _) => (addr, true),
+            (None, Some(addr)) => (addr, false),
+            (None, None) => return Err(ConfigError::NoEndpoints(SocketKind::Pair)),
+        };
+
+        Ok(PairConfig::from_parts(ctx, addr, bind, options))
+    }
+
+    fn check_kind(&self, expected: zmq::SocketType) -> Result<(), ConfigError> {
+        if zmq::SocketType::from(self.kind) != expected {
+            return Err(ConfigError::KindMismatch {
+                expected,
+                found: self.kind,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn options_with_identity(&self) -> SocketOptions {
+        let mut options = self.options.clone();
+        if let Some(ref identity) = self.identity {
+            options.identity = Some(identity.as_bytes().to_vec());
+        }
+        options
+    }
+}
@@ -17,9 +17,13 @@
  * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::marker::PhantomData;
+use std::{
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
 
-use futures::{Async, Stream};
+use futures::{ready, Stream, TryStream};
 
 use crate::{ControlHandler, EndHandler, Multipart};
 
@@ -27,7 +31,7 @@ use crate::{ControlHandler, EndHandler, Multipart};
 pub struct EndingStream<E, S, F>
 where
     E: EndHandler,
-    S: Stream<Item = Multipart, Error = F>,
+    S: TryStream<Ok = Multipart, Error = F>,
 {
     stream: S,
     // To handle stopping
@@ -38,7 +42,7 @@ where
 impl<E, S, F> EndingStream<E, S, F>
 where
     E: EndHandler,
-    S: Stream<Item = Multipart, Error = F>,
+    S: TryStream<Ok = Multipart, Error = F>,
 {
     /// Wrap a stream with an EndHandler
     pub fn new(stream: S, end_handler: E) -> Self
@@ -55,29 +59,36 @@ where
 
 impl<E, S, F> Stream for EndingStream<E, S, F>
 where
-    E: EndHandler,
-    S: Stream<Item = Multipart, Error = F>,
+    E: EndHandler + Unpin,
+    S: TryStream<Ok = Multipart, Error = F> + Unpin,
 {
-    type Item = Multipart;
-    type Error = F;
-
-    fn poll(&mut self) -> Result<Async<Option<Multipart>>, F> {
-        let res = match self.stream.poll()? {
-            Async::Ready(Some(item)) => {
-                if self.end_handler.should_stop(&item) {
-                    Async::Ready(None)
+    type Item = Result<Multipart, F>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        let res = match ready!(Pin::new(&mut this.stream).try_poll_next(cx)?) {
+            Some(item) => {
+                if this.end_handler.should_stop(&item) {
+                    None
                 } else {
-                    Async::Ready(Some(item))
+                    Some(Ok(item))
                 }
             }
-            Async::Ready(None) => Async::Ready(None),
-            Async::NotReady => Async::NotReady,
+            None => None,
         };
 
-        Ok(res)
+        Poll::Ready(res)
     }
 }
 
+impl<E, S, F> Unpin for EndingStream<E, S, F>
+where
+    E: EndHandler + Unpin,
+    S: TryStream<Ok = Multipart, Error = F> + Unpin,
+{
+}
+
 /// `ControlledStream`s are used when you want a stream of multiparts, but you want to be able to
 /// turn it off.
 ///
@@ -87,8 +98,8 @@ where
 pub struct ControlledStream<H, S, T, F>
 where
     H: ControlHandler,
-    S: Stream<Item = Multipart, Error = F>,
-    T: Stream<Item = Multipart, Error = F>,
+    S: TryStream<Ok = Multipart, Error = F>,
+    T: TryStream<Ok = Multipart, Error = F>,
 {
     stream: T,
     control: S,
@@ -99,8 +110,8 @@ where
 impl<H, S, T, F> ControlledStream<H, S, T, F>
 where
     H: ControlHandler,
-    S: Stream<Item = Multipart, Error = F>,
-    T: Stream<Item = Multipart, Error = F>,
+    S: TryStream<Ok = Multipart, Error = F>,
+    T: TryStream<Ok = Multipart, Error = F>,
 {
     /// Create a new ControlledStream.
     ///
@@ -118,29 +129,38 @@ where
 
 impl<H, S, T, F> Stream for ControlledStream<H, S, T, F>
 where
-    H: ControlHandler,
-    S: Stream<Item = Multipart, Error = F>,
-    T: Stream<Item = Multipart, Error = F>,
+    H: ControlHandler + Unpin,
+    S: TryStream<Ok = Multipart, Error = F> + Unpin,
+    T: TryStream<Ok = Multipart, Error = F> + Unpin,
 {
-    type Item = Multipart;
-    type Error = F;
+    type Item = Result<Multipart, F>;
 
     /// Poll the control stream, if it isn't ready, poll the producing stream
     ///
     /// If the control stream is ready, but has ended, stop the producting stream.
     /// If the control stream is ready with a Multipart, use the `ControlHandler`
     /// to determine if the producting stream should be stopped.
-    fn poll(&mut self) -> Result<Async<Option<Multipart>>, F> {
-        let stop = match self.control.poll()? {
-            Async::NotReady => false,
-            Async::Ready(None) => true,
-            Async::Ready(Some(multipart)) => self.handler.should_stop(multipart),
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        let stop = match Pin::new(&mut this.control).try_poll_next(cx)? {
+            Poll::Pending => false,
+            Poll::Ready(None) => true,
+            Poll::Ready(Some(multipart)) => this.handler.should_stop(multipart),
         };
 
         if stop {
-            Ok(Async::Ready(None))
+            Poll::Ready(None)
         } else {
-            self.stream.poll()
+            Pin::new(&mut this.stream).try_poll_next(cx)
         }
     }
 }
+
+impl<H, S, T, F> Unpin for ControlledStream<H, S, T, F>
+where
+    H: ControlHandler + Unpin,
+    S: TryStream<Ok = Multipart, Error = F> + Unpin,
+    T: TryStream<Ok = Multipart, Error = F> + Unpin,
+{
+}
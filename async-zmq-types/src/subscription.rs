@@ -0,0 +1,264 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains `Subscription`, the typed form of the messages SUB and XSUB sockets
+//! send upstream, along with adapters that read them from XPUB sockets and write them to XSUB
+//! sockets.
+//!
+//! On the wire, a subscription is a single frame holding `\x01` followed by the topic, and an
+//! unsubscription is the same with `\x00`.
+
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{Sink, Stream, TryStream};
+
+use crate::message::Multipart;
+
+/* ----------------------------------TYPES----------------------------------- */
+
+/// A change to a subscriber's subscriptions
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Subscription {
+    /// Start receiving messages whose first frame begins with the topic
+    Subscribe(Vec<u8>),
+    /// Stop receiving messages for a topic that was subscribed to
+    Unsubscribe(Vec<u8>),
+}
+
+/// An item from an XPUB socket
+#[derive(Debug)]
+pub enum XpubEvent {
+    /// A subscriber changed its subscriptions
+    Subscription(Subscription),
+    /// A subscriber sent something other than a subscription, which XSUB sockets can do
+    Message(Multipart),
+}
+
+/// An XPUB socket that reads subscriptions as `XpubEvent`s
+///
+/// Multiparts sent to the sink are published unchanged. The backends' `Xpub::subscription_stream`
+/// is the usual way to create one.
+pub struct XpubStream<S> {
+    inner: S,
+}
+
+/// An XSUB socket that takes `Subscription`s alongside the multiparts it sends upstream
+///
+/// The sink accepts both `Subscription`s and plain multiparts. Multiparts received from
+/// publishers are read from the stream unchanged. The backends' `Xsub::subscription_sink` is the
+/// usual way to create one.
+pub struct XsubSink<S> {
+    inner: S,
+}
+
+/* ----------------------------------impls----------------------------------- */
+
+impl Subscription {
+    /// The topic being subscribed to or unsubscribed from
+    pub fn topic(&self) -> &[u8] {
+        match *self {
+            Subscription::Subscribe(ref topic) | Subscription::Unsubscribe(ref topic) => topic,
+        }
+    }
+
+    /// Parse a subscription, returning `None` if the multipart isn't one
+    pub fn from_multipart(multipart: &Multipart) -> Option<Self> {
+        if multipart.len() != 1 {
+            return None;
+        }
+
+        let frame = multipart.get(0)?;
+
+        match frame.first() {
+            Some(1) => Some(Subscription::Subscribe(frame[1..].to_vec())),
+            Some(0) => Some(Subscription::Unsubscribe(frame[1..].to_vec())),
+            _ => None,
+        }
+    }
+
+    /// The subscription as it's sent upstream
+    pub fn to_multipart(&self) -> Multipart {
+        let flag = match *self {
+            Subscription::Subscribe(_) => 1,
+            Subscription::Unsubscribe(_) => 0,
+        };
+
+        let mut frame = Vec::with_capacity(self.topic().len() + 1);
+        frame.push(flag);
+        frame.extend_from_slice(self.topic());
+
+        zmq::Message::from(frame).into()
+    }
+}
+
+impl From<Subscription> for Multipart {
+    fn from(subscription: Subscription) -> Self {
+        subscription.to_multipart()
+    }
+}
+
+impl XpubEvent {
+    /// Tell a subscription apart from any other multipart an XPUB socket receives
+    pub fn from_multipart(multipart: Multipart) -> Self {
+        match Subscription::from_multipart(&multipart) {
+            Some(subscription) => XpubEvent::Subscription(subscription),
+            None => XpubEvent::Message(multipart),
+        }
+    }
+}
+
+impl<S> XpubStream<S> {
+    /// Read subscriptions from an XPUB socket's stream
+    pub fn new(inner: S) -> Self {
+        XpubStream { inner }
+    }
+
+    /// Borrow the wrapped socket
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Mutably borrow the wrapped socket
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Give back the wrapped socket
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S, E> Stream for XpubStream<S>
+where
+    S: TryStream<Ok = Multipart, Error = E> + Unpin,
+{
+    type Item = Result<XpubEvent, E>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner)
+            .try_poll_next(cx)
+            .map(|opt| opt.map(|res| res.map(XpubEvent::from_multipart)))
+    }
+}
+
+impl<S> Sink<Multipart> for XpubStream<S>
+where
+    S: Sink<Multipart> + Unpin,
+{
+    type Error = S::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, multipart: Multipart) -> Result<(), Self::Error> {
+        Pin::new(&mut self.inner).start_send(multipart)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+impl<S> XsubSink<S> {
+    /// Send subscriptions through an XSUB socket's sink
+    pub fn new(inner: S) -> Self {
+        XsubSink { inner }
+    }
+
+    /// Borrow the wrapped socket
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Mutably borrow the wrapped socket
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    /// Give back the wrapped socket
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S> Stream for XsubSink<S>
+where
+    S: Stream + Unpin,
+{
+    type Item = S::Item;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx)
+    }
+}
+
+impl<S> Sink<Subscription> for XsubSink<S>
+where
+    S: Sink<Multipart> + Unpin,
+{
+    type Error = S::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, subscription: Subscription) -> Result<(), Self::Error> {
+        Pin::new(&mut self.inner).start_send(subscription.to_multipart())
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
+
+impl<S> Sink<Multipart> for XsubSink<S>
+where
+    S: Sink<Multipart> + Unpin,
+{
+    type Error = S::Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, multipart: Multipart) -> Result<(), Self::Error> {
+        Pin::new(&mut self.inner).start_send(multipart)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
@@ -0,0 +1,523 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains the policies and protocol handling for a ZAP authenticator.
+//!
+//! ZeroMQ asks the handler bound to `inproc://zeromq.zap.01` whether each incoming connection
+//! should be accepted. The `Authenticator` here decides, and `ZapHandler` answers ZeroMQ's
+//! requests from a ROUTER socket, which each backend's `ZapHandler` binds.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt, fs,
+    io::{self, Read},
+    net::IpAddr,
+    path::Path,
+    sync::Arc,
+};
+
+use futures::{future, Sink, Stream, StreamExt, TryStreamExt};
+use log::warn;
+
+use crate::{
+    curve::CurvePublicKey,
+    message::{Envelope, Multipart},
+};
+
+/// The endpoint ZeroMQ sends authentication requests to
+pub const ZAP_ENDPOINT: &str = "inproc://zeromq.zap.01";
+
+const ZAP_VERSION: &str = "1.0";
+
+/// The security mechanism a peer is connecting with, along with its credentials
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Mechanism {
+    Null,
+    Plain { username: String, password: String },
+    Curve { public_key: CurvePublicKey },
+    Other(String),
+}
+
+/// A request from ZeroMQ to authenticate a connection
+#[derive(Clone, Debug)]
+pub struct ZapRequest {
+    pub request_id: Vec<u8>,
+    pub domain: String,
+    pub address: String,
+    pub routing_id: Vec<u8>,
+    pub mechanism: Mechanism,
+}
+
+impl ZapRequest {
+    /// The peer's IP address, if it connected over a network transport
+    pub fn ip(&self) -> Option<IpAddr> {
+        self.address.parse().ok()
+    }
+}
+
+/// The outcome of a `ZapPolicy` check
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ZapDecision {
+    /// Accept the connection as the given user id
+    Allow(String),
+    /// Reject the connection, no matter what other policies say
+    Deny(String),
+    /// The policy has nothing to say about this request
+    Pass,
+}
+
+/// A check applied to every authentication request in a domain
+///
+/// A request is rejected if any policy denies it. Otherwise, it is accepted if a policy allows
+/// it, or if it uses the NULL mechanism.
+pub trait ZapPolicy: Send + Sync {
+    fn check(&self, request: &ZapRequest) -> ZapDecision;
+}
+
+impl<F> ZapPolicy for F
+where
+    F: Fn(&ZapRequest) -> ZapDecision + Send + Sync,
+{
+    fn check(&self, request: &ZapRequest) -> ZapDecision {
+        (self)(request)
+    }
+}
+
+/// Allow or deny peers by IP address
+///
+/// If any addresses are allowed, every other address is denied.
+#[derive(Clone, Debug, Default)]
+pub struct IpFilter {
+    allow: HashSet<IpAddr>,
+    deny: HashSet<IpAddr>,
+}
+
+impl ZapPolicy for IpFilter {
+    fn check(&self, request: &ZapRequest) -> ZapDecision {
+        let ip = request.ip();
+
+        let denied = match ip {
+            Some(ref ip) => self.deny.contains(ip),
+            None => false,
+        };
+        let allowed = self.allow.is_empty() || ip.is_some_and(|ip| self.allow.contains(&ip));
+
+        if denied || !allowed {
+            ZapDecision::Deny(format!("Address {} is not allowed", request.address))
+        } else {
+            ZapDecision::Pass
+        }
+    }
+}
+
+/// Compare two byte strings in a time that only depends on their lengths
+///
+/// Stopping at the first difference would let a peer time how much of a guessed password was
+/// right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let diff = a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b));
+
+    // Keep the compiler from turning the fold back into an early exit
+    std::hint::black_box(diff) == 0
+}
+
+/// Check PLAIN usernames and passwords
+#[derive(Clone, Default)]
+pub struct PlainPasswords {
+    passwords: HashMap<String, String>,
+}
+
+impl ZapPolicy for PlainPasswords {
+    fn check(&self, request: &ZapRequest) -> ZapDecision {
+        match request.mechanism {
+            Mechanism::Plain {
+                ref username,
+                ref password,
+            } => match self.passwords.get(username) {
+                Some(expected) if constant_time_eq(expected.as_bytes(), password.as_bytes()) => {
+                    ZapDecision::Allow(username.clone())
+                }
+                _ => ZapDecision::Deny("Invalid username or password".to_owned()),
+            },
+            _ => ZapDecision::Pass,
+        }
+    }
+}
+
+impl fmt::Debug for PlainPasswords {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PlainPasswords")
+            .field("users", &self.passwords.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Check CURVE public keys against a list of known clients
+#[derive(Clone, Debug, Default)]
+pub struct CurveKeys {
+    allow_any: bool,
+    keys: HashSet<CurvePublicKey>,
+}
+
+impl ZapPolicy for CurveKeys {
+    fn check(&self, request: &ZapRequest) -> ZapDecision {
+        match request.mechanism {
+            Mechanism::Curve { ref public_key } => {
+                if self.allow_any || self.keys.contains(public_key) {
+                    ZapDecision::Allow(public_key.to_z85())
+                } else {
+                    ZapDecision::Deny("Unknown CURVE public key".to_owned())
+                }
+            }
+            _ => ZapDecision::Pass,
+        }
+    }
+}
+
+/// Read the public key from a certificate file
+///
+/// This understands the certificates written by CZMQ's `zcert`, which contain a line like
+/// `public-key = "<z85>"`, as well as files containing nothing but the Z85 key.
+fn read_public_key(contents: &str) -> Option<CurvePublicKey> {
+    contents.lines().find_map(|line| {
+        let line = line.trim();
+        let key = match line.find('=') {
+            Some(index) if line[..index].trim() == "public-key" => {
+                line[index + 1..].trim().trim_matches('"')
+            }
+            Some(_) => return None,
+            None => line,
+        };
+
+        CurvePublicKey::from_z85(key).ok()
+    })
+}
+
+/// The authentication rules for a single ZAP domain
+///
+/// Sockets choose their domain with the `zap_domain` builder option. PLAIN and CURVE sockets
+/// without a domain use the empty domain, `""`.
+///
+/// ### Example
+/// ```rust
+/// use async_zmq_types::zap::{Authenticator, ZapDomain};
+///
+/// let authenticator = Authenticator::new()
+///     .domain(
+///         "",
+///         ZapDomain::new()
+///             .allow("127.0.0.1".parse().unwrap())
+///             .plain("admin", "secret"),
+///     );
+/// # let _ = authenticator;
+/// ```
+#[derive(Clone, Default)]
+pub struct ZapDomain {
+    ip: IpFilter,
+    plain: PlainPasswords,
+    curve: CurveKeys,
+    policies: Vec<Arc<dyn ZapPolicy>>,
+}
+
+impl ZapDomain {
+    pub fn new() -> Self {
+        ZapDomain::default()
+    }
+
+    /// Only allow connections from the given address, along with any others allowed this way
+    pub fn allow(mut self, ip: IpAddr) -> Self {
+        self.ip.allow.insert(ip);
+        self
+    }
+
+    /// Deny connections from the given address
+    pub fn deny(mut self, ip: IpAddr) -> Self {
+        self.ip.deny.insert(ip);
+        self
+    }
+
+    /// Accept PLAIN connections with the given username and password
+    pub fn plain(mut self, username: &str, password: &str) -> Self {
+        self.plain
+            .passwords
+            .insert(username.to_owned(), password.to_owned());
+        self
+    }
+
+    /// Accept CURVE connections from the client with the given public key
+    pub fn curve_key(mut self, key: CurvePublicKey) -> Self {
+        self.curve.keys.insert(key);
+        self
+    }
+
+    /// Accept CURVE connections from every client with a certificate in the given directory
+    ///
+    /// Files that don't contain a public key are skipped.
+    pub fn curve_key_dir<P: AsRef<Path>>(mut self, dir: P) -> io::Result<Self> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if !path.is_file() {
+                continue;
+            }
+
+            let mut contents = String::new();
+            if fs::File::open(&path)?
+                .read_to_string(&mut contents)
+                .is_err()
+            {
+                continue;
+            }
+
+            if let Some(key) = read_public_key(&contents) {
+                self.curve.keys.insert(key);
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Accept CURVE connections from any client, only requiring that the link is encrypted
+    pub fn curve_allow_any(mut self) -> Self {
+        self.curve.allow_any = true;
+        self
+    }
+
+    /// Add a custom policy to the domain
+    pub fn policy<P>(mut self, policy: P) -> Self
+    where
+        P: ZapPolicy + 'static,
+    {
+        self.policies.push(Arc::new(policy));
+        self
+    }
+
+    fn authenticate(&self, request: &ZapRequest) -> ZapDecision {
+        let builtin: [&dyn ZapPolicy; 3] = [&self.ip, &self.plain, &self.curve];
+        let policies = builtin
+            .iter()
+            .copied()
+            .chain(self.policies.iter().map(|policy| &**policy));
+
+        let mut user_id = None;
+        for policy in policies {
+            match policy.check(request) {
+                ZapDecision::Deny(reason) => return ZapDecision::Deny(reason),
+                ZapDecision::Allow(id) => {
+                    user_id.get_or_insert(id);
+                }
+                ZapDecision::Pass => (),
+            }
+        }
+
+        match (user_id, &request.mechanism) {
+            (Some(user_id), _) => ZapDecision::Allow(user_id),
+            (None, Mechanism::Null) => ZapDecision::Allow(String::new()),
+            (None, Mechanism::Other(ref name)) => {
+                ZapDecision::Deny(format!("Mechanism {} is not supported", name))
+            }
+            (None, _) => ZapDecision::Deny("No credentials were accepted".to_owned()),
+        }
+    }
+}
+
+impl fmt::Debug for ZapDomain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ZapDomain")
+            .field("ip", &self.ip)
+            .field("plain", &self.plain)
+            .field("curve", &self.curve)
+            .field("policies", &self.policies.len())
+            .finish()
+    }
+}
+
+/// Decides which connections are accepted, based on per-domain rules
+///
+/// Requests for a domain without rules are denied, unless a fallback domain is configured.
+#[derive(Clone, Debug, Default)]
+pub struct Authenticator {
+    domains: HashMap<String, ZapDomain>,
+    fallback: Option<ZapDomain>,
+}
+
+impl Authenticator {
+    pub fn new() -> Self {
+        Authenticator::default()
+    }
+
+    /// Set the rules for a domain
+    pub fn domain(mut self, name: &str, domain: ZapDomain) -> Self {
+        self.domains.insert(name.to_owned(), domain);
+        self
+    }
+
+    /// Set the rules for every domain that hasn't been configured
+    pub fn fallback(mut self, domain: ZapDomain) -> Self {
+        self.fallback = Some(domain);
+        self
+    }
+
+    /// Decide whether the connection described by `request` should be accepted
+    pub fn authenticate(&self, request: &ZapRequest) -> ZapDecision {
+        match self.domains.get(&request.domain).or(self.fallback.as_ref()) {
+            Some(domain) => domain.authenticate(request),
+            None => ZapDecision::Deny(format!("Unknown domain {}", request.domain)),
+        }
+    }
+
+    /// Produce the reply for a ZAP request multipart
+    ///
+    /// Malformed requests are answered with a 500 status, since ZeroMQ waits for a reply to
+    /// every request it sends.
+    pub fn handle(&self, multipart: Multipart) -> Multipart {
+        let request_id = multipart.get(1).map(|msg| msg.to_vec()).unwrap_or_default();
+
+        let request = match parse_request(multipart) {
+            Ok(request) => request,
+            Err(reason) => return reply(&request_id, "500", reason, ""),
+        };
+
+        match self.authenticate(&request) {
+            ZapDecision::Allow(user_id) => reply(&request.request_id, "200", "OK", &user_id),
+            ZapDecision::Deny(reason) => reply(&request.request_id, "400", &reason, ""),
+            ZapDecision::Pass => reply(&request.request_id, "400", "Not allowed", ""),
+        }
+    }
+}
+
+/// Answers the requests read from a ROUTER socket bound to `ZAP_ENDPOINT` with an `Authenticator`
+///
+/// ZeroMQ sends its requests from REQ sockets, so each one arrives as an `Envelope`, and its
+/// reply is routed back through the same envelope. Requests without one can't be answered, and
+/// are dropped.
+///
+/// The backends' `zap::ZapHandler` types are the usual way to create one.
+pub struct ZapHandler<S> {
+    sock: S,
+    authenticator: Arc<Authenticator>,
+}
+
+impl<S, E> ZapHandler<S>
+where
+    S: Stream<Item = Result<Multipart, E>> + Sink<Multipart, Error = E>,
+{
+    pub fn new(sock: S, authenticator: Authenticator) -> Self {
+        ZapHandler {
+            sock,
+            authenticator: Arc::new(authenticator),
+        }
+    }
+
+    /// Answer authentication requests until the socket fails
+    pub async fn run(self) -> Result<(), E> {
+        let ZapHandler {
+            sock,
+            authenticator,
+        } = self;
+        let (sink, stream) = sock.split();
+
+        stream
+            .try_filter_map(move |multipart| {
+                let reply = match Envelope::from_multipart(multipart) {
+                    Ok(mut envelope) => {
+                        let request = std::mem::take(envelope.body_mut());
+                        Some(
+                            envelope
+                                .reply(authenticator.handle(request))
+                                .into_multipart(),
+                        )
+                    }
+                    Err(e) => {
+                        warn!("Dropping ZAP request, {}", e);
+                        None
+                    }
+                };
+
+                future::ready(Ok(reply))
+            })
+            .forward(sink)
+            .await
+    }
+}
+
+impl<S> fmt::Debug for ZapHandler<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ZapHandler")
+            .field("authenticator", &self.authenticator)
+            .finish()
+    }
+}
+
+fn frame_string(msg: zmq::Message) -> Result<String, &'static str> {
+    msg.as_str()
+        .map(|s| s.to_owned())
+        .ok_or("Frame is not valid UTF-8")
+}
+
+fn parse_request(multipart: Multipart) -> Result<ZapRequest, &'static str> {
+    let mut frames = multipart.into_iter();
+    let mut next = || frames.next().ok_or("Request is missing frames");
+
+    if frame_string(next()?)? != ZAP_VERSION {
+        return Err("Version number not valid");
+    }
+
+    let request_id = next()?.to_vec();
+    let domain = frame_string(next()?)?;
+    let address = frame_string(next()?)?;
+    let routing_id = next()?.to_vec();
+    let mechanism = frame_string(next()?)?;
+
+    let mechanism = match mechanism.as_str() {
+        "NULL" => Mechanism::Null,
+        "PLAIN" => Mechanism::Plain {
+            username: frame_string(next()?)?,
+            password: frame_string(next()?)?,
+        },
+        "CURVE" => Mechanism::Curve {
+            public_key: CurvePublicKey::from_bytes(&next()?)
+                .map_err(|_| "CURVE public key is not valid")?,
+        },
+        _ => Mechanism::Other(mechanism),
+    };
+
+    Ok(ZapRequest {
+        request_id,
+        domain,
+        address,
+        routing_id,
+        mechanism,
+    })
+}
+
+fn reply(request_id: &[u8], status_code: &str, status_text: &str, user_id: &str) -> Multipart {
+    vec![
+        zmq::Message::from(ZAP_VERSION),
+        zmq::Message::from(request_id),
+        zmq::Message::from(status_code),
+        zmq::Message::from(status_text),
+        zmq::Message::from(user_id),
+        zmq::Message::new(),
+    ]
+    .into()
+}
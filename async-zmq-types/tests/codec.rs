@@ -0,0 +1,149 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::sync::Arc;
+
+use async_zmq_types::{
+    codec::{CodecError, Decoder, Encoder},
+    Multipart,
+};
+use futures::{SinkExt, StreamExt};
+use tokio_zmq::{prelude::*, Pull, Push};
+
+/// Sends each string in a Vec as its own frame
+struct Frames;
+
+impl Decoder for Frames {
+    type Item = Vec<String>;
+    type Error = std::str::Utf8Error;
+
+    fn decode(&mut self, multipart: Multipart) -> Result<Self::Item, Self::Error> {
+        multipart
+            .iter()
+            .map(|msg| std::str::from_utf8(msg).map(String::from))
+            .collect()
+    }
+}
+
+impl Encoder<Vec<String>> for Frames {
+    type Error = std::str::Utf8Error;
+
+    fn encode(&mut self, item: Vec<String>) -> Result<Multipart, Self::Error> {
+        Ok(item
+            .iter()
+            .map(|s| zmq::Message::from(s.as_str()))
+            .collect::<Vec<_>>()
+            .into())
+    }
+}
+
+async fn pipe(endpoint: &str) -> (Push, Pull) {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let pull: Pull = Pull::builder(Arc::clone(&ctx))
+        .bind(endpoint)
+        .build()
+        .await
+        .unwrap();
+    let push: Push = Push::builder(ctx).connect(endpoint).build().await.unwrap();
+
+    (push, pull)
+}
+
+#[tokio::test]
+async fn custom_codec() {
+    let (push, pull) = pipe("inproc://codec-custom").await;
+
+    let mut sink = push.sink(1).with_codec(Frames);
+    let mut stream = pull.stream().framed(Frames);
+
+    let item = vec!["hello".to_owned(), "world".to_owned()];
+    sink.send(item.clone()).await.unwrap();
+
+    assert_eq!(stream.next().await.unwrap().unwrap(), item);
+}
+
+#[tokio::test]
+async fn decode_errors_are_reported() {
+    let (mut push, pull) = pipe("inproc://codec-errors").await;
+
+    let mut stream = pull.stream().framed(Frames);
+
+    push.send_mut(zmq::Message::from(&[0xff, 0xfe][..]).into())
+        .await
+        .unwrap();
+
+    match stream.next().await {
+        Some(Err(CodecError::Codec(_))) => (),
+        other => panic!("Unexpected result: {:?}", other.map(|res| res.is_ok())),
+    }
+}
+
+#[cfg(any(feature = "bincode", feature = "json", feature = "msgpack"))]
+mod serde_codecs {
+    use async_zmq_types::codec::{Decoder, SerdeError};
+    use futures::{SinkExt, StreamExt};
+    use serde::{Deserialize, Serialize};
+    use tokio_zmq::prelude::*;
+
+    use super::pipe;
+
+    #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+    struct Job {
+        id: u32,
+        name: String,
+        tags: Vec<String>,
+    }
+
+    fn job() -> Job {
+        Job {
+            id: 7,
+            name: "resize".to_owned(),
+            tags: vec!["images".to_owned()],
+        }
+    }
+
+    macro_rules! round_trip {
+        ($name:ident, $feature:literal, $codec:ident, $endpoint:literal) => {
+            #[cfg(feature = $feature)]
+            #[tokio::test]
+            async fn $name() {
+                use async_zmq_types::codec::$codec;
+
+                let (push, pull) = pipe($endpoint).await;
+
+                let mut sink = push.sink(1).with_codec($codec::new());
+                let mut stream = pull.stream().framed($codec::<Job>::new());
+
+                sink.send(job()).await.unwrap();
+                assert_eq!(stream.next().await.unwrap().unwrap(), job());
+
+                let multipart = vec![zmq::Message::new(), zmq::Message::new()].into();
+                match $codec::<Job>::new().decode(multipart) {
+                    Err(SerdeError::Frames(2)) => (),
+                    other => panic!("Unexpected result: {:?}", other),
+                }
+            }
+        };
+    }
+
+    round_trip!(json, "json", JsonCodec, "inproc://codec-json");
+    round_trip!(bincode, "bincode", BincodeCodec, "inproc://codec-bincode");
+    round_trip!(msgpack, "msgpack", MsgPackCodec, "inproc://codec-msgpack");
+}
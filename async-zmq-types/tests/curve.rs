@@ -0,0 +1,206 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use async_zmq_types::{CurveError, CurveKeyPair, CurvePublicKey, CurveSecretKey, Multipart};
+use tokio::time::{sleep, timeout};
+use tokio_zmq::{prelude::*, Pair, Pub, Rep, Req, Sub};
+
+fn message(s: &str) -> Multipart {
+    zmq::Message::from(s).into()
+}
+
+fn text(multipart: &Multipart) -> Option<&str> {
+    multipart.get(0).and_then(|msg| msg.as_str())
+}
+
+#[test]
+fn keys_round_trip() {
+    let keys = CurveKeyPair::generate().unwrap();
+
+    let public = keys.public().to_z85();
+    let secret = keys.secret().to_z85();
+    assert_eq!(public.len(), 40);
+    assert_eq!(CurveKeyPair::from_z85(&public, &secret).unwrap(), keys);
+
+    let public = keys.public().as_bytes();
+    let secret = keys.secret().as_bytes();
+    assert_eq!(CurveKeyPair::from_bytes(public, secret).unwrap(), keys);
+}
+
+#[test]
+fn invalid_keys_are_rejected() {
+    assert_eq!(
+        CurvePublicKey::from_z85("short"),
+        Err(CurveError::Z85Length(5))
+    );
+    assert_eq!(
+        CurvePublicKey::from_z85(&"~".repeat(40)),
+        Err(CurveError::Z85Char)
+    );
+    assert_eq!(
+        CurveSecretKey::from_bytes(&[0; 31]),
+        Err(CurveError::KeyLength(31))
+    );
+}
+
+#[test]
+fn mismatched_keypairs_are_rejected() {
+    let keys = CurveKeyPair::generate().unwrap();
+    let other = CurveKeyPair::generate().unwrap();
+
+    assert_eq!(
+        CurveKeyPair::new(*other.public(), keys.secret().clone()),
+        Err(CurveError::Mismatch)
+    );
+    assert_eq!(
+        CurveKeyPair::from_z85(&other.public().to_z85(), &keys.secret().to_z85()),
+        Err(CurveError::Mismatch)
+    );
+}
+
+#[tokio::test]
+async fn curve_req_rep() {
+    let ctx = Arc::new(zmq::Context::new());
+    let server_keys = CurveKeyPair::generate().unwrap();
+    let server_public = *server_keys.public();
+
+    let mut rep: Rep = Rep::builder(Arc::clone(&ctx))
+        .curve_server(server_keys)
+        .bind("tcp://127.0.0.1:5610")
+        .build()
+        .await
+        .unwrap();
+
+    let mut req: Req = Req::builder(ctx)
+        .curve_client(CurveKeyPair::generate().unwrap(), server_public)
+        .connect("tcp://127.0.0.1:5610")
+        .build()
+        .await
+        .unwrap();
+
+    let exchange = async {
+        req.send_mut(message("ping")).await.unwrap();
+        let request = rep.recv_mut().await.unwrap();
+        assert_eq!(text(&request), Some("ping"));
+
+        rep.send_mut(message("pong")).await.unwrap();
+        let reply = req.recv_mut().await.unwrap();
+        assert_eq!(text(&reply), Some("pong"));
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn wrong_server_key_never_connects() {
+    let ctx = Arc::new(zmq::Context::new());
+    let server_keys = CurveKeyPair::generate().unwrap();
+    let impostor = *CurveKeyPair::generate().unwrap().public();
+
+    let mut rep: Rep = Rep::builder(Arc::clone(&ctx))
+        .curve_server(server_keys)
+        .bind("tcp://127.0.0.1:5611")
+        .build()
+        .await
+        .unwrap();
+
+    let mut req: Req = Req::builder(ctx)
+        .curve_client(CurveKeyPair::generate().unwrap(), impostor)
+        .linger(Some(Duration::from_millis(0)))
+        .connect("tcp://127.0.0.1:5611")
+        .build()
+        .await
+        .unwrap();
+
+    req.send_mut(message("ping")).await.unwrap();
+
+    let received = timeout(Duration::from_millis(500), rep.recv_mut()).await;
+    assert!(received.is_err(), "Message crossed an unauthenticated link");
+}
+
+#[tokio::test]
+async fn curve_pub_sub() {
+    let ctx = Arc::new(zmq::Context::new());
+    let server_keys = CurveKeyPair::generate().unwrap();
+    let server_public = *server_keys.public();
+
+    let mut zpub: Pub = Pub::builder(Arc::clone(&ctx))
+        .bind("tcp://127.0.0.1:5612")
+        .curve_server(server_keys)
+        .build()
+        .await
+        .unwrap();
+
+    let mut sub: Sub = Sub::builder(ctx)
+        .connect("tcp://127.0.0.1:5612")
+        .filter(b"")
+        .curve_client(CurveKeyPair::generate().unwrap(), server_public)
+        .build()
+        .await
+        .unwrap();
+
+    // PUB drops messages until the subscription arrives, so keep publishing until one gets through
+    let publish = async {
+        loop {
+            zpub.send_mut(message("news")).await.unwrap();
+            sleep(Duration::from_millis(50)).await;
+        }
+    };
+
+    let received = async {
+        tokio::select! {
+            received = sub.recv_mut() => received.unwrap(),
+            _ = publish => unreachable!(),
+        }
+    };
+
+    let multipart = timeout(Duration::from_secs(5), received).await.unwrap();
+    assert_eq!(text(&multipart), Some("news"));
+}
+
+#[tokio::test]
+async fn curve_pair() {
+    let ctx = Arc::new(zmq::Context::new());
+    let server_keys = CurveKeyPair::generate().unwrap();
+    let server_public = *server_keys.public();
+
+    let mut server: Pair = Pair::builder(Arc::clone(&ctx))
+        .pair("tcp://127.0.0.1:5613", true)
+        .curve_server(server_keys)
+        .build()
+        .await
+        .unwrap();
+
+    let mut client: Pair = Pair::builder(ctx)
+        .pair("tcp://127.0.0.1:5613", false)
+        .curve_client(CurveKeyPair::generate().unwrap(), server_public)
+        .build()
+        .await
+        .unwrap();
+
+    let exchange = async {
+        client.send_mut(message("hello")).await.unwrap();
+        let multipart = server.recv_mut().await.unwrap();
+        assert_eq!(text(&multipart), Some("hello"));
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
@@ -0,0 +1,257 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    net::{Ipv4Addr, Ipv6Addr},
+    sync::Arc,
+};
+
+use async_zmq_types::{
+    endpoint::{Host, Port, TcpEndpoint, TcpSource, WsEndpoint},
+    BuildError, ConfigError, Endpoint, EndpointError, SocketConfig,
+};
+use tokio_zmq::{prelude::*, Error, Rep, Req};
+
+fn parse(endpoint: &str) -> Result<Endpoint, EndpointError> {
+    endpoint.parse()
+}
+
+#[test]
+fn endpoints_parse_and_print() {
+    let cases = vec![
+        (
+            "tcp://127.0.0.1:5555",
+            Endpoint::tcp(Ipv4Addr::LOCALHOST, 5555),
+        ),
+        ("tcp://*:*", Endpoint::tcp(Host::Any, Port::Any)),
+        ("tcp://[::1]:5555", Endpoint::tcp(Ipv6Addr::LOCALHOST, 5555)),
+        (
+            "tcp://eth0:5555",
+            Endpoint::tcp(Host::Name("eth0".to_owned()), 5555),
+        ),
+        (
+            "tcp://10.0.0.1:6000;example.com:5555",
+            Endpoint::Tcp(TcpEndpoint {
+                source: Some(TcpSource {
+                    host: Host::Ip(Ipv4Addr::new(10, 0, 0, 1).into()),
+                    port: Some(Port::Number(6000)),
+                }),
+                host: Host::Name("example.com".to_owned()),
+                port: Port::Number(5555),
+            }),
+        ),
+        ("ipc:///tmp/feeds/0", Endpoint::ipc("/tmp/feeds/0")),
+        ("ipc://@abstract", Endpoint::ipc("@abstract")),
+        ("inproc://workers", Endpoint::inproc("workers")),
+        (
+            "ws://localhost:8080/zmq",
+            Endpoint::Ws(WsEndpoint {
+                host: Host::Name("localhost".to_owned()),
+                port: Port::Number(8080),
+                path: "/zmq".to_owned(),
+            }),
+        ),
+        (
+            "pgm://eth0;239.192.1.1:5555",
+            Endpoint::Other {
+                transport: "pgm".to_owned(),
+                address: "eth0;239.192.1.1:5555".to_owned(),
+            },
+        ),
+    ];
+
+    for (s, endpoint) in cases {
+        assert_eq!(parse(s).as_ref(), Ok(&endpoint), "{}", s);
+        assert_eq!(endpoint.to_string(), s);
+    }
+
+    // Port 0 is the same as `*`
+    assert_eq!(parse("tcp://*:0"), parse("tcp://*:*"));
+    assert_eq!(parse("tcp://*:0").unwrap().to_string(), "tcp://*:*");
+}
+
+#[test]
+fn invalid_endpoints_are_explained() {
+    let cases = vec![
+        (
+            "localhost:5555",
+            EndpointError::MissingTransport("localhost:5555".to_owned()),
+        ),
+        (
+            "tpc://localhost:5555",
+            EndpointError::Transport("tpc".to_owned()),
+        ),
+        ("inproc://", EndpointError::Empty("inproc".to_owned())),
+        (
+            "tcp://localhost",
+            EndpointError::MissingPort("localhost".to_owned()),
+        ),
+        (
+            "tcp://localhost:http",
+            EndpointError::Port("http".to_owned()),
+        ),
+        (
+            "tcp://localhost:65536",
+            EndpointError::Port("65536".to_owned()),
+        ),
+        ("tcp://:5555", EndpointError::Host("".to_owned())),
+        (
+            "tcp://[::1:5555",
+            EndpointError::MissingPort("[::1:5555".to_owned()),
+        ),
+        (
+            "tcp://local host:5555",
+            EndpointError::Host("local host".to_owned()),
+        ),
+    ];
+
+    for (s, error) in cases {
+        assert_eq!(parse(s), Err(error), "{}", s);
+    }
+}
+
+#[test]
+fn endpoints_are_checked_for_their_direction() {
+    let wildcard = parse("tcp://*:5555").unwrap();
+    assert!(wildcard.check_bind().is_ok());
+    assert_eq!(
+        wildcard.check_connect(),
+        Err(EndpointError::Wildcard("tcp://*:5555".to_owned()))
+    );
+
+    let ephemeral = parse("tcp://127.0.0.1:0").unwrap();
+    assert!(ephemeral.is_ephemeral());
+    assert!(ephemeral.check_connect().is_err());
+
+    let sourced = parse("tcp://127.0.0.1;127.0.0.1:5555").unwrap();
+    assert!(sourced.check_connect().is_ok());
+    assert!(sourced.check_bind().is_err());
+}
+
+#[tokio::test]
+async fn builds_report_invalid_endpoints() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let res: Result<Rep, _> = Rep::builder(Arc::clone(&ctx))
+        .bind("tcp://127.0.0.1")
+        .build()
+        .await;
+    match res {
+        Err(Error::Build(BuildError::Endpoint(EndpointError::MissingPort(_)))) => (),
+        other => panic!("Unexpected result: {:?}", other.map(|_| ())),
+    }
+
+    let res: Result<Req, _> = Req::builder(ctx).connect("tcp://*:0").build().await;
+    match res {
+        Err(Error::Build(BuildError::Endpoint(EndpointError::Wildcard(_)))) => (),
+        other => panic!("Unexpected result: {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn configs_report_invalid_endpoints() {
+    let config: SocketConfig = toml::from_str(
+        r#"
+        kind = "req"
+        connect = ["tcp://localhost"]
+    "#,
+    )
+    .unwrap();
+
+    match config.validate() {
+        Err(ConfigError::Endpoint(EndpointError::MissingPort(_))) => (),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[cfg(unix)]
+mod ipc {
+    use std::{
+        fs,
+        os::unix::{fs::PermissionsExt, net::UnixListener},
+        path::PathBuf,
+        sync::Arc,
+    };
+
+    use async_zmq_types::{BuildError, Endpoint};
+    use tokio_zmq::{prelude::*, Error, Rep, Req};
+
+    fn socket_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "async-zmq-endpoint-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[tokio::test]
+    async fn stale_ipc_files_are_replaced() {
+        let path = socket_path("stale");
+
+        // A listener that goes away without cleaning up after itself
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let ctx = Arc::new(zmq::Context::new());
+        let endpoint = Endpoint::ipc(&path);
+
+        let mut rep: Rep = Rep::builder(Arc::clone(&ctx))
+            .ipc_permissions(0o600)
+            .bind(&endpoint)
+            .build()
+            .await
+            .unwrap();
+        let mut req: Req = Req::builder(ctx).connect(&endpoint).build().await.unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        req.send_mut(zmq::Message::from("hello").into())
+            .await
+            .unwrap();
+        let multipart = rep.recv_mut().await.unwrap();
+        assert_eq!(multipart.get(0).and_then(|msg| msg.as_str()), Some("hello"));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn live_ipc_sockets_are_left_alone() {
+        let path = socket_path("live");
+        let endpoint = Endpoint::ipc(&path);
+        let ctx = Arc::new(zmq::Context::new());
+
+        let first: Rep = Rep::builder(Arc::clone(&ctx))
+            .bind(&endpoint)
+            .build()
+            .await
+            .unwrap();
+
+        let second: Result<Rep, _> = Rep::builder(ctx).bind(&endpoint).build().await;
+        match second {
+            Err(Error::Build(BuildError::Ipc { .. })) => (),
+            other => panic!("Unexpected result: {:?}", other.map(|_| ())),
+        }
+
+        drop(first);
+        let _ = fs::remove_file(&path);
+    }
+}
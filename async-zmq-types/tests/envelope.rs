@@ -0,0 +1,191 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use async_zmq_types::{
+    codec::{CodecError, Decoder, Encoder, EnvelopeCodec, RouterCodec},
+    Envelope, EnvelopeError, Multipart, PeerId,
+};
+use futures::{SinkExt, StreamExt};
+use tokio::time::timeout;
+use tokio_zmq::{prelude::*, Dealer, Rep, Req, Router};
+
+fn multipart(frames: &[&str]) -> Multipart {
+    frames
+        .iter()
+        .map(|frame| zmq::Message::from(*frame))
+        .collect::<Vec<_>>()
+        .into()
+}
+
+fn frames(multipart: &Multipart) -> Vec<&str> {
+    multipart.iter().filter_map(|msg| msg.as_str()).collect()
+}
+
+#[test]
+fn envelope_round_trip() {
+    let envelope = Envelope::from_multipart(multipart(&["a", "b", "", "body", "more"])).unwrap();
+
+    assert_eq!(envelope.routes(), &[PeerId::from("a"), PeerId::from("b")]);
+    assert_eq!(frames(envelope.body()), vec!["body", "more"]);
+
+    let multipart = envelope.into_multipart();
+    assert_eq!(frames(&multipart), vec!["a", "b", "", "body", "more"]);
+}
+
+#[test]
+fn envelope_without_routes() {
+    let envelope = Envelope::from_multipart(multipart(&["", "body"])).unwrap();
+
+    assert_eq!(envelope.peer(), None);
+    assert_eq!(frames(envelope.body()), vec!["body"]);
+}
+
+#[test]
+fn malformed_envelopes_are_rejected() {
+    assert_eq!(
+        Envelope::from_multipart(multipart(&["a", "body"])).unwrap_err(),
+        EnvelopeError::MissingDelimiter
+    );
+
+    let mut codec = RouterCodec;
+    assert_eq!(
+        codec.decode(Multipart::new()).unwrap_err(),
+        EnvelopeError::MissingPeerId
+    );
+    assert_eq!(
+        codec.decode(multipart(&["", "body"])).unwrap_err(),
+        EnvelopeError::MissingPeerId
+    );
+    assert_eq!(
+        codec
+            .decode(multipart(&["a", "b", "", "body"]))
+            .unwrap_err(),
+        EnvelopeError::MissingDelimiter
+    );
+    assert_eq!(
+        codec
+            .encode((PeerId::new(Vec::new()), multipart(&["body"])))
+            .unwrap_err(),
+        EnvelopeError::MissingPeerId
+    );
+}
+
+#[tokio::test]
+async fn router_replies_to_req() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let router: Router = Router::builder(Arc::clone(&ctx))
+        .bind("inproc://envelope-router")
+        .build()
+        .await
+        .unwrap();
+
+    let mut req: Req = Req::builder(ctx)
+        .identity(b"client")
+        .connect("inproc://envelope-router")
+        .build()
+        .await
+        .unwrap();
+
+    let (sink, stream) = router.sink_stream(1).split();
+    let mut sink = sink.with_codec(RouterCodec);
+    let mut stream = stream.framed(RouterCodec);
+
+    let exchange = async {
+        req.send_mut(multipart(&["ping"])).await.unwrap();
+
+        let (peer, body) = stream.next().await.unwrap().unwrap();
+        assert_eq!(peer, PeerId::from("client"));
+        assert_eq!(frames(&body), vec!["ping"]);
+
+        sink.send((peer, multipart(&["pong"]))).await.unwrap();
+        let reply = req.recv_mut().await.unwrap();
+        assert_eq!(frames(&reply), vec!["pong"]);
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn router_reports_dealer_without_delimiter() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let router: Router = Router::builder(Arc::clone(&ctx))
+        .bind("inproc://envelope-dealer")
+        .build()
+        .await
+        .unwrap();
+
+    let mut dealer: Dealer = Dealer::builder(ctx)
+        .connect("inproc://envelope-dealer")
+        .build()
+        .await
+        .unwrap();
+
+    let mut stream = router.stream().framed(RouterCodec);
+
+    dealer.send_mut(multipart(&["no delimiter"])).await.unwrap();
+
+    match timeout(Duration::from_secs(5), stream.next())
+        .await
+        .unwrap()
+    {
+        Some(Err(CodecError::Codec(EnvelopeError::MissingDelimiter))) => (),
+        other => panic!("Unexpected result: {:?}", other.map(|res| res.is_ok())),
+    }
+}
+
+#[tokio::test]
+async fn dealer_talks_to_rep_with_envelopes() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let mut rep: Rep = Rep::builder(Arc::clone(&ctx))
+        .bind("inproc://envelope-rep")
+        .build()
+        .await
+        .unwrap();
+
+    let dealer: Dealer = Dealer::builder(ctx)
+        .connect("inproc://envelope-rep")
+        .build()
+        .await
+        .unwrap();
+
+    let (sink, stream) = dealer.sink_stream(1).split();
+    let mut sink = sink.with_codec(EnvelopeCodec);
+    let mut stream = stream.framed(EnvelopeCodec);
+
+    let exchange = async {
+        sink.send(Envelope::new(Vec::new(), multipart(&["ping"])))
+            .await
+            .unwrap();
+
+        let request = rep.recv_mut().await.unwrap();
+        assert_eq!(frames(&request), vec!["ping"]);
+        rep.send_mut(multipart(&["pong"])).await.unwrap();
+
+        let envelope = stream.next().await.unwrap().unwrap();
+        assert!(envelope.routes().is_empty());
+        assert_eq!(frames(envelope.body()), vec!["pong"]);
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
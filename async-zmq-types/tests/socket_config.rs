@@ -0,0 +1,207 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use async_zmq_types::{ConfigError, EndpointError, SocketConfig, SocketKind};
+use tokio::time::timeout;
+use tokio_zmq::{prelude::*, Pair, Pub, Sub};
+
+fn parse(toml: &str) -> SocketConfig {
+    toml::from_str(toml).unwrap()
+}
+
+#[test]
+fn configs_need_endpoints() {
+    let config = parse(r#"kind = "push""#);
+
+    match config.validate() {
+        Err(ConfigError::NoEndpoints(SocketKind::Push)) => (),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn subs_need_subscriptions() {
+    let config = parse(
+        r#"
+        kind = "sub"
+        connect = ["tcp://localhost:5556"]
+    "#,
+    );
+
+    match config.validate() {
+        Err(ConfigError::SubWithoutFilter) => (),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn only_subs_take_subscriptions() {
+    let config = parse(
+        r#"
+        kind = "pub"
+        bind = ["tcp://*:5556"]
+        subscriptions = ["news"]
+    "#,
+    );
+
+    match config.validate() {
+        Err(ConfigError::UnexpectedSubscriptions(SocketKind::Pub)) => (),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn pairs_need_exactly_one_endpoint() {
+    let config = parse(
+        r#"
+        kind = "pair"
+        bind = ["inproc://config-pair"]
+        connect = ["inproc://config-other"]
+    "#,
+    );
+
+    match config.validate() {
+        Err(ConfigError::PairEndpoints(2)) => (),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn configs_check_bind_and_connect_endpoints() {
+    let config = parse(
+        r#"
+        kind = "rep"
+        bind = ["tcp://localhost"]
+    "#,
+    );
+
+    match config.validate() {
+        Err(ConfigError::Endpoint(EndpointError::MissingPort(_))) => (),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+
+    // Wildcards can be bound to, but not connected to
+    let config = parse(
+        r#"
+        kind = "req"
+        connect = ["tcp://*:5556"]
+    "#,
+    );
+
+    match config.validate() {
+        Err(ConfigError::Endpoint(EndpointError::Wildcard(_))) => (),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+
+    let config = parse(
+        r#"
+        kind = "rep"
+        bind = ["carrier-pigeon://coop"]
+    "#,
+    );
+
+    match config.validate() {
+        Err(ConfigError::Endpoint(EndpointError::Transport(_))) => (),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn unknown_fields_are_rejected() {
+    let res: Result<SocketConfig, _> = toml::from_str(
+        r#"
+        kind = "rep"
+        bind = ["inproc://config-unknown"]
+        subscription = ["typo"]
+    "#,
+    );
+
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn configs_build_working_sockets() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let publisher = parse(
+        r#"
+        kind = "pub"
+        bind = ["inproc://config-build"]
+
+        [options]
+        linger = 0
+    "#,
+    );
+    let subscriber = parse(
+        r#"
+        kind = "sub"
+        connect = ["inproc://config-build"]
+        subscriptions = ["news"]
+    "#,
+    );
+    let pair = parse(
+        r#"
+        kind = "pair"
+        bind = ["inproc://config-build-pair"]
+    "#,
+    );
+
+    assert!(publisher.validate().is_ok());
+    assert!(subscriber.validate().is_ok());
+    assert!(pair.validate().is_ok());
+
+    let mut publisher: Pub = publisher
+        .sock_config(Arc::clone(&ctx))
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    let mut subscriber: Sub = subscriber
+        .sub_config(Arc::clone(&ctx))
+        .unwrap()
+        .build()
+        .await
+        .unwrap();
+    let _pair: Pair = pair.pair_config(ctx).unwrap().build().await.unwrap();
+
+    let exchange = async {
+        // The subscription reaches the publisher asynchronously, so keep publishing until it has
+        loop {
+            publisher
+                .send_mut(zmq::Message::from("sports").into())
+                .await
+                .unwrap();
+            publisher
+                .send_mut(zmq::Message::from("news").into())
+                .await
+                .unwrap();
+
+            let received = timeout(Duration::from_millis(100), subscriber.recv_mut()).await;
+            if let Ok(multipart) = received {
+                let multipart = multipart.unwrap();
+                assert_eq!(multipart.get(0).and_then(|msg| msg.as_str()), Some("news"));
+                break;
+            }
+        }
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
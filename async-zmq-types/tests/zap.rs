@@ -0,0 +1,99 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use async_zmq_types::{
+    zap::{Authenticator, Mechanism, ZapDecision, ZapDomain, ZapRequest},
+    Multipart,
+};
+
+fn request(domain: &str, mechanism: &str, credentials: &[&str]) -> Multipart {
+    let frames = ["1.0", "7", domain, "10.0.0.1", "", mechanism]
+        .iter()
+        .chain(credentials)
+        .map(|frame| zmq::Message::from(*frame))
+        .collect::<Vec<_>>();
+
+    frames.into()
+}
+
+fn status(reply: &Multipart) -> (&str, &str) {
+    let frame = |index| reply.get(index).and_then(|msg| msg.as_str()).unwrap();
+
+    assert_eq!(frame(0), "1.0");
+    assert_eq!(frame(1), "7");
+    (frame(2), frame(4))
+}
+
+#[test]
+fn domains_are_configured_separately() {
+    let authenticator = Authenticator::new()
+        .domain("admin", ZapDomain::new().plain("root", "hunter2"))
+        .domain("public", ZapDomain::new());
+
+    let reply = authenticator.handle(request("admin", "PLAIN", &["root", "hunter2"]));
+    assert_eq!(status(&reply), ("200", "root"));
+
+    let reply = authenticator.handle(request("public", "PLAIN", &["root", "hunter2"]));
+    assert_eq!(status(&reply).0, "400");
+
+    let reply = authenticator.handle(request("public", "NULL", &[]));
+    assert_eq!(status(&reply).0, "200");
+
+    let reply = authenticator.handle(request("other", "NULL", &[]));
+    assert_eq!(status(&reply).0, "400");
+}
+
+#[test]
+fn fallback_domain() {
+    let authenticator = Authenticator::new().fallback(ZapDomain::new());
+
+    let reply = authenticator.handle(request("anything", "NULL", &[]));
+    assert_eq!(status(&reply).0, "200");
+}
+
+#[test]
+fn custom_policies_can_deny() {
+    let authenticator = Authenticator::new().domain(
+        "",
+        ZapDomain::new().policy(|request: &ZapRequest| match request.mechanism {
+            Mechanism::Null if request.address.starts_with("10.") => {
+                ZapDecision::Deny("Private network".to_owned())
+            }
+            _ => ZapDecision::Pass,
+        }),
+    );
+
+    let reply = authenticator.handle(request("", "NULL", &[]));
+    assert_eq!(status(&reply).0, "400");
+    assert_eq!(
+        reply.get(3).and_then(|msg| msg.as_str()),
+        Some("Private network")
+    );
+}
+
+#[test]
+fn malformed_requests_are_answered() {
+    let authenticator = Authenticator::new().fallback(ZapDomain::new());
+
+    let reply = authenticator.handle(request("", "PLAIN", &["only-a-username"]));
+    assert_eq!(status(&reply).0, "500");
+
+    let reply = authenticator.handle(request("", "CURVE", &["not-a-key"]));
+    assert_eq!(status(&reply).0, "500");
+}
@@ -0,0 +1,86 @@
+/*
+ * This file is part of Async ZMQ Types.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Async ZMQ Types is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Async ZMQ Types is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Async ZMQ Types.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use async_zmq_types::{shared_message, Multipart};
+use bytes::Bytes;
+use tokio::time::timeout;
+use tokio_zmq::{prelude::*, Pull, Push};
+
+#[test]
+fn shared_frames_are_not_copied() {
+    let data: Arc<[u8]> = vec![7; 4096].into();
+
+    let msg = shared_message(Arc::clone(&data));
+    assert_eq!(msg.as_ptr(), data.as_ptr());
+    assert_eq!(Arc::strong_count(&data), 2);
+
+    drop(msg);
+    assert_eq!(Arc::strong_count(&data), 1);
+}
+
+#[test]
+fn multiparts_from_shared_buffers() {
+    let bytes = Bytes::from_static(b"hello");
+    let mut multipart = Multipart::from(bytes.clone());
+    multipart.push_back_shared(Arc::<[u8]>::from(&b"world"[..]));
+    multipart.push_front_shared(Bytes::new());
+
+    let frames: Vec<&[u8]> = multipart.iter().map(|msg| &msg[..]).collect();
+    assert_eq!(frames, vec![&b""[..], &b"hello"[..], &b"world"[..]]);
+    assert_eq!(multipart.get(1).unwrap().as_ptr(), bytes.as_ptr());
+}
+
+#[tokio::test]
+async fn refused_frames_are_kept_for_retry() {
+    let ctx = Arc::new(zmq::Context::new());
+    let data = Bytes::from(vec![42; 1 << 20]);
+
+    // A bound Push socket refuses to send until a peer connects
+    let mut push: Push = Push::builder(Arc::clone(&ctx))
+        .bind("inproc://zero-copy-retry")
+        .build()
+        .await
+        .unwrap();
+
+    let mut multipart = Multipart::from(data.clone());
+    multipart.push_back_shared(data.clone());
+
+    let send = push.send_mut(multipart);
+    tokio::pin!(send);
+    assert!(timeout(Duration::from_millis(100), &mut send)
+        .await
+        .is_err());
+
+    let mut pull: Pull = Pull::builder(ctx)
+        .connect("inproc://zero-copy-retry")
+        .build()
+        .await
+        .unwrap();
+
+    let exchange = async {
+        send.await.unwrap();
+        pull.recv_mut().await.unwrap()
+    };
+
+    let received = timeout(Duration::from_secs(5), exchange).await.unwrap();
+    assert_eq!(received.len(), 2);
+    assert!(received.iter().all(|msg| msg[..] == data[..]));
+}
@@ -20,13 +20,12 @@
 extern crate futures;
 extern crate futures_zmq;
 extern crate tokio;
-extern crate tokio_executor;
 extern crate zmq;
 
 use std::sync::Arc;
 
-use futures::{Future, Stream};
-use futures_zmq::{prelude::*, Multipart, Pub, Pull, Sub};
+use futures::{future::ready, try_join, StreamExt, TryStreamExt};
+use futures_zmq::{prelude::*, Error, Multipart, Pub, Pull, Sub};
 
 pub struct Stop;
 
@@ -37,7 +36,7 @@ impl ControlHandler for Stop {
     }
 }
 
-fn main() {
+async fn process() -> Result<(), Error> {
     let ctx = Arc::new(zmq::Context::new());
     let cmd_fut = Sub::builder(Arc::clone(&ctx))
         .connect("tcp://localhost:5559")
@@ -46,38 +45,38 @@ fn main() {
     let conn_fut = Pull::builder(Arc::clone(&ctx)).bind("tcp://*:5558").build();
     let send_cmd_fut = Pub::builder(ctx).bind("tcp://*:5559").build();
 
-    let process = cmd_fut
-        .join(conn_fut)
-        .join(send_cmd_fut)
-        .and_then(|((cmd, conn), send_cmd)| {
-            conn.stream()
-                .controlled(cmd.stream(), Stop)
-                .filter_map(|multipart| {
-                    multipart
-                        .into_iter()
-                        .filter_map(|msg| {
-                            let stop = if let Some(s_msg) = msg.as_str() {
-                                println!("msg: '{}'", s_msg);
-                                s_msg == "STOP"
-                            } else {
-                                false
-                            };
+    let (cmd, conn, send_cmd): (Sub, Pull, Pub) = try_join!(cmd_fut, conn_fut, send_cmd_fut)?;
 
-                            if stop {
-                                Some(msg)
-                            } else {
-                                None
-                            }
-                        })
-                        .collect::<Vec<_>>()
-                        .pop()
-                        .map(Multipart::from)
+    conn.stream()
+        .controlled(cmd.stream(), Stop)
+        .try_filter_map(|multipart| {
+            ready(Ok(multipart
+                .into_iter()
+                .filter_map(|msg| {
+                    let stop = if let Some(s_msg) = msg.as_str() {
+                        println!("msg: '{}'", s_msg);
+                        s_msg == "STOP"
+                    } else {
+                        false
+                    };
+
+                    if stop {
+                        Some(msg)
+                    } else {
+                        None
+                    }
                 })
-                .forward(send_cmd.sink(25))
-        });
+                .collect::<Vec<_>>()
+                .pop()
+                .map(Multipart::from)))
+        })
+        .forward(send_cmd.sink(25))
+        .await
+}
 
-    tokio::run(process.map(|_| ()).or_else(|e| {
+#[tokio::main]
+async fn main() {
+    if let Err(e) = process().await {
         println!("Error: {:?}", e);
-        Ok(())
-    }));
+    }
 }
@@ -26,32 +26,33 @@ extern crate zmq;
 
 use std::sync::Arc;
 
-use futures::{Future, Stream};
-use futures_zmq::{prelude::*, Rep};
-
-fn main() {
-    env_logger::init();
+use futures::{StreamExt, TryStreamExt};
+use futures_zmq::{prelude::*, Error, Rep};
 
+async fn run() -> Result<(), Error> {
     let ctx = Arc::new(zmq::Context::new());
-    let rep_fut = Rep::builder(ctx).bind("tcp://*:5560").build();
+    let rep: Rep = Rep::builder(ctx).bind("tcp://*:5560").build().await?;
 
-    let runner = rep_fut.and_then(|rep| {
-        let (sink, stream) = rep.sink_stream(25).split();
+    let (sink, stream) = rep.sink_stream(25).split();
 
-        stream
-            .map(|multipart| {
-                for msg in &multipart {
-                    if let Some(s) = msg.as_str() {
-                        println!("RECEIVED: {}", s);
-                    }
+    stream
+        .map_ok(|multipart| {
+            for msg in &multipart {
+                if let Some(s) = msg.as_str() {
+                    println!("RECEIVED: {}", s);
                 }
-                multipart
-            })
-            .forward(sink)
-    });
+            }
+            multipart
+        })
+        .forward(sink)
+        .await
+}
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
 
-    tokio::run(runner.map(|_| ()).or_else(|e| {
+    if let Err(e) = run().await {
         println!("Error: {:?}", e);
-        Ok(())
-    }));
+    }
 }
@@ -24,18 +24,19 @@ extern crate zmq;
 
 use std::sync::Arc;
 
-use futures::{Future, Stream};
-use futures_zmq::{prelude::*, Sub};
+use futures::TryStreamExt;
+use futures_zmq::{prelude::*, Error, Sub};
 
-fn main() {
+async fn consume() -> Result<(), Error> {
     let ctx = Arc::new(zmq::Context::new());
-    let sub_fut = Sub::builder(ctx)
+    let sub: Sub = Sub::builder(ctx)
         .connect("tcp://localhost:5556")
         .filter(b"")
-        .build();
+        .build()
+        .await?;
 
-    let consumer = sub_fut.and_then(|sub| {
-        sub.stream().for_each(|multipart| {
+    sub.stream()
+        .try_for_each(|multipart| async move {
             for msg in multipart {
                 if let Some(msg) = msg.as_str() {
                     println!("Received: {}", msg);
@@ -44,10 +45,12 @@ fn main() {
 
             Ok(())
         })
-    });
+        .await
+}
 
-    tokio::run(consumer.map(|_| ()).or_else(|e| {
+#[tokio::main]
+async fn main() {
+    if let Err(e) = consume().await {
         println!("Error in consumer: {:?}", e);
-        Ok(())
-    }));
+    }
 }
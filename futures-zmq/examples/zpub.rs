@@ -20,68 +20,33 @@
 extern crate futures;
 extern crate futures_zmq;
 extern crate tokio;
-extern crate tokio_timer;
 extern crate zmq;
 
-use std::{
-    io,
-    sync::Arc,
-    time::{Duration, Instant},
-};
+use std::{sync::Arc, time::Duration};
 
-use futures::{Future, Stream};
+use futures::{stream::poll_fn, StreamExt};
 use futures_zmq::prelude::*;
-use futures_zmq::{Error as ZmqFutError, Pub};
-use tokio_timer::{Error as TimerError, Interval};
+use futures_zmq::{Error, Pub};
+use tokio::time::interval;
 
-#[derive(Debug)]
-enum Error {
-    ZmqFut(ZmqFutError),
-    Zmq(zmq::Error),
-    Io(io::Error),
-    Timer(TimerError),
-}
-
-impl From<ZmqFutError> for Error {
-    fn from(e: ZmqFutError) -> Self {
-        Error::ZmqFut(e)
-    }
-}
+async fn produce() -> Result<(), Error> {
+    let ctx = Arc::new(zmq::Context::new());
+    let zpub: Pub = Pub::builder(ctx).bind("tcp://*:5556").build().await?;
 
-impl From<zmq::Error> for Error {
-    fn from(e: zmq::Error) -> Self {
-        Error::Zmq(e)
-    }
-}
+    let mut interval = interval(Duration::from_secs(1));
 
-impl From<io::Error> for Error {
-    fn from(e: io::Error) -> Self {
-        Error::Io(e)
-    }
+    poll_fn(move |cx| interval.poll_tick(cx).map(Some))
+        .map(|_| {
+            println!("Sending 'Hello'");
+            Ok(zmq::Message::from("Hello").into())
+        })
+        .forward(zpub.sink(25))
+        .await
 }
 
-impl From<TimerError> for Error {
-    fn from(e: TimerError) -> Self {
-        Error::Timer(e)
-    }
-}
-
-fn main() {
-    let ctx = Arc::new(zmq::Context::new());
-    let zpub_fut = Pub::builder(ctx).bind("tcp://*:5556").build();
-
-    let producer = zpub_fut.from_err().and_then(|zpub| {
-        Interval::new(Instant::now(), Duration::from_secs(1))
-            .map_err(Error::from)
-            .and_then(|_| {
-                println!("Sending 'Hello'");
-                Ok(zmq::Message::from("Hello").into())
-            })
-            .forward(zpub.sink(25))
-    });
-
-    tokio::run(producer.map(|_| ()).or_else(|e| {
+#[tokio::main]
+async fn main() {
+    if let Err(e) = produce().await {
         println!("Error in producer: {:?}", e);
-        Ok(())
-    }));
+    }
 }
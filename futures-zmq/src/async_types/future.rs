@@ -17,10 +17,16 @@
  * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::{fmt, marker::PhantomData, mem};
+use std::{
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    mem,
+    pin::Pin,
+    task::{Context, Poll},
+};
 
 use async_zmq_types::Multipart;
-use futures::{Async, Future};
 use log::error;
 
 use crate::{error::Error, socket::Socket, RecvFuture, SendFuture};
@@ -37,34 +43,42 @@ impl SendState {
         mem::replace(self, SendState::Polling)
     }
 
-    fn poll_fut(&mut self, mut fut: SendFuture) -> Result<Async<()>, Error> {
-        match fut.poll()? {
-            Async::Ready(Some(multipart)) => {
-                *self = SendState::Pending(multipart);
-                Ok(Async::NotReady)
-            }
-            Async::Ready(None) => {
+    fn poll_fut(&mut self, mut fut: SendFuture, cx: &mut Context) -> Poll<Result<(), Error>> {
+        match Pin::new(&mut fut).poll(cx) {
+            Poll::Ready(res) => {
                 *self = SendState::Ready;
-                Ok(Async::Ready(()))
+                Poll::Ready(res)
             }
-            Async::NotReady => {
+            Poll::Pending => {
                 *self = SendState::Running(fut);
-                Ok(Async::NotReady)
+                Poll::Pending
             }
         }
     }
 
-    pub(crate) fn poll_flush(&mut self, sock: &Socket) -> Result<Async<()>, Error> {
+    pub(crate) fn poll_flush(
+        &mut self,
+        sock: &Socket,
+        cx: &mut Context,
+    ) -> Poll<Result<(), Error>> {
         match self.polling() {
             SendState::Ready => {
                 *self = SendState::Ready;
-                Ok(Async::Ready(()))
+                Poll::Ready(Ok(()))
+            }
+            SendState::Pending(multipart) => {
+                // Wait for room in the poll thread's buffer before handing the multipart over
+                if sock.poll_reserve(cx).is_pending() {
+                    *self = SendState::Pending(multipart);
+                    return Poll::Pending;
+                }
+
+                self.poll_fut(sock.send_msg(multipart), cx)
             }
-            SendState::Pending(multipart) => self.poll_fut(sock.send_msg(multipart)),
-            SendState::Running(fut) => self.poll_fut(fut),
+            SendState::Running(fut) => self.poll_fut(fut, cx),
             SendState::Polling => {
                 error!("Called polling while polling");
-                return Err(Error::Polling);
+                Poll::Ready(Err(Error::Polling))
             }
         }
     }
@@ -96,23 +110,25 @@ impl<T> Future for MultipartRequest<T>
 where
     T: From<Socket>,
 {
-    type Item = T;
-    type Error = Error;
+    type Output = Result<T, Error>;
 
-    fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
-        let sock = self.sock.take().unwrap();
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let sock = this.sock.take().unwrap();
 
-        match self.state.poll_flush(&sock)? {
-            Async::Ready(_) => Ok(Async::Ready(T::from(sock))),
-            Async::NotReady => {
-                self.sock = Some(sock);
+        match this.state.poll_flush(&sock, cx)? {
+            Poll::Ready(_) => Poll::Ready(Ok(T::from(sock))),
+            Poll::Pending => {
+                this.sock = Some(sock);
 
-                Ok(Async::NotReady)
+                Poll::Pending
             }
         }
     }
 }
 
+impl<T> Unpin for MultipartRequest<T> where T: From<Socket> {}
+
 impl<T> fmt::Debug for MultipartRequest<T>
 where
     T: From<Socket>,
@@ -142,24 +158,32 @@ impl RecvState {
         mem::replace(self, RecvState::Polling)
     }
 
-    fn poll_fut(&mut self, mut fut: RecvFuture) -> Result<Async<Multipart>, Error> {
-        if let ready @ Async::Ready(_) = fut.poll()? {
+    fn poll_fut(
+        &mut self,
+        mut fut: RecvFuture,
+        cx: &mut Context,
+    ) -> Poll<Result<Multipart, Error>> {
+        if let Poll::Ready(res) = Pin::new(&mut fut).poll(cx) {
             *self = RecvState::Pending;
-            return Ok(ready);
+            return Poll::Ready(res);
         }
 
         *self = RecvState::Running(fut);
 
-        Ok(Async::NotReady)
+        Poll::Pending
     }
 
-    pub(crate) fn poll_fetch(&mut self, sock: &Socket) -> Result<Async<Multipart>, Error> {
+    pub(crate) fn poll_fetch(
+        &mut self,
+        sock: &Socket,
+        cx: &mut Context,
+    ) -> Poll<Result<Multipart, Error>> {
         match self.polling() {
-            RecvState::Pending => self.poll_fut(sock.recv_msg()),
-            RecvState::Running(fut) => self.poll_fut(fut),
+            RecvState::Pending => self.poll_fut(sock.recv_msg(), cx),
+            RecvState::Running(fut) => self.poll_fut(fut, cx),
             RecvState::Polling => {
                 error!("Called polling while polling");
-                return Err(Error::Polling);
+                Poll::Ready(Err(Error::Polling))
             }
         }
     }
@@ -191,23 +215,25 @@ impl<T> Future for MultipartResponse<T>
 where
     T: From<Socket>,
 {
-    type Item = (Multipart, T);
-    type Error = Error;
+    type Output = Result<(Multipart, T), Error>;
 
-    fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
-        let sock = self.sock.take().unwrap();
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let sock = this.sock.take().unwrap();
 
-        match self.state.poll_fetch(&sock)? {
-            Async::Ready(multipart) => Ok(Async::Ready((multipart, T::from(sock)))),
-            Async::NotReady => {
-                self.sock = Some(sock);
+        match this.state.poll_fetch(&sock, cx)? {
+            Poll::Ready(multipart) => Poll::Ready(Ok((multipart, T::from(sock)))),
+            Poll::Pending => {
+                this.sock = Some(sock);
 
-                Ok(Async::NotReady)
+                Poll::Pending
             }
         }
     }
 }
 
+impl<T> Unpin for MultipartResponse<T> where T: From<Socket> {}
+
 impl<T> fmt::Debug for MultipartResponse<T>
 where
     T: From<Socket>,
@@ -225,3 +251,75 @@ where
         write!(f, "RecvFuture({:?})", self.sock)
     }
 }
+
+pub struct MultipartRequestMut<'a> {
+    state: SendState,
+    sock: &'a mut Socket,
+}
+
+impl<'a> MultipartRequestMut<'a> {
+    pub fn new(sock: &'a mut Socket, multipart: Multipart) -> Self {
+        MultipartRequestMut {
+            state: SendState::Pending(multipart),
+            sock,
+        }
+    }
+}
+
+impl<'a> Future for MultipartRequestMut<'a> {
+    type Output = Result<(), Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        this.state.poll_flush(this.sock, cx)
+    }
+}
+
+impl<'a> fmt::Debug for MultipartRequestMut<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SendFuture({:?})", self.sock)
+    }
+}
+
+impl<'a> fmt::Display for MultipartRequestMut<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SendFuture({:?})", self.sock)
+    }
+}
+
+pub struct MultipartResponseMut<'a> {
+    state: RecvState,
+    sock: &'a mut Socket,
+}
+
+impl<'a> MultipartResponseMut<'a> {
+    pub fn new(sock: &'a mut Socket) -> Self {
+        MultipartResponseMut {
+            state: RecvState::Pending,
+            sock,
+        }
+    }
+}
+
+impl<'a> Future for MultipartResponseMut<'a> {
+    type Output = Result<Multipart, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        this.state.poll_fetch(this.sock, cx)
+    }
+}
+
+impl<'a> fmt::Debug for MultipartResponseMut<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RecvFuture({:?})", self.sock)
+    }
+}
+
+impl<'a> fmt::Display for MultipartResponseMut<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RecvFuture({:?})", self.sock)
+    }
+}
@@ -18,15 +18,18 @@
  */
 
 mod future;
+mod monitor;
 mod sink;
 mod sink_stream;
+mod sink_type;
 mod stream;
 
 pub use self::{
-    future::{MultipartRequest, MultipartResponse},
+    future::{MultipartRequest, MultipartRequestMut, MultipartResponse, MultipartResponseMut},
+    monitor::MonitorStream,
     sink::MultipartSink,
     sink_stream::MultipartSinkStream,
     stream::MultipartStream,
 };
 
-pub(crate) use self::future::{RecvState, SendState};
+pub(crate) use self::{future::RecvState, sink_type::SinkType};
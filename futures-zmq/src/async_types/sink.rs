@@ -17,21 +17,25 @@
  * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::{collections::VecDeque, fmt, marker::PhantomData};
+use std::{
+    fmt,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
 
-use async_zmq_types::{IntoSocket, Multipart};
-use futures::{try_ready, Async, AsyncSink, Sink};
+use async_zmq_types::{GracefulShutdown, IntoSocket, Multipart, ShutdownReport};
+use futures::{future::BoxFuture, Sink};
 
-use crate::{async_types::SendState, error::Error, socket::Socket};
+use crate::{async_types::SinkType, error::Error, socket::Socket};
 
 pub struct MultipartSink<T>
 where
     T: From<Socket>,
 {
-    state: SendState,
+    sink: SinkType,
     sock: Socket,
-    multiparts: VecDeque<Multipart>,
-    buffer_size: usize,
     phantom: PhantomData<T>,
 }
 
@@ -41,13 +45,33 @@ where
 {
     pub fn new(sock: Socket, buffer_size: usize) -> Self {
         MultipartSink {
-            state: SendState::Ready,
+            sink: SinkType::new(buffer_size),
             sock,
-            multiparts: VecDeque::new(),
-            buffer_size,
             phantom: PhantomData,
         }
     }
+    /// Wait for pending multiparts to be sent and close the socket, waiting as long as the
+    /// socket's ZMQ_LINGER allows
+    pub fn close(self) -> BoxFuture<'static, Result<ShutdownReport, Error>> {
+        self.graceful_shutdown(None)
+    }
+
+    /// Wait for pending multiparts to be sent and close the socket, waiting at most `timeout`
+    pub fn shutdown(self, timeout: Duration) -> BoxFuture<'static, Result<ShutdownReport, Error>> {
+        self.graceful_shutdown(Some(timeout))
+    }
+}
+
+impl<T> GracefulShutdown for MultipartSink<T>
+where
+    T: From<Socket>,
+{
+    type Error = Error;
+    type Future = BoxFuture<'static, Result<ShutdownReport, Error>>;
+
+    fn graceful_shutdown(self, timeout: Option<Duration>) -> Self::Future {
+        Box::pin(self.sink.shutdown(self.sock, timeout))
+    }
 }
 
 impl<T> IntoSocket<T, Socket> for MultipartSink<T>
@@ -59,39 +83,36 @@ where
     }
 }
 
-impl<T> Sink for MultipartSink<T>
+impl<T> Sink<Multipart> for MultipartSink<T>
 where
     T: From<Socket>,
 {
-    type SinkItem = Multipart;
-    type SinkError = Error;
-
-    fn start_send(
-        &mut self,
-        multipart: Self::SinkItem,
-    ) -> Result<AsyncSink<Self::SinkItem>, Self::SinkError> {
-        self.poll_complete()?;
+    type Error = Error;
 
-        if self.multiparts.len() >= self.buffer_size {
-            return Ok(AsyncSink::NotReady(multipart));
-        }
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        let this = &mut *self;
 
-        self.multiparts.push_back(multipart);
-        Ok(AsyncSink::Ready)
+        this.sink.poll_ready(&this.sock, cx)
     }
 
-    fn poll_complete(&mut self) -> Result<Async<()>, Self::SinkError> {
-        try_ready!(self.state.poll_flush(&self.sock));
+    fn start_send(mut self: Pin<&mut Self>, multipart: Multipart) -> Result<(), Self::Error> {
+        let this = &mut *self;
 
-        while let Some(multipart) = self.multiparts.pop_front() {
-            self.state = SendState::Pending(multipart);
-            try_ready!(self.state.poll_flush(&self.sock));
-        }
+        this.sink.start_send(&this.sock, multipart);
+        Ok(())
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.sink.poll_flush(cx)
+    }
 
-        Ok(Async::Ready(()))
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.poll_flush(cx)
     }
 }
 
+impl<T> Unpin for MultipartSink<T> where T: From<Socket> {}
+
 impl<T> fmt::Debug for MultipartSink<T>
 where
     T: From<Socket>,
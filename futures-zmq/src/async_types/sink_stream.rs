@@ -17,26 +17,30 @@
  * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::{collections::VecDeque, fmt, marker::PhantomData};
+use std::{
+    fmt,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
 
-use async_zmq_types::{IntoSocket, Multipart};
-use futures::{try_ready, Async, AsyncSink, Sink, Stream};
+use async_zmq_types::{GracefulShutdown, IntoSocket, Multipart, ShutdownReport};
+use futures::{future::BoxFuture, ready, Sink, Stream};
 
 use crate::{
-    async_types::{RecvState, SendState},
+    async_types::{RecvState, SinkType},
     error::Error,
-    socket::Socket,
+    socket::{types::Xpub, Socket},
 };
 
 pub struct MultipartSinkStream<T>
 where
     T: From<Socket>,
 {
-    send: SendState,
+    send: SinkType,
     recv: RecvState,
-    multiparts: VecDeque<Multipart>,
     sock: Socket,
-    buffer_size: usize,
     phantom: PhantomData<T>,
 }
 
@@ -46,14 +50,46 @@ where
 {
     pub fn new(sock: Socket, buffer_size: usize) -> Self {
         MultipartSinkStream {
-            send: SendState::Ready,
+            send: SinkType::new(buffer_size),
             recv: RecvState::Pending,
-            multiparts: VecDeque::new(),
             sock,
-            buffer_size,
             phantom: PhantomData,
         }
     }
+    /// Wait for pending multiparts to be sent and close the socket, waiting as long as the
+    /// socket's ZMQ_LINGER allows
+    pub fn close(self) -> BoxFuture<'static, Result<ShutdownReport, Error>> {
+        self.graceful_shutdown(None)
+    }
+
+    /// Wait for pending multiparts to be sent and close the socket, waiting at most `timeout`
+    pub fn shutdown(self, timeout: Duration) -> BoxFuture<'static, Result<ShutdownReport, Error>> {
+        self.graceful_shutdown(Some(timeout))
+    }
+}
+
+impl MultipartSinkStream<Xpub> {
+    /// Apply a subscription, for sockets built with `xpub_manual`, see `Xpub::subscribe`
+    pub fn subscribe(&self, topic: &[u8]) -> BoxFuture<'static, Result<(), Error>> {
+        Box::pin(self.sock.subscribe(topic))
+    }
+
+    /// Undo a subscription applied with `subscribe`
+    pub fn unsubscribe(&self, topic: &[u8]) -> BoxFuture<'static, Result<(), Error>> {
+        Box::pin(self.sock.unsubscribe(topic))
+    }
+}
+
+impl<T> GracefulShutdown for MultipartSinkStream<T>
+where
+    T: From<Socket>,
+{
+    type Error = Error;
+    type Future = BoxFuture<'static, Result<ShutdownReport, Error>>;
+
+    fn graceful_shutdown(self, timeout: Option<Duration>) -> Self::Future {
+        Box::pin(self.send.shutdown(self.sock, timeout))
+    }
 }
 
 impl<T> IntoSocket<T, Socket> for MultipartSinkStream<T>
@@ -65,36 +101,31 @@ where
     }
 }
 
-impl<T> Sink for MultipartSinkStream<T>
+impl<T> Sink<Multipart> for MultipartSinkStream<T>
 where
     T: From<Socket>,
 {
-    type SinkItem = Multipart;
-    type SinkError = Error;
-
-    fn start_send(
-        &mut self,
-        multipart: Self::SinkItem,
-    ) -> Result<AsyncSink<Self::SinkItem>, Self::SinkError> {
-        self.poll_complete()?;
+    type Error = Error;
 
-        if self.multiparts.len() >= self.buffer_size {
-            return Ok(AsyncSink::NotReady(multipart));
-        }
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        let this = &mut *self;
 
-        self.multiparts.push_back(multipart);
-        Ok(AsyncSink::Ready)
+        this.send.poll_ready(&this.sock, cx)
     }
 
-    fn poll_complete(&mut self) -> Result<Async<()>, Self::SinkError> {
-        try_ready!(self.send.poll_flush(&self.sock));
+    fn start_send(mut self: Pin<&mut Self>, multipart: Multipart) -> Result<(), Self::Error> {
+        let this = &mut *self;
 
-        while let Some(multipart) = self.multiparts.pop_front() {
-            self.send = SendState::Pending(multipart);
-            try_ready!(self.send.poll_flush(&self.sock));
-        }
+        this.send.start_send(&this.sock, multipart);
+        Ok(())
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.send.poll_flush(cx)
+    }
 
-        Ok(Async::Ready(()))
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.poll_flush(cx)
     }
 }
 
@@ -102,16 +133,18 @@ impl<T> Stream for MultipartSinkStream<T>
 where
     T: From<Socket>,
 {
-    type Item = Multipart;
-    type Error = Error;
+    type Item = Result<Multipart, Error>;
 
-    fn poll(&mut self) -> Result<Async<Option<Self::Item>>, Self::Error> {
-        let mpart = try_ready!(self.recv.poll_fetch(&self.sock));
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let mpart = ready!(this.recv.poll_fetch(&this.sock, cx));
 
-        Ok(Async::Ready(Some(mpart)))
+        Poll::Ready(Some(mpart))
     }
 }
 
+impl<T> Unpin for MultipartSinkStream<T> where T: From<Socket> {}
+
 impl<T> fmt::Debug for MultipartSinkStream<T>
 where
     T: From<Socket>,
@@ -0,0 +1,134 @@
+/*
+ * This file is part of Futures ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Futures ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Futures ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains `SinkType`, the sending half shared by `MultipartSink` and
+//! `MultipartSinkStream`.
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use async_zmq_types::{Multipart, ShutdownReport};
+use futures::ready;
+use log::trace;
+
+use crate::{error::Error, polling::Buffers, socket::Socket, SendFuture};
+
+/// Tracks the multiparts a sink has handed to the poll thread
+///
+/// Each multipart takes a credit from the socket's outbound buffer before it is handed over, so
+/// the sink waits for room in the poll thread instead of retrying. On top of that, at most
+/// `buffer_size` of the sink's multiparts can be waiting to be sent at once.
+pub(crate) struct SinkType {
+    buffer_size: usize,
+    in_flight: VecDeque<SendFuture>,
+    reserved: Option<Arc<Buffers>>,
+}
+
+impl SinkType {
+    pub(crate) fn new(buffer_size: usize) -> Self {
+        SinkType {
+            buffer_size,
+            in_flight: VecDeque::new(),
+            reserved: None,
+        }
+    }
+
+    /// Wait for sends that have finished, stopping at the first one that hasn't
+    fn poll_in_flight(&mut self, cx: &mut Context) -> Poll<Result<(), Error>> {
+        while let Some(fut) = self.in_flight.front_mut() {
+            let res = ready!(Pin::new(fut).poll(cx));
+            self.in_flight.pop_front();
+            res?;
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    pub(crate) fn poll_ready(
+        &mut self,
+        sock: &Socket,
+        cx: &mut Context,
+    ) -> Poll<Result<(), Error>> {
+        if self.reserved.is_some() {
+            return Poll::Ready(Ok(()));
+        }
+
+        if let Poll::Ready(Err(e)) = self.poll_in_flight(cx) {
+            return Poll::Ready(Err(e));
+        }
+
+        if self.in_flight.len() >= self.buffer_size.max(1) {
+            return Poll::Pending;
+        }
+
+        ready!(sock.poll_reserve(cx));
+        self.reserved = Some(Arc::clone(sock.buffers()));
+
+        Poll::Ready(Ok(()))
+    }
+
+    pub(crate) fn start_send(&mut self, sock: &Socket, multipart: Multipart) {
+        // Sinks should only be sent to after poll_ready, but don't lose the multipart if not
+        if self.reserved.take().is_none() {
+            sock.buffers().force_reserve();
+        }
+
+        self.in_flight.push_back(sock.send_msg(multipart));
+    }
+
+    pub(crate) fn poll_flush(&mut self, cx: &mut Context) -> Poll<Result<(), Error>> {
+        self.poll_in_flight(cx)
+    }
+
+    /// Wait for the poll thread to send the sink's multiparts, giving up after `timeout`, or the
+    /// socket's linger if there isn't one
+    pub(crate) async fn shutdown(
+        mut self,
+        sock: Socket,
+        timeout: Option<Duration>,
+    ) -> Result<ShutdownReport, Error> {
+        // If the session has stopped, the multiparts in flight fail below
+        if let Err(e) = sock.flush(timeout).await {
+            trace!("Flush didn't finish, {}", e);
+        }
+
+        let mut undelivered = 0;
+        while let Some(fut) = self.in_flight.pop_front() {
+            if fut.await.is_err() {
+                undelivered += 1;
+            }
+        }
+
+        Ok(ShutdownReport::new(undelivered))
+    }
+}
+
+impl Drop for SinkType {
+    fn drop(&mut self) {
+        if let Some(buffers) = self.reserved.take() {
+            buffers.unreserve();
+        }
+    }
+}
@@ -17,12 +17,21 @@
  * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::{fmt, marker::PhantomData};
+use std::{
+    fmt,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
 
 use async_zmq_types::{IntoSocket, Multipart};
-use futures::{try_ready, Async, Stream};
+use futures::{future::BoxFuture, ready, Stream};
 
-use crate::{async_types::RecvState, error::Error, socket::Socket};
+use crate::{
+    async_types::RecvState,
+    error::Error,
+    socket::{types::Sub, Socket},
+};
 
 pub struct MultipartStream<T>
 where
@@ -46,6 +55,22 @@ where
     }
 }
 
+impl MultipartStream<Sub> {
+    /// Start receiving messages whose first frame begins with `topic`, see `Sub::subscribe`
+    ///
+    /// Messages that were already queued for the stream are still delivered.
+    pub fn subscribe(&self, topic: &[u8]) -> BoxFuture<'static, Result<(), Error>> {
+        Box::pin(self.sock.subscribe(topic))
+    }
+
+    /// Stop receiving messages for a subscription, see `Sub::unsubscribe`
+    ///
+    /// Messages that were already queued for the stream are still delivered.
+    pub fn unsubscribe(&self, topic: &[u8]) -> BoxFuture<'static, Result<(), Error>> {
+        Box::pin(self.sock.unsubscribe(topic))
+    }
+}
+
 impl<T> IntoSocket<T, Socket> for MultipartStream<T>
 where
     T: From<Socket>,
@@ -59,16 +84,18 @@ impl<T> Stream for MultipartStream<T>
 where
     T: From<Socket>,
 {
-    type Item = Multipart;
-    type Error = Error;
+    type Item = Result<Multipart, Error>;
 
-    fn poll(&mut self) -> Result<Async<Option<Self::Item>>, Self::Error> {
-        let mpart = try_ready!(self.state.poll_fetch(&self.sock));
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let mpart = ready!(this.state.poll_fetch(&this.sock, cx));
 
-        Ok(Async::Ready(Some(mpart)))
+        Poll::Ready(Some(mpart))
     }
 }
 
+impl<T> Unpin for MultipartStream<T> where T: From<Socket> {}
+
 impl<T> fmt::Debug for MultipartStream<T>
 where
     T: From<Socket>,
@@ -17,14 +17,30 @@
  * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
  */
 
+// failure's derive expands into impls nested inside of a const block
+#![allow(non_local_definitions)]
+
+use std::io;
+
+use async_zmq_types::{proxy::ProxyError, BuildError, ConfigError};
 use failure::Fail;
-use futures::sync::oneshot::Canceled;
+use futures::channel::oneshot::Canceled;
 
 #[derive(Debug, Fail)]
 pub enum Error {
     #[fail(display = "Error in zeroMQ, {}", _0)]
     Zmq(#[cause] zmq::Error),
 
+    #[fail(display = "Invalid socket config, {}", _0)]
+    Config(#[cause] ConfigError),
+
+    #[fail(display = "Could not build socket, {}", _0)]
+    Build(#[cause] BuildError),
+
+    #[fail(display = "Could not create proxy: {}", _0)]
+    /// Stores sockets that can't be proxied in either direction
+    Proxy(#[cause] ProxyError),
+
     #[fail(display = "Sender was dropped")]
     Canceled,
 
@@ -33,6 +49,9 @@ pub enum Error {
 
     #[fail(display = "Socket dropped")]
     Dropped,
+
+    #[fail(display = "Error starting poll thread, {}", _0)]
+    Signal(#[cause] io::Error),
 }
 
 impl From<zmq::Error> for Error {
@@ -41,6 +60,27 @@ impl From<zmq::Error> for Error {
     }
 }
 
+impl From<ProxyError> for Error {
+    fn from(e: ProxyError) -> Self {
+        Error::Proxy(e)
+    }
+}
+
+impl From<BuildError> for Error {
+    fn from(e: BuildError) -> Self {
+        match e {
+            BuildError::Zmq(e) => Error::Zmq(e),
+            e => Error::Build(e),
+        }
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(e: ConfigError) -> Self {
+        Error::Config(e)
+    }
+}
+
 impl From<Canceled> for Error {
     fn from(_: Canceled) -> Self {
         Error::Canceled
@@ -23,7 +23,7 @@
 //! with [tokio-zmq](https://docs.rs/tokio-zmq), but unlike tokio-zmq, futures-zmq is OS and
 //! Executor agnostic. This comes at the cost of performance, as futures-zmq relies on spinning up
 //! a separate thread for managing the ZeroMQ sockets, while tokio-zmq can avoid this issue by
-//! letting mio manage the sockets.
+//! letting the Tokio reactor manage the sockets.
 //!
 //! This crate provides Streams, Sinks, and Futures for ZeroMQ Sockets, which deal in structures
 //! caled Multiparts. Currently, a Multipart is a simple wrapper around `VecDeque<zmq::Message>`,
@@ -43,17 +43,12 @@
 //! Without further ado, creating and using a socket:
 //!
 //! ```rust
-//! extern crate zmq;
-//! extern crate futures;
-//! extern crate tokio;
-//! extern crate futures_zmq;
-//!
 //! use std::sync::Arc;
 //!
-//! use futures::{Future, Stream};
+//! use futures::{try_join, StreamExt, TryStreamExt};
 //! use futures_zmq::{prelude::*, Socket, Pub, Sub, Error};
 //!
-//! fn run() -> Result<(), Error> {
+//! async fn run() -> Result<(), Error> {
 //!     // Create a new ZeroMQ Context. This context will be used to create all the sockets.
 //!     let context = Arc::new(zmq::Context::new());
 //!
@@ -68,55 +63,63 @@
 //!         .filter(b"")
 //!         .build();
 //!
+//!     let (zpub, sub): (Pub, Sub) = try_join!(zpub, sub)?;
+//!
 //!     // Create our simple server. This forwards messages from the Subscriber socket to the
 //!     // Publisher socket, and prints them as they go by.
-//!     let runner = zpub
-//!         .join(sub)
-//!         .and_then(|(zpub, sub)| {
-//!             sub.stream()
-//!                 .map(|multipart| {
-//!                     for msg in &multipart {
-//!                         if let Some(msg) = msg.as_str() {
-//!                             println!("Forwarding: {}", msg);
-//!                         }
-//!                     }
-//!                     multipart
-//!                 })
-//!                 .forward(zpub.sink(25))
-//!         });
-//!
-//!     // To avoid an infinte doctest, the actual tokio::run is commented out.
-//!     // tokio::run(runner.map(|_| ()).or_else(|e| {
-//!     //     println!("Error: {}", e);
-//!     // })?;
-//!     # let _ = runner;
-//!     # Ok(())
+//!     sub.stream()
+//!         .map_ok(|multipart| {
+//!             for msg in &multipart {
+//!                 if let Some(msg) = msg.as_str() {
+//!                     println!("Forwarding: {}", msg);
+//!                 }
+//!             }
+//!             multipart
+//!         })
+//!         .forward(zpub.sink(25))
+//!         .await
 //! }
 //!
-//! # fn main() {
-//! #     run().unwrap();
-//! # }
+//! fn main() {
+//!     let runner = run();
+//!
+//!     // To avoid an infinte doctest, the actual executor is commented out.
+//!     // futures::executor::block_on(runner).unwrap();
+//!     # let _ = runner;
+//! }
 //! ```
 
 pub mod async_types;
+pub mod broker;
 pub mod error;
 mod polling;
 pub mod prelude;
+pub mod proxy;
 mod socket;
+pub mod zap;
 
 use lazy_static::lazy_static;
 
-pub use async_zmq_types::Multipart;
+pub use async_zmq_types::{
+    codec, subscription, ConfigError, CurveError, CurveKeyPair, CurvePublicKey, CurveSecretKey,
+    Envelope, EnvelopeError, Multipart, PeerId, ShutdownReport, SocketConfig, SocketEvent,
+    SocketKind,
+};
 
 pub use self::{
     error::Error,
-    polling::{RecvFuture, SendFuture, Session},
+    polling::{RecvFuture, SendFuture, Session, SessionPool, SocketStats},
     socket::{
         types::{Dealer, Pair, Pub, Pull, Push, Rep, Req, Router, Sub, Xpub, Xsub},
         Socket,
     },
 };
 
+#[cfg(feature = "draft")]
+pub use self::socket::types::{Channel, Client, Dish, Gather, Peer, Radio, Scatter, Server};
+#[cfg(feature = "draft")]
+pub use async_zmq_types::draft;
+
 lazy_static! {
     pub static ref SESSION: Session = Session::new();
 }
@@ -0,0 +1,185 @@
+/*
+ * This file is part of Futures ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Futures ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Futures ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains the state a socket shares with the poll thread to bound how many
+//! multiparts the poll thread holds for it.
+//!
+//! Sends are credit-based. A socket takes a credit before handing a multipart to the poll thread,
+//! and the poll thread returns it once the multipart has left its buffer, waking the socket if it
+//! was waiting for room.
+
+use std::{
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    task::{Context, Poll},
+};
+
+use async_zmq_types::SocketOptions;
+use futures::task::AtomicWaker;
+
+const DEFAULT_OUTBOUND: usize = 30;
+const DEFAULT_INBOUND: usize = 0;
+
+/// The sizes of the buffers the poll thread keeps for a socket
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct BufferConfig {
+    pub(crate) outbound: usize,
+    pub(crate) inbound: usize,
+}
+
+impl BufferConfig {
+    pub(crate) fn from_options(options: &SocketOptions) -> Self {
+        BufferConfig {
+            outbound: options.outbound_buffer.unwrap_or(DEFAULT_OUTBOUND).max(1),
+            inbound: options.inbound_buffer.unwrap_or(DEFAULT_INBOUND),
+        }
+    }
+}
+
+impl Default for BufferConfig {
+    fn default() -> Self {
+        BufferConfig::from_options(&SocketOptions::default())
+    }
+}
+
+/// A snapshot of the multiparts the poll thread is holding for a socket, and how many it has
+/// handled
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct SocketStats {
+    /// Multiparts waiting in the poll thread to be sent
+    pub outbound_queued: usize,
+    /// Multiparts the poll thread has read ahead of the socket's consumer
+    pub inbound_queued: usize,
+    /// Multiparts handed to ZeroMQ
+    pub sent: u64,
+    /// Multiparts read from ZeroMQ
+    pub received: u64,
+    /// Multiparts that were thrown away, because sending them failed or the socket was dropped
+    /// while they were buffered
+    pub dropped: u64,
+}
+
+pub(crate) struct Buffers {
+    config: BufferConfig,
+    outbound: AtomicUsize,
+    inbound: AtomicUsize,
+    sent: AtomicU64,
+    received: AtomicU64,
+    dropped: AtomicU64,
+    waker: AtomicWaker,
+}
+
+impl Buffers {
+    pub(crate) fn new(config: BufferConfig) -> Self {
+        Buffers {
+            config,
+            outbound: AtomicUsize::new(0),
+            inbound: AtomicUsize::new(0),
+            sent: AtomicU64::new(0),
+            received: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            waker: AtomicWaker::new(),
+        }
+    }
+
+    pub(crate) fn config(&self) -> BufferConfig {
+        self.config
+    }
+
+    /// Take a credit for a multipart, if the outbound buffer has room
+    pub(crate) fn try_reserve(&self) -> bool {
+        let capacity = self.config.outbound;
+
+        self.outbound
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |queued| {
+                if queued < capacity {
+                    Some(queued + 1)
+                } else {
+                    None
+                }
+            })
+            .is_ok()
+    }
+
+    /// Wait until a credit can be taken
+    pub(crate) fn poll_reserve(&self, cx: &mut Context) -> Poll<()> {
+        if self.try_reserve() {
+            return Poll::Ready(());
+        }
+
+        self.waker.register(cx.waker());
+
+        // A credit may have been returned between the first check and registering
+        if self.try_reserve() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    /// Take a credit even if the outbound buffer is full
+    pub(crate) fn force_reserve(&self) {
+        self.outbound.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Give back a credit that was never used
+    pub(crate) fn unreserve(&self) {
+        self.release();
+    }
+
+    /// Record that a multipart left the outbound buffer by being sent
+    pub(crate) fn sent(&self) {
+        self.sent.fetch_add(1, Ordering::Relaxed);
+        self.release();
+    }
+
+    /// Record that a multipart left the outbound buffer without being sent
+    pub(crate) fn dropped_outbound(&self) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+        self.release();
+    }
+
+    /// Record that a multipart was read into the inbound buffer
+    pub(crate) fn received(&self) {
+        self.received.fetch_add(1, Ordering::Relaxed);
+        self.inbound.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record that a multipart left the inbound buffer, either handed out or thrown away
+    pub(crate) fn taken(&self, dropped: bool) {
+        if dropped {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        self.inbound.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn stats(&self) -> SocketStats {
+        SocketStats {
+            outbound_queued: self.outbound.load(Ordering::Acquire),
+            inbound_queued: self.inbound.load(Ordering::Relaxed),
+            sent: self.sent.load(Ordering::Relaxed),
+            received: self.received.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+
+    fn release(&self) {
+        self.outbound.fetch_sub(1, Ordering::AcqRel);
+        self.waker.wake();
+    }
+}
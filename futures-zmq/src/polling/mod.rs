@@ -17,36 +17,54 @@
  * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-#[cfg(unix)]
-use std::os::unix::io::{AsRawFd, RawFd};
-#[cfg(windows)]
-use std::os::windows::io::{AsRawSocket, RawSocket};
-
 use std::{
     fmt,
-    io::{self, Read, Write},
-    net::{TcpListener, TcpStream},
+    future::Future,
+    pin::Pin,
     sync::{mpsc, Arc, Mutex},
+    task::{Context, Poll},
     thread,
+    time::{Duration, Instant},
 };
 
-use async_zmq_types::Multipart;
-use futures::{executor::Notify, sync::oneshot, Async, Future, Poll};
+#[cfg(feature = "draft")]
+use async_zmq_types::draft::{Group, RoutingId};
+use async_zmq_types::{Multipart, ShutdownReport};
+use futures::{channel::oneshot, ready};
 use log::{error, info, trace};
-use zmq::Socket;
+use zmq::{PollItem, Socket};
 
 use crate::error::Error;
 
+mod buffers;
 mod poll_thread;
 mod pollable;
+mod pool;
+mod signal;
+
+pub(crate) use self::buffers::{BufferConfig, Buffers};
+pub use self::{buffers::SocketStats, pool::SessionPool};
 
-use self::{poll_thread::PollThread, pollable::Pollable};
+use self::{
+    poll_thread::PollThread,
+    pollable::Pollable,
+    signal::{Notify, Wait},
+};
 
-pub struct SockId(usize, Arc<Mutex<SockIdInner>>);
+pub struct SockId(
+    usize,
+    // Only held so the socket is dropped in the poll thread along with the last SockId
+    #[allow(dead_code)] Arc<Mutex<SockIdInner>>,
+    Arc<Buffers>,
+);
 
 impl SockId {
-    fn new(id: usize, tx: Sender) -> Self {
-        SockId(id, Arc::new(Mutex::new(SockIdInner(id, tx))))
+    fn new(id: usize, tx: Sender, buffers: Arc<Buffers>) -> Self {
+        SockId(id, Arc::new(Mutex::new(SockIdInner(id, tx))), buffers)
+    }
+
+    pub(crate) fn buffers(&self) -> &Arc<Buffers> {
+        &self.2
     }
 }
 
@@ -67,82 +85,63 @@ struct SockIdInner(usize, Sender);
 impl Drop for SockIdInner {
     fn drop(&mut self) {
         trace!("Dropping {}", self.0);
-        let _ = self.1.send(Request::DropSocket(self.0));
+        self.1.send(Request::DropSocket(self.0));
     }
 }
 
 pub(crate) enum Request {
-    Init(Socket, oneshot::Sender<SockId>),
+    Init(Socket, BufferConfig, Option<u32>, oneshot::Sender<SockId>),
     SendMessage(usize, Multipart, oneshot::Sender<Response>),
     ReceiveMessage(usize, oneshot::Sender<Response>),
+    CancelReceive(usize),
+    Monitor(usize, String, oneshot::Sender<Result<(), Error>>),
+    Subscribe(usize, Vec<u8>, oneshot::Sender<Result<(), Error>>),
+    Unsubscribe(usize, Vec<u8>, oneshot::Sender<Result<(), Error>>),
+    Bind(usize, String, oneshot::Sender<Result<String, Error>>),
+    Unbind(usize, String, oneshot::Sender<Result<(), Error>>),
+    Connect(usize, String, oneshot::Sender<Result<(), Error>>),
+    Disconnect(usize, String, oneshot::Sender<Result<(), Error>>),
+    #[cfg(feature = "draft")]
+    Join(usize, Group, oneshot::Sender<Result<(), Error>>),
+    #[cfg(feature = "draft")]
+    Leave(usize, Group, oneshot::Sender<Result<(), Error>>),
+    #[cfg(feature = "draft")]
+    ConnectPeer(usize, String, oneshot::Sender<Result<RoutingId, Error>>),
+    Flush(usize, Option<Duration>, oneshot::Sender<()>),
     DropSocket(usize),
+    Drain(Instant, oneshot::Sender<ShutdownReport>),
     Done,
 }
 
 pub(crate) enum Response {
     Sent,
     Received(Multipart),
-    Full(Multipart),
     Error(Error),
 }
 
-struct Channel {
-    tx: TcpStream,
-    rx: TcpStream,
-}
-
-impl Channel {
-    fn notify(&self) {
-        self.drain();
-
-        if let Err(e) = (&self.tx).write(&[1]) {
-            error!("Error notifying channel, {}", e);
-        }
-    }
-
-    fn drain(&self) -> bool {
-        let mut new_data = false;
-        loop {
-            match (&self.rx).read(&mut [0; 32]) {
-                Ok(_) => {
-                    new_data = true;
-                }
-                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => break,
-                Err(e) => panic!("I/O error: {}", e),
-            }
-        }
-        new_data
-    }
-
-    #[cfg(unix)]
-    fn read_fd(&self) -> RawFd {
-        self.rx.as_raw_fd()
-    }
-
-    #[cfg(windows)]
-    fn read_fd(&self) -> RawSocket {
-        self.rx.as_raw_socket()
-    }
-}
-
 #[derive(Clone)]
 pub(crate) struct Sender {
     tx: mpsc::Sender<Request>,
-    channel: Arc<Channel>,
+    notify: Arc<dyn Notify>,
 }
 
 impl Sender {
     fn send(&self, request: Request) {
-        if let Err(_) = self.tx.send(request) {
-            error!("Error sending request");
+        // The request, and any responder in it, is dropped if the session has shut down
+        if self.tx.send(request).is_err() {
+            trace!("Poll thread has stopped, dropping request");
+            return;
+        }
+
+        if let Err(e) = self.notify.notify() {
+            error!("Error notifying poll thread, {}", e);
         }
-        self.channel.notify();
     }
 }
 
 pub(crate) struct Receiver {
     rx: mpsc::Receiver<Request>,
-    channel: Arc<Channel>,
+    wait: Box<dyn Wait>,
 }
 
 impl Receiver {
@@ -150,15 +149,46 @@ impl Receiver {
         self.rx.try_recv().ok()
     }
 
-    /// Returns whether there are messages to look at
-    fn drain(&self) -> bool {
-        self.channel.drain()
+    fn as_poll_item(&self) -> PollItem<'_> {
+        self.wait.as_poll_item()
+    }
+
+    /// How long the poll thread can block for, in milliseconds
+    ///
+    /// The thread blocks until a socket is ready, a request arrives, or `deadline` passes, unless
+    /// requests have already arrived. If the signaler fails, the thread falls back to checking
+    /// for requests every 50 milliseconds.
+    fn poll_timeout(&self, deadline: Option<Instant>) -> i64 {
+        let timeout = match self.wait.drain() {
+            Ok(true) => return 0,
+            Ok(false) => -1,
+            Err(e) => {
+                error!("Error draining poll thread signaler, {}", e);
+                50
+            }
+        };
+
+        match deadline {
+            Some(deadline) => {
+                let left = deadline.saturating_duration_since(Instant::now());
+                // Round up, so the deadline has passed when the thread wakes
+                let millis = (left.as_micros() as i64 + 999) / 1000;
+
+                if timeout < 0 {
+                    millis
+                } else {
+                    millis.min(timeout)
+                }
+            }
+            None => timeout,
+        }
     }
 }
 
 /// A local copy of Session
 ///
 /// This is useful so we don't invoke mutex locks to send commands to the poll thread
+#[derive(Clone)]
 pub struct LocalSession {
     sender: Sender,
     #[allow(dead_code)]
@@ -166,6 +196,9 @@ pub struct LocalSession {
 }
 
 impl LocalSession {
+    /// Hand a multipart to the poll thread
+    ///
+    /// The caller must already hold a credit for the multipart from the socket's buffers.
     pub fn send(&self, id: &SockId, msg: Multipart) -> SendFuture {
         let (tx, rx) = oneshot::channel();
 
@@ -179,112 +212,295 @@ impl LocalSession {
 
         self.sender.send(Request::ReceiveMessage(id.0, tx));
 
-        RecvFuture { rx }
+        RecvFuture {
+            rx,
+            cancel: Some((id.0, self.sender.clone())),
+        }
+    }
+
+    pub fn monitor(&self, id: &SockId, endpoint: String) -> MonitorFuture {
+        let (tx, rx) = oneshot::channel();
+
+        self.sender.send(Request::Monitor(id.0, endpoint, tx));
+
+        MonitorFuture { rx }
+    }
+
+    /// Have the poll thread subscribe a SUB or XPUB socket to `topic`
+    pub fn subscribe(&self, id: &SockId, topic: Vec<u8>) -> SubscribeFuture {
+        let (tx, rx) = oneshot::channel();
+
+        self.sender.send(Request::Subscribe(id.0, topic, tx));
+
+        SubscribeFuture { rx }
+    }
+
+    /// Have the poll thread unsubscribe a SUB or XPUB socket from `topic`
+    pub fn unsubscribe(&self, id: &SockId, topic: Vec<u8>) -> SubscribeFuture {
+        let (tx, rx) = oneshot::channel();
+
+        self.sender.send(Request::Unsubscribe(id.0, topic, tx));
+
+        SubscribeFuture { rx }
+    }
+
+    /// Have the poll thread bind a socket to `endpoint`
+    pub fn bind(&self, id: &SockId, endpoint: String) -> BindFuture {
+        let (tx, rx) = oneshot::channel();
+
+        self.sender.send(Request::Bind(id.0, endpoint, tx));
+
+        BindFuture { rx }
+    }
+
+    /// Have the poll thread unbind a socket from `endpoint`
+    pub fn unbind(&self, id: &SockId, endpoint: String) -> EndpointFuture {
+        let (tx, rx) = oneshot::channel();
+
+        self.sender.send(Request::Unbind(id.0, endpoint, tx));
+
+        EndpointFuture { rx }
+    }
+
+    /// Have the poll thread connect a socket to `endpoint`
+    pub fn connect(&self, id: &SockId, endpoint: String) -> EndpointFuture {
+        let (tx, rx) = oneshot::channel();
+
+        self.sender.send(Request::Connect(id.0, endpoint, tx));
+
+        EndpointFuture { rx }
+    }
+
+    /// Have the poll thread disconnect a socket from `endpoint`
+    pub fn disconnect(&self, id: &SockId, endpoint: String) -> EndpointFuture {
+        let (tx, rx) = oneshot::channel();
+
+        self.sender.send(Request::Disconnect(id.0, endpoint, tx));
+
+        EndpointFuture { rx }
+    }
+
+    /// Have the poll thread join a DISH socket to `group`
+    #[cfg(feature = "draft")]
+    pub fn join(&self, id: &SockId, group: Group) -> SubscribeFuture {
+        let (tx, rx) = oneshot::channel();
+
+        self.sender.send(Request::Join(id.0, group, tx));
+
+        SubscribeFuture { rx }
+    }
+
+    /// Have the poll thread make a DISH socket leave `group`
+    #[cfg(feature = "draft")]
+    pub fn leave(&self, id: &SockId, group: Group) -> SubscribeFuture {
+        let (tx, rx) = oneshot::channel();
+
+        self.sender.send(Request::Leave(id.0, group, tx));
+
+        SubscribeFuture { rx }
+    }
+
+    /// Have the poll thread connect a PEER socket to `endpoint`
+    #[cfg(feature = "draft")]
+    pub fn connect_peer(&self, id: &SockId, endpoint: String) -> ConnectPeerFuture {
+        let (tx, rx) = oneshot::channel();
+
+        self.sender.send(Request::ConnectPeer(id.0, endpoint, tx));
+
+        ConnectPeerFuture { rx }
+    }
+
+    /// Wait until every multipart queued for the socket has been sent, dropping what's left after
+    /// `timeout`, or the socket's ZMQ_LINGER if there isn't one
+    pub fn flush(&self, id: &SockId, timeout: Option<Duration>) -> FlushFuture {
+        let (tx, rx) = oneshot::channel();
+
+        self.sender.send(Request::Flush(id.0, timeout, tx));
+
+        FlushFuture { rx }
     }
 
     pub fn init(&self, sock: Socket) -> InitFuture {
+        self.init_with_config(sock, BufferConfig::default(), None)
+    }
+
+    /// Hand a built socket to the poll thread, along with the options it keeps applying
+    pub(crate) fn init_with_config(
+        &self,
+        sock: Socket,
+        buffers: BufferConfig,
+        ipc_permissions: Option<u32>,
+    ) -> InitFuture {
         let (tx, rx) = oneshot::channel();
 
-        self.sender.send(Request::Init(sock, tx));
+        self.sender
+            .send(Request::Init(sock, buffers, ipc_permissions, tx));
 
         InitFuture { rx }
     }
 }
 
+/// A poll thread, and the handle used to send it sockets
+///
+/// Every socket is polled from the thread of the session it was built in. Sockets built with
+/// `Build::build` use the global `SESSION`, while `Build::build_in` takes a session to use, so
+/// sockets can be spread over several threads, or kept apart from each other.
 #[derive(Clone)]
 pub struct Session {
     inner: Arc<Mutex<Option<InnerSession>>>,
+    tx: Sender,
+    thread: Arc<Mutex<Option<thread::JoinHandle<()>>>>,
 }
 
 impl Session {
+    /// Create a session, and start its poll thread
+    ///
+    /// ### Panics
+    /// This panics if the poll thread's wakeup signaler can't be created, see `Session::try_new`.
     pub fn new() -> Self {
-        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
-        let addr = listener.local_addr().unwrap();
+        Session::try_new().expect("Failed to create session")
+    }
 
-        let conn1 = TcpStream::connect(&addr).unwrap();
-        let conn2 = listener.accept().unwrap().0;
+    /// Create a session, and start its poll thread
+    ///
+    /// The poll thread is woken with an eventfd on Linux, a pipe on other unix systems, and an
+    /// inproc PAIR pair otherwise, or when neither can be created.
+    pub fn try_new() -> Result<Self, Error> {
+        let (notify, wait) = signal::signaler().map_err(Error::Signal)?;
 
-        drop(listener);
+        let (tx, rx) = mpsc::channel();
 
-        conn1.set_nonblocking(true).unwrap();
-        conn2.set_nonblocking(true).unwrap();
+        let tx = Sender { tx, notify };
+        let rx = Receiver { rx, wait };
 
-        let channel = Arc::new(Channel {
-            tx: conn1,
-            rx: conn2,
-        });
+        let tx2 = tx.clone();
 
-        let (tx, rx) = mpsc::channel();
+        let handle = thread::Builder::new()
+            .name("futures-zmq-poll".to_owned())
+            .spawn(move || {
+                PollThread::new(tx2, rx).run();
+            })
+            .map_err(Error::Signal)?;
+
+        Ok(Session {
+            inner: InnerSession::init(tx.clone()),
+            tx,
+            thread: Arc::new(Mutex::new(Some(handle))),
+        })
+    }
 
-        let tx = Sender {
-            tx: tx.clone(),
-            channel: channel.clone(),
-        };
-        let rx = Receiver {
-            rx: rx,
-            channel: channel,
-        };
+    /// Stop the session's poll thread, and wait for it to exit
+    ///
+    /// Sends and receives that are waiting on the thread fail with `Error::Dropped`, and the
+    /// thread closes its sockets before exiting. Sockets built in this session can't be used
+    /// afterwards, and building new ones fails.
+    pub fn shutdown(&self) {
+        *self.inner.lock().unwrap() = None;
 
-        let tx2 = tx.clone();
+        self.join();
+    }
+
+    /// Stop the session's poll thread once it has sent everything it's holding, waiting at most
+    /// `timeout`
+    ///
+    /// From the moment the drain starts, waiting receives fail with `Error::Dropped`, and new
+    /// requests are treated as they would be after `Session::shutdown`. The thread keeps sending
+    /// the multiparts its sockets have already queued until they're all sent or `timeout` passes.
+    /// The report counts the multiparts that were dropped instead, then the thread closes its
+    /// sockets, which hold on to anything ZeroMQ hasn't sent yet for as long as ZMQ_LINGER allows.
+    pub fn drain(&self, timeout: Duration) -> ShutdownReport {
+        let (tx, mut rx) = oneshot::channel();
+
+        self.tx.send(Request::Drain(Instant::now() + timeout, tx));
+        *self.inner.lock().unwrap() = None;
 
-        thread::spawn(move || {
-            PollThread::new(tx2, rx).run();
-        });
+        self.join();
 
-        Session {
-            inner: InnerSession::init(tx),
+        match rx.try_recv() {
+            Ok(Some(report)) => report,
+            _ => ShutdownReport::default(),
         }
     }
 
-    pub fn shutdown(&self) {
-        *self.inner.lock().unwrap() = None;
+    fn join(&self) {
+        let handle = self.thread.lock().unwrap().take();
+
+        if let Some(handle) = handle {
+            if handle.thread().id() == thread::current().id() {
+                return;
+            }
+
+            if handle.join().is_err() {
+                error!("Poll thread panicked");
+            }
+        }
     }
 
     pub fn local_session(&self) -> LocalSession {
         let session = self.clone();
-        let sender = self.inner.lock().unwrap().as_ref().unwrap().tx.clone();
+        let sender = self.tx.clone();
 
         LocalSession { sender, session }
     }
 }
 
+impl Default for Session {
+    fn default() -> Self {
+        Session::new()
+    }
+}
+
+/// A multipart being sent by the poll thread
+///
+/// Dropping the future doesn't cancel the send, the multipart was already handed to the poll
+/// thread and is sent regardless.
 pub struct SendFuture {
     rx: oneshot::Receiver<Response>,
 }
 
 impl Future for SendFuture {
-    type Item = Option<Multipart>;
-    type Error = Error;
-
-    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-        match self.rx.poll()? {
-            Async::Ready(res) => match res {
-                Response::Sent => Ok(Async::Ready(None)),
-                Response::Full(msg) => Ok(Async::Ready(Some(msg))),
-                Response::Error(e) => Err(e),
-                _ => panic!("Response kind was not sent"),
-            },
-            Async::NotReady => Ok(Async::NotReady),
+    type Output = Result<(), Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match ready!(Pin::new(&mut self.rx).poll(cx))? {
+            Response::Sent => Poll::Ready(Ok(())),
+            Response::Error(e) => Poll::Ready(Err(e)),
+            _ => panic!("Response kind was not sent"),
         }
     }
 }
 
+/// A multipart being received by the poll thread
+///
+/// Dropping the future before it resolves tells the poll thread to stop waiting for a multipart
+/// on its behalf. A multipart the thread has already read stays in the socket's inbound buffer for
+/// the next receive.
 pub struct RecvFuture {
     rx: oneshot::Receiver<Response>,
+    cancel: Option<(usize, Sender)>,
 }
 
 impl Future for RecvFuture {
-    type Item = Multipart;
-    type Error = Error;
-
-    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-        match self.rx.poll()? {
-            Async::Ready(res) => match res {
-                Response::Received(msg) => Ok(Async::Ready(msg)),
-                Response::Error(e) => Err(e),
-                _ => panic!("Response kind was not received"),
-            },
-            Async::NotReady => Ok(Async::NotReady),
+    type Output = Result<Multipart, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let res = ready!(Pin::new(&mut self.rx).poll(cx));
+        self.cancel = None;
+
+        match res? {
+            Response::Received(msg) => Poll::Ready(Ok(msg)),
+            Response::Error(e) => Poll::Ready(Err(e)),
+            _ => panic!("Response kind was not received"),
+        }
+    }
+}
+
+impl Drop for RecvFuture {
+    fn drop(&mut self) {
+        if let Some((id, sender)) = self.cancel.take() {
+            // Closing first lets the poll thread tell this responder apart from newer ones
+            self.rx.close();
+            sender.send(Request::CancelReceive(id));
         }
     }
 }
@@ -294,57 +510,108 @@ pub struct InitFuture {
 }
 
 impl Future for InitFuture {
-    type Item = SockId;
-    type Error = Error;
+    type Output = Result<SockId, Error>;
 
-    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
-        Ok(self.rx.poll()?)
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        Poll::Ready(Ok(ready!(Pin::new(&mut self.rx).poll(cx))?))
     }
 }
 
-struct InnerSession {
-    tx: Sender,
+pub struct FlushFuture {
+    rx: oneshot::Receiver<()>,
 }
 
-impl InnerSession {
-    fn init(tx: Sender) -> Arc<Mutex<Option<Self>>> {
-        Arc::new(Mutex::new(Some(InnerSession { tx })))
+impl Future for FlushFuture {
+    type Output = Result<(), Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        Poll::Ready(Ok(ready!(Pin::new(&mut self.rx).poll(cx))?))
     }
 }
 
-impl Drop for InnerSession {
-    fn drop(&mut self) {
-        info!("Dropping session");
-        self.tx.send(Request::Done);
+pub struct MonitorFuture {
+    rx: oneshot::Receiver<Result<(), Error>>,
+}
+
+impl Future for MonitorFuture {
+    type Output = Result<(), Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        ready!(Pin::new(&mut self.rx).poll(cx))??;
+
+        Poll::Ready(Ok(()))
     }
 }
 
-#[derive(Clone)]
-struct NotifyCanceled {
-    channel: Arc<Channel>,
+/// A subscription being changed by the poll thread
+pub struct SubscribeFuture {
+    rx: oneshot::Receiver<Result<(), Error>>,
+}
+
+impl Future for SubscribeFuture {
+    type Output = Result<(), Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        ready!(Pin::new(&mut self.rx).poll(cx))??;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// A socket being bound by the poll thread, resolving to the endpoint it was bound to
+pub struct BindFuture {
+    rx: oneshot::Receiver<Result<String, Error>>,
 }
 
-impl NotifyCanceled {
-    fn new(channel: Arc<Channel>) -> Self {
-        NotifyCanceled { channel }
+impl Future for BindFuture {
+    type Output = Result<String, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        Poll::Ready(ready!(Pin::new(&mut self.rx).poll(cx))?)
     }
 }
 
-impl Notify for NotifyCanceled {
-    fn notify(&self, _id: usize) {
-        self.channel.notify();
+/// A socket being unbound, connected, or disconnected by the poll thread
+pub struct EndpointFuture {
+    rx: oneshot::Receiver<Result<(), Error>>,
+}
+
+impl Future for EndpointFuture {
+    type Output = Result<(), Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        Poll::Ready(ready!(Pin::new(&mut self.rx).poll(cx))?)
     }
 }
 
-struct CheckCanceled<'a> {
-    sender: &'a mut oneshot::Sender<Response>,
+/// A PEER socket being connected by the poll thread, resolving to the new peer's routing id
+#[cfg(feature = "draft")]
+pub struct ConnectPeerFuture {
+    rx: oneshot::Receiver<Result<RoutingId, Error>>,
 }
 
-impl<'a> Future for CheckCanceled<'a> {
-    type Item = ();
-    type Error = ();
+#[cfg(feature = "draft")]
+impl Future for ConnectPeerFuture {
+    type Output = Result<RoutingId, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        Poll::Ready(ready!(Pin::new(&mut self.rx).poll(cx))?)
+    }
+}
+
+struct InnerSession {
+    tx: Sender,
+}
 
-    fn poll(&mut self) -> Poll<(), ()> {
-        self.sender.poll_cancel()
+impl InnerSession {
+    fn init(tx: Sender) -> Arc<Mutex<Option<Self>>> {
+        Arc::new(Mutex::new(Some(InnerSession { tx })))
+    }
+}
+
+impl Drop for InnerSession {
+    fn drop(&mut self) {
+        info!("Dropping session");
+        self.tx.send(Request::Done);
     }
 }
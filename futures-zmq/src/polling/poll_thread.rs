@@ -17,16 +17,15 @@
  * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::{collections::BTreeMap, sync::Arc};
+use std::{collections::BTreeMap, sync::Arc, time::Instant};
+
+use async_zmq_types::ShutdownReport;
+use futures::channel::oneshot;
 
-use futures::{executor, sync::oneshot, Async};
 use log::{error, info, trace};
-use zmq::{poll, PollItem, POLLIN};
+use zmq::poll;
 
-use super::{
-    Channel, CheckCanceled, Error, NotifyCanceled, Pollable, Receiver, Request, Response, Sender,
-    SockId,
-};
+use super::{Buffers, Error, Pollable, Receiver, Request, Response, Sender, SockId};
 
 enum Action {
     Rcv(usize),
@@ -38,79 +37,143 @@ pub(crate) struct PollThread {
     tx: Sender,
     rx: Receiver,
     should_stop: bool,
-    notify: Arc<NotifyCanceled>,
+    draining: Option<(Instant, oneshot::Sender<ShutdownReport>)>,
+    // Multiparts dropped along with their socket since the drain started
+    undelivered: usize,
     sockets: BTreeMap<usize, Pollable>,
-    channel: Arc<Channel>,
 }
 
 impl PollThread {
     pub(crate) fn new(tx: Sender, rx: Receiver) -> Self {
-        let channel = rx.channel.clone();
-
         PollThread {
             next_sock_id: 0,
             tx,
             rx,
             should_stop: false,
-            notify: Arc::new(NotifyCanceled::new(channel.clone())),
+            draining: None,
+            undelivered: 0,
             sockets: BTreeMap::new(),
-            channel,
         }
     }
 
     pub(crate) fn run(&mut self) {
-        loop {
+        while !self.should_stop {
             self.turn();
         }
+
+        self.stop();
+    }
+
+    /// Answer every outstanding request and close the sockets
+    fn stop(&mut self) {
+        while let Some(request) = self.rx.try_recv() {
+            self.respond_stopping(request);
+        }
+
+        let undelivered = self.undelivered
+            + self
+                .sockets
+                .values()
+                .map(Pollable::outbound_len)
+                .sum::<usize>();
+
+        for pollable in self.sockets.values_mut() {
+            pollable.drop_buffers();
+        }
+        self.sockets.clear();
+
+        if let Some((_, responder)) = self.draining.take() {
+            if responder.send(ShutdownReport::new(undelivered)).is_err() {
+                error!("Error responding with drained session");
+            }
+        }
+
+        info!("Poll thread stopped");
     }
 
     fn try_recv(&mut self) {
         while let Some(msg) = self.rx.try_recv() {
-            if !self.should_stop {
+            if !self.should_stop && self.draining.is_none() {
                 self.handle_request(msg);
             } else {
                 self.respond_stopping(msg);
             }
         }
-        self.rx.drain();
     }
 
     fn respond_stopping(&mut self, request: Request) {
         match request {
-            Request::Init(_, responder) => {
+            Request::Init(_, config, _, responder) => {
                 let id = self.next_sock_id;
+                let buffers = Arc::new(Buffers::new(config));
 
-                if let Err(_) = responder.send(SockId::new(id, self.tx.clone())) {
+                if responder
+                    .send(SockId::new(id, self.tx.clone(), buffers))
+                    .is_err()
+                {
                     error!("Error responding with init socket, {}", id);
                 }
 
                 self.next_sock_id += 1;
             }
             Request::SendMessage(id, _, responder) => {
-                if let Err(_) = responder.send(Response::Error(Error::Dropped)) {
+                if let Some(pollable) = self.sockets.get(&id) {
+                    pollable.buffers().dropped_outbound();
+                }
+
+                if responder.send(Response::Error(Error::Dropped)).is_err() {
                     error!("Error responding with dropped, {}", id);
                 }
             }
             Request::ReceiveMessage(id, responder) => {
-                if let Err(_) = responder.send(Response::Error(Error::Dropped)) {
+                if responder.send(Response::Error(Error::Dropped)).is_err() {
+                    error!("Error responding with dropped, {}", id);
+                }
+            }
+            Request::CancelReceive(_) => (),
+            Request::Flush(id, timeout, responder) => {
+                // While draining, a socket's flush can still finish before the thread stops
+                match self.sockets.get_mut(&id) {
+                    Some(pollable) => pollable.wait_for_flush(timeout, responder),
+                    None => drop(responder),
+                }
+            }
+            Request::Monitor(id, _, responder)
+            | Request::Subscribe(id, _, responder)
+            | Request::Unsubscribe(id, _, responder)
+            | Request::Unbind(id, _, responder)
+            | Request::Connect(id, _, responder)
+            | Request::Disconnect(id, _, responder) => {
+                if responder.send(Err(Error::Dropped)).is_err() {
+                    error!("Error responding with dropped, {}", id);
+                }
+            }
+            Request::Bind(id, _, responder) => {
+                if responder.send(Err(Error::Dropped)).is_err() {
+                    error!("Error responding with dropped, {}", id);
+                }
+            }
+            #[cfg(feature = "draft")]
+            Request::Join(id, _, responder) | Request::Leave(id, _, responder) => {
+                if responder.send(Err(Error::Dropped)).is_err() {
+                    error!("Error responding with dropped, {}", id);
+                }
+            }
+            #[cfg(feature = "draft")]
+            Request::ConnectPeer(id, _, responder) => {
+                if responder.send(Err(Error::Dropped)).is_err() {
                     error!("Error responding with dropped, {}", id);
                 }
             }
             Request::DropSocket(id) => {
                 if let Some(mut pollable) = self.sockets.remove(&id) {
-                    if let Some(responder) = pollable.send_responder() {
-                        if let Err(_) = responder.send(Response::Error(Error::Dropped)) {
-                            error!("Error notifying dropped socket, {}", id);
-                        }
-                    }
-
-                    if let Some(responder) = pollable.recv_responder() {
-                        if let Err(_) = responder.send(Response::Error(Error::Dropped)) {
-                            error!("Error notifying dropped socket, {}", id);
-                        }
-                    }
+                    self.undelivered += pollable.outbound_len();
+                    pollable.drop_buffers();
                 }
             }
+            Request::Drain(deadline, responder) => self.start_draining(deadline, responder),
+            // A draining session stops itself once it has finished sending
+            Request::Done if self.draining.is_some() => (),
             Request::Done => {
                 info!("Handling done");
                 self.should_stop = true;
@@ -118,13 +181,28 @@ impl PollThread {
         }
     }
 
+    fn start_draining(&mut self, deadline: Instant, responder: oneshot::Sender<ShutdownReport>) {
+        info!("Draining");
+
+        for pollable in self.sockets.values_mut() {
+            pollable.stop_receiving();
+        }
+
+        self.draining = Some((deadline, responder));
+    }
+
     fn handle_request(&mut self, request: Request) {
         match request {
-            Request::Init(sock, responder) => {
+            Request::Init(sock, config, ipc_permissions, responder) => {
                 let id = self.next_sock_id;
+                let buffers = Arc::new(Buffers::new(config));
+                let pollable = Pollable::new(sock, id, Arc::clone(&buffers), ipc_permissions);
 
-                self.sockets.insert(id, Pollable::new(sock, id));
-                if let Err(_) = responder.send(SockId::new(id, self.tx.clone())) {
+                self.sockets.insert(id, pollable);
+                if responder
+                    .send(SockId::new(id, self.tx.clone(), buffers))
+                    .is_err()
+                {
                     error!("Error responding with init socket, {}", id);
                 }
 
@@ -132,24 +210,13 @@ impl PollThread {
             }
             Request::SendMessage(id, message, responder) => {
                 if let Some(pollable) = self.sockets.get_mut(&id) {
-                    if let Some(message) = pollable.queue_message(message) {
-                        trace!("Buffer full, flushing, {}", pollable.id());
-                        pollable.flush_multiparts();
-
-                        if let Some(msg) = pollable.queue_message(message) {
-                            trace!("Buffer full, {}", id);
-                            if let Err(_) = responder.send(Response::Full(msg)) {
-                                error!("Error notifying of full buffer, {}", id);
-                            }
-                            return;
-                        }
-                    }
+                    trace!("Queueing message, {}", pollable.id());
+                    pollable.queue_message(message, responder);
                     pollable.write();
-                    pollable.set_send_responder(responder);
                     pollable.flush_multiparts();
                 } else {
                     error!("Tried to send to dropped socket, {}", id);
-                    if let Err(_) = responder.send(Response::Error(Error::Dropped)) {
+                    if responder.send(Response::Error(Error::Dropped)).is_err() {
                         error!("Error responding with dropped, {}", id);
                     }
                 }
@@ -161,26 +228,60 @@ impl PollThread {
                     pollable.fetch_multiparts();
                 } else {
                     error!("Tried to receive from dropped socket, {}", id);
-                    if let Err(_) = responder.send(Response::Error(Error::Dropped)) {
+                    if responder.send(Response::Error(Error::Dropped)).is_err() {
                         error!("Error responding with dropped, {}", id);
                     }
                 }
             }
+            Request::CancelReceive(id) => {
+                if let Some(pollable) = self.sockets.get_mut(&id) {
+                    pollable.cancel_recv();
+                }
+            }
+            Request::Flush(id, timeout, responder) => {
+                if let Some(pollable) = self.sockets.get_mut(&id) {
+                    pollable.wait_for_flush(timeout, responder);
+                }
+            }
+            Request::Monitor(id, endpoint, responder) => {
+                self.configure(id, "monitor", responder, |p| p.monitor(&endpoint))
+            }
+            Request::Subscribe(id, topic, responder) => {
+                self.configure(id, "subscribe", responder, |p| p.subscribe(&topic))
+            }
+            Request::Unsubscribe(id, topic, responder) => {
+                self.configure(id, "unsubscribe", responder, |p| p.unsubscribe(&topic))
+            }
+            Request::Bind(id, endpoint, responder) => {
+                self.configure(id, "bind", responder, |p| p.bind(&endpoint))
+            }
+            Request::Unbind(id, endpoint, responder) => {
+                self.configure(id, "unbind", responder, |p| p.unbind(&endpoint))
+            }
+            Request::Connect(id, endpoint, responder) => {
+                self.configure(id, "connect", responder, |p| p.connect(&endpoint))
+            }
+            Request::Disconnect(id, endpoint, responder) => {
+                self.configure(id, "disconnect", responder, |p| p.disconnect(&endpoint))
+            }
+            #[cfg(feature = "draft")]
+            Request::Join(id, group, responder) => {
+                self.configure(id, "join", responder, |p| p.join(&group))
+            }
+            #[cfg(feature = "draft")]
+            Request::Leave(id, group, responder) => {
+                self.configure(id, "leave", responder, |p| p.leave(&group))
+            }
+            #[cfg(feature = "draft")]
+            Request::ConnectPeer(id, endpoint, responder) => {
+                self.configure(id, "connect peer", responder, |p| p.connect_peer(&endpoint))
+            }
             Request::DropSocket(id) => {
                 if let Some(mut pollable) = self.sockets.remove(&id) {
-                    if let Some(responder) = pollable.send_responder() {
-                        if let Err(_) = responder.send(Response::Error(Error::Dropped)) {
-                            error!("Error notifying dropped socket, {}", id);
-                        }
-                    }
-
-                    if let Some(responder) = pollable.recv_responder() {
-                        if let Err(_) = responder.send(Response::Error(Error::Dropped)) {
-                            error!("Error notifying dropped socket, {}", id);
-                        }
-                    }
+                    pollable.drop_buffers();
                 }
             }
+            Request::Drain(deadline, responder) => self.start_draining(deadline, responder),
             Request::Done => {
                 info!("Handling done");
                 self.should_stop = true;
@@ -188,35 +289,52 @@ impl PollThread {
         }
     }
 
-    fn check_responder(
-        notify: &Arc<NotifyCanceled>,
-        sender: &mut oneshot::Sender<Response>,
-    ) -> bool {
-        let mut cancel_check = executor::spawn(CheckCanceled { sender });
+    /// Change a socket's configuration, and respond with the result
+    fn configure<T, E, F>(
+        &self,
+        id: usize,
+        action: &str,
+        responder: oneshot::Sender<Result<T, Error>>,
+        f: F,
+    ) where
+        F: FnOnce(&Pollable) -> Result<T, E>,
+        Error: From<E>,
+    {
+        let res = match self.sockets.get(&id) {
+            Some(pollable) => f(pollable).map_err(Error::from),
+            None => {
+                error!("Tried to {} dropped socket, {}", action, id);
+                Err(Error::Dropped)
+            }
+        };
 
-        if let Ok(Async::Ready(())) = cancel_check.poll_future_notify(notify, 0) {
-            true
-        } else {
-            false
+        if responder.send(res).is_err() {
+            error!("Error responding with {}, {}", action, id);
         }
     }
 
-    fn drop_inactive(&mut self) {
-        for ref mut pollable in self.sockets.values_mut() {
-            if let Some(mut responder) = pollable.recv_responder() {
-                let to_clear = Self::check_responder(&self.notify, &mut responder);
+    /// The earliest moment the thread needs to wake up for, if any
+    fn next_deadline(&self) -> Option<Instant> {
+        let flushes = self.sockets.values().filter_map(Pollable::flush_deadline);
+        let drain = self.draining.as_ref().map(|(deadline, _)| *deadline);
 
-                if !to_clear {
-                    pollable.set_recv_responder(responder);
-                }
-            }
+        flushes.chain(drain).min()
+    }
 
-            if let Some(mut responder) = pollable.send_responder() {
-                let to_clear = Self::check_responder(&self.notify, &mut responder);
+    /// Give up on flushes that have waited too long, and stop once a drain has finished
+    fn check_deadlines(&mut self) {
+        let now = Instant::now();
 
-                if !to_clear {
-                    pollable.set_send_responder(responder);
-                }
+        for pollable in self.sockets.values_mut() {
+            pollable.expire_flush(now);
+        }
+
+        if let Some((deadline, _)) = self.draining {
+            let drained = self.sockets.values().all(|p| p.outbound_len() == 0);
+
+            if drained || deadline <= now {
+                info!("Drained");
+                self.should_stop = true;
             }
         }
     }
@@ -228,14 +346,10 @@ impl PollThread {
             .map(|(id, p)| (id, p.as_poll_item()))
             .unzip();
 
-        let io_item = PollItem::from_fd(self.channel.read_fd(), POLLIN);
-        poll_items.push(io_item);
+        poll_items.push(self.rx.as_poll_item());
 
-        let res = if self.channel.drain() {
-            poll(&mut poll_items, 0)
-        } else {
-            poll(&mut poll_items, 50)
-        };
+        let timeout = self.rx.poll_timeout(self.next_deadline());
+        let res = poll(&mut poll_items, timeout);
 
         let _num_signalled = match res {
             Ok(num) => num,
@@ -262,26 +376,29 @@ impl PollThread {
         for action in actions {
             match action {
                 Action::Snd(id) => {
-                    self.sockets.get_mut(&id).map(|s| {
+                    if let Some(s) = self.sockets.get_mut(&id) {
                         if s.is_writable() {
                             s.flush_multiparts()
                         }
-                    });
+                    }
                 }
                 Action::Rcv(id) => {
-                    self.sockets.get_mut(&id).map(|s| {
+                    if let Some(s) = self.sockets.get_mut(&id) {
                         if s.is_readable() {
                             s.fetch_multiparts()
                         }
-                    });
+                    }
                 }
             }
         }
     }
 
     fn turn(&mut self) {
-        self.drop_inactive();
         self.try_recv();
-        self.poll();
+        self.check_deadlines();
+
+        if !self.should_stop {
+            self.poll();
+        }
     }
 }
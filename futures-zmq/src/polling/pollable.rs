@@ -17,35 +17,61 @@
  * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::{collections::VecDeque, mem::replace};
-
-use async_zmq_types::Multipart;
-use futures::sync::oneshot;
+use std::{
+    collections::VecDeque,
+    mem::replace,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "draft")]
+use async_zmq_types::draft::{self, Group, RoutingId};
+use async_zmq_types::{BuildError, Multipart};
+use futures::channel::oneshot;
 use log::{error, trace, warn};
-use zmq::{Message, PollEvents, PollItem, Socket, DONTWAIT, POLLIN, POLLOUT, SNDMORE};
+use zmq::{Message, PollEvents, PollItem, Socket, DONTWAIT, POLLIN, POLLOUT};
 
-use super::Response;
+use super::{Buffers, Response};
+use crate::error::Error;
 
 pub(crate) struct Pollable {
     sock: Socket,
     id: usize,
+    // Applied to the files of ipc endpoints bound after the socket was built
+    ipc_permissions: Option<u32>,
     kind: PollKind,
-    outbound_message_buffer: VecDeque<Multipart>,
+    buffers: Arc<Buffers>,
+    outbound_message_buffer: VecDeque<(Multipart, oneshot::Sender<Response>)>,
+    inbound_message_buffer: VecDeque<Multipart>,
+    inbound_error: Option<zmq::Error>,
     inbound_message_cache: Multipart,
-    send_responder: Option<oneshot::Sender<Response>>,
     recv_responder: Option<oneshot::Sender<Response>>,
+    reading: bool,
+    flush_responders: Vec<oneshot::Sender<()>>,
+    flush_deadline: Option<Instant>,
 }
 
 impl Pollable {
-    pub(crate) fn new(sock: Socket, id: usize) -> Self {
+    pub(crate) fn new(
+        sock: Socket,
+        id: usize,
+        buffers: Arc<Buffers>,
+        ipc_permissions: Option<u32>,
+    ) -> Self {
         Pollable {
             sock,
             id,
-            kind: PollKind::UNUSED,
+            ipc_permissions,
+            kind: PollKind::Unused,
+            buffers,
             outbound_message_buffer: VecDeque::new(),
+            inbound_message_buffer: VecDeque::new(),
+            inbound_error: None,
             inbound_message_cache: Multipart::new(),
-            send_responder: None,
             recv_responder: None,
+            reading: false,
+            flush_responders: Vec::new(),
+            flush_deadline: None,
         }
     }
 
@@ -53,15 +79,56 @@ impl Pollable {
         self.id
     }
 
-    pub(crate) fn send_responder(&mut self) -> Option<oneshot::Sender<Response>> {
-        self.send_responder.take()
+    pub(crate) fn buffers(&self) -> &Arc<Buffers> {
+        &self.buffers
+    }
+
+    pub(crate) fn monitor(&self, endpoint: &str) -> zmq::Result<()> {
+        self.sock.monitor(endpoint, zmq::SocketEvent::ALL as i32)
+    }
+
+    pub(crate) fn subscribe(&self, topic: &[u8]) -> zmq::Result<()> {
+        self.sock.set_subscribe(topic)
+    }
+
+    pub(crate) fn unsubscribe(&self, topic: &[u8]) -> zmq::Result<()> {
+        self.sock.set_unsubscribe(topic)
     }
 
-    pub(crate) fn recv_responder(&mut self) -> Option<oneshot::Sender<Response>> {
-        self.recv_responder.take()
+    /// Bind to `endpoint`, returning the endpoint ZeroMQ resolved it to
+    pub(crate) fn bind(&self, endpoint: &str) -> Result<String, BuildError> {
+        async_zmq_types::bind_endpoint(&self.sock, endpoint, self.ipc_permissions)
     }
 
-    pub(crate) fn as_poll_item(&self) -> PollItem {
+    pub(crate) fn unbind(&self, endpoint: &str) -> zmq::Result<()> {
+        async_zmq_types::unbind(&self.sock, endpoint)
+    }
+
+    pub(crate) fn connect(&self, endpoint: &str) -> Result<(), BuildError> {
+        async_zmq_types::connect_endpoint(&self.sock, endpoint)
+    }
+
+    pub(crate) fn disconnect(&self, endpoint: &str) -> zmq::Result<()> {
+        self.sock.disconnect(endpoint)
+    }
+
+    #[cfg(feature = "draft")]
+    pub(crate) fn join(&self, group: &Group) -> zmq::Result<()> {
+        draft::join(&self.sock, group)
+    }
+
+    #[cfg(feature = "draft")]
+    pub(crate) fn leave(&self, group: &Group) -> zmq::Result<()> {
+        draft::leave(&self.sock, group)
+    }
+
+    /// Connect a PEER socket, returning the routing id of the new peer
+    #[cfg(feature = "draft")]
+    pub(crate) fn connect_peer(&self, endpoint: &str) -> zmq::Result<RoutingId> {
+        draft::connect_peer(&self.sock, endpoint)
+    }
+
+    pub(crate) fn as_poll_item(&self) -> PollItem<'_> {
         self.sock.as_poll_item(self.kind.as_events())
     }
 
@@ -93,27 +160,156 @@ impl Pollable {
         self.kind.clear_write();
     }
 
-    pub(crate) fn queue_message(&mut self, multipart: Multipart) -> Option<Multipart> {
-        if self.outbound_message_buffer.len() < 30 {
-            self.outbound_message_buffer.push_back(multipart);
-            None
-        } else {
-            Some(multipart)
+    /// Add a multipart to the outbound buffer
+    ///
+    /// The socket took a credit before sending this, so the buffer has room for it.
+    pub(crate) fn queue_message(
+        &mut self,
+        multipart: Multipart,
+        responder: oneshot::Sender<Response>,
+    ) {
+        self.outbound_message_buffer
+            .push_back((multipart, responder));
+    }
+
+    pub(crate) fn set_recv_responder(&mut self, r: oneshot::Sender<Response>) {
+        // A receive that was dropped can be replaced before its cancellation is handled
+        if let Some(ref responder) = self.recv_responder {
+            if !responder.is_canceled() {
+                panic!("Overwriting an existing responder, {}", self.id);
+            }
+        }
+        self.recv_responder = Some(r);
+        self.reading = true;
+    }
+
+    /// How many multiparts are waiting to be sent
+    pub(crate) fn outbound_len(&self) -> usize {
+        self.outbound_message_buffer.len()
+    }
+
+    /// When the outbound buffer will be given up on, if a flush is waiting on it
+    pub(crate) fn flush_deadline(&self) -> Option<Instant> {
+        self.flush_deadline
+    }
+
+    /// Respond once the outbound buffer is empty
+    ///
+    /// Multiparts still waiting after `timeout`, or the socket's ZMQ_LINGER if there isn't one,
+    /// are dropped.
+    pub(crate) fn wait_for_flush(
+        &mut self,
+        timeout: Option<Duration>,
+        responder: oneshot::Sender<()>,
+    ) {
+        self.flush_responders.push(responder);
+
+        let timeout = match timeout {
+            Some(timeout) => Some(timeout),
+            None => match self.sock.get_linger() {
+                Ok(linger) if linger >= 0 => Some(Duration::from_millis(linger as u64)),
+                Ok(_) => None,
+                Err(e) => {
+                    error!("Error getting linger, {}, {}", self.id, e);
+                    Some(Duration::from_millis(0))
+                }
+            },
+        };
+
+        if let Some(timeout) = timeout {
+            let deadline = Instant::now() + timeout;
+            self.flush_deadline = Some(match self.flush_deadline {
+                Some(current) => current.min(deadline),
+                None => deadline,
+            });
+        }
+
+        if self.outbound_message_buffer.is_empty() {
+            self.respond_flushed();
         }
     }
 
-    pub(crate) fn set_send_responder(&mut self, r: oneshot::Sender<Response>) {
-        if self.send_responder.is_some() {
-            panic!("Overwriting an existing responder, {}", self.id);
+    /// Give up on the outbound buffer if a flush has waited long enough
+    pub(crate) fn expire_flush(&mut self, now: Instant) {
+        match self.flush_deadline {
+            Some(deadline) if deadline <= now => (),
+            _ => return,
         }
-        self.send_responder = Some(r);
+
+        trace!("Flush timed out, {}", self.id);
+        self.drop_outbound();
+        self.clear_write();
+        self.respond_flushed();
     }
 
-    pub(crate) fn set_recv_responder(&mut self, r: oneshot::Sender<Response>) {
-        if self.recv_responder.is_some() {
-            panic!("Overwriting an existing responder, {}", self.id);
+    fn respond_flushed(&mut self) {
+        self.flush_deadline = None;
+
+        for responder in self.flush_responders.drain(..) {
+            if responder.send(()).is_err() {
+                trace!("Flusher stopped waiting, {}", self.id);
+            }
+        }
+    }
+
+    fn drop_outbound(&mut self) {
+        for (_, responder) in self.outbound_message_buffer.drain(..) {
+            self.buffers.dropped_outbound();
+
+            if responder.send(Response::Error(Error::Dropped)).is_err() {
+                error!("Error notifying dropped socket, {}", self.id);
+            }
+        }
+    }
+
+    /// Answer a waiting receive with `Error::Dropped`, and stop reading ahead
+    pub(crate) fn stop_receiving(&mut self) {
+        if let Some(responder) = self.recv_responder.take() {
+            if responder.send(Response::Error(Error::Dropped)).is_err() {
+                error!("Error notifying dropped socket, {}", self.id);
+            }
+        }
+
+        self.reading = false;
+        self.clear_read();
+    }
+
+    /// Stop waiting for a multipart on behalf of a receive that was dropped
+    ///
+    /// Only a canceled responder is cleared, since a newer receive may have replaced it already.
+    pub(crate) fn cancel_recv(&mut self) {
+        let canceled = match self.recv_responder {
+            Some(ref responder) => responder.is_canceled(),
+            None => false,
+        };
+
+        if canceled {
+            trace!("Receiver stopped waiting, {}", self.id);
+            self.recv_responder = None;
+        }
+
+        if !self.wants_read() {
+            self.clear_read();
+        }
+    }
+
+    /// Respond to every request the poll thread is holding for this socket
+    ///
+    /// This is called when the socket is dropped, so any buffered multiparts are counted as
+    /// dropped.
+    pub(crate) fn drop_buffers(&mut self) {
+        self.drop_outbound();
+        self.respond_flushed();
+
+        for _ in self.inbound_message_buffer.drain(..) {
+            self.buffers.taken(true);
+        }
+
+        if let Some(responder) = self.recv_responder.take() {
+            if responder.send(Response::Error(Error::Dropped)).is_err() {
+                error!("Error notifying dropped socket, {}", self.id);
+            }
         }
-        self.recv_responder = Some(r);
     }
 
     pub(crate) fn try_recieve_message(&mut self) -> Result<Option<Message>, zmq::Error> {
@@ -148,107 +344,125 @@ impl Pollable {
         Ok(None)
     }
 
-    pub(crate) fn fetch_multiparts(&mut self) {
+    /// Whether the socket should be read from
+    ///
+    /// Reads happen when someone is waiting for a multipart, or, once the socket has started
+    /// receiving, to read ahead until the inbound buffer is full.
+    fn wants_read(&self) -> bool {
+        if self.inbound_error.is_some() {
+            return false;
+        }
+
+        self.recv_responder.is_some()
+            || (self.reading && self.inbound_message_buffer.len() < self.buffers.config().inbound)
+    }
+
+    /// Hand a buffered multipart, or a buffered error, to whoever is waiting for one
+    fn respond_buffered(&mut self) {
+        match self.recv_responder {
+            // Keep the multipart for the next receive rather than losing it
+            Some(ref responder) if responder.is_canceled() => {
+                self.recv_responder = None;
+                return;
+            }
+            Some(_) => (),
+            None => return,
+        }
+
+        let response = if let Some(multipart) = self.inbound_message_buffer.pop_front() {
+            self.buffers.taken(false);
+            Response::Received(multipart)
+        } else if let Some(e) = self.inbound_error.take() {
+            Response::Error(e.into())
+        } else {
+            return;
+        };
+
         if let Some(responder) = self.recv_responder.take() {
+            if responder.send(response).is_err() {
+                error!("Error responding to receive, {}", self.id);
+            }
+        }
+    }
+
+    pub(crate) fn fetch_multiparts(&mut self) {
+        loop {
+            self.respond_buffered();
+
+            if !self.wants_read() {
+                self.clear_read();
+                return;
+            }
+
             match self.try_receive_multipart() {
                 Ok(Some(multipart)) => {
-                    self.clear_read();
-
-                    if let Err(_) = responder.send(Response::Received(multipart)) {
-                        error!("Error responding with Received, {}", self.id);
-                    }
-                }
-                Ok(None) => {
-                    self.recv_responder = Some(responder);
+                    self.buffers.received();
+                    self.inbound_message_buffer.push_back(multipart);
                 }
+                Ok(None) => return,
                 Err(zmq::Error::EFSM) => {
                     warn!("EFSM while receiving, {}", self.id);
-                    self.recv_responder = Some(responder);
+                    return;
                 }
                 Err(e) => {
-                    self.clear_read();
-
                     error!("Error fetching, {}, {}", self.id, e);
-                    if let Err(_) = responder.send(Response::Error(e.into())) {
-                        error!("Error responding with Error, {}", self.id);
-                    }
+                    self.inbound_error = Some(e);
                 }
             }
         }
     }
 
-    pub(crate) fn try_send_message(
-        &self,
-        message: Message,
-        flags: i32,
-    ) -> Result<Option<Message>, zmq::Error> {
-        let msg_clone = Message::from(&*message);
-
-        match self.sock.send(message, flags) {
-            Ok(_) => {
-                trace!("SENT msg, {}", self.id);
-                Ok(None)
-            }
-            Err(zmq::Error::EAGAIN) => {
-                warn!("EAGAIN while sending, {}", self.id);
-                Ok(Some(msg_clone))
-            }
-            Err(e) => Err(e),
-        }
-    }
-
-    pub(crate) fn try_send_multipart(
-        &self,
-        mut multipart: Multipart,
-    ) -> Result<Option<Multipart>, zmq::Error> {
-        while let Some(msg) = multipart.pop_front() {
-            let flags = DONTWAIT | if multipart.is_empty() { 0 } else { SNDMORE };
-
-            if let Some(msg) = self.try_send_message(msg, flags)? {
-                multipart.push_front(msg);
-                return Ok(Some(multipart));
+    /// Send as much of `multipart` as ZeroMQ will take, returning whether all of it was sent
+    ///
+    /// Frames are only removed once they've been sent, so a multipart that hits EAGAIN can be
+    /// retried as-is.
+    fn try_send_multipart(
+        sock: &mut Socket,
+        id: usize,
+        multipart: &mut Multipart,
+    ) -> Result<bool, zmq::Error> {
+        while !multipart.is_empty() {
+            match multipart.send_front(sock, DONTWAIT) {
+                Ok(()) => trace!("SENT msg, {}", id),
+                Err(zmq::Error::EAGAIN) => {
+                    warn!("EAGAIN while sending, {}", id);
+                    return Ok(false);
+                }
+                Err(e) => return Err(e),
             }
         }
 
-        Ok(None)
+        Ok(true)
     }
 
     pub(crate) fn flush_multiparts(&mut self) {
-        while let Some(multipart) = self.outbound_message_buffer.pop_front() {
-            match self.try_send_multipart(multipart) {
-                Ok(Some(multipart)) => {
-                    self.outbound_message_buffer.push_front(multipart);
-                    return;
-                }
-                Ok(None) => {
-                    if let Some(responder) = self.send_responder.take() {
-                        if let Err(_) = responder.send(Response::Sent) {
-                            error!("Error responding with Sent, {}", self.id);
-                        }
-                    }
-                    if self.outbound_message_buffer.is_empty() {
-                        self.clear_write();
-                        return;
-                    }
-                    continue;
+        while let Some((multipart, _)) = self.outbound_message_buffer.front_mut() {
+            let res = match Self::try_send_multipart(&mut self.sock, self.id, multipart) {
+                Ok(false) => return,
+                Ok(true) => {
+                    self.buffers.sent();
+                    Response::Sent
                 }
                 Err(zmq::Error::EFSM) => {
                     warn!("EFSM while sending, {}", self.id);
                     return;
                 }
                 Err(e) => {
-                    self.clear_write();
-
                     error!("Error flushing, {}, {}", self.id, e);
-                    if let Some(responder) = self.send_responder.take() {
-                        if let Err(_) = responder.send(Response::Error(e.into())) {
-                            error!("Error responding with Error, {}", self.id);
-                        }
-                    }
-                    return;
+                    self.buffers.dropped_outbound();
+                    Response::Error(e.into())
+                }
+            };
+
+            if let Some((_, responder)) = self.outbound_message_buffer.pop_front() {
+                if responder.send(res).is_err() {
+                    trace!("Sender stopped waiting, {}", self.id);
                 }
             }
         }
+
+        self.clear_write();
+        self.respond_flushed();
     }
 }
 
@@ -256,7 +470,7 @@ pub(crate) enum PollKind {
     SendMsg,
     RecvMsg,
     SendRecv,
-    UNUSED,
+    Unused,
 }
 
 impl PollKind {
@@ -287,7 +501,7 @@ impl PollKind {
     pub(crate) fn clear_read(&mut self) {
         match *self {
             PollKind::SendRecv | PollKind::SendMsg => *self = PollKind::SendMsg,
-            _ => *self = PollKind::UNUSED,
+            _ => *self = PollKind::Unused,
         }
     }
 
@@ -301,7 +515,7 @@ impl PollKind {
     pub(crate) fn clear_write(&mut self) {
         match *self {
             PollKind::SendRecv | PollKind::RecvMsg => *self = PollKind::RecvMsg,
-            _ => *self = PollKind::UNUSED,
+            _ => *self = PollKind::Unused,
         }
     }
 }
@@ -0,0 +1,76 @@
+/*
+ * This file is part of Futures ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Futures ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Futures ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains `SessionPool`, for spreading sockets over several poll threads.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::Session;
+
+/// A fixed set of sessions, each with its own poll thread
+///
+/// Sockets can be handed out round-robin with `session`, or pinned to a session with
+/// `session_for`, so that sockets sharing a hint always share a thread.
+pub struct SessionPool {
+    sessions: Vec<Session>,
+    next: AtomicUsize,
+}
+
+impl SessionPool {
+    /// Start `size` sessions, or one if `size` is zero
+    pub fn new(size: usize) -> Self {
+        SessionPool {
+            sessions: (0..size.max(1)).map(|_| Session::new()).collect(),
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// The next session, round-robin
+    pub fn session(&self) -> &Session {
+        let next = self.next.fetch_add(1, Ordering::Relaxed);
+
+        self.session_for(next)
+    }
+
+    /// The session for the given hint
+    ///
+    /// The same hint always picks the same session.
+    pub fn session_for(&self, hint: usize) -> &Session {
+        &self.sessions[hint % self.sessions.len()]
+    }
+
+    pub fn sessions(&self) -> &[Session] {
+        &self.sessions
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Shut down every session, waiting for their threads to exit
+    pub fn shutdown(&self) {
+        for session in &self.sessions {
+            session.shutdown();
+        }
+    }
+}
@@ -0,0 +1,242 @@
+/*
+ * This file is part of Futures ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Futures ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Futures ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! The mechanisms used to wake a session's poll thread when a request is sent to it.
+
+use std::{io, sync::Arc};
+
+#[cfg(unix)]
+use log::warn;
+use zmq::PollItem;
+
+/// The half of a wakeup mechanism that requests are sent with
+pub(crate) trait Notify: Send + Sync {
+    /// Wake the poll thread
+    ///
+    /// Notifying a signaler that is already signalled does nothing.
+    fn notify(&self) -> io::Result<()>;
+}
+
+/// The half of a wakeup mechanism that the poll thread waits on
+pub(crate) trait Wait: Send {
+    /// The item the poll thread includes in its call to `zmq::poll`
+    fn as_poll_item(&self) -> PollItem<'_>;
+
+    /// Reset the signaler, returning whether it had been notified
+    fn drain(&self) -> io::Result<bool>;
+}
+
+pub(crate) type Signaler = (Arc<dyn Notify>, Box<dyn Wait>);
+
+/// Create the best wakeup mechanism available on this platform
+///
+/// Linux uses an eventfd, and other unix systems use a pipe. If those can't be created, or on
+/// other platforms, an inproc PAIR pair in a private context is used instead.
+pub(crate) fn signaler() -> io::Result<Signaler> {
+    #[cfg(unix)]
+    match fd::signaler() {
+        Ok(signaler) => return Ok(signaler),
+        Err(e) => warn!("Falling back to an inproc signaler, {}", e),
+    }
+
+    inproc::signaler()
+}
+
+#[cfg(unix)]
+mod fd {
+    use std::{
+        io,
+        os::unix::io::{AsRawFd, FromRawFd, OwnedFd},
+        sync::Arc,
+    };
+
+    use zmq::{PollItem, POLLIN};
+
+    use super::{Notify, Signaler, Wait};
+
+    fn cvt(res: libc::c_int) -> io::Result<libc::c_int> {
+        if res == -1 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(res)
+        }
+    }
+
+    fn write(fd: &OwnedFd, buf: &[u8]) -> io::Result<()> {
+        let res = unsafe { libc::write(fd.as_raw_fd(), buf.as_ptr() as *const _, buf.len()) };
+
+        match res {
+            -1 => match io::Error::last_os_error() {
+                // The signaler is full, so the poll thread is already awake
+                ref e if e.kind() == io::ErrorKind::WouldBlock => Ok(()),
+                e => Err(e),
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn drain(fd: &OwnedFd, buf: &mut [u8]) -> io::Result<bool> {
+        let mut signalled = false;
+
+        loop {
+            let res = unsafe { libc::read(fd.as_raw_fd(), buf.as_mut_ptr() as *mut _, buf.len()) };
+
+            match res {
+                -1 => match io::Error::last_os_error() {
+                    ref e if e.kind() == io::ErrorKind::WouldBlock => return Ok(signalled),
+                    ref e if e.kind() == io::ErrorKind::Interrupted => continue,
+                    e => return Err(e),
+                },
+                0 => return Ok(signalled),
+                _ => signalled = true,
+            }
+        }
+    }
+
+    // An eventfd is a counter, which is written as a native u64
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const SIGNAL: &[u8] = &1u64.to_ne_bytes();
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    const SIGNAL: &[u8] = &[1];
+
+    struct FdNotify(OwnedFd);
+
+    struct FdWait(OwnedFd);
+
+    impl Notify for FdNotify {
+        fn notify(&self) -> io::Result<()> {
+            write(&self.0, SIGNAL)
+        }
+    }
+
+    impl Wait for FdWait {
+        fn as_poll_item(&self) -> PollItem<'_> {
+            PollItem::from_fd(self.0.as_raw_fd(), POLLIN)
+        }
+
+        fn drain(&self) -> io::Result<bool> {
+            drain(&self.0, &mut [0; 32])
+        }
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub(super) fn signaler() -> io::Result<Signaler> {
+        let fd = cvt(unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) })?;
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        let notify = FdNotify(fd.try_clone()?);
+
+        Ok((Arc::new(notify), Box::new(FdWait(fd))))
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    pub(super) fn signaler() -> io::Result<Signaler> {
+        let mut fds = [0; 2];
+        cvt(unsafe { libc::pipe(fds.as_mut_ptr()) })?;
+
+        let (rx, tx) = unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+
+        for fd in &[&rx, &tx] {
+            let fd = fd.as_raw_fd();
+            let flags = cvt(unsafe { libc::fcntl(fd, libc::F_GETFL) })?;
+            cvt(unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) })?;
+            cvt(unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) })?;
+        }
+
+        Ok((Arc::new(FdNotify(tx)), Box::new(FdWait(rx))))
+    }
+}
+
+mod inproc {
+    use std::{
+        io,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    use zmq::{PollItem, DONTWAIT, POLLIN};
+
+    use super::{zmq_to_io, Notify, Signaler, Wait};
+
+    static SIGNALER_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    struct InprocNotify(Mutex<zmq::Socket>);
+
+    struct InprocWait(zmq::Socket);
+
+    impl Notify for InprocNotify {
+        fn notify(&self) -> io::Result<()> {
+            let sock = self.0.lock().unwrap();
+
+            match sock.send(&[][..], DONTWAIT) {
+                // The pair's queue is full, so the poll thread is already awake
+                Ok(()) | Err(zmq::Error::EAGAIN) => Ok(()),
+                Err(e) => Err(zmq_to_io(e)),
+            }
+        }
+    }
+
+    impl Wait for InprocWait {
+        fn as_poll_item(&self) -> PollItem<'_> {
+            self.0.as_poll_item(POLLIN)
+        }
+
+        fn drain(&self) -> io::Result<bool> {
+            let mut signalled = false;
+
+            loop {
+                match self.0.recv_bytes(DONTWAIT) {
+                    Ok(_) => signalled = true,
+                    Err(zmq::Error::EAGAIN) => return Ok(signalled),
+                    Err(zmq::Error::EINTR) => continue,
+                    Err(e) => return Err(zmq_to_io(e)),
+                }
+            }
+        }
+    }
+
+    pub(super) fn signaler() -> io::Result<Signaler> {
+        // A private context, so the signaler works no matter which contexts sockets use
+        let ctx = zmq::Context::new();
+        let endpoint = format!(
+            "inproc://futures-zmq-signal-{}",
+            SIGNALER_COUNT.fetch_add(1, Ordering::Relaxed)
+        );
+
+        let rx = ctx.socket(zmq::PAIR).map_err(zmq_to_io)?;
+        let tx = ctx.socket(zmq::PAIR).map_err(zmq_to_io)?;
+
+        for sock in &[&rx, &tx] {
+            sock.set_linger(0).map_err(zmq_to_io)?;
+        }
+
+        rx.bind(&endpoint).map_err(zmq_to_io)?;
+        tx.connect(&endpoint).map_err(zmq_to_io)?;
+
+        Ok((
+            Arc::new(InprocNotify(Mutex::new(tx))),
+            Box::new(InprocWait(rx)),
+        ))
+    }
+}
+
+fn zmq_to_io(e: zmq::Error) -> io::Error {
+    io::Error::from_raw_os_error(e.to_raw())
+}
@@ -19,14 +19,52 @@
 
 //! Provide useful types and traits for working with Futures ZMQ.
 
-use crate::error::Error;
+use crate::{error::Error, Session, SESSION};
 
 pub use async_zmq_types::{
-    ControlHandler, Controllable, EndHandler, HasBuilder, IntoInnerSocket, SinkSocket,
-    SinkStreamSocket, StreamSocket, WithEndHandler,
+    codec::{Framed, WithCodec},
+    ControlHandler, Controllable, EndHandler, GracefulShutdown, HasBuilder, IntoInnerSocket,
+    SinkSocket, SinkStreamSocket, StreamSocket, WithEndHandler,
 };
-use futures::Future;
+use futures::future::BoxFuture;
 
 pub trait Build<T>: Sized {
-    fn build(self) -> Box<dyn Future<Item = T, Error = Error> + Send>;
+    /// Build the socket, polling it from the global session's thread
+    fn build(self) -> BoxFuture<'static, Result<T, Error>> {
+        self.build_in(&SESSION)
+    }
+
+    /// Build the socket, polling it from the given session's thread
+    ///
+    /// ### Example, spreading sockets over a pool of sessions
+    /// ```rust
+    /// use std::sync::Arc;
+    ///
+    /// use futures_zmq::{prelude::*, Error, Pull, SessionPool};
+    ///
+    /// async fn pulls(context: Arc<zmq::Context>, pool: &SessionPool) -> Result<Vec<Pull>, Error> {
+    ///     let mut pulls = Vec::new();
+    ///
+    ///     for port in 5582..5586 {
+    ///         let pull: Pull = Pull::builder(Arc::clone(&context))
+    ///             .bind(&format!("tcp://*:{}", port))
+    ///             .build_in(pool.session())
+    ///             .await?;
+    ///
+    ///         pulls.push(pull);
+    ///     }
+    ///
+    ///     Ok(pulls)
+    /// }
+    ///
+    /// fn main() {
+    ///     let context = Arc::new(zmq::Context::new());
+    ///     let pool = SessionPool::new(2);
+    ///     let fut = pulls(context, &pool);
+    ///
+    ///     // futures::executor::block_on(fut).unwrap();
+    ///     # let _ = fut;
+    /// }
+    /// ```
+    fn build_in(self, session: &Session) -> BoxFuture<'static, Result<T, Error>>;
 }
@@ -0,0 +1,148 @@
+/*
+ * This file is part of Futures ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Futures ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Futures ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains `proxy` and `proxy_steerable`, which forward multiparts between a frontend
+//! and a backend socket like `zmq_proxy` and `zmq_proxy_steerable`.
+//!
+//! The proxy itself lives in `async_zmq_types::proxy`, and its types are re-exported here.
+
+use futures::future::{self, BoxFuture};
+
+pub use async_zmq_types::proxy::{ProxyCommand, ProxyError, ProxyStatistics};
+
+use async_zmq_types::proxy::Proxy;
+
+use crate::{
+    async_types::{MultipartSink, MultipartSinkStream},
+    error::Error,
+    prelude::*,
+    Pair, Socket,
+};
+
+// How many multiparts each of the proxy's sinks can hold before it waits
+const BUFFER_SIZE: usize = 25;
+
+type SocketProxy<F, B, C, K> =
+    Proxy<MultipartSinkStream<F>, MultipartSinkStream<B>, MultipartSink<C>, MultipartSinkStream<K>>;
+
+/// Forward multiparts between `frontend` and `backend` until one of them fails
+///
+/// Multiparts are forwarded in each direction the sockets allow, so a Pull frontend and a Push
+/// backend forward from the frontend to the backend, while Router and Dealer, or Xsub and Xpub,
+/// forward both ways. Every forwarded multipart is also copied to `capture`, if there is one,
+/// which is usually a Pub or Push socket.
+///
+/// ### Example
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use futures_zmq::{prelude::*, proxy::proxy, Dealer, Error, Pub, Router};
+///
+/// async fn broker(context: Arc<zmq::Context>) -> Result<(), Error> {
+///     let frontend: Router = Router::builder(Arc::clone(&context))
+///         .bind("tcp://*:5593")
+///         .build()
+///         .await?;
+///
+///     let backend: Dealer = Dealer::builder(context)
+///         .bind("tcp://*:5594")
+///         .build()
+///         .await?;
+///
+///     let stats = proxy(frontend, backend, None::<Pub>).await?;
+///     println!("Forwarded {} requests", stats.frontend_messages_in);
+///
+///     Ok(())
+/// }
+///
+/// fn main() {
+///     let context = Arc::new(zmq::Context::new());
+///     let fut = broker(context);
+///
+///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+///     # let _ = fut;
+/// }
+/// ```
+pub fn proxy<F, B, C>(
+    frontend: F,
+    backend: B,
+    capture: Option<C>,
+) -> BoxFuture<'static, Result<ProxyStatistics, Error>>
+where
+    F: IntoInnerSocket<Socket = Socket> + Send + 'static,
+    B: IntoInnerSocket<Socket = Socket> + Send + 'static,
+    C: IntoInnerSocket<Socket = Socket> + Send + 'static,
+{
+    let proxy: Result<Proxy<_, _, _, MultipartSinkStream<Pair>>, _> =
+        new_proxy(frontend, backend, capture);
+
+    match proxy {
+        Ok(proxy) => Box::pin(proxy.run()),
+        Err(e) => Box::pin(future::err(e.into())),
+    }
+}
+
+/// Forward multiparts between `frontend` and `backend`, taking commands from `control`
+///
+/// This works like `proxy`, but the proxy can be paused, resumed, and terminated by sending it a
+/// `ProxyCommand`. STATISTICS is answered with the proxy's `ProxyStatistics`. A Rep control
+/// socket answers every other command with an empty frame, while a Pair doesn't answer them.
+/// The future resolves with the final statistics once the proxy is terminated.
+pub fn proxy_steerable<F, B, C, K>(
+    frontend: F,
+    backend: B,
+    capture: Option<C>,
+    control: K,
+) -> BoxFuture<'static, Result<ProxyStatistics, Error>>
+where
+    F: IntoInnerSocket<Socket = Socket> + Send + 'static,
+    B: IntoInnerSocket<Socket = Socket> + Send + 'static,
+    C: IntoInnerSocket<Socket = Socket> + Send + 'static,
+    K: IntoInnerSocket<Socket = Socket> + Send + 'static,
+{
+    let control = MultipartSinkStream::<K>::new(control.socket(), BUFFER_SIZE);
+    match new_proxy(frontend, backend, capture) {
+        Ok(proxy) => Box::pin(proxy.control(control, K::kind()).run()),
+        Err(e) => Box::pin(future::err(e.into())),
+    }
+}
+
+fn new_proxy<F, B, C, K>(
+    frontend: F,
+    backend: B,
+    capture: Option<C>,
+) -> Result<SocketProxy<F, B, C, K>, ProxyError>
+where
+    F: IntoInnerSocket<Socket = Socket>,
+    B: IntoInnerSocket<Socket = Socket>,
+    C: IntoInnerSocket<Socket = Socket>,
+    K: IntoInnerSocket<Socket = Socket>,
+{
+    let proxy = Proxy::new(
+        MultipartSinkStream::new(frontend.socket(), BUFFER_SIZE),
+        F::kind(),
+        MultipartSinkStream::new(backend.socket(), BUFFER_SIZE),
+        B::kind(),
+    )?;
+
+    Ok(match capture {
+        Some(capture) => proxy.capture(MultipartSink::new(capture.socket(), BUFFER_SIZE)),
+        None => proxy,
+    })
+}
@@ -20,68 +20,72 @@
 //! This module contains `SocketBuilder` and related types.
 
 use async_zmq_types::{IntoInnerSocket, PairConfig, SockConfig, SubConfig, UnPair};
-use futures::{future::lazy, Future};
+use futures::future::BoxFuture;
 
 use crate::{
     error::Error,
+    polling::BufferConfig,
     prelude::Build,
     socket::{
         types::{Pair, Sub},
         Socket,
     },
-    SESSION,
+    Session,
 };
 
-impl<'a, T> Build<T> for SockConfig<'a, T>
+impl<T> Build<T> for SockConfig<T>
 where
     T: UnPair + IntoInnerSocket + From<Socket> + 'static,
 {
-    fn build(self) -> Box<dyn Future<Item = T, Error = Error> + Send> {
+    fn build_in(self, session: &Session) -> BoxFuture<'static, Result<T, Error>> {
+        let buffers = BufferConfig::from_options(&self.options);
+        let ipc_permissions = self.options.ipc_permissions;
         let res = self.do_build();
+        let session = session.local_session();
 
-        let fut = lazy(move || res)
-            .from_err()
-            .and_then(|sock| {
-                let session = SESSION.local_session();
-                session.init(sock).map(move |socket| (socket, session))
-            })
-            .map(|(sock, sess)| Socket::from_sock_and_session(sock, sess))
-            .map(T::from);
+        Box::pin(async move {
+            let sock = res?;
+            let id = session
+                .init_with_config(sock, buffers, ipc_permissions)
+                .await?;
 
-        Box::new(fut)
+            Ok(T::from(Socket::from_sock_and_session(id, session)))
+        })
     }
 }
 
-impl<'a> Build<Sub> for SubConfig<'a> {
-    fn build(self) -> Box<dyn Future<Item = Sub, Error = Error> + Send> {
-        let sock = self.do_build();
+impl Build<Sub> for SubConfig {
+    fn build_in(self, session: &Session) -> BoxFuture<'static, Result<Sub, Error>> {
+        let buffers = BufferConfig::from_options(&self.options);
+        let ipc_permissions = self.options.ipc_permissions;
+        let res = self.do_build();
+        let session = session.local_session();
 
-        let fut = lazy(move || sock)
-            .from_err()
-            .and_then(|sock| {
-                let session = SESSION.local_session();
-                session.init(sock).map(move |socket| (socket, session))
-            })
-            .map(|(sock, sess)| Socket::from_sock_and_session(sock, sess))
-            .map(Sub::from);
+        Box::pin(async move {
+            let sock = res?;
+            let id = session
+                .init_with_config(sock, buffers, ipc_permissions)
+                .await?;
 
-        Box::new(fut)
+            Ok(Sub::from(Socket::from_sock_and_session(id, session)))
+        })
     }
 }
 
-impl<'a> Build<Pair> for PairConfig<'a> {
-    fn build(self) -> Box<dyn Future<Item = Pair, Error = Error> + Send> {
-        let sock = self.do_build();
+impl Build<Pair> for PairConfig {
+    fn build_in(self, session: &Session) -> BoxFuture<'static, Result<Pair, Error>> {
+        let buffers = BufferConfig::from_options(&self.options);
+        let ipc_permissions = self.options.ipc_permissions;
+        let res = self.do_build();
+        let session = session.local_session();
 
-        let fut = lazy(move || sock)
-            .from_err()
-            .and_then(|sock| {
-                let session = SESSION.local_session();
-                session.init(sock).map(move |sock| (sock, session))
-            })
-            .map(|(sock, sess)| Socket::from_sock_and_session(sock, sess))
-            .map(Pair::from);
+        Box::pin(async move {
+            let sock = res?;
+            let id = session
+                .init_with_config(sock, buffers, ipc_permissions)
+                .await?;
 
-        Box::new(fut)
+            Ok(Pair::from(Socket::from_sock_and_session(id, session)))
+        })
     }
 }
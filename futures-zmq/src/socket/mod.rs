@@ -22,16 +22,33 @@
 pub mod config;
 pub mod types;
 
-use std::{fmt, sync::Arc};
+use std::{
+    fmt,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
 
-use async_zmq_types::{InnerSocket, IntoInnerSocket, Multipart, SocketBuilder};
-use zmq;
+#[cfg(feature = "draft")]
+use async_zmq_types::draft::Group;
+use async_zmq_types::{
+    monitor::{connect_monitor, monitor_endpoint},
+    HasBuilder, InnerSocket, IntoInnerSocket, Multipart, SocketBuilder,
+};
+use futures::future::BoxFuture;
 
+#[cfg(feature = "draft")]
+use crate::polling::ConnectPeerFuture;
 use crate::{
     async_types::{
-        MultipartRequest, MultipartResponse, MultipartSink, MultipartSinkStream, MultipartStream,
+        MonitorStream, MultipartRequest, MultipartRequestMut, MultipartResponse,
+        MultipartResponseMut, MultipartSink, MultipartSinkStream, MultipartStream,
+    },
+    error::Error,
+    polling::{
+        BindFuture, Buffers, EndpointFuture, FlushFuture, LocalSession, SockId, SocketStats,
+        SubscribeFuture,
     },
-    polling::{LocalSession, SockId},
     RecvFuture, SendFuture,
 };
 
@@ -44,9 +61,9 @@ pub struct Socket {
 
 impl Socket {
     /// Start a new Socket Config builder
-    pub fn builder<T>(ctx: Arc<zmq::Context>) -> SocketBuilder<'static, T>
+    pub fn builder<T>(ctx: Arc<zmq::Context>) -> SocketBuilder<T>
     where
-        T: IntoInnerSocket,
+        T: HasBuilder,
     {
         SocketBuilder::new(ctx)
     }
@@ -64,6 +81,74 @@ impl Socket {
         Socket { sock, session }
     }
 
+    /// Send a single multipart message without giving up ownership of the socket
+    pub fn send_mut(&mut self, multipart: Multipart) -> MultipartRequestMut<'_> {
+        MultipartRequestMut::new(self, multipart)
+    }
+
+    /// Receive a single multipart message without giving up ownership of the socket
+    pub fn recv_mut(&mut self) -> MultipartResponseMut<'_> {
+        MultipartResponseMut::new(self)
+    }
+
+    /// Start receiving the connection events ZeroMQ reports for this socket
+    ///
+    /// The events are delivered over an inproc PAIR socket, so `ctx` must be the context this
+    /// socket was created with. The monitor is started from the poll thread that owns the socket,
+    /// and the PAIR socket joins the same session.
+    pub fn monitor(&self, ctx: &zmq::Context) -> BoxFuture<'static, Result<MonitorStream, Error>> {
+        let endpoint = monitor_endpoint();
+        let pair = connect_monitor(ctx, &endpoint);
+        let monitoring = self.session.monitor(&self.sock, endpoint);
+        let session = self.session.clone();
+
+        Box::pin(async move {
+            monitoring.await?;
+            let id = session.init(pair?).await?;
+
+            Ok(MonitorStream::new(Socket::from_sock_and_session(
+                id, session,
+            )))
+        })
+    }
+
+    /// Count the multiparts the poll thread is holding for this socket, and how many it has
+    /// sent, received, and dropped
+    pub fn stats(&self) -> SocketStats {
+        self.sock.buffers().stats()
+    }
+
+    pub(crate) fn subscribe(&self, topic: &[u8]) -> SubscribeFuture {
+        self.session.subscribe(&self.sock, topic.to_vec())
+    }
+
+    pub(crate) fn unsubscribe(&self, topic: &[u8]) -> SubscribeFuture {
+        self.session.unsubscribe(&self.sock, topic.to_vec())
+    }
+
+    #[cfg(feature = "draft")]
+    pub(crate) fn join(&self, group: &Group) -> SubscribeFuture {
+        self.session.join(&self.sock, group.clone())
+    }
+
+    #[cfg(feature = "draft")]
+    pub(crate) fn leave(&self, group: &Group) -> SubscribeFuture {
+        self.session.leave(&self.sock, group.clone())
+    }
+
+    #[cfg(feature = "draft")]
+    pub(crate) fn connect_peer(&self, endpoint: &str) -> ConnectPeerFuture {
+        self.session.connect_peer(&self.sock, endpoint.to_owned())
+    }
+
+    pub(crate) fn poll_reserve(&self, cx: &mut Context) -> Poll<()> {
+        self.sock.buffers().poll_reserve(cx)
+    }
+
+    pub(crate) fn buffers(&self) -> &Arc<Buffers> {
+        self.sock.buffers()
+    }
+
     pub(crate) fn recv_msg(&self) -> RecvFuture {
         self.session.recv(&self.sock)
     }
@@ -71,6 +156,10 @@ impl Socket {
     pub(crate) fn send_msg(&self, multipart: Multipart) -> SendFuture {
         self.session.send(&self.sock, multipart)
     }
+
+    pub(crate) fn flush(&self, timeout: Option<Duration>) -> FlushFuture {
+        self.session.flush(&self.sock, timeout)
+    }
 }
 
 impl<T> InnerSocket<T> for Socket
@@ -85,6 +174,12 @@ where
 
     type SinkStream = MultipartSinkStream<T>;
 
+    type RequestMut<'a> = MultipartRequestMut<'a>;
+    type ResponseMut<'a> = MultipartResponseMut<'a>;
+
+    type Bind = BindFuture;
+    type Endpoint = EndpointFuture;
+
     fn send(self, multipart: Multipart) -> Self::Request {
         MultipartRequest::new(self, multipart)
     }
@@ -93,6 +188,14 @@ where
         MultipartResponse::new(self)
     }
 
+    fn send_mut(&mut self, multipart: Multipart) -> Self::RequestMut<'_> {
+        Socket::send_mut(self, multipart)
+    }
+
+    fn recv_mut(&mut self) -> Self::ResponseMut<'_> {
+        Socket::recv_mut(self)
+    }
+
     fn stream(self) -> Self::Stream {
         MultipartStream::new(self)
     }
@@ -104,6 +207,22 @@ where
     fn sink_stream(self, buffer_size: usize) -> Self::SinkStream {
         MultipartSinkStream::new(self, buffer_size)
     }
+
+    fn bind(&self, endpoint: &str) -> Self::Bind {
+        self.session.bind(&self.sock, endpoint.to_owned())
+    }
+
+    fn unbind(&self, endpoint: &str) -> Self::Endpoint {
+        self.session.unbind(&self.sock, endpoint.to_owned())
+    }
+
+    fn connect(&self, endpoint: &str) -> Self::Endpoint {
+        self.session.connect(&self.sock, endpoint.to_owned())
+    }
+
+    fn disconnect(&self, endpoint: &str) -> Self::Endpoint {
+        self.session.disconnect(&self.sock, endpoint.to_owned())
+    }
 }
 
 impl From<(SockId, LocalSession)> for Socket {
@@ -0,0 +1,96 @@
+/*
+ * This file is part of Futures ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Futures ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Futures ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains `ZapHandler`, which answers ZeroMQ's authentication requests.
+//!
+//! The policies and the request handling live in `async_zmq_types::zap`, and the policies are
+//! re-exported here.
+
+use std::{sync::Arc, time::Duration};
+
+pub use async_zmq_types::zap::{
+    Authenticator, CurveKeys, IpFilter, Mechanism, PlainPasswords, ZapDecision, ZapDomain,
+    ZapPolicy, ZapRequest, ZAP_ENDPOINT,
+};
+
+use async_zmq_types::zap;
+
+use crate::{async_types::MultipartSinkStream, error::Error, prelude::*, Router};
+
+// How many replies the handler's sink can hold before it waits
+const BUFFER_SIZE: usize = 25;
+
+/// Serves an `Authenticator` to every socket in a context
+///
+/// ZeroMQ only authenticates connections while a handler is bound, so the handler should be bound
+/// before any socket that needs authentication, and kept running for as long as they are in use.
+/// Only one handler can be bound per context.
+///
+/// ### Example
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use futures_zmq::{
+///     prelude::*,
+///     zap::{Authenticator, ZapDomain, ZapHandler},
+///     Error, Rep,
+/// };
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Error> {
+///     let context = Arc::new(zmq::Context::new());
+///
+///     let authenticator = Authenticator::new().domain("", ZapDomain::new().plain("admin", "secret"));
+///     let handler = ZapHandler::bind(Arc::clone(&context), authenticator).await?;
+///     tokio::spawn(handler.run());
+///
+///     let rep: Rep = Rep::builder(context)
+///         .plain_server()
+///         .bind("tcp://127.0.0.1:5621")
+///         .build()
+///         .await?;
+///     # let _ = rep;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ZapHandler {
+    inner: zap::ZapHandler<MultipartSinkStream<Router>>,
+}
+
+impl ZapHandler {
+    /// Bind a handler to the context's ZAP endpoint
+    pub async fn bind(ctx: Arc<zmq::Context>, authenticator: Authenticator) -> Result<Self, Error> {
+        let router: Router = Router::builder(ctx)
+            .linger(Some(Duration::from_millis(0)))
+            .bind(ZAP_ENDPOINT)
+            .build()
+            .await?;
+
+        Ok(ZapHandler {
+            inner: zap::ZapHandler::new(router.sink_stream(BUFFER_SIZE), authenticator),
+        })
+    }
+
+    /// Answer authentication requests until the handler's socket fails
+    pub async fn run(self) -> Result<(), Error> {
+        self.inner.run().await
+    }
+}
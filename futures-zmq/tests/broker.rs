@@ -0,0 +1,202 @@
+/*
+ * This file is part of Futures ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Futures ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Futures ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use futures::StreamExt;
+use futures_zmq::{
+    broker::{LoadBalancer, LoadBalancerStats, StatsStream, READY},
+    prelude::*,
+    Envelope, Multipart, Req, Router,
+};
+use tokio::time::timeout;
+
+fn message(s: &str) -> Multipart {
+    zmq::Message::from(s).into()
+}
+
+fn text(multipart: &Multipart) -> Option<&str> {
+    multipart.get(0).and_then(|msg| msg.as_str())
+}
+
+async fn load_balancer(ctx: &Arc<zmq::Context>, name: &str) -> LoadBalancer {
+    let frontend: Router = Router::builder(Arc::clone(ctx))
+        .bind(format!("inproc://{}-front", name))
+        .build()
+        .await
+        .unwrap();
+    let backend: Router = Router::builder(Arc::clone(ctx))
+        .bind(format!("inproc://{}-back", name))
+        .build()
+        .await
+        .unwrap();
+
+    LoadBalancer::new(frontend, backend)
+}
+
+async fn client(ctx: &Arc<zmq::Context>, name: &str) -> Req {
+    Req::builder(Arc::clone(ctx))
+        .connect(format!("inproc://{}-front", name))
+        .build()
+        .await
+        .unwrap()
+}
+
+// Answers every request with its own id
+async fn worker(ctx: Arc<zmq::Context>, name: &'static str, id: &'static str) {
+    let mut worker: Req = Req::builder(ctx)
+        .connect(format!("inproc://{}-back", name))
+        .build()
+        .await
+        .unwrap();
+
+    worker.send_mut(message(READY)).await.unwrap();
+
+    loop {
+        let request = Envelope::from_multipart(worker.recv_mut().await.unwrap()).unwrap();
+        worker
+            .send_mut(request.reply(message(id)).into())
+            .await
+            .unwrap();
+    }
+}
+
+async fn wait_for<P>(stats: &mut StatsStream, predicate: P) -> LoadBalancerStats
+where
+    P: Fn(&LoadBalancerStats) -> bool,
+{
+    loop {
+        let latest = stats.next().await.unwrap();
+        if predicate(&latest) {
+            return latest;
+        }
+    }
+}
+
+#[tokio::test]
+async fn requests_go_to_the_longest_idle_worker() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let mut broker = load_balancer(&ctx, "broker-lru").await;
+    let mut stats = broker.stats();
+    let running = tokio::spawn(broker.run());
+
+    for id in &["w0", "w1", "w2"] {
+        tokio::spawn(worker(Arc::clone(&ctx), "broker-lru", id));
+    }
+
+    let mut client = client(&ctx, "broker-lru").await;
+
+    let exchange = async {
+        wait_for(&mut stats, |stats| stats.idle_workers == 3).await;
+
+        let mut served = Vec::new();
+        for _ in 0..6 {
+            client.send_mut(message("hello")).await.unwrap();
+            let reply = client.recv_mut().await.unwrap();
+            served.push(text(&reply).unwrap().to_owned());
+        }
+
+        // Each reply sends its worker to the back of the queue, so the workers take turns
+        assert_ne!(served[0], served[1]);
+        assert_ne!(served[1], served[2]);
+        assert_ne!(served[0], served[2]);
+        assert_eq!(served[..3], served[3..]);
+
+        let latest = wait_for(&mut stats, |stats| stats.replies_forwarded == 6).await;
+        assert_eq!(latest.workers_registered, 3);
+        assert_eq!(latest.requests_received, 6);
+        assert_eq!(latest.requests_dispatched, 6);
+        assert_eq!(latest.idle_workers, 3);
+        assert_eq!(latest.queued_requests, 0);
+
+        // Stopping the broker ends its statistics
+        running.abort();
+        while stats.next().await.is_some() {}
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn requests_wait_for_a_worker() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let mut broker = load_balancer(&ctx, "broker-queue").await;
+    let mut stats = broker.stats();
+    tokio::spawn(broker.run());
+
+    let mut clients = Vec::new();
+    for _ in 0..3 {
+        let mut client = client(&ctx, "broker-queue").await;
+        client.send_mut(message("hello")).await.unwrap();
+        clients.push(client);
+    }
+
+    let exchange = async {
+        wait_for(&mut stats, |stats| stats.queued_requests == 3).await;
+
+        tokio::spawn(worker(Arc::clone(&ctx), "broker-queue", "late"));
+
+        for client in &mut clients {
+            let reply = client.recv_mut().await.unwrap();
+            assert_eq!(text(&reply), Some("late"));
+        }
+
+        let latest = wait_for(&mut stats, |stats| stats.replies_forwarded == 3).await;
+        assert_eq!(latest.queued_requests, 0);
+        assert_eq!(latest.idle_workers, 1);
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn queue_limit_leaves_requests_in_zeromq() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let mut broker = load_balancer(&ctx, "broker-limit").await.queue_limit(1);
+    let mut stats = broker.stats();
+    tokio::spawn(broker.run());
+
+    let mut clients = Vec::new();
+    for _ in 0..3 {
+        let mut client = client(&ctx, "broker-limit").await;
+        client.send_mut(message("hello")).await.unwrap();
+        clients.push(client);
+    }
+
+    let exchange = async {
+        let held = wait_for(&mut stats, |stats| stats.queued_requests == 1).await;
+        assert_eq!(held.requests_received, 1);
+
+        // The other requests stay with ZeroMQ until the queue has room
+        let changed = timeout(Duration::from_millis(100), stats.next()).await;
+        assert!(changed.is_err(), "A full queue accepted another request");
+
+        tokio::spawn(worker(Arc::clone(&ctx), "broker-limit", "w0"));
+
+        for client in &mut clients {
+            let reply = client.recv_mut().await.unwrap();
+            assert_eq!(text(&reply), Some("w0"));
+        }
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
@@ -0,0 +1,115 @@
+/*
+ * This file is part of Futures ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Futures ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Futures ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use async_zmq_types::IntoSocket;
+use futures::{stream, SinkExt, StreamExt};
+use futures_zmq::{async_types::MultipartSink, prelude::*, Multipart, Pull, Push};
+use tokio::time::{sleep, timeout};
+
+fn message(i: usize) -> Multipart {
+    zmq::Message::from(&format!("{}", i)).into()
+}
+
+fn text(multipart: &Multipart) -> Option<&str> {
+    multipart.get(0).and_then(|msg| msg.as_str())
+}
+
+#[tokio::test]
+async fn sinks_wait_for_room_in_the_outbound_buffer() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    // A bound Push socket holds on to messages until a peer connects
+    let push: Push = Push::builder(Arc::clone(&ctx))
+        .outbound_buffer(2)
+        .bind("inproc://buffers-outbound")
+        .build()
+        .await
+        .unwrap();
+
+    let mut sink: MultipartSink<Push> = push.sink(10);
+    let mut multiparts = stream::iter((0..5).map(message).map(Ok));
+
+    let sending = timeout(Duration::from_millis(200), sink.send_all(&mut multiparts)).await;
+    assert!(sending.is_err(), "Sink accepted more than the buffer holds");
+
+    let mut push = sink.into_socket();
+    let stats = push.socket_mut().stats();
+    assert_eq!(stats.outbound_queued, 2);
+    assert_eq!(stats.sent, 0);
+
+    let mut pull: Pull = Pull::builder(ctx)
+        .connect("inproc://buffers-outbound")
+        .build()
+        .await
+        .unwrap();
+
+    for i in 0..2 {
+        let multipart = timeout(Duration::from_secs(5), pull.recv_mut())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(text(&multipart), Some(format!("{}", i).as_str()));
+    }
+
+    let stats = push.socket_mut().stats();
+    assert_eq!(stats.outbound_queued, 0);
+    assert_eq!(stats.sent, 2);
+    assert_eq!(stats.dropped, 0);
+}
+
+#[tokio::test]
+async fn receives_read_ahead_into_the_inbound_buffer() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let mut pull: Pull = Pull::builder(Arc::clone(&ctx))
+        .inbound_buffer(3)
+        .bind("inproc://buffers-inbound")
+        .build()
+        .await
+        .unwrap();
+
+    let push: Push = Push::builder(ctx)
+        .connect("inproc://buffers-inbound")
+        .build()
+        .await
+        .unwrap();
+
+    stream::iter((0..5).map(message).map(Ok))
+        .forward(push.sink(5))
+        .await
+        .unwrap();
+
+    let first = timeout(Duration::from_secs(5), pull.recv_mut())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(text(&first), Some("0"));
+
+    sleep(Duration::from_millis(100)).await;
+
+    let stats = pull.socket_mut().stats();
+    assert_eq!(stats.inbound_queued, 3);
+    assert_eq!(stats.received, 4);
+
+    let rest: Vec<_> = pull.stream().take(4).map(Result::unwrap).collect().await;
+    let rest: Vec<_> = rest.iter().filter_map(text).collect();
+    assert_eq!(rest, vec!["1", "2", "3", "4"]);
+}
@@ -0,0 +1,184 @@
+/*
+ * This file is part of Futures ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Futures ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Futures ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use async_zmq_types::{BuildError, EndpointError};
+use futures_zmq::{prelude::*, Error, Multipart, Rep, Req};
+use tokio::time::{sleep, timeout};
+
+fn message(s: &str) -> Multipart {
+    zmq::Message::from(s).into()
+}
+
+fn text(multipart: &Multipart) -> Option<&str> {
+    multipart.get(0).and_then(|msg| msg.as_str())
+}
+
+async fn rep(ctx: &Arc<zmq::Context>, endpoint: &str) -> Rep {
+    Rep::builder(Arc::clone(ctx))
+        .bind(endpoint)
+        .build()
+        .await
+        .unwrap()
+}
+
+/// Bind to an endpoint that was just unbound
+///
+/// ZeroMQ closes a listener on its I/O thread after `unbind` returns, so the port can take a
+/// moment to free up.
+async fn rebind(ctx: &Arc<zmq::Context>, endpoint: &str) -> Rep {
+    loop {
+        match Rep::builder(Arc::clone(ctx)).bind(endpoint).build().await {
+            Ok(rep) => return rep,
+            Err(Error::Zmq(zmq::Error::EADDRINUSE)) => sleep(Duration::from_millis(10)).await,
+            Err(e) => panic!("Couldn't rebind {}, {}", endpoint, e),
+        }
+    }
+}
+
+async fn req(ctx: &Arc<zmq::Context>, endpoint: &str) -> Req {
+    Req::builder(Arc::clone(ctx))
+        .connect(endpoint)
+        .build()
+        .await
+        .unwrap()
+}
+
+async fn echo(req: &mut Req, rep: &mut Rep, body: &str) {
+    req.send_mut(message(body)).await.unwrap();
+
+    let multipart = rep.recv_mut().await.unwrap();
+    rep.send_mut(multipart).await.unwrap();
+
+    assert_eq!(text(&req.recv_mut().await.unwrap()), Some(body));
+}
+
+#[tokio::test]
+async fn ephemeral_binds_resolve_their_port() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let mut server = rep(&ctx, "inproc://endpoints-ephemeral").await;
+
+    let exchange = async {
+        let endpoint = server.bind("tcp://127.0.0.1:0").await.unwrap();
+
+        let port = endpoint.trim_start_matches("tcp://127.0.0.1:");
+        assert_ne!(port, endpoint);
+        assert_ne!(port.parse::<u16>().unwrap(), 0);
+
+        let mut client = req(&ctx, &endpoint).await;
+        echo(&mut client, &mut server, "hello").await;
+
+        // Once unbound, the port is free for another socket
+        server.unbind(&endpoint).await.unwrap();
+        let mut other = rebind(&ctx, &endpoint).await;
+        let mut client = req(&ctx, &endpoint).await;
+        echo(&mut client, &mut other, "again").await;
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn connections_come_and_go() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let mut first = rep(&ctx, "inproc://endpoints-first").await;
+    let mut second = rep(&ctx, "inproc://endpoints-second").await;
+    let mut client = req(&ctx, "inproc://endpoints-first").await;
+
+    let exchange = async {
+        echo(&mut client, &mut first, "first").await;
+
+        client.disconnect("inproc://endpoints-first").await.unwrap();
+        client.connect("inproc://endpoints-second").await.unwrap();
+
+        echo(&mut client, &mut second, "second").await;
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn unknown_endpoints_are_errors() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let mut server = rep(&ctx, "inproc://endpoints-unknown").await;
+
+    assert!(server
+        .unbind("inproc://endpoints-never-bound")
+        .await
+        .is_err());
+    assert!(server
+        .disconnect("inproc://endpoints-never-connected")
+        .await
+        .is_err());
+    assert!(server.connect("not-an-endpoint").await.is_err());
+}
+
+#[tokio::test]
+async fn live_endpoints_are_checked() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let mut server = rep(&ctx, "inproc://endpoints-checked").await;
+
+    match server.bind("tcp://127.0.0.1").await {
+        Err(Error::Build(BuildError::Endpoint(EndpointError::MissingPort(_)))) => (),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+    match server.connect("tcp://*:0").await {
+        Err(Error::Build(BuildError::Endpoint(EndpointError::Wildcard(_)))) => (),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn live_ipc_binds_are_checked() {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    let path =
+        std::env::temp_dir().join(format!("futures-zmq-endpoints-live-{}", std::process::id()));
+    let endpoint = format!("ipc://{}", path.display());
+    let ctx = Arc::new(zmq::Context::new());
+
+    let mut server: Rep = Rep::builder(ctx)
+        .ipc_permissions(0o600)
+        .bind("inproc://endpoints-live-ipc")
+        .build()
+        .await
+        .unwrap();
+
+    // A file that isn't a socket is never replaced
+    fs::write(&path, "in the way").unwrap();
+    match server.bind(&endpoint).await {
+        Err(Error::Build(BuildError::Ipc { .. })) => (),
+        other => panic!("Unexpected result: {:?}", other),
+    }
+
+    fs::remove_file(&path).unwrap();
+    server.bind(&endpoint).await.unwrap();
+
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    server.unbind(&endpoint).await.unwrap();
+    let _ = fs::remove_file(&path);
+}
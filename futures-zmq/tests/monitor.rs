@@ -0,0 +1,86 @@
+/*
+ * This file is part of Futures ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Futures ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Futures ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use futures::StreamExt;
+use futures_zmq::{async_types::MonitorStream, prelude::*, Rep, Req, SocketEvent};
+use tokio::time::timeout;
+
+// Skip the events whose timing depends on the connection, rather than on what the test does
+async fn next_event(events: &mut MonitorStream) -> SocketEvent {
+    loop {
+        match events.next().await.expect("Monitor stopped") {
+            SocketEvent::ConnectDelayed { .. } | SocketEvent::HandshakeSucceeded { .. } => (),
+            event => return event,
+        }
+    }
+}
+
+#[tokio::test]
+async fn monitors_report_connections_coming_and_going() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let mut rep: Rep = Rep::builder(Arc::clone(&ctx))
+        .bind("inproc://monitor-rep")
+        .build()
+        .await
+        .unwrap();
+    let mut req: Req = Req::builder(Arc::clone(&ctx))
+        .connect("inproc://monitor-req")
+        .linger(Some(Duration::from_millis(0)))
+        .build()
+        .await
+        .unwrap();
+
+    let exchange = async {
+        // An ephemeral port, so the test doesn't depend on a fixed one being free
+        let endpoint = rep.bind("tcp://127.0.0.1:0").await.unwrap();
+
+        let mut accepted = rep.socket_mut().monitor(&ctx).await.unwrap();
+        let mut connected = req.socket_mut().monitor(&ctx).await.unwrap();
+
+        req.connect(&endpoint).await.unwrap();
+
+        match next_event(&mut connected).await {
+            SocketEvent::Connected {
+                endpoint: ref e, ..
+            } if *e == endpoint => (),
+            other => panic!("Expected Connected, got {:?}", other),
+        }
+        match next_event(&mut accepted).await {
+            SocketEvent::Accepted {
+                endpoint: ref e, ..
+            } if *e == endpoint => (),
+            other => panic!("Expected Accepted, got {:?}", other),
+        }
+
+        drop(connected);
+        drop(req);
+
+        match next_event(&mut accepted).await {
+            SocketEvent::Disconnected {
+                endpoint: ref e, ..
+            } if *e == endpoint => (),
+            other => panic!("Expected Disconnected, got {:?}", other),
+        }
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
@@ -0,0 +1,175 @@
+/*
+ * This file is part of Futures ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Futures ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Futures ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use futures_zmq::{prelude::*, Multipart, Pull, Rep, Req, Session};
+use tokio::time::{sleep, timeout};
+
+fn message(s: &str) -> Multipart {
+    zmq::Message::from(s).into()
+}
+
+fn text(multipart: &Multipart) -> Option<&str> {
+    multipart.get(0).and_then(|msg| msg.as_str())
+}
+
+/// Context switches and CPU time of every poll thread in this process
+#[cfg(target_os = "linux")]
+fn poll_thread_usage() -> (u64, u64) {
+    use std::fs;
+
+    let mut switches = 0;
+    let mut ticks = 0;
+
+    for task in fs::read_dir("/proc/self/task").unwrap() {
+        let path = task.unwrap().path();
+
+        match fs::read_to_string(path.join("comm")) {
+            Ok(ref comm) if comm.starts_with("futures-zmq") => (),
+            _ => continue,
+        }
+
+        let status = fs::read_to_string(path.join("status")).unwrap_or_default();
+        for line in status.lines() {
+            if line.starts_with("voluntary_ctxt_switches:")
+                || line.starts_with("nonvoluntary_ctxt_switches:")
+            {
+                switches += line
+                    .split_whitespace()
+                    .nth(1)
+                    .unwrap()
+                    .parse::<u64>()
+                    .unwrap();
+            }
+        }
+
+        // utime and stime are the 14th and 15th fields, after the parenthesized name
+        let stat = fs::read_to_string(path.join("stat")).unwrap_or_default();
+        if let Some(fields) = stat.rsplit(')').next() {
+            let fields: Vec<_> = fields.split_whitespace().collect();
+            ticks += fields[11].parse::<u64>().unwrap() + fields[12].parse::<u64>().unwrap();
+        }
+    }
+
+    (switches, ticks)
+}
+
+// This is the only test in this file, so no other poll threads are busy while it measures
+
+/// Measures how often idle poll threads wake up, and the round trip latency through a session
+///
+/// Timings depend on the machine and whatever else it's running, so this only runs when asked
+/// for, with `cargo test --test poll_thread -- --ignored`.
+#[tokio::test]
+#[ignore]
+async fn benchmark_idle_wakeups_and_latency() {
+    let ctx = Arc::new(zmq::Context::new());
+    let sessions: Vec<_> = (0..4).map(|_| Session::new()).collect();
+
+    // Every session has a socket waiting on a receive that never completes
+    let mut idle = Vec::new();
+    for (i, session) in sessions.iter().enumerate() {
+        let pull: Pull = Pull::builder(Arc::clone(&ctx))
+            .bind(format!("inproc://poll-thread-idle-{}", i))
+            .build_in(session)
+            .await
+            .unwrap();
+
+        idle.push(tokio::spawn(async move {
+            let mut pull = pull;
+            let _ = pull.recv_mut().await;
+        }));
+    }
+
+    sleep(Duration::from_millis(100)).await;
+
+    #[cfg(target_os = "linux")]
+    {
+        let (switches_before, ticks_before) = poll_thread_usage();
+        sleep(Duration::from_secs(1)).await;
+        let (switches_after, ticks_after) = poll_thread_usage();
+
+        let switches = switches_after - switches_before;
+        let ticks = ticks_after - ticks_before;
+
+        // Polling on a timeout would wake each thread 20 times a second
+        assert!(
+            switches < 8,
+            "Idle poll threads woke {} times, using {} clock ticks",
+            switches,
+            ticks
+        );
+    }
+
+    let mut rep: Rep = Rep::builder(Arc::clone(&ctx))
+        .bind("inproc://poll-thread-latency")
+        .build_in(&sessions[0])
+        .await
+        .unwrap();
+
+    let mut req: Req = Req::builder(ctx)
+        .connect("inproc://poll-thread-latency")
+        .build_in(&sessions[1])
+        .await
+        .unwrap();
+
+    let server = tokio::spawn(async move {
+        while let Ok(multipart) = rep.recv_mut().await {
+            if rep.send_mut(multipart).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut latencies = Vec::with_capacity(1000);
+    for _ in 0..1000 {
+        let start = Instant::now();
+        req.send_mut(message("ping")).await.unwrap();
+        let reply = timeout(Duration::from_secs(5), req.recv_mut())
+            .await
+            .unwrap()
+            .unwrap();
+        latencies.push(start.elapsed());
+
+        assert_eq!(text(&reply), Some("ping"));
+    }
+
+    latencies.sort();
+    let p50 = latencies[latencies.len() / 2];
+    let p99 = latencies[latencies.len() * 99 / 100];
+    assert!(
+        p99 < Duration::from_millis(50),
+        "p99 latency was {:?}, p50 was {:?}",
+        p99,
+        p50
+    );
+
+    for session in &sessions {
+        session.shutdown();
+    }
+
+    server.await.unwrap();
+    for task in idle {
+        task.await.unwrap();
+    }
+}
@@ -0,0 +1,206 @@
+/*
+ * This file is part of Futures ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Futures ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Futures ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use futures_zmq::{
+    prelude::*,
+    proxy::{proxy, proxy_steerable, ProxyCommand, ProxyError, ProxyStatistics},
+    Dealer, Error, Multipart, Pair, Pull, Push, Rep, Req, Router,
+};
+use tokio::time::timeout;
+
+fn message(s: &str) -> Multipart {
+    zmq::Message::from(s).into()
+}
+
+fn text(multipart: &Multipart) -> Option<&str> {
+    multipart.get(0).and_then(|msg| msg.as_str())
+}
+
+async fn pull(ctx: &Arc<zmq::Context>, endpoint: &str) -> Pull {
+    Pull::builder(Arc::clone(ctx))
+        .bind(endpoint)
+        .build()
+        .await
+        .unwrap()
+}
+
+async fn push(ctx: &Arc<zmq::Context>, endpoint: &str) -> Push {
+    Push::builder(Arc::clone(ctx))
+        .connect(endpoint)
+        .build()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn pipeline_with_capture() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let frontend = pull(&ctx, "inproc://proxy-pipeline-in").await;
+    let mut sender = push(&ctx, "inproc://proxy-pipeline-in").await;
+
+    let mut receiver = pull(&ctx, "inproc://proxy-pipeline-out").await;
+    let backend = push(&ctx, "inproc://proxy-pipeline-out").await;
+
+    let mut captured = pull(&ctx, "inproc://proxy-pipeline-capture").await;
+    let capture = push(&ctx, "inproc://proxy-pipeline-capture").await;
+
+    tokio::spawn(proxy(frontend, backend, Some(capture)));
+
+    let exchange = async {
+        for word in &["one", "two", "three"] {
+            sender.send_mut(message(word)).await.unwrap();
+        }
+
+        for word in &["one", "two", "three"] {
+            assert_eq!(text(&receiver.recv_mut().await.unwrap()), Some(*word));
+            assert_eq!(text(&captured.recv_mut().await.unwrap()), Some(*word));
+        }
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn router_dealer_broker() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let frontend: Router = Router::builder(Arc::clone(&ctx))
+        .bind("inproc://proxy-broker-front")
+        .build()
+        .await
+        .unwrap();
+    let backend: Dealer = Dealer::builder(Arc::clone(&ctx))
+        .bind("inproc://proxy-broker-back")
+        .build()
+        .await
+        .unwrap();
+
+    let mut rep: Rep = Rep::builder(Arc::clone(&ctx))
+        .connect("inproc://proxy-broker-back")
+        .build()
+        .await
+        .unwrap();
+    let mut req: Req = Req::builder(ctx)
+        .connect("inproc://proxy-broker-front")
+        .build()
+        .await
+        .unwrap();
+
+    tokio::spawn(proxy(frontend, backend, None::<Push>));
+
+    let exchange = async {
+        for _ in 0..3 {
+            req.send_mut(message("ping")).await.unwrap();
+            let request = rep.recv_mut().await.unwrap();
+            assert_eq!(text(&request), Some("ping"));
+
+            rep.send_mut(message("pong")).await.unwrap();
+            let reply = req.recv_mut().await.unwrap();
+            assert_eq!(text(&reply), Some("pong"));
+        }
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn steerable_proxy() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let frontend = pull(&ctx, "inproc://proxy-steer-in").await;
+    let mut sender = push(&ctx, "inproc://proxy-steer-in").await;
+
+    let mut receiver = pull(&ctx, "inproc://proxy-steer-out").await;
+    let backend = push(&ctx, "inproc://proxy-steer-out").await;
+
+    let control: Pair = Pair::builder(Arc::clone(&ctx))
+        .pair("inproc://proxy-steer-control", true)
+        .build()
+        .await
+        .unwrap();
+    let mut controller: Pair = Pair::builder(ctx)
+        .pair("inproc://proxy-steer-control", false)
+        .build()
+        .await
+        .unwrap();
+
+    let running = tokio::spawn(proxy_steerable(frontend, backend, None::<Push>, control));
+
+    let steer = async {
+        controller
+            .send_mut(ProxyCommand::Pause.into())
+            .await
+            .unwrap();
+        controller
+            .send_mut(ProxyCommand::Statistics.into())
+            .await
+            .unwrap();
+        controller.recv_mut().await.unwrap();
+
+        sender.send_mut(message("held")).await.unwrap();
+        let received = timeout(Duration::from_millis(100), receiver.recv_mut()).await;
+        assert!(received.is_err(), "A paused proxy forwarded a message");
+
+        controller
+            .send_mut(ProxyCommand::Resume.into())
+            .await
+            .unwrap();
+        assert_eq!(text(&receiver.recv_mut().await.unwrap()), Some("held"));
+
+        controller
+            .send_mut(ProxyCommand::Statistics.into())
+            .await
+            .unwrap();
+        let stats = ProxyStatistics::from_multipart(&controller.recv_mut().await.unwrap()).unwrap();
+        assert_eq!(stats.frontend_messages_in, 1);
+        assert_eq!(stats.frontend_bytes_in, 4);
+        assert_eq!(stats.backend_messages_out, 1);
+
+        controller
+            .send_mut(ProxyCommand::Terminate.into())
+            .await
+            .unwrap();
+        running.await.unwrap().unwrap()
+    };
+
+    let stats = timeout(Duration::from_secs(5), steer).await.unwrap();
+    assert_eq!(stats.backend_bytes_out, 4);
+}
+
+#[tokio::test]
+async fn sockets_that_cant_forward_are_rejected() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let frontend = pull(&ctx, "inproc://proxy-futures-nowhere-in").await;
+    let backend = pull(&ctx, "inproc://proxy-futures-nowhere-out").await;
+
+    match proxy(frontend, backend, None::<Push>).await {
+        Err(Error::Proxy(ProxyError::NoDirection { frontend, backend })) => {
+            assert_eq!(frontend, zmq::PULL);
+            assert_eq!(backend, zmq::PULL);
+        }
+        other => panic!(
+            "Expected the proxy to be rejected, got {:?}",
+            other.map(|_| ())
+        ),
+    }
+}
@@ -0,0 +1,177 @@
+/*
+ * This file is part of Futures ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Futures ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Futures ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{ptr, sync::Arc, time::Duration};
+
+use futures_zmq::{prelude::*, Error, Multipart, Pull, Push, Session, SessionPool};
+use tokio::time::timeout;
+
+fn message(s: &str) -> Multipart {
+    zmq::Message::from(s).into()
+}
+
+#[tokio::test]
+async fn sockets_in_separate_sessions_talk() {
+    let ctx = Arc::new(zmq::Context::new());
+    let first = Session::new();
+    let second = Session::new();
+
+    let mut pull: Pull = Pull::builder(Arc::clone(&ctx))
+        .bind("inproc://sessions-separate")
+        .build_in(&first)
+        .await
+        .unwrap();
+
+    let mut push: Push = Push::builder(ctx)
+        .connect("inproc://sessions-separate")
+        .build_in(&second)
+        .await
+        .unwrap();
+
+    let exchange = async {
+        push.send_mut(message("hello")).await.unwrap();
+        pull.recv_mut().await.unwrap()
+    };
+
+    let multipart = timeout(Duration::from_secs(5), exchange).await.unwrap();
+    assert_eq!(multipart.get(0).and_then(|msg| msg.as_str()), Some("hello"));
+
+    drop((pull, push));
+    first.shutdown();
+    second.shutdown();
+}
+
+#[test]
+fn pools_shard_sessions() {
+    let pool = SessionPool::new(3);
+    assert_eq!(pool.len(), 3);
+
+    let picked: Vec<_> = (0..6).map(|_| pool.session()).collect();
+    for (i, session) in picked.into_iter().enumerate() {
+        assert!(ptr::eq(session, &pool.sessions()[i % 3]));
+    }
+
+    assert!(ptr::eq(pool.session_for(4), pool.session_for(7)));
+    assert!(ptr::eq(pool.session_for(4), &pool.sessions()[1]));
+
+    pool.shutdown();
+}
+
+#[tokio::test]
+async fn shutdown_fails_waiting_requests_and_joins() {
+    let ctx = Arc::new(zmq::Context::new());
+    let session = Session::new();
+
+    let mut pull: Pull = Pull::builder(Arc::clone(&ctx))
+        .bind("inproc://sessions-shutdown")
+        .build_in(&session)
+        .await
+        .unwrap();
+
+    let recv = pull.recv_mut();
+    tokio::pin!(recv);
+    assert!(timeout(Duration::from_millis(100), &mut recv)
+        .await
+        .is_err());
+
+    let shutdown = tokio::task::spawn_blocking({
+        let session = session.clone();
+        move || session.shutdown()
+    });
+    timeout(Duration::from_secs(5), shutdown)
+        .await
+        .unwrap()
+        .unwrap();
+
+    match timeout(Duration::from_secs(5), recv).await.unwrap() {
+        Err(Error::Dropped) => (),
+        other => panic!("Unexpected result: {:?}", other.map(|_| ())),
+    }
+
+    let res: Result<Pull, Error> = Pull::builder(ctx)
+        .bind("inproc://sessions-shutdown-again")
+        .build_in(&session)
+        .await;
+    assert!(res.is_err(), "Built a socket in a stopped session");
+}
+
+#[tokio::test]
+async fn sessions_start_without_loopback_sockets() {
+    let ctx = Arc::new(zmq::Context::new());
+    let sessions: Vec<_> = (0..32).map(|_| Session::try_new().unwrap()).collect();
+
+    let mut pull: Pull = Pull::builder(Arc::clone(&ctx))
+        .bind("inproc://sessions-many")
+        .build_in(&sessions[0])
+        .await
+        .unwrap();
+
+    let mut push: Push = Push::builder(ctx)
+        .connect("inproc://sessions-many")
+        .build_in(&sessions[31])
+        .await
+        .unwrap();
+
+    let exchange = async {
+        push.send_mut(message("wake")).await.unwrap();
+        pull.recv_mut().await.unwrap()
+    };
+
+    let multipart = timeout(Duration::from_secs(5), exchange).await.unwrap();
+    assert_eq!(multipart.get(0).and_then(|msg| msg.as_str()), Some("wake"));
+
+    drop((pull, push));
+    for session in sessions {
+        session.shutdown();
+    }
+}
+
+#[tokio::test]
+async fn dropped_receives_leave_messages_for_the_next() {
+    let ctx = Arc::new(zmq::Context::new());
+    let session = Session::new();
+
+    let mut pull: Pull = Pull::builder(Arc::clone(&ctx))
+        .bind("inproc://poll-thread-cancel")
+        .build_in(&session)
+        .await
+        .unwrap();
+
+    let mut push: Push = Push::builder(ctx)
+        .connect("inproc://poll-thread-cancel")
+        .build_in(&session)
+        .await
+        .unwrap();
+
+    for _ in 0..3 {
+        let received = timeout(Duration::from_millis(20), pull.recv_mut()).await;
+        assert!(received.is_err(), "Received a message that wasn't sent");
+    }
+
+    push.send_mut(message("kept")).await.unwrap();
+
+    let multipart = timeout(Duration::from_secs(5), pull.recv_mut())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(multipart.get(0).and_then(|msg| msg.as_str()), Some("kept"));
+
+    drop((pull, push));
+    session.shutdown();
+}
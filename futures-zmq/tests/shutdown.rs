@@ -0,0 +1,207 @@
+/*
+ * This file is part of Futures ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Futures ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Futures ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use futures::SinkExt;
+use futures_zmq::{prelude::*, Dealer, Error, Multipart, Pull, Push, Session};
+use tokio::time::{sleep, timeout};
+
+fn message(i: usize) -> Multipart {
+    zmq::Message::from(&format!("{}", i)).into()
+}
+
+// A bound Push socket without any peers can't send, so the poll thread holds on to everything
+async fn lonely_push(session: &Session, endpoint: &str) -> Push {
+    Push::builder(Arc::new(zmq::Context::new()))
+        .bind(endpoint)
+        .build_in(session)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn close_flushes_once_a_peer_connects() {
+    let ctx = Arc::new(zmq::Context::new());
+    let session = Session::new();
+
+    let push: Push = Push::builder(Arc::clone(&ctx))
+        .bind("inproc://shutdown-flush")
+        .build_in(&session)
+        .await
+        .unwrap();
+
+    let mut sink = push.sink(10);
+    for i in 0..5 {
+        sink.feed(message(i)).await.unwrap();
+    }
+
+    let pull_session = session.clone();
+    let receiver = tokio::spawn(async move {
+        sleep(Duration::from_millis(100)).await;
+
+        let mut pull: Pull = Pull::builder(ctx)
+            .connect("inproc://shutdown-flush")
+            .build_in(&pull_session)
+            .await
+            .unwrap();
+
+        for _ in 0..5 {
+            pull.recv_mut().await.unwrap();
+        }
+    });
+
+    let report = sink.shutdown(Duration::from_secs(5)).await.unwrap();
+    assert!(
+        report.is_complete(),
+        "Undelivered: {}",
+        report.undelivered()
+    );
+
+    timeout(Duration::from_secs(5), receiver)
+        .await
+        .unwrap()
+        .unwrap();
+    session.shutdown();
+}
+
+#[tokio::test]
+async fn close_respects_linger() {
+    let session = Session::new();
+
+    // Like Push, a bound Dealer can't send without peers
+    let dealer: Dealer = Dealer::builder(Arc::new(zmq::Context::new()))
+        .bind("inproc://shutdown-linger")
+        .linger(Some(Duration::from_millis(50)))
+        .build_in(&session)
+        .await
+        .unwrap();
+
+    let mut sink = dealer.sink_stream(10);
+    for i in 0..3 {
+        sink.feed(message(i)).await.unwrap();
+    }
+
+    let report = timeout(Duration::from_secs(5), sink.close())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(report.undelivered(), 3);
+
+    session.shutdown();
+}
+
+#[tokio::test]
+async fn drain_sends_queued_multiparts_before_stopping() {
+    let ctx = Arc::new(zmq::Context::new());
+    let session = Session::new();
+
+    let mut pull: Pull = Pull::builder(Arc::clone(&ctx))
+        .bind("inproc://shutdown-drain")
+        .build_in(&session)
+        .await
+        .unwrap();
+
+    let push: Push = Push::builder(ctx)
+        .connect("inproc://shutdown-drain")
+        .build_in(&session)
+        .await
+        .unwrap();
+
+    let mut sink = push.sink(10);
+    for i in 0..5 {
+        sink.feed(message(i)).await.unwrap();
+    }
+
+    let waiting = tokio::spawn(async move { pull.recv_mut().await.map(|_| ()) });
+    sleep(Duration::from_millis(50)).await;
+
+    let drained = session.clone();
+    let report = tokio::task::spawn_blocking(move || drained.drain(Duration::from_secs(5)))
+        .await
+        .unwrap();
+    assert!(
+        report.is_complete(),
+        "Undelivered: {}",
+        report.undelivered()
+    );
+
+    // The receive was either answered before the drain started, or failed once it did
+    match waiting.await.unwrap() {
+        Ok(()) | Err(Error::Dropped) => (),
+        Err(e) => panic!("Unexpected error: {}", e),
+    }
+
+    assert!(sink.flush().await.is_ok());
+}
+
+#[tokio::test]
+async fn drain_reports_what_timed_out() {
+    let session = Session::new();
+    let push = lonely_push(&session, "inproc://shutdown-drain-timeout").await;
+
+    let mut sink = push.sink(10);
+    for i in 0..4 {
+        sink.feed(message(i)).await.unwrap();
+    }
+
+    let drained = session.clone();
+    let report = tokio::task::spawn_blocking(move || drained.drain(Duration::from_millis(50)))
+        .await
+        .unwrap();
+    assert_eq!(report.undelivered(), 4);
+
+    match sink.flush().await {
+        Err(Error::Dropped) => (),
+        other => panic!(
+            "Unexpected flush result: {:?}",
+            other.map_err(|e| e.to_string())
+        ),
+    }
+}
+
+#[tokio::test]
+async fn sockets_close_like_their_sinks() {
+    let ctx = Arc::new(zmq::Context::new());
+    let session = Session::new();
+
+    let mut push: Push = Push::builder(Arc::clone(&ctx))
+        .bind("inproc://shutdown-socket")
+        .build_in(&session)
+        .await
+        .unwrap();
+    let mut pull: Pull = Pull::builder(ctx)
+        .connect("inproc://shutdown-socket")
+        .build_in(&session)
+        .await
+        .unwrap();
+
+    push.send_mut(message(0)).await.unwrap();
+
+    let report = timeout(Duration::from_secs(5), push.close())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(report.is_complete());
+
+    let multipart = pull.recv_mut().await.unwrap();
+    assert_eq!(multipart.get(0).and_then(|msg| msg.as_str()), Some("0"));
+
+    session.shutdown();
+}
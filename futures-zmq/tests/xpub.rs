@@ -0,0 +1,188 @@
+/*
+ * This file is part of Futures ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Futures ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Futures ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use futures::{SinkExt, TryStreamExt};
+use futures_zmq::{
+    prelude::*,
+    subscription::{Subscription, XpubEvent},
+    Multipart, Sub, Xpub, Xsub,
+};
+use tokio::time::timeout;
+
+fn message(s: &str) -> Multipart {
+    zmq::Message::from(s).into()
+}
+
+fn text(multipart: &Multipart) -> Option<&str> {
+    multipart.get(0).and_then(|msg| msg.as_str())
+}
+
+fn subscribe(topic: &str) -> Subscription {
+    Subscription::Subscribe(topic.as_bytes().to_vec())
+}
+
+async fn subscriber(ctx: &Arc<zmq::Context>, endpoint: &str, filter: &str) -> Sub {
+    Sub::builder(Arc::clone(ctx))
+        .connect(endpoint)
+        .filter(filter.as_bytes())
+        .build()
+        .await
+        .unwrap()
+}
+
+async fn next_subscription<S>(stream: &mut S) -> Subscription
+where
+    S: TryStreamExt<Ok = XpubEvent, Error = futures_zmq::Error> + Unpin,
+{
+    match stream.try_next().await.unwrap().unwrap() {
+        XpubEvent::Subscription(subscription) => subscription,
+        XpubEvent::Message(multipart) => panic!("Expected a subscription, got {:?}", multipart),
+    }
+}
+
+#[tokio::test]
+async fn xpub_reads_subscriptions() {
+    let ctx = Arc::new(zmq::Context::new());
+    let endpoint = "inproc://xpub-subscriptions";
+
+    let xpub: Xpub = Xpub::builder(Arc::clone(&ctx))
+        .bind(endpoint)
+        .xpub_verbose(true)
+        .build()
+        .await
+        .unwrap();
+    let mut xpub = xpub.subscription_stream(25);
+
+    let exchange = async {
+        let first = subscriber(&ctx, endpoint, "news").await;
+        assert_eq!(next_subscription(&mut xpub).await, subscribe("news"));
+
+        // Verbose sockets pass on subscriptions to topics that already have subscribers
+        let second = subscriber(&ctx, endpoint, "news").await;
+        assert_eq!(next_subscription(&mut xpub).await, subscribe("news"));
+
+        xpub.send(message("news today")).await.unwrap();
+
+        for sub in [first, second] {
+            let mut stream = sub.stream();
+            let multipart = stream.try_next().await.unwrap().unwrap();
+            assert_eq!(text(&multipart), Some("news today"));
+
+            stream.unsubscribe(b"news").await.unwrap();
+        }
+
+        // Unsubscriptions are passed on once a topic has no subscribers left
+        assert_eq!(
+            next_subscription(&mut xpub).await,
+            Subscription::Unsubscribe(b"news".to_vec())
+        );
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn xsub_sends_subscriptions_and_messages() {
+    let ctx = Arc::new(zmq::Context::new());
+    let endpoint = "inproc://xsub-subscriptions";
+
+    let xpub: Xpub = Xpub::builder(Arc::clone(&ctx))
+        .bind(endpoint)
+        .build()
+        .await
+        .unwrap();
+    let mut xpub = xpub.subscription_stream(25);
+
+    let xsub: Xsub = Xsub::builder(Arc::clone(&ctx))
+        .connect(endpoint)
+        .build()
+        .await
+        .unwrap();
+    let mut xsub = xsub.subscription_sink(25);
+
+    let exchange = async {
+        xsub.send(subscribe("news")).await.unwrap();
+        assert_eq!(next_subscription(&mut xpub).await, subscribe("news"));
+
+        let mut upstream = message("hello");
+        upstream.push_back(zmq::Message::from("world"));
+        xsub.send(upstream).await.unwrap();
+
+        match xpub.try_next().await.unwrap().unwrap() {
+            XpubEvent::Message(multipart) => {
+                assert_eq!(multipart.len(), 2);
+                assert_eq!(text(&multipart), Some("hello"));
+            }
+            other => panic!("Expected a message, got {:?}", other),
+        }
+
+        xpub.send(message("sports")).await.unwrap();
+        xpub.send(message("news today")).await.unwrap();
+
+        let multipart = xsub.try_next().await.unwrap().unwrap();
+        assert_eq!(text(&multipart), Some("news today"));
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn manual_xpubs_choose_subscriptions() {
+    let ctx = Arc::new(zmq::Context::new());
+    let endpoint = "inproc://xpub-manual";
+
+    let xpub: Xpub = Xpub::builder(Arc::clone(&ctx))
+        .bind(endpoint)
+        .xpub_manual(true)
+        .xpub_welcome_msg(b"welcome")
+        .build()
+        .await
+        .unwrap();
+    let mut xpub = xpub.subscription_stream(25);
+
+    let sub = subscriber(&ctx, endpoint, "welcome").await;
+
+    let exchange = async {
+        let mut sub = sub.stream();
+
+        assert_eq!(next_subscription(&mut xpub).await, subscribe("welcome"));
+
+        // Welcome messages skip the publisher's filter
+        let multipart = sub.try_next().await.unwrap().unwrap();
+        assert_eq!(text(&multipart), Some("welcome"));
+
+        sub.subscribe(b"sports").await.unwrap();
+        sub.subscribe(b"news").await.unwrap();
+        assert_eq!(next_subscription(&mut xpub).await, subscribe("sports"));
+        assert_eq!(next_subscription(&mut xpub).await, subscribe("news"));
+
+        // Only the last subscription read can be applied
+        xpub.get_ref().subscribe(b"news").await.unwrap();
+
+        xpub.send(message("sports")).await.unwrap();
+        xpub.send(message("news today")).await.unwrap();
+
+        let multipart = sub.try_next().await.unwrap().unwrap();
+        assert_eq!(text(&multipart), Some("news today"));
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
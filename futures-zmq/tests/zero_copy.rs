@@ -0,0 +1,56 @@
+/*
+ * This file is part of Futures ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Futures ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Futures ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Futures ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use futures_zmq::{prelude::*, Multipart, Pull, Push};
+use tokio::time::timeout;
+
+#[tokio::test]
+async fn refused_frames_are_requeued() {
+    let ctx = Arc::new(zmq::Context::new());
+    let data: Arc<[u8]> = vec![3; 1 << 16].into();
+
+    // A bound Push socket refuses to send until a peer connects
+    let mut push: Push = Push::builder(Arc::clone(&ctx))
+        .bind("inproc://zero-copy-futures")
+        .build()
+        .await
+        .unwrap();
+
+    let send = push.send_mut(Multipart::from(Arc::clone(&data)));
+    tokio::pin!(send);
+    assert!(timeout(Duration::from_millis(100), &mut send)
+        .await
+        .is_err());
+
+    let mut pull: Pull = Pull::builder(ctx)
+        .connect("inproc://zero-copy-futures")
+        .build()
+        .await
+        .unwrap();
+
+    let exchange = async {
+        send.await.unwrap();
+        pull.recv_mut().await.unwrap()
+    };
+
+    let received = timeout(Duration::from_secs(5), exchange).await.unwrap();
+    assert_eq!(received.get(0).map(|msg| &msg[..]), Some(&data[..]));
+}
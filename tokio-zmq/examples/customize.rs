@@ -19,7 +19,8 @@
 
 use std::{sync::Arc, thread, time::Duration};
 
-use futures::{stream::iter_ok, Future, Sink, Stream};
+use futures::{stream::iter, try_join, SinkExt, StreamExt, TryStreamExt};
+use tokio::runtime::Runtime;
 use tokio_zmq::{prelude::*, Error, Multipart, Pub, Rep, Req, Sub};
 
 // On my quad-core i7, if I run with too many threads, the context switching takes too long and
@@ -45,94 +46,84 @@ impl EndHandler for Stop {
     }
 }
 
-fn publisher_thread() {
+async fn publisher() -> Result<(), Error> {
     let ctx = Arc::new(zmq::Context::new());
 
     let publisher_fut = Pub::builder(Arc::clone(&ctx))
-        .customize(|sock| {
-            let _ = sock.set_maxmsgsize(256);
-        })
+        .customize(|sock| sock.set_maxmsgsize(256))
         .bind("tcp://*:5561")
         .build();
 
     let syncservice_fut = Rep::builder(ctx)
-        .customize(|sock| {
-            let _ = sock.set_maxmsgsize(256);
-        })
-        .bind("tcp://*:5562").build();
+        .customize(|sock| sock.set_maxmsgsize(256))
+        .bind("tcp://*:5562")
+        .build();
 
     println!("Waiting for subscribers");
 
-    let runner = publisher_fut
-        .join(syncservice_fut)
-        .and_then(|(publisher, syncservice)| {
-            let (sync_sink, sync_stream) = syncservice.sink_stream(25).split();
-
-            iter_ok(0..SUBSCRIBERS)
-                .zip(sync_stream)
-                .map(|(_, _)| zmq::Message::from("").into())
-                .forward(sync_sink)
-                .and_then(move |_| {
-                    println!("Broadcasting message");
-
-                    iter_ok(0..MESSAGES)
-                        .map(|_| zmq::Message::from("Rhubarb").into())
-                        .forward(publisher.sink(25))
-                })
-                .and_then(|(_stream, sink)| {
-                    let msg = zmq::Message::from("END");
-
-                    sink.send(msg.into())
-                })
-        });
-
-    tokio::run(runner.map(|_| ()).or_else(|e| {
+    let (publisher, syncservice): (Pub, Rep) = try_join!(publisher_fut, syncservice_fut)?;
+
+    let (sync_sink, sync_stream) = syncservice.sink_stream(25).split();
+
+    sync_stream
+        .take(SUBSCRIBERS)
+        .map_ok(|_| zmq::Message::from("").into())
+        .forward(sync_sink)
+        .await?;
+
+    println!("Broadcasting message");
+
+    let mut sink = publisher.sink(25);
+
+    sink.send_all(&mut iter(0..MESSAGES).map(|_| Ok(zmq::Message::from("Rhubarb").into())))
+        .await?;
+
+    let msg = zmq::Message::from("END");
+
+    sink.send(msg.into()).await
+}
+
+fn publisher_thread() {
+    if let Err(e) = Runtime::new().unwrap().block_on(publisher()) {
         println!("Error in publisher: {:?}", e);
-        Ok(())
-    }));
+    }
 }
 
-fn subscriber_thread() {
+async fn subscriber() -> Result<(), Error> {
     let ctx = Arc::new(zmq::Context::new());
 
     let subscriber_fut = Sub::builder(Arc::clone(&ctx))
-        .customize(|sock| {
-            let _ = sock.set_maxmsgsize(256);
-        })
+        .customize(|sock| sock.set_maxmsgsize(256))
         .connect("tcp://localhost:5561")
         .filter(b"")
         .build();
 
     let syncclient_fut = Req::builder(ctx)
-        .customize(|sock| {
-            let _ = sock.set_maxmsgsize(256);
-        })
-        .connect("tcp://localhost:5562").build();
+        .customize(|sock| sock.set_maxmsgsize(256))
+        .connect("tcp://localhost:5562")
+        .build();
 
     let msg = zmq::Message::from("");
 
-    let runner = subscriber_fut
-        .join(syncclient_fut)
-        .and_then(|(subscriber, syncclient)| {
-            syncclient
-                .send(msg.into())
-                .and_then(|syncclient| syncclient.recv())
-                .and_then(move |_| {
-                    subscriber
-                        .stream()
-                        .with_end_handler(Stop)
-                        .fold(0, |counter, _| Ok(counter + 1) as Result<usize, Error>)
-                        .and_then(|total| {
-                            println!("Received {} updates", total);
-                            Ok(())
-                        })
-                })
-        });
-
-    tokio::run(runner.map(|_| ()).or_else(|e| {
+    let (subscriber, syncclient): (Sub, Req) = try_join!(subscriber_fut, syncclient_fut)?;
+
+    let syncclient: Req = syncclient.send(msg.into()).await?;
+    let (_, _syncclient): (_, Req) = syncclient.recv().await?;
+
+    let total = subscriber
+        .stream()
+        .with_end_handler(Stop)
+        .try_fold(0, |counter, _| async move { Ok(counter + 1) })
+        .await?;
+
+    println!("Received {} updates", total);
+    Ok(())
+}
+
+fn subscriber_thread() {
+    if let Err(e) = Runtime::new().unwrap().block_on(subscriber()) {
         println!("Error in subscriber: {:?}", e);
-        Ok(())
-    }));
+    }
 }
 
 fn main() {
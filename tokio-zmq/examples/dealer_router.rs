@@ -21,14 +21,14 @@ extern crate env_logger;
 extern crate futures;
 extern crate log;
 extern crate tokio;
-extern crate tokio_executor;
 extern crate tokio_zmq;
 extern crate zmq;
 
 use std::{env, sync::Arc, thread};
 
-use futures::{stream::iter_ok, Future, Stream};
-use tokio_zmq::{prelude::*, Dealer, Multipart, Pub, Rep, Req, Router, Sub};
+use futures::{stream::iter, try_join, StreamExt, TryStreamExt};
+use tokio::runtime::Runtime;
+use tokio_zmq::{prelude::*, Dealer, Error, Multipart, Pub, Rep, Req, Router, Sub};
 
 const CLIENT_REQUESTS: usize = 1000;
 
@@ -41,7 +41,7 @@ impl ControlHandler for Stop {
     }
 }
 
-fn client() {
+async fn run_client() -> Result<(), Error> {
     let ctx = Arc::new(zmq::Context::new());
     let req_fut = Req::builder(Arc::clone(&ctx))
         .connect("tcp://localhost:5559")
@@ -49,55 +49,50 @@ fn client() {
 
     let zpub_fut = Pub::builder(Arc::clone(&ctx)).bind("tcp://*:5561").build();
 
+    let (req, zpub): (Req, Pub) = try_join!(req_fut, zpub_fut)?;
+
     println!("Sending 'Hewwo?' for 0");
-    let runner = req_fut
-        .and_then(|req| {
-            req.send(zmq::Message::from("Hewwo?").into())
-                .and_then(|req| {
-                    let (sink, stream) = req.sink_stream(25).split();
-
-                    stream
-                        .zip(iter_ok(1..CLIENT_REQUESTS))
-                        .map(|(multipart, request_nbr)| {
-                            for msg in multipart {
-                                if let Some(msg) = msg.as_str() {
-                                    println!("Received reply {} {}", request_nbr, msg);
-                                }
-                            }
-
-                            println!("Sending 'Hewwo?' for {}", request_nbr);
-                            zmq::Message::from("Hewwo?").into()
-                        })
-                        .forward(sink)
-                })
+    let req: Req = req.send(zmq::Message::from("Hewwo?").into()).await?;
+
+    let (mut sink, mut stream) = req.sink_stream(25).split();
+
+    iter(1..CLIENT_REQUESTS)
+        .zip(&mut stream)
+        .map(|(request_nbr, multipart)| {
+            for msg in multipart? {
+                if let Some(msg) = msg.as_str() {
+                    println!("Received reply {} {}", request_nbr, msg);
+                }
+            }
+
+            println!("Sending 'Hewwo?' for {}", request_nbr);
+            Ok(zmq::Message::from("Hewwo?").into())
         })
-        .join(zpub_fut)
-        .and_then(move |((stream, _sink), zpub)| {
-            stream
-                .into_future()
-                .map_err(|(e, _)| e)
-                .and_then(|(maybe_last_item, _stream)| {
-                    if let Some(multipart) = maybe_last_item {
-                        for msg in multipart {
-                            if let Some(msg) = msg.as_str() {
-                                println!("Received last reply {}", msg);
-                            }
-                        }
-                    }
-
-                    let msg = zmq::Message::from("");
-
-                    zpub.send(msg.into())
-                })
-        });
-
-    tokio::run(runner.map(|_| ()).or_else(|e| {
+        .forward(&mut sink)
+        .await?;
+
+    if let Some(multipart) = stream.try_next().await? {
+        for msg in multipart {
+            if let Some(msg) = msg.as_str() {
+                println!("Received last reply {}", msg);
+            }
+        }
+    }
+
+    let msg = zmq::Message::from("");
+
+    let _zpub: Pub = zpub.send(msg.into()).await?;
+
+    Ok(())
+}
+
+fn client() {
+    if let Err(e) = Runtime::new().unwrap().block_on(run_client()) {
         println!("Error in client: {:?}", e);
-        Ok(())
-    }));
+    }
 }
 
-fn worker() {
+async fn run_worker() -> Result<(), Error> {
     let ctx = Arc::new(zmq::Context::new());
 
     let rep_fut = Rep::builder(Arc::clone(&ctx))
@@ -109,34 +104,36 @@ fn worker() {
         .filter(b"")
         .build();
 
-    let runner = rep_fut.join(cmd_fut).and_then(|(rep, cmd)| {
-        let (rep_sink, rep_stream) = rep.sink_stream(25).split();
-
-        rep_stream
-            .controlled(cmd.stream(), Stop)
-            .map(|multipart| {
-                for msg in multipart {
-                    if let Some(msg) = msg.as_str() {
-                        println!("Received request: {}", msg);
-                    } else {
-                        println!("Received unparsable request: {:?}", msg);
-                    }
+    let (rep, cmd): (Rep, Sub) = try_join!(rep_fut, cmd_fut)?;
+
+    let (rep_sink, rep_stream) = rep.sink_stream(25).split();
+
+    rep_stream
+        .controlled(cmd.stream(), Stop)
+        .map_ok(|multipart| {
+            for msg in multipart {
+                if let Some(msg) = msg.as_str() {
+                    println!("Received request: {}", msg);
+                } else {
+                    println!("Received unparsable request: {:?}", msg);
                 }
+            }
 
-                let msg = zmq::Message::from("Mr Obama???");
+            let msg = zmq::Message::from("Mr Obama???");
 
-                msg.into()
-            })
-            .forward(rep_sink)
-    });
+            msg.into()
+        })
+        .forward(rep_sink)
+        .await
+}
 
-    tokio::run(runner.map(|_| ()).or_else(|e| {
+fn worker() {
+    if let Err(e) = Runtime::new().unwrap().block_on(run_worker()) {
         println!("Error in worker: {:?}", e);
-        Ok(())
-    }));
+    }
 }
 
-fn broker() {
+async fn run_broker() -> Result<(), Error> {
     let ctx = Arc::new(zmq::Context::new());
 
     let router_fut = Router::builder(Arc::clone(&ctx))
@@ -156,48 +153,49 @@ fn broker() {
         .filter(b"")
         .build();
 
-    let runner = dealer_fut
-        .join(router_fut)
-        .join(cmd1_fut.join(cmd2_fut))
-        .and_then(|((dealer, router), (cmd1, cmd2))| {
-            let (dealer_sink, dealer_stream) = dealer.sink_stream(25).split();
-            let (router_sink, router_stream) = router.sink_stream(25).split();
-
-            let d2r = dealer_stream
-                .controlled(cmd1.stream(), Stop)
-                .map(|multipart| {
-                    for msg in &multipart {
-                        if let Some(msg) = msg.as_str() {
-                            println!("Relaying message '{}' to router", msg);
-                        } else {
-                            println!("Relaying unknown message to router");
-                        }
-                    }
-                    multipart
-                })
-                .forward(router_sink);
-
-            let r2d = router_stream
-                .controlled(cmd2.stream(), Stop)
-                .map(|multipart| {
-                    for msg in &multipart {
-                        if let Some(msg) = msg.as_str() {
-                            println!("Relaying message '{}' to dealer", msg);
-                        } else {
-                            println!("Relaying unknown message to dealer");
-                        }
-                    }
-                    multipart
-                })
-                .forward(dealer_sink);
-
-            d2r.join(r2d)
-        });
-
-    tokio::run(runner.map(|_| ()).or_else(|e| {
+    let (dealer, router, cmd1, cmd2): (Dealer, Router, Sub, Sub) =
+        try_join!(dealer_fut, router_fut, cmd1_fut, cmd2_fut)?;
+
+    let (dealer_sink, dealer_stream) = dealer.sink_stream(25).split();
+    let (router_sink, router_stream) = router.sink_stream(25).split();
+
+    let d2r = dealer_stream
+        .controlled(cmd1.stream(), Stop)
+        .map_ok(|multipart| {
+            for msg in &multipart {
+                if let Some(msg) = msg.as_str() {
+                    println!("Relaying message '{}' to router", msg);
+                } else {
+                    println!("Relaying unknown message to router");
+                }
+            }
+            multipart
+        })
+        .forward(router_sink);
+
+    let r2d = router_stream
+        .controlled(cmd2.stream(), Stop)
+        .map_ok(|multipart| {
+            for msg in &multipart {
+                if let Some(msg) = msg.as_str() {
+                    println!("Relaying message '{}' to dealer", msg);
+                } else {
+                    println!("Relaying unknown message to dealer");
+                }
+            }
+            multipart
+        })
+        .forward(dealer_sink);
+
+    try_join!(d2r, r2d)?;
+
+    Ok(())
+}
+
+fn broker() {
+    if let Err(e) = Runtime::new().unwrap().block_on(run_broker()) {
         println!("broker bailed: {:?}", e);
-        Ok(())
-    }));
+    }
 }
 
 #[derive(Debug, PartialEq)]
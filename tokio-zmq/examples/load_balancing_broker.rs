@@ -27,9 +27,17 @@ extern crate zmq;
 
 use std::{env, fmt, sync::Arc, thread, time::Duration};
 
-use futures::{sync::mpsc, Future, Sink, Stream};
+use futures::{
+    future::{ready, select, Either},
+    try_join, SinkExt, StreamExt, TryFutureExt, TryStreamExt,
+};
 use rand::RngCore;
-use tokio_zmq::{prelude::*, Multipart, Pub, Req, Router, Sub};
+use tokio::runtime::Runtime;
+use tokio_zmq::{
+    broker::{LoadBalancer, READY},
+    prelude::*,
+    Multipart, Pub, Req, Router, Sub,
+};
 
 const NUM_CLIENTS: usize = 1000;
 const NUM_WORKERS: usize = 5;
@@ -41,8 +49,6 @@ const BATCH_SIZE: usize = 10;
 enum Error {
     Zmq(zmq::Error),
     TokioZmq(tokio_zmq::Error),
-    WorkerSend,
-    WorkerRecv,
     NotEnoughMessages,
     TooManyMessages,
     MsgNotEmpty,
@@ -53,8 +59,6 @@ impl fmt::Display for Error {
         match *self {
             Error::Zmq(ref e) => write!(f, "Error in ZeroMQ: {}", e),
             Error::TokioZmq(ref e) => write!(f, "Error in Tokio ZMQ: {}", e),
-            Error::WorkerSend => write!(f, "Error sending to worker"),
-            Error::WorkerRecv => write!(f, "Error receiving from worker"),
             Error::NotEnoughMessages => write!(f, "Not enough messages"),
             Error::TooManyMessages => write!(f, "Too many messages"),
             Error::MsgNotEmpty => write!(f, "Message not empty"),
@@ -140,38 +144,36 @@ impl ControlHandler for Stop {
 
 /* ----------------------------------client---------------------------------- */
 
-fn client_task(client_num: usize) -> usize {
+async fn client(client_num: usize) -> Result<(), Error> {
     let context = Arc::new(zmq::Context::new());
 
-    let client_fut = Req::builder(context)
+    let client: Req = Req::builder(context)
         .identity(format!("c{}", client_num).as_bytes())
         .connect("tcp://localhost:5672")
-        .build();
+        .build()
+        .await?;
 
     let msg = zmq::Message::from("HELLO");
-    let fut = client_fut.and_then(move |client| {
-        client
-            .send(msg.into())
-            .from_err()
-            .and_then(|client| client.recv())
-            .and_then(move |(multipart, _)| {
-                if let Some(msg) = multipart.get(0) {
-                    println!("Client {}: {}", client_num, msg.as_str().unwrap());
-                }
-                Ok(())
-            })
-    });
-
-    tokio::run(fut.map(|_| ()).or_else(|e| {
+
+    let client: Req = client.send(msg.into()).await?;
+    let (multipart, _): (_, Req) = client.recv().await?;
+
+    if let Some(msg) = multipart.get(0) {
+        println!("Client {}: {}", client_num, msg.as_str().unwrap());
+    }
+    Ok(())
+}
+
+fn client_task(client_num: usize) -> usize {
+    if let Err(e) = Runtime::new().unwrap().block_on(client(client_num)) {
         println!("Error in client: {}, {:?}", e, e);
-        Ok(())
-    }));
+    }
     client_num
 }
 
 /* ----------------------------------worker---------------------------------- */
 
-fn worker_task(worker_num: usize) -> usize {
+async fn worker(worker_num: usize) -> Result<(), Error> {
     let context = Arc::new(zmq::Context::new());
 
     let control_fut = Sub::builder(Arc::clone(&context))
@@ -183,149 +185,81 @@ fn worker_task(worker_num: usize) -> usize {
         .connect("tcp://localhost:5673")
         .build();
 
-    let msg = zmq::Message::from("READY");
-
-    let fut = worker_fut
-        .join(control_fut)
-        .from_err()
-        .and_then(move |(worker, control)| {
-            worker
-                .send(msg.into())
-                .map_err(Error::from)
-                .and_then(move |worker| {
-                    let (sink, stream) = worker.sink_stream(25).split();
-
-                    stream
-                        .controlled(control.stream(), Stop("worker", worker_num))
-                        .map_err(Error::from)
-                        .and_then(move |multipart| {
-                            let mut envelope: Envelope = Envelope::from_multipart(multipart)?;
-
-                            println!(
-                                "Worker {}: {} from {}",
-                                worker_num,
-                                envelope.request().as_str().unwrap(),
-                                envelope.addr().as_str().unwrap()
-                            );
-
-                            let msg = zmq::Message::from("OK");
-                            envelope.set_request(msg);
-
-                            Ok(envelope.into())
-                        })
-                        .forward(sink)
-                })
-        });
+    let msg = zmq::Message::from(READY);
 
-    tokio::run(fut.map(|_| ()).or_else(|e| {
+    let (worker, control): (Req, Sub) = try_join!(worker_fut, control_fut)?;
+
+    let worker: Req = worker.send(msg.into()).await?;
+
+    let (sink, stream) = worker.sink_stream(25).split();
+
+    stream
+        .controlled(control.stream(), Stop("worker", worker_num))
+        .map_err(Error::from)
+        .and_then(move |multipart| {
+            ready(Envelope::from_multipart(multipart).map(|mut envelope| {
+                println!(
+                    "Worker {}: {} from {}",
+                    worker_num,
+                    envelope.request().as_str().unwrap(),
+                    envelope.addr().as_str().unwrap()
+                );
+
+                let msg = zmq::Message::from("OK");
+                envelope.set_request(msg);
+
+                envelope.into()
+            }))
+        })
+        .forward(sink.sink_err_into::<Error>())
+        .await
+}
+
+fn worker_task(worker_num: usize) -> usize {
+    if let Err(e) = Runtime::new().unwrap().block_on(worker(worker_num)) {
         println!("Error in worker: {}, {:?}", e, e);
-        Ok(())
-    }));
+    }
     println!("Worker {} is done", worker_num);
     worker_num
 }
 
 /* ----------------------------------broker---------------------------------- */
 
-fn broker_task() {
+async fn broker() -> Result<(), Error> {
     let context = Arc::new(zmq::Context::new());
 
     let frontend_fut = Router::builder(Arc::clone(&context))
         .bind("tcp://*:5672")
         .build();
 
-    let control0_fut = Sub::builder(Arc::clone(&context))
-        .connect("tcp://localhost:5674")
-        .filter(b"")
+    let backend_fut = Router::builder(Arc::clone(&context))
+        .bind("tcp://*:5673")
         .build();
 
-    let control1_fut = Sub::builder(Arc::clone(&context))
+    let control_fut = Sub::builder(context)
         .connect("tcp://localhost:5674")
         .filter(b"")
         .build();
 
-    let backend_fut = Router::builder(context).bind("tcp://*:5673").build();
-
-    let runner = frontend_fut
-        .join(backend_fut)
-        .join(control0_fut.join(control1_fut))
-        .from_err()
-        .and_then(|((frontend, backend), (control0, control1))| {
-            let (worker_send, worker_recv) = mpsc::channel::<zmq::Message>(10);
-
-            let (frontend_sink, frontend_stream) = frontend.sink_stream(25).split();
-            let (backend_sink, backend_stream) = backend.sink_stream(25).split();
-
-            let back2front = backend_stream
-                .controlled(control0.stream(), Stop("broker", 0))
-                .map_err(Error::from)
-                .and_then(|mut multipart| {
-                    let worker_id = multipart.pop_front().ok_or(Error::NotEnoughMessages)?;
-
-                    Ok((multipart, worker_id))
-                })
-                .and_then(move |(multipart, worker_id)| {
-                    worker_send
-                        .clone()
-                        .send(worker_id)
-                        .map(|_| multipart)
-                        .map_err(|_| Error::WorkerSend)
-                })
-                .filter_map(|mut multipart| {
-                    let empty = multipart.pop_front().unwrap();
-                    assert!(empty.is_empty());
-                    let client_id = multipart.pop_front().unwrap();
-
-                    if &*client_id == b"READY" {
-                        None
-                    } else {
-                        Some((multipart, client_id))
-                    }
-                })
-                .and_then(|(mut multipart, client_id)| {
-                    let empty = multipart.pop_front().ok_or(Error::NotEnoughMessages)?;
-                    assert!(empty.is_empty());
-                    let reply = multipart.pop_front().ok_or(Error::NotEnoughMessages)?;
+    let (frontend, backend, control): (Router, Router, Sub) =
+        try_join!(frontend_fut, backend_fut, control_fut)?;
 
-                    let mut response = Multipart::new();
+    let broker = LoadBalancer::new(frontend, backend).run();
 
-                    response.push_back(client_id);
-                    response.push_back(empty);
-                    response.push_back(reply);
-
-                    Ok(response)
-                })
-                .forward(frontend_sink);
-
-            let front2back = frontend_stream
-                .controlled(control1.stream(), Stop("broker", 1))
-                .map_err(Error::from)
-                .zip(worker_recv.map_err(|_| Error::WorkerRecv))
-                .and_then(|(mut multipart, worker_id)| {
-                    let client_id = multipart.pop_front().ok_or(Error::NotEnoughMessages)?;
-                    let empty = multipart.pop_front().ok_or(Error::NotEnoughMessages)?;
-                    assert!(empty.is_empty());
-                    let request = multipart.pop_front().ok_or(Error::NotEnoughMessages)?;
-
-                    let mut response = Multipart::new();
-
-                    response.push_back(worker_id);
-                    response.push_back(empty);
-                    response.push_back(client_id);
-                    response.push_back(zmq::Message::new());
-                    response.push_back(request);
-
-                    Ok(response)
-                })
-                .forward(backend_sink);
+    match select(broker, control.stream().into_future()).await {
+        Either::Left((res, _)) => {
+            res?;
+        }
+        Either::Right(_) => println!("Received stop signal! broker"),
+    }
 
-            front2back.join(back2front)
-        });
+    Ok(())
+}
 
-    tokio::run(runner.map(|_| ()).or_else(|e| {
+fn broker_task() {
+    if let Err(e) = Runtime::new().unwrap().block_on(broker()) {
         println!("Error in broker: {}, {:?}", e, e);
-        Ok(())
-    }));
+    }
     println!("Broker is done");
 }
 
@@ -398,15 +332,15 @@ fn main() {
             thread::sleep(Duration::from_secs(1));
 
             // Signal end when all clients have joined
-            tokio::run(
+            let res = Runtime::new().unwrap().block_on(
                 control_fut
-                    .and_then(|control| control.send(zmq::Message::new().into()))
-                    .map(|_| ())
-                    .or_else(|e| {
-                        println!("Error in main loop {}, {:?}", e, e);
-                        Ok(())
-                    }),
+                    .and_then(|control: Pub| control.send(zmq::Message::new().into()))
+                    .map_ok(|_: Pub| ()),
             );
+
+            if let Err(e) = res {
+                println!("Error in main loop {}, {:?}", e, e);
+            }
         }
         _ => (),
     };
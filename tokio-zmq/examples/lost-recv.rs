@@ -1,30 +1,27 @@
 use std::sync::Arc;
 
-use futures::{future::Future, stream::Stream};
-use tokio::runtime::current_thread;
+use futures::{StreamExt, TryStreamExt};
 use tokio_zmq::{prelude::*, Dealer};
 
-fn main() {
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
     std::env::set_var("RUST_LOG", "tokio_zmq=trace");
     env_logger::init();
 
-    let socket = Dealer::builder(Arc::new(zmq::Context::new()))
+    let socket: Dealer = Dealer::builder(Arc::new(zmq::Context::new()))
         .connect("ipc:///tmp/lost-send")
         .build()
-        .wait()
+        .await
         .unwrap();
     let (_, stream) = socket.sink_stream(8192).split();
 
-    let process = stream.fold(1, |count, _| {
-        if count % 100 == 0 {
-            println!("{}", count);
-        }
-        Ok(count + 1) as Result<_, zmq::Error>
-    });
-
-    current_thread::Runtime::new()
-        .unwrap()
-        .spawn(process.map(|_| ()).map_err(|_| panic!()))
-        .run()
+    stream
+        .try_fold(1, |count, _| async move {
+            if count % 100 == 0 {
+                println!("{}", count);
+            }
+            Ok(count + 1)
+        })
+        .await
         .unwrap();
 }
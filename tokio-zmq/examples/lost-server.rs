@@ -1,36 +1,32 @@
 use std::sync::Arc;
 
-use failure::{Error, Fail};
-use futures::{future::Future, stream::Stream, sync::mpsc::channel};
-use tokio::runtime::current_thread;
+use failure::{err_msg, Error};
+use futures::{channel::mpsc::channel, SinkExt, StreamExt, TryStreamExt};
 use tokio_zmq::{prelude::*, Dealer};
 
 const COUNT: usize = 500;
 
-fn main() {
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
     std::env::set_var("RUST_LOG", "tokio_zmq=trace");
     env_logger::init();
 
-    let socket = Dealer::builder(Arc::new(zmq::Context::new()))
+    let socket: Dealer = Dealer::builder(Arc::new(zmq::Context::new()))
         .bind("ipc:///tmp/lost-send")
         .build()
-        .wait()
+        .await
         .unwrap();
     let (sink, stream) = socket.sink_stream(COUNT).split();
 
     let (tx, rx) = channel(COUNT);
 
-    let receive_process = stream.from_err::<Error>().forward(tx);
-    let send_process = rx.map_err(|_| RecvError).from_err::<Error>().forward(sink);
+    let receive_process = stream
+        .err_into::<Error>()
+        .forward(tx.sink_map_err(|_| err_msg("Failed to send data to mpsc")));
+    let send_process = rx.map(Ok).forward(sink);
 
-    current_thread::Runtime::new()
-        .unwrap()
-        .spawn(send_process.map(|_| ()).map_err(|_| panic!()))
-        .spawn(receive_process.map(|_| ()).map_err(|_| panic!()))
-        .run()
-        .unwrap();
-}
+    let receive = tokio::spawn(async move { receive_process.await.unwrap() });
+    let send = tokio::spawn(async move { send_process.await.unwrap() });
 
-#[derive(Clone, Debug, Fail)]
-#[fail(display = "Failed to receive data from mpsc")]
-struct RecvError;
+    let _ = futures::join!(receive, send);
+}
@@ -24,8 +24,8 @@ extern crate zmq;
 
 use std::sync::Arc;
 
-use futures::{Future, Stream};
-use tokio_zmq::{prelude::*, Multipart, Pull, Push, Sub};
+use futures::{try_join, StreamExt, TryStreamExt};
+use tokio_zmq::{prelude::*, Error, Multipart, Pull, Push, Sub};
 
 pub struct Stop;
 
@@ -36,7 +36,7 @@ impl ControlHandler for Stop {
     }
 }
 
-fn main() {
+async fn run() -> Result<(), Error> {
     let ctx = Arc::new(zmq::Context::new());
     let cmd_fut = Sub::builder(Arc::clone(&ctx))
         .connect("tcp://localhost:5559")
@@ -47,26 +47,26 @@ fn main() {
         .build();
     let sink_fut = Push::builder(ctx).connect("tcp://localhost:5558").build();
 
-    let runner = cmd_fut
-        .join(stream_fut)
-        .join(sink_fut)
-        .and_then(|((cmd, stream), sink)| {
-            stream
-                .stream()
-                .controlled(cmd.stream(), Stop)
-                .map(|multipart| {
-                    for msg in &multipart {
-                        if let Some(msg) = msg.as_str() {
-                            println!("Relaying: {}", msg);
-                        }
-                    }
-                    multipart
-                })
-                .forward(sink.sink(25))
-        });
+    let (cmd, stream, sink): (Sub, Pull, Push) = try_join!(cmd_fut, stream_fut, sink_fut)?;
 
-    tokio::run(runner.map(|_| ()).or_else(|e| {
+    stream
+        .stream()
+        .controlled(cmd.stream(), Stop)
+        .map_ok(|multipart| {
+            for msg in &multipart {
+                if let Some(msg) = msg.as_str() {
+                    println!("Relaying: {}", msg);
+                }
+            }
+            multipart
+        })
+        .forward(sink.sink(25))
+        .await
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run().await {
         println!("Error!: {:?}", e);
-        Ok(())
-    }));
+    }
 }
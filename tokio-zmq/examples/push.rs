@@ -19,54 +19,20 @@
 
 extern crate futures;
 extern crate tokio;
-extern crate tokio_timer;
 extern crate tokio_zmq;
 extern crate zmq;
 
-use std::{
-    io,
-    sync::Arc,
-    time::{Duration, Instant},
-};
+use std::{sync::Arc, time::Duration};
 
-use futures::{stream::iter_ok, Future, Stream};
-use tokio_timer::{Error as TimerError, Interval};
+use futures::{
+    stream::{iter, poll_fn},
+    try_join, StreamExt,
+};
+use tokio::time::interval;
 use tokio_zmq::prelude::*;
-use tokio_zmq::{Error as ZmqFutError, Push};
-
-#[derive(Debug)]
-enum Error {
-    ZmqFut(ZmqFutError),
-    Zmq(zmq::Error),
-    Io(io::Error),
-    Timer(TimerError),
-}
-
-impl From<ZmqFutError> for Error {
-    fn from(e: ZmqFutError) -> Self {
-        Error::ZmqFut(e)
-    }
-}
-
-impl From<zmq::Error> for Error {
-    fn from(e: zmq::Error) -> Self {
-        Error::Zmq(e)
-    }
-}
-
-impl From<io::Error> for Error {
-    fn from(e: io::Error) -> Self {
-        Error::Io(e)
-    }
-}
-
-impl From<TimerError> for Error {
-    fn from(e: TimerError) -> Self {
-        Error::Timer(e)
-    }
-}
+use tokio_zmq::{Error, Push};
 
-fn main() {
+async fn process() -> Result<(), Error> {
     let ctx = Arc::new(zmq::Context::new());
     let workers_fut = Push::builder(Arc::clone(&ctx)).bind("tcp://*:5557").build();
     let sink_fut = Push::builder(Arc::clone(&ctx))
@@ -77,31 +43,33 @@ fn main() {
     let start_msg = zmq::Message::from("START").into();
     let stop_msg = zmq::Message::from("STOP").into();
 
-    let interval = Interval::new(Instant::now(), Duration::from_millis(200));
+    let (workers, sink, sink2): (Push, Push, Push) = try_join!(workers_fut, sink_fut, sink2_fut)?;
+
+    let _sink: Push = sink.send(start_msg).await?;
 
-    let process = workers_fut
-        .join(sink_fut)
-        .join(sink2_fut)
-        .from_err()
-        .and_then(move |((workers, sink), sink2)| {
-            sink.send(start_msg).map_err(Error::from).and_then(|_| {
-                iter_ok(0..10)
-                    .zip(interval)
-                    .map_err(Error::from)
-                    .and_then(|(i, _)| {
-                        println!("Sending: {}", i);
+    let mut interval = interval(Duration::from_millis(200));
+    let ticks = poll_fn(move |cx| interval.poll_tick(cx).map(Some));
 
-                        let msg = zmq::Message::from(&format!("{}", i));
+    iter(0..10)
+        .zip(ticks)
+        .map(|(i, _)| {
+            println!("Sending: {}", i);
 
-                        Ok(msg.into())
-                    })
-                    .forward(workers.sink(25))
-                    .and_then(move |_| sink2.send(stop_msg).map_err(Error::from))
-            })
-        });
+            let msg = zmq::Message::from(&format!("{}", i));
 
-    tokio::run(process.map(|_| ()).or_else(|e| {
+            Ok(msg.into())
+        })
+        .forward(workers.sink(25))
+        .await?;
+
+    let _sink2: Push = sink2.send(stop_msg).await?;
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = process().await {
         println!("Error: {:?}", e);
-        Ok(())
-    }));
+    }
 }
@@ -26,8 +26,8 @@ extern crate zmq;
 
 use std::sync::Arc;
 
-use futures::{stream::iter_ok, Future, Stream};
-use tokio_zmq::{prelude::*, Multipart, Req};
+use futures::{stream::iter, StreamExt};
+use tokio_zmq::{prelude::*, Error, Multipart, Req};
 
 fn build_multipart(i: usize) -> Multipart {
     let mut multipart = Multipart::new();
@@ -40,32 +40,35 @@ fn build_multipart(i: usize) -> Multipart {
     multipart
 }
 
-fn main() {
-    env_logger::init();
-
+async fn run() -> Result<(), Error> {
     let ctx = Arc::new(zmq::Context::new());
-    let req_fut = Req::builder(ctx).connect("tcp://localhost:5560").build();
+    let req: Req = Req::builder(ctx)
+        .connect("tcp://localhost:5560")
+        .build()
+        .await?;
 
-    let runner = req_fut.and_then(|req| {
-        req.send(build_multipart(0)).and_then(|req| {
-            let (sink, stream) = req.sink_stream(25).split();
+    let req: Req = req.send(build_multipart(0)).await?;
+    let (sink, stream) = req.sink_stream(25).split();
 
-            stream
-                .zip(iter_ok(1..10_000))
-                .map(|(multipart, i)| {
-                    for msg in multipart {
-                        if let Some(msg) = msg.as_str() {
-                            println!("Received: {}", msg);
-                        }
-                    }
-                    build_multipart(i)
-                })
-                .forward(sink)
+    stream
+        .zip(iter(1..10_000))
+        .map(|(res, i)| {
+            for msg in res? {
+                if let Some(msg) = msg.as_str() {
+                    println!("Received: {}", msg);
+                }
+            }
+            Ok(build_multipart(i))
         })
-    });
+        .forward(sink)
+        .await
+}
+
+#[tokio::main]
+async fn main() {
+    env_logger::init();
 
-    tokio::run(runner.map(|_| ()).or_else(|e| {
+    if let Err(e) = run().await {
         println!("Error: {:?}", e);
-        Ok(())
-    }));
+    }
 }
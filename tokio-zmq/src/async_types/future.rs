@@ -17,13 +17,19 @@
  * along with Tokio ZMQ.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-//! This module contains definitions for `MultipartRequest` and `MultipartResponse`, the two types that
+//! This module contains definitions for `MultipartRequest` and `MultipartResponse`, and their
+//! borrowing counterparts `MultipartRequestMut` and `MultipartResponseMut`, the types that
 //! implement `futures::Future`.
 
-use std::{fmt, marker::PhantomData};
+use std::{
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
 
 use async_zmq_types::Multipart;
-use futures::{Async, Future};
 
 use crate::{
     async_types::future_types::{request, response},
@@ -35,31 +41,24 @@ use crate::{
 ///
 /// ### Example
 /// ```rust
-/// # extern crate zmq;
-/// # extern crate futures;
-/// # extern crate tokio_zmq;
-/// #
 /// # use std::sync::Arc;
 /// #
-/// # use futures::Future;
 /// # use tokio_zmq::{prelude::*, async_types::MultipartRequest, Error, Rep};
 /// #
 /// # fn main() {
-/// #     get_sock();
+/// #     let _ = get_sock();
 /// # }
-/// # fn get_sock() -> impl Future<Item = (), Error = Error> {
+/// # async fn get_sock() -> Result<(), Error> {
 /// #     let ctx = Arc::new(zmq::Context::new());
-/// #     let rep = Rep::builder(ctx)
+/// #     let rep: Rep = Rep::builder(ctx)
 /// #         .bind("tcp://*:5567")
-/// #         .build();
+/// #         .build()
+/// #         .await?;
 /// #
-/// #     rep.and_then(|rep| {
-/// #       let msg = zmq::Message::from(&format!("Hey"));
-/// MultipartRequest::new(rep.socket(), msg.into()).and_then(|_: Rep| {
-///     // succesfull request
-/// #       Ok(())
-/// })
-/// # })
+/// #     let msg = zmq::Message::from(&format!("Hey"));
+/// let _: Rep = MultipartRequest::new(rep.socket(), msg.into()).await?;
+/// // succesfull request
+/// # Ok(())
 /// # }
 /// ```
 pub struct MultipartRequest<T>
@@ -78,7 +77,7 @@ where
     pub fn new(sock: Socket, multipart: Multipart) -> Self {
         MultipartRequest {
             socks: Some(sock),
-            multipart: multipart,
+            multipart,
             phantom: PhantomData,
         }
     }
@@ -88,23 +87,25 @@ impl<T> Future for MultipartRequest<T>
 where
     T: From<Socket>,
 {
-    type Item = T;
-    type Error = Error;
+    type Output = Result<T, Error>;
 
-    fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
-        let sock = self.socks.take().ok_or(Error::Reused)?;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let mut sock = this.socks.take().ok_or(Error::Reused)?;
 
-        match request::poll(&sock, &mut self.multipart, None)? {
-            Async::Ready(()) => Ok(Async::Ready(sock.into())),
-            Async::NotReady => {
-                self.socks = Some(sock);
+        match request::poll(&mut sock, &mut this.multipart, cx, &mut None)? {
+            Poll::Ready(()) => Poll::Ready(Ok(sock.into())),
+            Poll::Pending => {
+                this.socks = Some(sock);
 
-                Ok(Async::NotReady)
+                Poll::Pending
             }
         }
     }
 }
 
+impl<T> Unpin for MultipartRequest<T> where T: From<Socket> {}
+
 impl<T> fmt::Debug for MultipartRequest<T>
 where
     T: From<Socket>,
@@ -127,29 +128,22 @@ where
 ///
 /// ### Example
 /// ```rust
-/// # extern crate zmq;
-/// # extern crate futures;
-/// # extern crate tokio_zmq;
-/// #
 /// # use std::sync::Arc;
 /// #
-/// # use futures::Future;
 /// # use tokio_zmq::{prelude::*, async_types::MultipartResponse, Error, Multipart, Rep};
 /// #
 /// # fn main() {
-/// #     get_sock();
+/// #     let _ = get_sock();
 /// # }
-/// # fn get_sock() -> impl Future<Item = Multipart, Error = Error> {
+/// # async fn get_sock() -> Result<Multipart, Error> {
 /// #     let ctx = Arc::new(zmq::Context::new());
-/// #     let rep = Rep::builder(ctx)
+/// #     let rep: Rep = Rep::builder(ctx)
 /// #         .bind("tcp://*:5567")
-/// #         .build();
-/// #     rep.and_then(|rep| {
-/// MultipartResponse::new(rep.socket()).and_then(|(multipart, _): (_, Rep)| {
-///     // handle multipart response
-///     # Ok(multipart)
-/// })
-/// # })
+/// #         .build()
+/// #         .await?;
+/// let (multipart, _): (_, Rep) = MultipartResponse::new(rep.socket()).await?;
+/// // handle multipart response
+/// # Ok(multipart)
 /// # }
 /// ```
 pub struct MultipartResponse<T>
@@ -178,23 +172,25 @@ impl<T> Future for MultipartResponse<T>
 where
     T: From<Socket>,
 {
-    type Item = (Multipart, T);
-    type Error = Error;
+    type Output = Result<(Multipart, T), Error>;
 
-    fn poll(&mut self) -> Result<Async<Self::Item>, Self::Error> {
-        let sock = self.socks.take().ok_or(Error::Reused)?;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+        let sock = this.socks.take().ok_or(Error::Reused)?;
 
-        match response::poll(&sock, &mut self.multipart, None)? {
-            Async::Ready(multipart) => Ok(Async::Ready((multipart, sock.into()))),
-            Async::NotReady => {
-                self.socks = Some(sock);
+        match response::poll(&sock, &mut this.multipart, cx, &mut None)? {
+            Poll::Ready(multipart) => Poll::Ready(Ok((multipart, sock.into()))),
+            Poll::Pending => {
+                this.socks = Some(sock);
 
-                Ok(Async::NotReady)
+                Poll::Pending
             }
         }
     }
 }
 
+impl<T> Unpin for MultipartResponse<T> where T: From<Socket> {}
+
 impl<T> fmt::Debug for MultipartResponse<T>
 where
     T: From<Socket>,
@@ -212,3 +208,118 @@ where
         write!(f, "RecvFuture")
     }
 }
+
+/// The `MultipartRequestMut` Future handles asynchronously sending data to a borrowed socket.
+///
+/// ### Example
+/// ```rust
+/// # use std::sync::Arc;
+/// #
+/// # use tokio_zmq::{prelude::*, async_types::MultipartRequestMut, Error, Rep};
+/// #
+/// # fn main() {
+/// #     let _ = get_sock();
+/// # }
+/// # async fn get_sock() -> Result<(), Error> {
+/// #     let ctx = Arc::new(zmq::Context::new());
+/// #     let mut rep: Rep = Rep::builder(ctx)
+/// #         .bind("tcp://*:5567")
+/// #         .build()
+/// #         .await?;
+/// #
+/// #     let msg = zmq::Message::from(&format!("Hey"));
+/// MultipartRequestMut::new(rep.socket_mut(), msg.into()).await?;
+/// // succesfull request, and rep is still usable
+/// # Ok(())
+/// # }
+/// ```
+pub struct MultipartRequestMut<'a> {
+    sock: &'a mut Socket,
+    multipart: Multipart,
+}
+
+impl<'a> MultipartRequestMut<'a> {
+    pub fn new(sock: &'a mut Socket, multipart: Multipart) -> Self {
+        MultipartRequestMut { sock, multipart }
+    }
+}
+
+impl<'a> Future for MultipartRequestMut<'a> {
+    type Output = Result<(), Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        request::poll(this.sock, &mut this.multipart, cx, &mut None)
+    }
+}
+
+impl<'a> fmt::Debug for MultipartRequestMut<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SendFuture")
+    }
+}
+
+impl<'a> fmt::Display for MultipartRequestMut<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SendFuture")
+    }
+}
+
+/// The `MultipartResponseMut` Future handles asynchronously getting data from a borrowed socket.
+///
+/// ### Example
+/// ```rust
+/// # use std::sync::Arc;
+/// #
+/// # use tokio_zmq::{prelude::*, async_types::MultipartResponseMut, Error, Multipart, Rep};
+/// #
+/// # fn main() {
+/// #     let _ = get_sock();
+/// # }
+/// # async fn get_sock() -> Result<(), Error> {
+/// #     let ctx = Arc::new(zmq::Context::new());
+/// #     let mut rep: Rep = Rep::builder(ctx)
+/// #         .bind("tcp://*:5567")
+/// #         .build()
+/// #         .await?;
+/// let multipart: Multipart = MultipartResponseMut::new(rep.socket_mut()).await?;
+/// // handle multipart response, and rep is still usable
+/// # Ok(())
+/// # }
+/// ```
+pub struct MultipartResponseMut<'a> {
+    sock: &'a mut Socket,
+    multipart: Multipart,
+}
+
+impl<'a> MultipartResponseMut<'a> {
+    pub fn new(sock: &'a mut Socket) -> Self {
+        MultipartResponseMut {
+            sock,
+            multipart: Multipart::new(),
+        }
+    }
+}
+
+impl<'a> Future for MultipartResponseMut<'a> {
+    type Output = Result<Multipart, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
+
+        response::poll(this.sock, &mut this.multipart, cx, &mut None)
+    }
+}
+
+impl<'a> fmt::Debug for MultipartResponseMut<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RecvFuture")
+    }
+}
+
+impl<'a> fmt::Display for MultipartResponseMut<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RecvFuture")
+    }
+}
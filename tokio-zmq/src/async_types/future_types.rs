@@ -23,61 +23,50 @@
 /*-------------------------------RequestFuture--------------------------------*/
 
 pub(crate) mod request {
+    use std::task::{Context, Poll, Waker};
+
     use async_zmq_types::Multipart;
-    use futures::{task::Task, try_ready, Async, Poll};
+    use futures::ready;
     use log::{debug, error};
-    use zmq::{self, Message, DONTWAIT, SNDMORE};
+    use zmq::{self, DONTWAIT};
 
     use crate::{error::Error, Socket};
 
-    fn send(sock: &Socket, multipart: &mut Multipart) -> Poll<(), Error> {
-        while let Some(msg) = multipart.pop_front() {
-            match send_msg(sock, msg, multipart.is_empty())? {
-                Some(msg) => {
-                    multipart.push_front(msg);
-                    return Ok(Async::NotReady);
+    fn send(sock: &mut Socket, multipart: &mut Multipart) -> Poll<Result<(), Error>> {
+        while !multipart.is_empty() {
+            // Frames stay in the multipart until ZeroMQ accepts them, so nothing is lost on EAGAIN
+            match sock.send_front(multipart, DONTWAIT) {
+                Ok(()) => (),
+                Err(zmq::Error::EAGAIN) => {
+                    debug!("RequestFuture: EAGAIN");
+                    return Poll::Pending;
+                }
+                Err(e) => {
+                    error!("Send error: {}", e);
+                    return Poll::Ready(Err(e.into()));
                 }
-                None => continue,
             }
         }
 
-        Ok(Async::Ready(()))
-    }
-
-    fn send_msg(sock: &Socket, msg: Message, last: bool) -> Result<Option<Message>, Error> {
-        let flags = DONTWAIT | if last { 0 } else { SNDMORE };
-
-        let msg_clone = Message::from(&*msg);
-
-        match sock.send_msg(msg, flags) {
-            Ok(_) => Ok(None),
-            Err(zmq::Error::EAGAIN) => {
-                // return message in future
-                debug!("RequestFuture: EAGAIN");
-                Ok(Some(msg_clone))
-            }
-            Err(e) => {
-                error!("Send error: {}", e);
-                Err(e.into())
-            }
-        }
+        Poll::Ready(Ok(()))
     }
 
     pub(crate) fn poll(
-        sock: &Socket,
+        sock: &mut Socket,
         multipart: &mut Multipart,
-        task: Option<&Task>,
-    ) -> Poll<(), Error> {
-        try_ready!(sock.poll_write_ready(task));
-
-        match send(sock, multipart)? {
-            Async::Ready(()) => {
-                task.map(|t| t.notify());
-                Ok(Async::Ready(()))
-            },
-            Async::NotReady => {
-                sock.clear_write_ready()?;
-                Ok(Async::NotReady)
+        cx: &mut Context,
+        other: &mut Option<Waker>,
+    ) -> Poll<Result<(), Error>> {
+        loop {
+            ready!(sock.poll_write_ready(cx, other))?;
+
+            // If ZeroMQ refused part of the multipart, loop back around so the socket's events
+            // are checked again and readiness is cleared before we wait.
+            if let Poll::Ready(()) = send(sock, multipart)? {
+                if let Some(waker) = other.take() {
+                    waker.wake();
+                }
+                return Poll::Ready(Ok(()));
             }
         }
     }
@@ -86,41 +75,43 @@ pub(crate) mod request {
 /*-------------------------------ResponseFuture-------------------------------*/
 
 pub(crate) mod response {
-    use std::mem;
+    use std::{
+        mem,
+        task::{Context, Poll, Waker},
+    };
 
     use async_zmq_types::Multipart;
-    use futures::{task::Task, try_ready, Async, Poll};
+    use futures::ready;
     use log::{debug, error};
-    use mio::Ready;
     use zmq::{self, Message};
 
     use crate::{error::Error, Socket};
 
-    fn recv(sock: &Socket, multipart: &mut Multipart) -> Poll<Multipart, Error> {
+    fn recv(sock: &Socket, multipart: &mut Multipart) -> Poll<Result<Multipart, Error>> {
         loop {
-            let msg = try_ready!(recv_msg(sock));
+            let msg = ready!(recv_msg(sock))?;
             let more = msg.get_more();
 
             multipart.push_back(msg);
 
             if !more {
-                return Ok(Async::Ready(mem::replace(multipart, Multipart::new())));
+                return Poll::Ready(Ok(mem::replace(multipart, Multipart::new())));
             }
         }
     }
 
-    fn recv_msg(sock: &Socket) -> Poll<Message, Error> {
+    fn recv_msg(sock: &Socket) -> Poll<Result<Message, Error>> {
         let mut msg = Message::new();
 
         match sock.recv_msg(&mut msg) {
-            Ok(_) => Ok(Async::Ready(msg)),
+            Ok(_) => Poll::Ready(Ok(msg)),
             Err(zmq::Error::EAGAIN) => {
                 debug!("ResponseFuture: EAGAIN");
-                Ok(Async::NotReady)
+                Poll::Pending
             }
             Err(e) => {
                 error!("Recv error: {}", e);
-                Err(e.into())
+                Poll::Ready(Err(e.into()))
             }
         }
     }
@@ -128,20 +119,18 @@ pub(crate) mod response {
     pub(crate) fn poll(
         sock: &Socket,
         multipart: &mut Multipart,
-        task: Option<&Task>,
-    ) -> Poll<Multipart, Error> {
-        let ready = Ready::readable();
-
-        try_ready!(sock.poll_read_ready(ready, task));
+        cx: &mut Context,
+        other: &mut Option<Waker>,
+    ) -> Poll<Result<Multipart, Error>> {
+        loop {
+            ready!(sock.poll_read_ready(cx, other))?;
 
-        match recv(sock, multipart)? {
-            Async::Ready(multipart) => {
-                task.map(|t| t.notify());
-                Ok(Async::Ready(multipart))
-            }
-            Async::NotReady => {
-                sock.clear_read_ready(ready)?;
-                Ok(Async::NotReady)
+            // As with sending, an EAGAIN here means our readiness was stale, so check again.
+            if let Poll::Ready(multipart) = recv(sock, multipart)? {
+                if let Some(waker) = other.take() {
+                    waker.wake();
+                }
+                return Poll::Ready(Ok(multipart));
             }
         }
     }
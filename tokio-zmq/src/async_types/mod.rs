@@ -22,12 +22,13 @@
 //! defines receiving data from a socket as an asychronous stream, and the `sink` module, which
 //! defines sending data to a socket as an asychronous sink.
 
-use tokio_reactor::PollEvented;
+use tokio::io::unix::AsyncFd;
 
 use crate::file::ZmqFile;
 
 pub mod future;
 mod future_types;
+pub mod monitor;
 pub mod sink;
 pub mod sink_stream;
 mod sink_type;
@@ -35,10 +36,11 @@ pub mod stream;
 mod stream_type;
 
 pub use self::{
-    future::{MultipartRequest, MultipartResponse},
+    future::{MultipartRequest, MultipartRequestMut, MultipartResponse, MultipartResponseMut},
+    monitor::MonitorStream,
     sink::MultipartSink,
     sink_stream::MultipartSinkStream,
     stream::{ControlledStream, EndingStream, MultipartStream, TimeoutStream},
 };
 
-pub type EventedFile = PollEvented<ZmqFile>;
+pub type EventedFile = AsyncFd<ZmqFile>;
@@ -0,0 +1,134 @@
+/*
+ * This file is part of Tokio ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Tokio ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Tokio ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Tokio ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module defines the `MonitorStream` type, a stream of events from a socket monitor.
+
+use std::{
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use async_zmq_types::SocketEvent;
+use futures::{ready, Stream};
+use log::{error, warn};
+
+use crate::{async_types::stream_type::StreamType, socket::Socket};
+
+/// The `MonitorStream` produces the events ZeroMQ reports for a monitored socket.
+///
+/// It is created with `Socket::monitor`. The stream ends once the monitored socket is closed, or
+/// if the monitor's PAIR socket fails.
+///
+/// ### Example
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use futures::StreamExt;
+/// use tokio_zmq::{prelude::*, Error, Rep, Req, SocketEvent};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Error> {
+///     let context = Arc::new(zmq::Context::new());
+///
+///     let mut rep: Rep = Rep::builder(Arc::clone(&context))
+///         .bind("inproc://monitor-example")
+///         .build()
+///         .await?;
+///
+///     // ZeroMQ only reports connection events for transports like TCP
+///     let endpoint = rep.bind("tcp://127.0.0.1:0").await?;
+///     let mut events = rep.socket_mut().monitor(&context)?;
+///
+///     let _req: Req = Req::builder(context)
+///         .connect(&endpoint)
+///         .build()
+///         .await?;
+///
+///     match events.next().await {
+///         Some(SocketEvent::Accepted { endpoint, .. }) => println!("Accepted on {}", endpoint),
+///         other => panic!("Unexpected event: {:?}", other),
+///     }
+///
+///     Ok(())
+/// }
+/// ```
+pub struct MonitorStream {
+    sock: Socket,
+    inner: StreamType,
+    done: bool,
+}
+
+impl MonitorStream {
+    pub(crate) fn new(sock: Socket) -> Self {
+        MonitorStream {
+            sock,
+            inner: StreamType::new(),
+            done: false,
+        }
+    }
+}
+
+impl Stream for MonitorStream {
+    type Item = SocketEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        while !this.done {
+            let multipart = match ready!(this.inner.poll_next(&this.sock, cx, &mut None)) {
+                Some(Ok(multipart)) => multipart,
+                Some(Err(e)) => {
+                    error!("Error receiving monitor event, {}", e);
+                    this.done = true;
+                    break;
+                }
+                None => {
+                    this.done = true;
+                    break;
+                }
+            };
+
+            match SocketEvent::from_multipart(&multipart) {
+                Some(event) => {
+                    if let SocketEvent::MonitorStopped { .. } = event {
+                        this.done = true;
+                    }
+
+                    return Poll::Ready(Some(event));
+                }
+                None => warn!("Ignoring malformed monitor event"),
+            }
+        }
+
+        Poll::Ready(None)
+    }
+}
+
+impl fmt::Debug for MonitorStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MonitorStream")
+    }
+}
+
+impl fmt::Display for MonitorStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "MonitorStream")
+    }
+}
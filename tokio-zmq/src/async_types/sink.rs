@@ -20,10 +20,16 @@
 //! This module defines the `MultipartSink` type. A wrapper around Sockets that implements
 //! `futures::Sink`.
 
-use std::{fmt, marker::PhantomData};
+use std::{
+    fmt,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
 
-use async_zmq_types::{IntoSocket, Multipart};
-use futures::{Async, AsyncSink, Sink};
+use async_zmq_types::{GracefulShutdown, IntoSocket, Multipart, ShutdownReport};
+use futures::{future::BoxFuture, Sink};
 
 use crate::{async_types::sink_type::SinkType, error::Error, socket::Socket};
 
@@ -34,30 +40,30 @@ use crate::{async_types::sink_type::SinkType, error::Error, socket::Socket};
 ///
 /// ### Example
 /// ```rust
-/// extern crate zmq;
-/// extern crate futures;
-/// extern crate tokio;
-/// extern crate tokio_zmq;
-///
 /// use std::sync::Arc;
 ///
-/// use futures::{Future, Sink};
-/// use tokio_zmq::{prelude::*, Error, Multipart, Pub, Socket};
+/// use futures::SinkExt;
+/// use tokio_zmq::{prelude::*, Error, Pub};
 ///
-/// fn main() {
-///     let context = Arc::new(zmq::Context::new());
-///     let fut = Pub::builder(context)
+/// async fn run(context: Arc<zmq::Context>) -> Result<(), Error> {
+///     let zpub: Pub = Pub::builder(context)
 ///         .bind("tcp://*:5568")
 ///         .build()
-///         .and_then(|zpub| {
-///             let sink = zpub.sink(25);
+///         .await?;
 ///
-///             let msg = zmq::Message::from("Some message");
+///     let mut sink = zpub.sink(25);
+///
+///     let msg = zmq::Message::from("Some message");
+///
+///     sink.send(msg.into()).await
+/// }
 ///
-///             sink.send(msg.into())
-///         });
+/// fn main() {
+///     let context = Arc::new(zmq::Context::new());
+///     let fut = run(context);
 ///
-///     // tokio::run(fut.map(|_| ()).map_err(|_| ()));
+///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+///     # let _ = fut;
 /// }
 /// ```
 pub struct MultipartSink<T>
@@ -80,6 +86,60 @@ where
             phantom: PhantomData,
         }
     }
+    /// Flush pending multiparts and close the socket, waiting as long as the socket's ZMQ_LINGER
+    /// allows
+    ///
+    /// ### Example
+    /// ```rust
+    /// use std::sync::Arc;
+    ///
+    /// use futures::SinkExt;
+    /// use tokio_zmq::{prelude::*, Error, Push};
+    ///
+    /// async fn run(context: Arc<zmq::Context>) -> Result<(), Error> {
+    ///     let push: Push = Push::builder(context)
+    ///         .connect("tcp://localhost:5592")
+    ///         .linger(Some(std::time::Duration::from_secs(1)))
+    ///         .build()
+    ///         .await?;
+    ///
+    ///     let mut sink = push.sink(25);
+    ///     sink.feed(zmq::Message::from("Some message").into()).await?;
+    ///
+    ///     let report = sink.close().await?;
+    ///     println!("{} multiparts were never sent", report.undelivered());
+    ///
+    ///     Ok(())
+    /// }
+    ///
+    /// fn main() {
+    ///     let context = Arc::new(zmq::Context::new());
+    ///     let fut = run(context);
+    ///
+    ///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+    ///     # let _ = fut;
+    /// }
+    /// ```
+    pub fn close(self) -> BoxFuture<'static, Result<ShutdownReport, Error>> {
+        self.graceful_shutdown(None)
+    }
+
+    /// Flush pending multiparts and close the socket, waiting at most `timeout`
+    pub fn shutdown(self, timeout: Duration) -> BoxFuture<'static, Result<ShutdownReport, Error>> {
+        self.graceful_shutdown(Some(timeout))
+    }
+}
+
+impl<T> GracefulShutdown for MultipartSink<T>
+where
+    T: From<Socket>,
+{
+    type Error = Error;
+    type Future = BoxFuture<'static, Result<ShutdownReport, Error>>;
+
+    fn graceful_shutdown(self, timeout: Option<Duration>) -> Self::Future {
+        Box::pin(self.inner.shutdown(self.sock, timeout))
+    }
 }
 
 impl<T> IntoSocket<T, Socket> for MultipartSink<T>
@@ -91,25 +151,34 @@ where
     }
 }
 
-impl<T> Sink for MultipartSink<T>
+impl<T> Sink<Multipart> for MultipartSink<T>
 where
     T: From<Socket>,
 {
-    type SinkItem = Multipart;
-    type SinkError = Error;
-
-    fn start_send(
-        &mut self,
-        multipart: Self::SinkItem,
-    ) -> Result<AsyncSink<Self::SinkItem>, Self::SinkError> {
-        self.inner.start_send(multipart, &self.sock, None)
+    type Error = Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        let this = &mut *self;
+        this.inner.poll_ready(&mut this.sock, cx, &mut None)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, multipart: Multipart) -> Result<(), Self::Error> {
+        self.inner.start_send(multipart);
+        Ok(())
     }
 
-    fn poll_complete(&mut self) -> Result<Async<()>, Self::SinkError> {
-        self.inner.poll_complete(&self.sock, None)
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        let this = &mut *self;
+        this.inner.poll_flush(&mut this.sock, cx, &mut None)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.poll_flush(cx)
     }
 }
 
+impl<T> Unpin for MultipartSink<T> where T: From<Socket> {}
+
 impl<T> fmt::Debug for MultipartSink<T>
 where
     T: From<Socket>,
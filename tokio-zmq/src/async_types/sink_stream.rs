@@ -20,15 +20,24 @@
 //! This module defines the `MultipartSinkStream` type. A wrapper around Sockets that implements
 //! `futures::Sink` and `futures::Stream`.
 
-use std::{fmt, marker::PhantomData};
+use std::{
+    fmt,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
 
-use async_zmq_types::{IntoSocket, Multipart};
-use futures::{task::Task, AsyncSink, Poll, Sink, Stream};
+use async_zmq_types::{GracefulShutdown, IntoSocket, Multipart, ShutdownReport};
+use futures::{
+    future::{ready, BoxFuture, Ready},
+    Sink, Stream,
+};
 
 use crate::{
     async_types::{sink_type::SinkType, stream_type::StreamType},
     error::Error,
-    socket::Socket,
+    socket::{types::Xpub, Socket},
 };
 
 /// The `MultipartSinkStream` handles sending and receiving streams of data to and from ZeroMQ
@@ -36,30 +45,30 @@ use crate::{
 ///
 /// ### Example
 /// ```rust
-/// extern crate zmq;
-/// extern crate futures;
-/// extern crate tokio;
-/// extern crate tokio_zmq;
-///
 /// use std::sync::Arc;
 ///
-/// use futures::{Future, Sink, Stream};
-/// use tokio_zmq::{prelude::*, Error, Multipart, Rep, Socket};
+/// use futures::StreamExt;
+/// use tokio_zmq::{prelude::*, Error, Rep};
 ///
-/// fn main() {
-///     let context = Arc::new(zmq::Context::new());
-///     let fut = Rep::builder(context)
+/// async fn run(context: Arc<zmq::Context>) -> Result<(), Error> {
+///     let rep: Rep = Rep::builder(context)
 ///         .bind("tcp://*:5575")
 ///         .build()
-///         .and_then(|rep| {
-///             let sink_stream = rep.sink_stream(25);
+///         .await?;
 ///
-///             let (sink, stream) = sink_stream.split();
+///     let sink_stream = rep.sink_stream(25);
 ///
-///             stream.forward(sink)
-///         });
+///     let (sink, stream) = sink_stream.split();
+///
+///     stream.forward(sink).await
+/// }
+///
+/// fn main() {
+///     let context = Arc::new(zmq::Context::new());
+///     let fut = run(context);
 ///
-///     // tokio::run(fut.map(|_| ()).map_err(|_| ()));
+///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+///     # let _ = fut;
 /// }
 /// ```
 pub struct MultipartSinkStream<T>
@@ -69,8 +78,8 @@ where
     sock: Socket,
     sink: SinkType,
     stream: StreamType,
-    sink_task: Option<Task>,
-    stream_task: Option<Task>,
+    sink_waker: Option<Waker>,
+    stream_waker: Option<Waker>,
     phantom: PhantomData<T>,
 }
 
@@ -80,14 +89,61 @@ where
 {
     pub fn new(buffer_size: usize, sock: Socket) -> Self {
         MultipartSinkStream {
-            sock: sock,
+            sock,
             sink: SinkType::new(buffer_size),
             stream: StreamType::new(),
-            sink_task: None,
-            stream_task: None,
+            sink_waker: None,
+            stream_waker: None,
             phantom: PhantomData,
         }
     }
+
+    /// Flush pending multiparts and close the socket, waiting as long as the socket's ZMQ_LINGER
+    /// allows
+    pub fn close(self) -> BoxFuture<'static, Result<ShutdownReport, Error>> {
+        self.graceful_shutdown(None)
+    }
+
+    /// Flush pending multiparts and close the socket, waiting at most `timeout`
+    pub fn shutdown(self, timeout: Duration) -> BoxFuture<'static, Result<ShutdownReport, Error>> {
+        self.graceful_shutdown(Some(timeout))
+    }
+}
+
+impl MultipartSinkStream<Xpub> {
+    /// Apply a subscription, for sockets built with `xpub_manual`, see `Xpub::subscribe`
+    pub fn subscribe(&self, topic: &[u8]) -> Ready<Result<(), Error>> {
+        ready(self.sock.subscribe(topic).map_err(Error::from))
+    }
+
+    /// Undo a subscription applied with `subscribe`
+    pub fn unsubscribe(&self, topic: &[u8]) -> Ready<Result<(), Error>> {
+        ready(self.sock.unsubscribe(topic).map_err(Error::from))
+    }
+}
+
+impl<T> GracefulShutdown for MultipartSinkStream<T>
+where
+    T: From<Socket>,
+{
+    type Error = Error;
+    type Future = BoxFuture<'static, Result<ShutdownReport, Error>>;
+
+    fn graceful_shutdown(self, timeout: Option<Duration>) -> Self::Future {
+        Box::pin(self.sink.shutdown(self.sock, timeout))
+    }
+}
+
+/// Remember who to wake if this half had to wait, so the other half can wake it after consuming
+/// a shared readiness notification.
+fn register<R>(slot: &mut Option<Waker>, cx: &Context, res: Poll<R>) -> Poll<R> {
+    *slot = if res.is_pending() {
+        Some(cx.waker().clone())
+    } else {
+        None
+    };
+
+    res
 }
 
 impl<T> IntoSocket<T, Socket> for MultipartSinkStream<T>
@@ -99,27 +155,35 @@ where
     }
 }
 
-impl<T> Sink for MultipartSinkStream<T>
+impl<T> Sink<Multipart> for MultipartSinkStream<T>
 where
     T: From<Socket>,
 {
-    type SinkItem = Multipart;
-    type SinkError = Error;
-
-    fn start_send(
-        &mut self,
-        multipart: Self::SinkItem,
-    ) -> Result<AsyncSink<Self::SinkItem>, Self::SinkError> {
-        if self.sink_task.is_none() {
-            self.sink_task = Some(futures::task::current());
-        }
-        self.sink
-            .start_send(multipart, &self.sock, self.stream_task.as_ref())
+    type Error = Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        let this = &mut *self;
+        let res = this
+            .sink
+            .poll_ready(&mut this.sock, cx, &mut this.stream_waker);
+        register(&mut this.sink_waker, cx, res)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, multipart: Multipart) -> Result<(), Self::Error> {
+        self.sink.start_send(multipart);
+        Ok(())
     }
 
-    fn poll_complete(&mut self) -> Poll<(), Self::SinkError> {
-        self.sink
-            .poll_complete(&self.sock, self.stream_task.as_ref())
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        let this = &mut *self;
+        let res = this
+            .sink
+            .poll_flush(&mut this.sock, cx, &mut this.stream_waker);
+        register(&mut this.sink_waker, cx, res)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        self.poll_flush(cx)
     }
 }
 
@@ -127,17 +191,17 @@ impl<T> Stream for MultipartSinkStream<T>
 where
     T: From<Socket>,
 {
-    type Item = Multipart;
-    type Error = Error;
+    type Item = Result<Multipart, Error>;
 
-    fn poll(&mut self) -> Poll<Option<Multipart>, Self::Error> {
-        if self.stream_task.is_none() {
-            self.stream_task = Some(futures::task::current());
-        }
-        self.stream.poll(&self.sock, self.sink_task.as_ref())
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let res = this.stream.poll_next(&this.sock, cx, &mut this.sink_waker);
+        register(&mut this.stream_waker, cx, res)
     }
 }
 
+impl<T> Unpin for MultipartSinkStream<T> where T: From<Socket> {}
+
 impl<T> fmt::Debug for MultipartSinkStream<T>
 where
     T: From<Socket>,
@@ -20,10 +20,14 @@
 //! This module defines the `SinkType` type. A wrapper around Sockets that implements
 //! `futures::Sink`.
 
-use std::collections::VecDeque;
+use std::{
+    collections::VecDeque,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
 
-use async_zmq_types::Multipart;
-use futures::{task::Task, Async, AsyncSink, Poll};
+use async_zmq_types::{Multipart, ShutdownReport};
+use futures::future::poll_fn;
 use log::{debug, error};
 
 use crate::{async_types::future_types::request, error::Error, Socket};
@@ -35,8 +39,11 @@ pub(crate) struct SinkType {
 
 impl Drop for SinkType {
     fn drop(&mut self) {
-        if self.pending.len() > 0 {
-            error!("DROPPING NON-EMPTY PENDING BUFFER, {}", self.pending.len());
+        if !self.pending.is_empty() {
+            error!(
+                "Dropping {} pending multiparts, close the sink to flush them",
+                self.pending.len()
+            );
         }
     }
 }
@@ -49,34 +56,73 @@ impl SinkType {
         }
     }
 
-    pub(crate) fn start_send(
+    pub(crate) fn poll_ready(
         &mut self,
-        multipart: Multipart,
-        sock: &Socket,
-        task: Option<&Task>,
-    ) -> Result<AsyncSink<Multipart>, Error> {
-        self.poll_complete(sock, task)?;
+        sock: &mut Socket,
+        cx: &mut Context,
+        other: &mut Option<Waker>,
+    ) -> Poll<Result<(), Error>> {
+        if self.pending.len() < self.buffer_size.max(1) {
+            return Poll::Ready(Ok(()));
+        }
+
+        let _ = self.poll_flush(sock, cx, other)?;
 
-        if self.pending.len() > 0 && self.pending.len() > self.buffer_size {
+        if self.pending.len() >= self.buffer_size.max(1) {
             debug!("Sink is not ready!");
-            return Ok(AsyncSink::NotReady(multipart));
+            return Poll::Pending;
         }
 
+        Poll::Ready(Ok(()))
+    }
+
+    pub(crate) fn start_send(&mut self, multipart: Multipart) {
         self.pending.push_back(multipart);
-        Ok(AsyncSink::Ready)
     }
 
-    pub(crate) fn poll_complete(&mut self, sock: &Socket, task: Option<&Task>) -> Poll<(), Error> {
+    pub(crate) fn poll_flush(
+        &mut self,
+        sock: &mut Socket,
+        cx: &mut Context,
+        other: &mut Option<Waker>,
+    ) -> Poll<Result<(), Error>> {
         while let Some(mut multipart) = self.pending.pop_front() {
-            match request::poll(sock, &mut multipart, task)? {
-                Async::Ready(()) => continue,
-                Async::NotReady => {
-                    self.pending.push_front(multipart);
-                    return Ok(Async::NotReady);
+            if request::poll(sock, &mut multipart, cx, other)?.is_pending() {
+                self.pending.push_front(multipart);
+                return Poll::Pending;
+            }
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    /// Flush the pending multiparts, giving up after `timeout`, or the socket's linger if there
+    /// isn't one
+    pub(crate) async fn shutdown(
+        mut self,
+        mut sock: Socket,
+        timeout: Option<Duration>,
+    ) -> Result<ShutdownReport, Error> {
+        let timeout = match timeout {
+            Some(timeout) => Some(timeout),
+            None => sock.linger()?,
+        };
+
+        let flush = poll_fn(|cx| self.poll_flush(&mut sock, cx, &mut None));
+
+        match timeout {
+            Some(timeout) => {
+                // A timed out flush leaves the rest of the multiparts pending
+                if let Ok(res) = tokio::time::timeout(timeout, flush).await {
+                    res?;
                 }
             }
+            None => flush.await?,
         }
 
-        Ok(Async::Ready(()))
+        let undelivered = self.pending.len();
+        self.pending.clear();
+
+        Ok(ShutdownReport::new(undelivered))
     }
 }
@@ -20,16 +20,25 @@
 use std::{
     fmt,
     marker::PhantomData,
-    time::{Duration, Instant},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
 };
 
 use async_zmq_types::{IntoSocket, Multipart};
-use futures::{future::Either, Async, Future, Stream};
-use tokio_timer::Delay;
+use futures::{
+    future::{ready, Either, Ready},
+    Future, Stream, TryStream,
+};
+use tokio::time::{sleep, Instant, Sleep};
 
 pub use async_zmq_types::{ControlledStream, EndingStream};
 
-use crate::{async_types::stream_type::StreamType, error::Error, socket::Socket};
+use crate::{
+    async_types::stream_type::StreamType,
+    error::Error,
+    socket::{types::Sub, Socket},
+};
 
 /// The `MultipartStream` Sink handles receiving streams of data from ZeroMQ Sockets.
 ///
@@ -38,29 +47,33 @@ use crate::{async_types::stream_type::StreamType, error::Error, socket::Socket};
 ///
 /// ### Example
 /// ```rust
-/// extern crate zmq;
-/// extern crate futures;
-/// extern crate tokio_zmq;
-///
 /// use std::sync::Arc;
 ///
-/// use futures::{Future, Stream};
-/// use tokio_zmq::{async_types::MultipartStream, prelude::*, Error, Multipart, Socket, Sub};
+/// use futures::TryStreamExt;
+/// use tokio_zmq::{prelude::*, Error, Sub};
 ///
-/// fn main() {
-///     let context = Arc::new(zmq::Context::new());
-///     let fut = Sub::builder(context)
+/// async fn run(context: Arc<zmq::Context>) -> Result<(), Error> {
+///     let sub: Sub = Sub::builder(context)
 ///         .connect("tcp://localhost:5568")
 ///         .filter(b"")
 ///         .build()
-///         .and_then(|sub| {
-///             sub.stream()
-///                 .and_then(|multipart| {
-///                     // handle multipart
-///                     Ok(multipart)
-///                 })
-///                 .for_each(|_| Ok(()))
-///         });
+///         .await?;
+///
+///     sub.stream()
+///         .and_then(|multipart| async move {
+///             // handle multipart
+///             Ok(multipart)
+///         })
+///         .try_for_each(|_| async { Ok(()) })
+///         .await
+/// }
+///
+/// fn main() {
+///     let context = Arc::new(zmq::Context::new());
+///     let fut = run(context);
+///
+///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+///     # let _ = fut;
 /// }
 /// ```
 pub struct MultipartStream<T>
@@ -85,6 +98,22 @@ where
     }
 }
 
+impl MultipartStream<Sub> {
+    /// Start receiving messages whose first frame begins with `topic`, see `Sub::subscribe`
+    ///
+    /// Messages that were already queued for the stream are still delivered.
+    pub fn subscribe(&self, topic: &[u8]) -> Ready<Result<(), Error>> {
+        ready(self.sock.subscribe(topic).map_err(Error::from))
+    }
+
+    /// Stop receiving messages for a subscription, see `Sub::unsubscribe`
+    ///
+    /// Messages that were already queued for the stream are still delivered.
+    pub fn unsubscribe(&self, topic: &[u8]) -> Ready<Result<(), Error>> {
+        ready(self.sock.unsubscribe(topic).map_err(Error::from))
+    }
+}
+
 impl<T> IntoSocket<T, Socket> for MultipartStream<T>
 where
     T: From<Socket>,
@@ -98,14 +127,16 @@ impl<T> Stream for MultipartStream<T>
 where
     T: From<Socket>,
 {
-    type Item = Multipart;
-    type Error = Error;
+    type Item = Result<Multipart, Error>;
 
-    fn poll(&mut self) -> Result<Async<Option<Multipart>>, Self::Error> {
-        self.inner.poll(&self.sock, None)
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        this.inner.poll_next(&this.sock, cx, &mut None)
     }
 }
 
+impl<T> Unpin for MultipartStream<T> where T: From<Socket> {}
+
 impl<T> fmt::Debug for MultipartStream<T>
 where
     T: From<Socket>,
@@ -129,23 +160,25 @@ pub struct Timeout;
 
 /// A stream that provides either an `Item` or a `Timeout`
 ///
-/// This is different from `tokio_timer::TimeoutStream<T>`, since that stream errors on timeout.
+/// This is different from `tokio::time::timeout`, since that errors on timeout.
 pub struct TimeoutStream<S>
 where
-    S: Stream,
+    S: TryStream,
 {
     stream: S,
     duration: Duration,
-    timeout: Delay,
+    timeout: Pin<Box<Sleep>>,
 }
 
 impl<S> TimeoutStream<S>
 where
-    S: Stream<Error = Error>,
+    S: TryStream<Error = Error>,
 {
     /// Add a timeout to a stream
+    ///
+    /// The timer is started immediately, so this must be called from within a Tokio runtime.
     pub fn new(stream: S, duration: Duration) -> Self {
-        let timeout = Delay::new(Instant::now() + duration);
+        let timeout = Box::pin(sleep(duration));
 
         TimeoutStream {
             stream,
@@ -157,24 +190,24 @@ where
 
 impl<S> Stream for TimeoutStream<S>
 where
-    S: Stream<Error = Error>,
+    S: TryStream<Error = Error> + Unpin,
 {
-    type Item = Either<S::Item, Timeout>;
-    type Error = Error;
+    type Item = Result<Either<S::Ok, Timeout>, Error>;
 
-    fn poll(&mut self) -> Result<Async<Option<Self::Item>>, Self::Error> {
-        if let Async::Ready(_) = self.timeout.poll()? {
-            self.timeout = Delay::new(Instant::now() + self.duration);
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
 
-            return Ok(Async::Ready(Some(Either::B(Timeout))));
-        }
+        if let Poll::Ready(()) = this.timeout.as_mut().poll(cx) {
+            let deadline = Instant::now() + this.duration;
+            this.timeout.as_mut().reset(deadline);
 
-        let res = match self.stream.poll()? {
-            Async::Ready(Some(item)) => Async::Ready(Some(Either::A(item))),
-            Async::Ready(None) => Async::Ready(None),
-            Async::NotReady => Async::NotReady,
-        };
+            return Poll::Ready(Some(Ok(Either::Right(Timeout))));
+        }
 
-        Ok(res)
+        match Pin::new(&mut this.stream).try_poll_next(cx)? {
+            Poll::Ready(Some(item)) => Poll::Ready(Some(Ok(Either::Left(item)))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
     }
 }
@@ -17,8 +17,10 @@
  * along with Tokio ZMQ.  If not, see <http://www.gnu.org/licenses/>.
  */
 
+use std::task::{Context, Poll, Waker};
+
 use async_zmq_types::Multipart;
-use futures::{task::Task, try_ready, Async, Poll};
+use futures::ready;
 use log::error;
 
 use crate::{async_types::future_types::response, error::Error, Socket};
@@ -34,20 +36,21 @@ impl StreamType {
         }
     }
 
-    pub(crate) fn poll(
+    pub(crate) fn poll_next(
         &mut self,
         sock: &Socket,
-        task: Option<&Task>,
-    ) -> Poll<Option<Multipart>, Error> {
-        let mpart = try_ready!(response::poll(&sock, &mut self.multipart, task));
+        cx: &mut Context,
+        other: &mut Option<Waker>,
+    ) -> Poll<Option<Result<Multipart, Error>>> {
+        let mpart = ready!(response::poll(sock, &mut self.multipart, cx, other))?;
 
-        Ok(Async::Ready(Some(mpart)))
+        Poll::Ready(Some(Ok(mpart)))
     }
 }
 
 impl Drop for StreamType {
     fn drop(&mut self) {
-        if self.multipart.len() > 0 {
+        if !self.multipart.is_empty() {
             error!(
                 "DROPPING RECEIVED NON-EMPTY MULTIPART, {}",
                 self.multipart.len()
@@ -0,0 +1,118 @@
+/*
+ * This file is part of Tokio ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Tokio ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Tokio ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Tokio ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains `LoadBalancer`, a broker that hands requests from clients to the worker
+//! that has been idle the longest.
+//!
+//! The queueing logic lives in `async_zmq_types::broker`, and its types are re-exported here.
+
+use futures::future::BoxFuture;
+
+pub use async_zmq_types::broker::{LoadBalancerStats, StatsStream, READY};
+
+use async_zmq_types::broker;
+
+use crate::{async_types::MultipartSinkStream, error::Error, prelude::*, Router};
+
+// How many multiparts each of the broker's sinks can hold before it waits
+const BUFFER_SIZE: usize = 25;
+
+/// A load balancing broker between a Router frontend for clients, and a Router backend for
+/// workers
+///
+/// Workers are usually Req sockets. Each one sends `READY` when it starts, and then loops,
+/// receiving a request and sending back a reply. Clients are usually Req sockets too, and don't
+/// need to know about the broker at all. Requests are queued while every worker is busy, up to
+/// the `queue_limit`, after which the broker stops reading from the frontend.
+///
+/// ### Example
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use futures::StreamExt;
+/// use tokio_zmq::{broker::LoadBalancer, prelude::*, Error, Router};
+///
+/// async fn broker(context: Arc<zmq::Context>) -> Result<(), Error> {
+///     let frontend: Router = Router::builder(Arc::clone(&context))
+///         .bind("tcp://*:5595")
+///         .build()
+///         .await?;
+///
+///     let backend: Router = Router::builder(context)
+///         .bind("tcp://*:5596")
+///         .build()
+///         .await?;
+///
+///     let mut broker = LoadBalancer::new(frontend, backend).queue_limit(100);
+///     let mut stats = broker.stats();
+///
+///     tokio::spawn(async move {
+///         while let Some(stats) = stats.next().await {
+///             println!("{} requests waiting", stats.queued_requests);
+///         }
+///     });
+///
+///     broker.run().await?;
+///     Ok(())
+/// }
+///
+/// fn main() {
+///     let context = Arc::new(zmq::Context::new());
+///     let fut = broker(context);
+///
+///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+///     # let _ = fut;
+/// }
+/// ```
+pub struct LoadBalancer {
+    inner: broker::LoadBalancer<MultipartSinkStream<Router>, MultipartSinkStream<Router>>,
+}
+
+impl LoadBalancer {
+    pub fn new(frontend: Router, backend: Router) -> Self {
+        LoadBalancer {
+            inner: broker::LoadBalancer::new(
+                MultipartSinkStream::new(BUFFER_SIZE, frontend.socket()),
+                MultipartSinkStream::new(BUFFER_SIZE, backend.socket()),
+            ),
+        }
+    }
+
+    /// Limit how many requests can wait for a worker, by default the queue is unbounded
+    pub fn queue_limit(self, limit: usize) -> Self {
+        LoadBalancer {
+            inner: self.inner.queue_limit(limit),
+        }
+    }
+
+    /// Watch the broker's statistics
+    ///
+    /// The stream yields the latest statistics whenever they change, and ends when the broker
+    /// stops.
+    pub fn stats(&mut self) -> StatsStream {
+        self.inner.stats()
+    }
+
+    /// Balance requests until the frontend or backend fails
+    ///
+    /// The future resolves with the final statistics.
+    pub fn run(self) -> BoxFuture<'static, Result<LoadBalancerStats, Error>> {
+        Box::pin(self.inner.run())
+    }
+}
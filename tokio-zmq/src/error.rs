@@ -17,17 +17,20 @@
  * along with Tokio ZMQ.  If not, see <http://www.gnu.org/licenses/>.
  */
 
+// failure's derive expands into impls nested inside of a const block
+#![allow(non_local_definitions)]
+
 use std::io::Error as IoError;
 
+use async_zmq_types::{proxy::ProxyError, BuildError, ConfigError};
 use failure::Fail;
-use tokio_timer::Error as TimerError;
 use zmq::Error as ZmqError;
 
 /// Defines the error type for Tokio ZMQ.
 ///
 /// Errors here can come from two places, IO, and ZeroMQ. Most errors encountered in this
 /// application are ZeroMQ errors, so `Error::Zmq(_)` is common, although we also need to catch IO
-/// errors from Tokio's `PollEvented` creation and TokioFileUnix's File creation.
+/// errors from registering the socket's file descriptor with Tokio's `AsyncFd`.
 #[derive(Debug, Fail)]
 pub enum Error {
     #[fail(display = "Error in ZeroMQ: {}", _0)]
@@ -35,12 +38,20 @@ pub enum Error {
     Zmq(#[cause] ZmqError),
 
     #[fail(display = "Error creating file descriptor: {}", _0)]
-    /// Stores PollEvented and File creation errors
+    /// Stores AsyncFd registration and readiness errors
     Io(#[cause] IoError),
 
-    #[fail(display = "Error creating timer: {}", _0)]
-    /// Stores Tokio Timer errors
-    Timer(#[cause] TimerError),
+    #[fail(display = "Invalid socket config: {}", _0)]
+    /// Stores errors from converting a `SocketConfig`
+    Config(#[cause] ConfigError),
+
+    #[fail(display = "Could not build socket: {}", _0)]
+    /// Stores invalid endpoints and ipc failures from building a socket
+    Build(#[cause] BuildError),
+
+    #[fail(display = "Could not create proxy: {}", _0)]
+    /// Stores sockets that can't be proxied in either direction
+    Proxy(#[cause] ProxyError),
 
     #[fail(display = "Could not send message to ZeroMQ")]
     /// If Sink socket is not done handling current request
@@ -53,6 +64,14 @@ pub enum Error {
     #[fail(display = "Attempted to re-use already-used future")]
     /// If a future is used after it is consumed
     Reused,
+
+    #[fail(display = "Timed out waiting for a reply")]
+    /// If a request wasn't answered in time
+    Timeout,
+
+    #[fail(display = "No reply after {} attempts", _0)]
+    /// If a request wasn't answered in time, however many times it was sent
+    Unanswered(usize),
 }
 
 impl From<ZmqError> for Error {
@@ -61,14 +80,29 @@ impl From<ZmqError> for Error {
     }
 }
 
-impl From<IoError> for Error {
-    fn from(e: IoError) -> Self {
-        Error::Io(e)
+impl From<ProxyError> for Error {
+    fn from(e: ProxyError) -> Self {
+        Error::Proxy(e)
     }
 }
 
-impl From<TimerError> for Error {
-    fn from(e: TimerError) -> Self {
-        Error::Timer(e)
+impl From<BuildError> for Error {
+    fn from(e: BuildError) -> Self {
+        match e {
+            BuildError::Zmq(e) => Error::Zmq(e),
+            e => Error::Build(e),
+        }
+    }
+}
+
+impl From<ConfigError> for Error {
+    fn from(e: ConfigError) -> Self {
+        Error::Config(e)
+    }
+}
+
+impl From<IoError> for Error {
+    fn from(e: IoError) -> Self {
+        Error::Io(e)
     }
 }
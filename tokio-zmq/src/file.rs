@@ -18,46 +18,54 @@
  */
 
 //! This module contains definitions for the `ZmqFile` type, a small wrapper around a `RawFd` so
-//! `tokio_file_unix` can interact with it.
+//! tokio's `AsyncFd` can interact with it.
 
-use std::{io, os::unix::io::RawFd};
+use std::os::unix::io::{AsRawFd, RawFd};
 
-use mio::{event::Evented, unix::EventedFd, Poll, PollOpt, Ready, Token};
+#[cfg(feature = "draft")]
+use async_zmq_types::draft::Poller;
 
-/// Create a simple wraper struct to hand to `tokio_file_unix`'s File `new_nb` constructor
+/// Create a simple wraper struct to hand to tokio's `AsyncFd` constructor
 pub struct ZmqFile {
     fd: RawFd,
+    // Draft sockets have no file descriptor, so they're waited on through a poller's
+    #[cfg(feature = "draft")]
+    poller: Option<Poller>,
 }
 
 impl ZmqFile {
     /// Create a ZmqFile from a file descriptor
     pub fn from_raw_fd(fd: RawFd) -> Self {
-        ZmqFile { fd }
+        ZmqFile {
+            fd,
+            #[cfg(feature = "draft")]
+            poller: None,
+        }
     }
-}
 
-impl Evented for ZmqFile {
-    fn register(
-        &self,
-        poll: &Poll,
-        token: Token,
-        interest: Ready,
-        opts: PollOpt,
-    ) -> io::Result<()> {
-        EventedFd(&self.fd).register(poll, token, interest, opts)
+    /// Create a ZmqFile from the file descriptor of a poller holding a draft socket
+    #[cfg(feature = "draft")]
+    pub fn from_poller(poller: Poller) -> Self {
+        ZmqFile {
+            fd: poller.as_raw_fd(),
+            poller: Some(poller),
+        }
     }
 
-    fn reregister(
-        &self,
-        poll: &Poll,
-        token: Token,
-        interest: Ready,
-        opts: PollOpt,
-    ) -> io::Result<()> {
-        EventedFd(&self.fd).reregister(poll, token, interest, opts)
+    /// Reset the file descriptor after it has been signalled
+    ///
+    /// ZeroMQ resets a socket's own file descriptor when its events are read, so this only does
+    /// anything for pollers.
+    pub fn drain(&self) {
+        #[cfg(feature = "draft")]
+        if let Some(ref poller) = self.poller {
+            poller.drain();
+        }
     }
+}
 
-    fn deregister(&self, poll: &Poll) -> io::Result<()> {
-        EventedFd(&self.fd).deregister(poll)
+impl AsRawFd for ZmqFile {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd
     }
 }
@@ -0,0 +1,322 @@
+/*
+ * This file is part of Tokio ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Tokio ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Tokio ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Tokio ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains `Heartbeat`, which tracks which of a socket's peers are alive by
+//! exchanging heartbeats with them.
+//!
+//! Heartbeats are single-frame `PING` and `PONG` messages. Sockets wrapped in a `Heartbeat`
+//! answer every `PING` with a `PONG`, and Dealers send a `PING` every interval, so a Router
+//! hears from each of its Dealer peers at least that often, and each Dealer hears back.
+//!
+//! ZeroMQ 4.2 added heartbeats to ZMTP itself, which close connections to dead peers instead of
+//! queueing messages for them. ZeroMQ only applies them to connections bound or connected after
+//! they're set, so `Heartbeat` can't turn them on for a socket that's already built. Set
+//! `heartbeat_ivl` and `heartbeat_timeout` on the socket's builder to use them as well.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::{Sink, Stream, TryStream};
+use tokio::time::{sleep_until, Instant, Sleep};
+
+use crate::{
+    async_types::MultipartSinkStream, error::Error, prelude::*, Multipart, PeerId, Socket,
+};
+
+/// The body of a heartbeat asking the peer to answer
+pub const PING: &[u8] = b"\x00PING";
+
+/// The body of a heartbeat answering a `PING`
+pub const PONG: &[u8] = b"\x00PONG";
+
+/* ----------------------------------TYPES----------------------------------- */
+
+/// An item from a `Heartbeat` stream
+#[derive(Debug)]
+pub enum PeerEvent {
+    /// A multipart received from a peer, heartbeats aren't included
+    Message(Multipart),
+    /// A peer was heard from for the first time, or for the first time since it went down
+    PeerUp(PeerId),
+    /// A peer hasn't been heard from in `interval * liveness`
+    PeerDown(PeerId),
+}
+
+/// A socket that exchanges heartbeats with its peers, and reports when they come and go
+///
+/// The wrapped socket is a `Stream` of `PeerEvent`s, and a `Sink` of multiparts, just like the
+/// `MultipartSinkStream` it wraps. Heartbeats are only sent and answered while the stream is
+/// being polled.
+///
+/// Routers track each of their peers by routing id. Other sockets, usually Dealers, can't tell
+/// their peers apart, so they track them together as a single peer with an empty id. Peers don't
+/// have to send heartbeats themselves, since any message they send keeps them alive, but a
+/// Router can only tell that an idle peer is alive if the peer is a Dealer wrapped in a
+/// `Heartbeat`.
+///
+/// ### Example
+/// ```rust
+/// use std::{sync::Arc, time::Duration};
+///
+/// use futures::TryStreamExt;
+/// use tokio_zmq::{heartbeat::{Heartbeat, PeerEvent}, prelude::*, Error, Router};
+///
+/// async fn run(context: Arc<zmq::Context>) -> Result<(), Error> {
+///     let router: Router = Router::builder(context)
+///         .heartbeat_ivl(Duration::from_secs(1))
+///         .heartbeat_timeout(Duration::from_secs(3))
+///         .bind("tcp://*:5599")
+///         .build()
+///         .await?;
+///
+///     let heartbeat = Heartbeat::new(router.sink_stream(25), Duration::from_secs(1));
+///
+///     heartbeat
+///         .try_for_each(|event| async move {
+///             match event {
+///                 PeerEvent::PeerUp(peer) => println!("{:?} is up", peer),
+///                 PeerEvent::PeerDown(peer) => println!("{:?} is down", peer),
+///                 PeerEvent::Message(_) => (),
+///             }
+///             Ok(())
+///         })
+///         .await
+/// }
+///
+/// fn main() {
+///     let context = Arc::new(zmq::Context::new());
+///     let fut = run(context);
+///
+///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+///     # let _ = fut;
+/// }
+/// ```
+pub struct Heartbeat<T>
+where
+    T: From<Socket>,
+{
+    sock: MultipartSinkStream<T>,
+    routed: bool,
+    interval: Duration,
+    liveness: u32,
+    peers: HashMap<PeerId, Instant>,
+    // Heartbeats waiting for room in the socket's sink
+    heartbeats: VecDeque<Multipart>,
+    events: VecDeque<PeerEvent>,
+    tick: Pin<Box<Sleep>>,
+}
+
+/* ----------------------------------impls----------------------------------- */
+
+impl<T> Heartbeat<T>
+where
+    T: IntoInnerSocket + From<Socket>,
+{
+    /// Exchange heartbeats every `interval`
+    ///
+    /// Peers that haven't been heard from in three intervals are reported as down, see
+    /// `liveness`. Since the first heartbeat is scheduled immediately, this must be called from
+    /// within a Tokio runtime.
+    pub fn new(sock: MultipartSinkStream<T>, interval: Duration) -> Self {
+        Heartbeat {
+            sock,
+            routed: T::kind() == zmq::ROUTER,
+            interval,
+            liveness: 3,
+            peers: HashMap::new(),
+            heartbeats: VecDeque::new(),
+            events: VecDeque::new(),
+            tick: Box::pin(sleep_until(Instant::now())),
+        }
+    }
+
+    /// How many intervals a peer can stay quiet before it's reported as down
+    ///
+    /// A liveness of zero would report every peer as down as soon as it's seen, so it's rejected
+    /// with `EINVAL`.
+    pub fn liveness(mut self, liveness: u32) -> Result<Self, Error> {
+        if liveness == 0 {
+            return Err(zmq::Error::EINVAL.into());
+        }
+
+        self.liveness = liveness;
+        Ok(self)
+    }
+
+    /// The peers that are currently up
+    pub fn peers(&self) -> impl Iterator<Item = &PeerId> {
+        self.peers.keys()
+    }
+
+    /// Give back the wrapped socket
+    pub fn into_inner(self) -> MultipartSinkStream<T> {
+        self.sock
+    }
+
+    fn seen(&mut self, peer: PeerId, now: Instant) {
+        if self.peers.insert(peer.clone(), now).is_none() {
+            self.events.push_back(PeerEvent::PeerUp(peer));
+        }
+    }
+
+    fn received(&mut self, mut multipart: Multipart) {
+        let now = Instant::now();
+
+        let peer = if self.routed {
+            match multipart.pop_front() {
+                Some(id) => PeerId::from(&id[..]),
+                None => return,
+            }
+        } else {
+            PeerId::new(Vec::new())
+        };
+
+        self.seen(peer.clone(), now);
+
+        let heartbeat = match multipart.get(0) {
+            Some(frame) if multipart.len() == 1 => &frame[..],
+            _ => &[],
+        };
+
+        if heartbeat == PING {
+            self.heartbeats.push_back(self.address(peer, PONG));
+        } else if heartbeat != PONG {
+            if self.routed {
+                multipart.push_front(peer.into());
+            }
+
+            self.events.push_back(PeerEvent::Message(multipart));
+        }
+    }
+
+    fn address(&self, peer: PeerId, body: &[u8]) -> Multipart {
+        let mut multipart: Multipart = zmq::Message::from(body).into();
+
+        if self.routed {
+            multipart.push_front(peer.into());
+        }
+
+        multipart
+    }
+
+    fn tick(&mut self) {
+        let now = Instant::now();
+        let ttl = self.interval * self.liveness;
+
+        let down: Vec<PeerId> = self
+            .peers
+            .iter()
+            .filter(|(_, seen)| now.duration_since(**seen) >= ttl)
+            .map(|(peer, _)| peer.clone())
+            .collect();
+
+        for peer in down {
+            self.peers.remove(&peer);
+            self.events.push_back(PeerEvent::PeerDown(peer));
+        }
+
+        // Routers can't reach peers they haven't heard from, so only their peers start pinging
+        if !self.routed {
+            self.heartbeats.push_back(zmq::Message::from(PING).into());
+        }
+    }
+
+    // Move queued heartbeats into the socket's sink, and push them towards ZeroMQ
+    fn poll_heartbeats(&mut self, cx: &mut Context) -> Result<(), Error> {
+        while !self.heartbeats.is_empty() {
+            match Pin::new(&mut self.sock).poll_ready(cx)? {
+                Poll::Ready(()) => {
+                    let heartbeat = self.heartbeats.pop_front().unwrap();
+                    Pin::new(&mut self.sock).start_send(heartbeat)?;
+                }
+                Poll::Pending => return Ok(()),
+            }
+        }
+
+        let _ = Pin::new(&mut self.sock).poll_flush(cx)?;
+        Ok(())
+    }
+}
+
+impl<T> Stream for Heartbeat<T>
+where
+    T: IntoInnerSocket + From<Socket>,
+{
+    type Item = Result<PeerEvent, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            if let Some(event) = this.events.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+
+            if let Err(e) = this.poll_heartbeats(cx) {
+                return Poll::Ready(Some(Err(e)));
+            }
+
+            match Pin::new(&mut this.sock).try_poll_next(cx)? {
+                Poll::Ready(Some(multipart)) => {
+                    this.received(multipart);
+                    continue;
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => (),
+            }
+
+            if this.tick.as_mut().poll(cx).is_ready() {
+                let next = Instant::now() + this.interval;
+                this.tick.as_mut().reset(next);
+                this.tick();
+                continue;
+            }
+
+            return Poll::Pending;
+        }
+    }
+}
+
+impl<T> Sink<Multipart> for Heartbeat<T>
+where
+    T: IntoInnerSocket + From<Socket>,
+{
+    type Error = Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.sock).poll_ready(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, multipart: Multipart) -> Result<(), Self::Error> {
+        Pin::new(&mut self.sock).start_send(multipart)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.sock).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        Pin::new(&mut self.sock).poll_close(cx)
+    }
+}
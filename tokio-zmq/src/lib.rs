@@ -19,10 +19,13 @@
 
 //! Tokio ZMQ, bringing ZeroMQ to the Tokio event loop
 //!
-//! This crate provides Streams, Sinks, and Futures for ZeroMQ Sockets, which deal in structures
-//! caled Multiparts. Currently, a Multipart is a simple wrapper around `VecDeque<zmq::Message>`,
-//! but in the future this will be represented as a wrapper around `VecDeque<S: zmq::Sendable>`
-//! with the zmq 0.9 release.
+//! This crate provides `std::future::Future`s, and futures 0.3 Streams and Sinks for ZeroMQ
+//! Sockets, which deal in structures caled Multiparts. Currently, a Multipart is a simple wrapper
+//! around `VecDeque<zmq::Message>`, but in the future this will be represented as a wrapper around
+//! `VecDeque<S: zmq::Sendable>` with the zmq 0.9 release.
+//!
+//! Sockets are registered with Tokio's reactor through `AsyncFd`, so they must be built from
+//! within a Tokio runtime.
 //!
 //! # Creating a socket
 //!
@@ -37,17 +40,12 @@
 //! Without further ado, creating and using a socket:
 //!
 //! ```rust
-//! extern crate zmq;
-//! extern crate futures;
-//! extern crate tokio;
-//! extern crate tokio_zmq;
-//!
 //! use std::sync::Arc;
 //!
-//! use futures::{Future, Stream};
+//! use futures::{try_join, StreamExt, TryStreamExt};
 //! use tokio_zmq::{prelude::*, Socket, Pub, Sub, Error};
 //!
-//! fn run() -> Result<(), Error> {
+//! async fn run() -> Result<(), Error> {
 //!     // Create a new ZeroMQ Context. This context will be used to create all the sockets.
 //!     let context = Arc::new(zmq::Context::new());
 //!
@@ -62,43 +60,49 @@
 //!         .filter(b"")
 //!         .build();
 //!
+//!     let (zpub, sub): (Pub, Sub) = try_join!(zpub, sub)?;
+//!
 //!     // Create our simple server. This forwards messages from the Subscriber socket to the
 //!     // Publisher socket, and prints them as they go by.
-//!     let runner = zpub
-//!         .join(sub)
-//!         .and_then(|(zpub, sub)| {
-//!             sub.stream()
-//!                 .map(|multipart| {
-//!                     for msg in &multipart {
-//!                         if let Some(msg) = msg.as_str() {
-//!                             println!("Forwarding: {}", msg);
-//!                         }
-//!                     }
-//!                     multipart
-//!                 })
-//!                 .forward(zpub.sink(25))
-//!         });
+//!     sub.stream()
+//!         .map_ok(|multipart| {
+//!             for msg in &multipart {
+//!                 if let Some(msg) = msg.as_str() {
+//!                     println!("Forwarding: {}", msg);
+//!                 }
+//!             }
+//!             multipart
+//!         })
+//!         .forward(zpub.sink(25))
+//!         .await
+//! }
 //!
-//!     // To avoid an infinte doctest, the actual tokio::run is commented out.
-//!     // tokio::run(runner.map(|_| ()).or_else(|e| {
-//!     //     println!("Error: {}", e);
-//!     // })?;
+//! fn main() {
+//!     let runner = run();
+//!
+//!     // To avoid an infinte doctest, the actual runtime is commented out.
+//!     // tokio::runtime::Runtime::new().unwrap().block_on(runner).unwrap();
 //!     # let _ = runner;
-//!     # Ok(())
 //! }
-//!
-//! # fn main() {
-//! #     run().unwrap();
-//! # }
 //! ```
 
 pub mod async_types;
+pub mod broker;
 mod error;
 mod file;
+pub mod heartbeat;
+pub mod mdp;
 pub mod prelude;
+pub mod proxy;
+pub mod reliable;
 mod socket;
+pub mod zap;
 
-pub use async_zmq_types::Multipart;
+pub use async_zmq_types::{
+    codec, subscription, ConfigError, CurveError, CurveKeyPair, CurvePublicKey, CurveSecretKey,
+    Envelope, EnvelopeError, Multipart, PeerId, ShutdownReport, SocketConfig, SocketEvent,
+    SocketKind,
+};
 
 pub use self::{
     error::Error,
@@ -107,3 +111,8 @@ pub use self::{
         Socket,
     },
 };
+
+#[cfg(feature = "draft")]
+pub use self::socket::types::{Channel, Client, Dish, Gather, Peer, Radio, Scatter, Server};
+#[cfg(feature = "draft")]
+pub use async_zmq_types::draft;
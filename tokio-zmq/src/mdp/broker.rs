@@ -0,0 +1,407 @@
+/*
+ * This file is part of Tokio ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Tokio ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Tokio ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Tokio ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::Duration,
+};
+
+use futures::{
+    future::{select, BoxFuture, Either},
+    SinkExt, TryStreamExt,
+};
+use log::{debug, warn};
+use tokio::time::{sleep_until, Instant};
+
+use super::{
+    ClientCommand, ClientMessage, WorkerMessage, BUFFER_SIZE, CLIENT, HEARTBEAT_INTERVAL,
+    HEARTBEAT_LIVENESS, WORKER,
+};
+use crate::{
+    async_types::MultipartSinkStream, error::Error, prelude::*, Multipart, PeerId, Router,
+};
+
+/* ----------------------------------TYPES----------------------------------- */
+
+/// A Majordomo broker
+///
+/// Clients and workers both connect to the broker's Router socket. Requests for a service wait
+/// in a queue until one of its workers is ready, and requests for services that have no workers
+/// yet wait until one registers. Clients can use Dealer sockets, like `MdpClient`, or Req
+/// sockets, as long as they only expect a single reply to each request.
+///
+/// ### Example
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use tokio_zmq::{mdp::MdpBroker, prelude::*, Error, Router};
+///
+/// async fn run(context: Arc<zmq::Context>) -> Result<(), Error> {
+///     let router: Router = Router::builder(context)
+///         .bind("tcp://*:5597")
+///         .build()
+///         .await?;
+///
+///     MdpBroker::new(router).run().await
+/// }
+///
+/// fn main() {
+///     let context = Arc::new(zmq::Context::new());
+///     let fut = run(context);
+///
+///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+///     # let _ = fut;
+/// }
+/// ```
+pub struct MdpBroker {
+    sock: Router,
+    heartbeat: Duration,
+    liveness: u32,
+}
+
+struct Broker {
+    sock: MultipartSinkStream<Router>,
+    heartbeat: Duration,
+    expiry: Duration,
+    services: HashMap<String, Service>,
+    workers: HashMap<PeerId, Worker>,
+    // Req clients waiting for a reply, which needs an empty delimiter frame
+    req_clients: HashSet<PeerId>,
+}
+
+#[derive(Default)]
+struct Service {
+    requests: VecDeque<(Vec<PeerId>, Multipart)>,
+    waiting: VecDeque<PeerId>,
+}
+
+struct Worker {
+    service: String,
+    expires_at: Instant,
+}
+
+/* ----------------------------------impls----------------------------------- */
+
+impl MdpBroker {
+    pub fn new(sock: Router) -> Self {
+        MdpBroker {
+            sock,
+            heartbeat: HEARTBEAT_INTERVAL,
+            liveness: HEARTBEAT_LIVENESS,
+        }
+    }
+
+    /// How often to send heartbeats to idle workers
+    pub fn heartbeat(mut self, interval: Duration) -> Self {
+        self.heartbeat = interval;
+        self
+    }
+
+    /// How many heartbeats a worker can miss before it's forgotten, even while it's busy
+    pub fn liveness(mut self, liveness: u32) -> Self {
+        self.liveness = liveness;
+        self
+    }
+
+    /// Route requests until the socket fails
+    pub fn run(self) -> BoxFuture<'static, Result<(), Error>> {
+        let broker = Broker {
+            sock: self.sock.sink_stream(BUFFER_SIZE),
+            heartbeat: self.heartbeat,
+            expiry: self.heartbeat * self.liveness,
+            services: HashMap::new(),
+            workers: HashMap::new(),
+            req_clients: HashSet::new(),
+        };
+
+        Box::pin(broker.run())
+    }
+}
+
+impl Broker {
+    async fn run(mut self) -> Result<(), Error> {
+        let mut heartbeat_at = Instant::now() + self.heartbeat;
+
+        loop {
+            let tick = Box::pin(sleep_until(heartbeat_at));
+
+            match select(self.sock.try_next(), tick).await {
+                Either::Left((multipart, _)) => match multipart? {
+                    Some(multipart) => self.message(multipart).await?,
+                    None => return Ok(()),
+                },
+                Either::Right(_) => {
+                    self.purge();
+                    self.send_heartbeats().await?;
+                    heartbeat_at = Instant::now() + self.heartbeat;
+                }
+            }
+        }
+    }
+
+    async fn message(&mut self, mut multipart: Multipart) -> Result<(), Error> {
+        let sender = match multipart.pop_front() {
+            Some(sender) => PeerId::from(&sender[..]),
+            None => return Ok(()),
+        };
+
+        // Req sockets put an empty delimiter frame before the message
+        let delimited = multipart.get(0).map(|frame| frame.is_empty()) == Some(true);
+        if delimited {
+            multipart.pop_front();
+        }
+
+        match multipart.get(0).map(|frame| &frame[..]) {
+            Some(header) if header == CLIENT.as_bytes() => {
+                if delimited {
+                    self.req_clients.insert(sender.clone());
+                } else {
+                    self.req_clients.remove(&sender);
+                }
+
+                self.client_message(sender, multipart).await
+            }
+            Some(header) if header == WORKER.as_bytes() => {
+                self.worker_message(sender, multipart).await
+            }
+            _ => {
+                warn!("Dropping message that isn't a Majordomo message");
+                Ok(())
+            }
+        }
+    }
+
+    async fn client_message(&mut self, client: PeerId, multipart: Multipart) -> Result<(), Error> {
+        let request = match ClientMessage::from_multipart(multipart) {
+            Ok(ref request) if request.command != ClientCommand::Request => {
+                warn!("Dropping reply sent by client");
+                return Ok(());
+            }
+            Ok(request) => request,
+            Err(e) => {
+                warn!("Dropping invalid request, {}", e);
+                return Ok(());
+            }
+        };
+
+        if request.service.starts_with("mmi.") {
+            return self.mmi(client, request).await;
+        }
+
+        let service = request.service;
+
+        self.services
+            .entry(service.clone())
+            .or_default()
+            .requests
+            .push_back((vec![client], request.body));
+
+        self.dispatch(&service).await
+    }
+
+    // Answer Majordomo Management Interface requests, which are handled by the broker itself
+    async fn mmi(&mut self, client: PeerId, request: ClientMessage) -> Result<(), Error> {
+        let status = if request.service == "mmi.service" {
+            let service = request.body.get(0).and_then(|msg| msg.as_str());
+            let has_workers = service
+                .map(|service| self.workers.values().any(|w| w.service == service))
+                .unwrap_or(false);
+
+            if has_workers {
+                "200"
+            } else {
+                "404"
+            }
+        } else {
+            "501"
+        };
+
+        let reply = ClientMessage {
+            command: ClientCommand::Final,
+            service: request.service,
+            body: zmq::Message::from(status).into(),
+        };
+
+        self.send_client(vec![client], reply).await
+    }
+
+    async fn worker_message(&mut self, worker: PeerId, multipart: Multipart) -> Result<(), Error> {
+        let message = match WorkerMessage::from_multipart(multipart) {
+            Ok(message) => message,
+            Err(e) => {
+                warn!("Dropping invalid message from worker, {}", e);
+                return Ok(());
+            }
+        };
+
+        let expires_at = Instant::now() + self.expiry;
+        let service = match self.workers.get_mut(&worker) {
+            Some(state) => {
+                state.expires_at = expires_at;
+                Some(state.service.clone())
+            }
+            None => None,
+        };
+
+        match (message, service) {
+            (WorkerMessage::Ready { service }, None) if !service.starts_with("mmi.") => {
+                debug!("Registering worker for {}", service);
+                self.workers.insert(
+                    worker.clone(),
+                    Worker {
+                        service: service.clone(),
+                        expires_at,
+                    },
+                );
+                self.ready(worker, &service).await
+            }
+            (WorkerMessage::Final { client, body }, Some(service)) => {
+                let reply = ClientMessage {
+                    command: ClientCommand::Final,
+                    service: service.clone(),
+                    body,
+                };
+                self.send_client(client, reply).await?;
+                self.ready(worker, &service).await
+            }
+            (WorkerMessage::Partial { client, body }, Some(service)) => {
+                let reply = ClientMessage {
+                    command: ClientCommand::Partial,
+                    service,
+                    body,
+                };
+                self.send_client(client, reply).await
+            }
+            (WorkerMessage::Heartbeat, Some(_)) => Ok(()),
+            (WorkerMessage::Disconnect, _) => {
+                self.remove_worker(&worker);
+                Ok(())
+            }
+            _ => {
+                // Either the worker broke the protocol, or it's talking to a broker that has
+                // forgotten about it, and it needs to register again
+                warn!("Disconnecting worker after unexpected command");
+                self.remove_worker(&worker);
+                self.send_worker(worker, WorkerMessage::Disconnect).await
+            }
+        }
+    }
+
+    async fn ready(&mut self, worker: PeerId, service: &str) -> Result<(), Error> {
+        self.services
+            .entry(service.to_owned())
+            .or_default()
+            .waiting
+            .push_back(worker);
+
+        self.dispatch(service).await
+    }
+
+    async fn dispatch(&mut self, service: &str) -> Result<(), Error> {
+        loop {
+            let next = match self.services.get_mut(service) {
+                Some(state) if !state.waiting.is_empty() && !state.requests.is_empty() => {
+                    state.waiting.pop_front().zip(state.requests.pop_front())
+                }
+                _ => None,
+            };
+
+            match next {
+                Some((worker, (client, body))) => {
+                    self.send_worker(worker, WorkerMessage::Request { client, body })
+                        .await?
+                }
+                None => return Ok(()),
+            }
+        }
+    }
+
+    // Forget workers that haven't been heard from in a while, busy or not
+    fn purge(&mut self) {
+        let now = Instant::now();
+
+        let expired: Vec<PeerId> = self
+            .workers
+            .iter()
+            .filter(|(_, state)| state.expires_at <= now)
+            .map(|(worker, _)| worker.clone())
+            .collect();
+
+        for worker in expired {
+            if let Some(state) = self.workers.get(&worker) {
+                debug!("Forgetting worker for {}", state.service);
+            }
+
+            self.remove_worker(&worker);
+        }
+    }
+
+    async fn send_heartbeats(&mut self) -> Result<(), Error> {
+        let waiting: Vec<PeerId> = self
+            .services
+            .values()
+            .flat_map(|service| service.waiting.iter().cloned())
+            .collect();
+
+        for worker in waiting {
+            self.send_worker(worker, WorkerMessage::Heartbeat).await?;
+        }
+
+        Ok(())
+    }
+
+    fn remove_worker(&mut self, worker: &PeerId) {
+        if let Some(state) = self.workers.remove(worker) {
+            if let Some(service) = self.services.get_mut(&state.service) {
+                service.waiting.retain(|waiting| waiting != worker);
+            }
+        }
+    }
+
+    async fn send_worker(&mut self, worker: PeerId, message: WorkerMessage) -> Result<(), Error> {
+        let mut multipart = message.into_multipart();
+        multipart.push_front(worker.into());
+
+        self.sock.send(multipart).await
+    }
+
+    async fn send_client(
+        &mut self,
+        client: Vec<PeerId>,
+        reply: ClientMessage,
+    ) -> Result<(), Error> {
+        let delimited = match client.first() {
+            Some(first) if reply.command == ClientCommand::Final => self.req_clients.remove(first),
+            Some(first) => self.req_clients.contains(first),
+            None => false,
+        };
+
+        let mut multipart = reply.into_multipart();
+
+        if delimited {
+            multipart.push_front(zmq::Message::new());
+        }
+
+        for route in client.into_iter().rev() {
+            multipart.push_front(route.into());
+        }
+
+        self.sock.send(multipart).await
+    }
+}
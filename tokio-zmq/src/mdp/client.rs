@@ -0,0 +1,124 @@
+/*
+ * This file is part of Tokio ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Tokio ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Tokio ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Tokio ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::time::Duration;
+
+use log::warn;
+
+use super::{ClientCommand, ClientMessage};
+use crate::{error::Error, prelude::*, Dealer, Multipart};
+
+/// A Majordomo client
+///
+/// The client sends one request at a time, and waits for the final reply to each. Replies that
+/// arrive after a request timed out are recognised by their service and thrown away, but a client
+/// that sends several requests to the same service after timeouts may receive a late reply to
+/// an earlier one.
+///
+/// ### Example
+/// ```rust
+/// use std::{sync::Arc, time::Duration};
+///
+/// use tokio_zmq::{mdp::MdpClient, prelude::*, Dealer, Error};
+///
+/// async fn run(context: Arc<zmq::Context>) -> Result<(), Error> {
+///     let dealer: Dealer = Dealer::builder(context)
+///         .connect("tcp://localhost:5597")
+///         .build()
+///         .await?;
+///
+///     let mut client = MdpClient::new(dealer).timeout(Duration::from_secs(5));
+///
+///     let reply = client.request("echo", zmq::Message::from("Hello").into()).await?;
+///     println!("{:?}", reply.get(0).and_then(|msg| msg.as_str()));
+///
+///     Ok(())
+/// }
+///
+/// fn main() {
+///     let context = Arc::new(zmq::Context::new());
+///     let fut = run(context);
+///
+///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+///     # let _ = fut;
+/// }
+/// ```
+pub struct MdpClient {
+    sock: Dealer,
+    timeout: Option<Duration>,
+}
+
+impl MdpClient {
+    pub fn new(sock: Dealer) -> Self {
+        MdpClient {
+            sock,
+            timeout: None,
+        }
+    }
+
+    /// Give up on requests that haven't been answered within `timeout`
+    ///
+    /// By default, requests wait forever.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send a request to `service`, and wait for its final reply
+    ///
+    /// Partial replies some workers send before their final reply are skipped. If the request
+    /// times out, this resolves with `Error::Timeout`.
+    pub async fn request(&mut self, service: &str, body: Multipart) -> Result<Multipart, Error> {
+        self.sock
+            .send_mut(ClientMessage::request(service, body).into())
+            .await?;
+
+        match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, self.reply(service))
+                .await
+                .map_err(|_| Error::Timeout)?,
+            None => self.reply(service).await,
+        }
+    }
+
+    async fn reply(&mut self, service: &str) -> Result<Multipart, Error> {
+        loop {
+            let multipart = self.sock.recv_mut().await?;
+
+            let reply = match ClientMessage::from_multipart(multipart) {
+                Ok(reply) => reply,
+                Err(e) => {
+                    warn!("Dropping invalid reply, {}", e);
+                    continue;
+                }
+            };
+
+            if reply.service != service {
+                warn!("Dropping late reply from {}", reply.service);
+                continue;
+            }
+
+            match reply.command {
+                ClientCommand::Final => return Ok(reply.body),
+                ClientCommand::Partial => continue,
+                ClientCommand::Request => warn!("Dropping request sent to client"),
+            }
+        }
+    }
+}
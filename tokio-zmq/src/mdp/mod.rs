@@ -0,0 +1,58 @@
+/*
+ * This file is part of Tokio ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Tokio ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Tokio ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Tokio ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains a client, worker, and broker for the Majordomo Protocol, MDP/0.2, which
+//! routes requests to workers by the name of the service they provide.
+//!
+//! Clients and workers connect Dealer sockets to a broker's Router socket. Workers send a READY
+//! message naming their service, and the broker hands each request for that service to the
+//! worker that has been waiting the longest. Brokers and idle workers exchange heartbeats, so
+//! brokers forget workers that go away, and workers reconnect to brokers that go away.
+//!
+//! Brokers also answer the `mmi.service` request from
+//! [ZeroMQ RFC 8](https://rfc.zeromq.org/spec/8/), replying `200` if a service has workers and
+//! `404` if it doesn't.
+//!
+//! The protocol's messages live in `async_zmq_types::mdp`, and its types are re-exported here.
+
+mod broker;
+mod client;
+mod worker;
+
+use std::time::Duration;
+
+pub use async_zmq_types::mdp::{
+    ClientCommand, ClientMessage, MdpError, WorkerMessage, CLIENT, WORKER,
+};
+
+pub use self::{
+    broker::MdpBroker,
+    client::MdpClient,
+    worker::{MdpReply, MdpRequest, MdpWorker},
+};
+
+// How many multiparts each sink can hold before it waits
+const BUFFER_SIZE: usize = 25;
+
+/// How often brokers and idle workers send heartbeats, unless they're configured otherwise
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_millis(2500);
+
+/// How many heartbeats can be missed before a peer is considered dead, unless brokers and
+/// workers are configured otherwise
+pub const HEARTBEAT_LIVENESS: u32 = 3;
@@ -0,0 +1,418 @@
+/*
+ * This file is part of Tokio ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Tokio ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Tokio ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Tokio ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::{future::BoxFuture, Sink, Stream, TryStream};
+use log::{info, warn};
+use tokio::time::{sleep, Instant, Sleep};
+
+use super::{WorkerMessage, BUFFER_SIZE, HEARTBEAT_INTERVAL, HEARTBEAT_LIVENESS};
+use crate::{
+    async_types::MultipartSinkStream, error::Error, prelude::*, Dealer, Multipart, PeerId,
+};
+
+/* ----------------------------------TYPES----------------------------------- */
+
+/// A request handed to a Majordomo worker
+#[derive(Debug)]
+pub struct MdpRequest {
+    client: Vec<PeerId>,
+    body: Multipart,
+    // The connection the request arrived on
+    generation: u64,
+}
+
+/// A worker's reply to an `MdpRequest`
+#[derive(Debug)]
+pub struct MdpReply {
+    client: Vec<PeerId>,
+    body: Multipart,
+    generation: u64,
+}
+
+/// A Majordomo worker
+///
+/// The worker is a `Stream` of the requests the broker hands it, and a `Sink` for the replies to
+/// them, which can be split to handle each half separately. Each request should be answered
+/// before the next one is received, since the broker won't hand the worker another request until
+/// it has replied.
+///
+/// The worker owns its connection to the broker. It registers its service when it connects, and
+/// while its stream is being polled it exchanges heartbeats with the broker. If the broker misses
+/// `liveness` heartbeats, or tells the worker to disconnect, the worker waits for
+/// `reconnect_delay` and then connects again with a new socket. Replies to requests received
+/// before reconnecting are thrown away, since the broker has forgotten about them. If connecting
+/// fails, the error is returned from the stream, and polling it again tries again.
+///
+/// ### Example
+/// ```rust
+/// use std::sync::Arc;
+///
+/// use futures::{StreamExt, TryStreamExt};
+/// use tokio_zmq::{mdp::MdpWorker, Error};
+///
+/// async fn run(context: Arc<zmq::Context>) -> Result<(), Error> {
+///     let worker = MdpWorker::new(context, "tcp://localhost:5597", "echo");
+///     let (replies, requests) = worker.split();
+///
+///     requests
+///         .map_ok(|request| request.reply(zmq::Message::from("OK").into()))
+///         .forward(replies)
+///         .await
+/// }
+///
+/// fn main() {
+///     let context = Arc::new(zmq::Context::new());
+///     let fut = run(context);
+///
+///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+///     # let _ = fut;
+/// }
+/// ```
+pub struct MdpWorker {
+    context: Arc<zmq::Context>,
+    endpoint: String,
+    service: String,
+    heartbeat: Duration,
+    liveness: u32,
+    reconnect_delay: Duration,
+    state: State,
+    // Counts connections to the broker, so replies can be matched to the connection they're for
+    generation: u64,
+    // Commands waiting for room in the socket's sink
+    commands: VecDeque<Multipart>,
+    disconnecting: bool,
+    heartbeat_at: Pin<Box<Sleep>>,
+    expires_at: Pin<Box<Sleep>>,
+}
+
+enum State {
+    Connecting(BoxFuture<'static, Result<Dealer, Error>>),
+    Connected(MultipartSinkStream<Dealer>),
+}
+
+/* ----------------------------------impls----------------------------------- */
+
+impl MdpRequest {
+    pub fn body(&self) -> &Multipart {
+        &self.body
+    }
+
+    pub fn body_mut(&mut self) -> &mut Multipart {
+        &mut self.body
+    }
+
+    pub fn into_body(self) -> Multipart {
+        self.body
+    }
+
+    /// Create the reply to this request
+    pub fn reply(&self, body: Multipart) -> MdpReply {
+        MdpReply {
+            client: self.client.clone(),
+            body,
+            generation: self.generation,
+        }
+    }
+}
+
+impl MdpReply {
+    pub fn body(&self) -> &Multipart {
+        &self.body
+    }
+}
+
+fn connect(
+    context: Arc<zmq::Context>,
+    endpoint: String,
+    delay: Duration,
+) -> BoxFuture<'static, Result<Dealer, Error>> {
+    Box::pin(async move {
+        sleep(delay).await;
+
+        // Messages for a broker that's gone are useless, so don't hold on to them
+        Dealer::builder(context)
+            .linger(Some(Duration::from_millis(0)))
+            .connect(&endpoint)
+            .build()
+            .await
+    })
+}
+
+impl MdpWorker {
+    /// Create a worker for `service`, connecting to the broker at `endpoint`
+    ///
+    /// The worker starts connecting immediately, so this must be called from within a Tokio
+    /// runtime.
+    pub fn new(context: Arc<zmq::Context>, endpoint: &str, service: &str) -> Self {
+        let now = Instant::now();
+
+        MdpWorker {
+            state: State::Connecting(connect(
+                Arc::clone(&context),
+                endpoint.to_owned(),
+                Duration::from_millis(0),
+            )),
+            generation: 0,
+            context,
+            endpoint: endpoint.to_owned(),
+            service: service.to_owned(),
+            heartbeat: HEARTBEAT_INTERVAL,
+            liveness: HEARTBEAT_LIVENESS,
+            reconnect_delay: HEARTBEAT_INTERVAL,
+            commands: VecDeque::new(),
+            disconnecting: false,
+            heartbeat_at: Box::pin(tokio::time::sleep_until(now)),
+            expires_at: Box::pin(tokio::time::sleep_until(now)),
+        }
+    }
+
+    /// How often to send heartbeats to the broker
+    pub fn heartbeat(mut self, interval: Duration) -> Self {
+        self.heartbeat = interval;
+        self
+    }
+
+    /// How many heartbeats the broker can miss before the worker reconnects
+    pub fn liveness(mut self, liveness: u32) -> Self {
+        self.liveness = liveness;
+        self
+    }
+
+    /// How long to wait before reconnecting to a broker that went away
+    pub fn reconnect_delay(mut self, delay: Duration) -> Self {
+        self.reconnect_delay = delay;
+        self
+    }
+
+    fn reconnect(&mut self) {
+        self.commands.clear();
+        self.state = State::Connecting(connect(
+            Arc::clone(&self.context),
+            self.endpoint.clone(),
+            self.reconnect_delay,
+        ));
+    }
+
+    fn connected(&mut self, dealer: Dealer) {
+        let now = Instant::now();
+
+        self.commands.push_back(
+            WorkerMessage::Ready {
+                service: self.service.clone(),
+            }
+            .into(),
+        );
+        self.heartbeat_at.as_mut().reset(now + self.heartbeat);
+        self.refresh(now);
+        self.generation += 1;
+        self.state = State::Connected(dealer.sink_stream(BUFFER_SIZE));
+    }
+
+    // The broker is alive, or is about to be told this worker is
+    fn refresh(&mut self, now: Instant) {
+        let expiry = now + self.heartbeat * self.liveness;
+        self.expires_at.as_mut().reset(expiry);
+    }
+
+    // Move queued commands into the socket's sink, and push them towards ZeroMQ
+    fn poll_commands(&mut self, cx: &mut Context) -> Result<(), Error> {
+        let sock = match self.state {
+            State::Connected(ref mut sock) => sock,
+            State::Connecting(_) => return Ok(()),
+        };
+
+        while !self.commands.is_empty() {
+            match Pin::new(&mut *sock).poll_ready(cx)? {
+                Poll::Ready(()) => {
+                    let command = self.commands.pop_front().unwrap();
+                    Pin::new(&mut *sock).start_send(command)?;
+                }
+                Poll::Pending => return Ok(()),
+            }
+        }
+
+        let _ = Pin::new(sock).poll_flush(cx)?;
+        Ok(())
+    }
+
+    fn poll_request(&mut self, cx: &mut Context) -> Poll<Option<Result<MdpRequest, Error>>> {
+        loop {
+            let sock = match self.state {
+                State::Connecting(ref mut fut) => match fut.as_mut().poll(cx) {
+                    Poll::Ready(Ok(dealer)) => {
+                        info!("Connected to broker at {}", self.endpoint);
+                        self.connected(dealer);
+                        self.poll_commands(cx)?;
+                        continue;
+                    }
+                    Poll::Ready(Err(e)) => {
+                        // The finished attempt can't be polled again, so start another one
+                        warn!("Couldn't connect to broker at {}, {}", self.endpoint, e);
+                        self.reconnect();
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                    Poll::Pending => return Poll::Pending,
+                },
+                State::Connected(ref mut sock) => sock,
+            };
+
+            match Pin::new(sock).try_poll_next(cx)? {
+                Poll::Ready(Some(multipart)) => {
+                    self.refresh(Instant::now());
+
+                    match WorkerMessage::from_multipart(multipart) {
+                        Ok(WorkerMessage::Request { client, body }) => {
+                            let request = MdpRequest {
+                                client,
+                                body,
+                                generation: self.generation,
+                            };
+
+                            return Poll::Ready(Some(Ok(request)));
+                        }
+                        Ok(WorkerMessage::Heartbeat) => (),
+                        Ok(WorkerMessage::Disconnect) => {
+                            info!("Broker at {} disconnected, reconnecting", self.endpoint);
+                            self.reconnect();
+                        }
+                        Ok(_) => warn!("Dropping unexpected command from broker"),
+                        Err(e) => warn!("Dropping invalid message from broker, {}", e),
+                    }
+
+                    continue;
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => (),
+            }
+
+            if self.expires_at.as_mut().poll(cx).is_ready() {
+                warn!("Lost broker at {}, reconnecting", self.endpoint);
+                self.reconnect();
+                continue;
+            }
+
+            if self.heartbeat_at.as_mut().poll(cx).is_ready() {
+                let next = Instant::now() + self.heartbeat;
+                self.heartbeat_at.as_mut().reset(next);
+                self.commands.push_back(WorkerMessage::Heartbeat.into());
+                self.poll_commands(cx)?;
+                continue;
+            }
+
+            return Poll::Pending;
+        }
+    }
+}
+
+impl Stream for MdpWorker {
+    type Item = Result<MdpRequest, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        if let Err(e) = this.poll_commands(cx) {
+            return Poll::Ready(Some(Err(e)));
+        }
+
+        this.poll_request(cx)
+    }
+}
+
+impl Sink<MdpReply> for MdpWorker {
+    type Error = Error;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        match self.state {
+            State::Connected(ref mut sock) => Pin::new(sock).poll_ready(cx),
+            State::Connecting(_) => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, reply: MdpReply) -> Result<(), Self::Error> {
+        let this = &mut *self;
+
+        match this.state {
+            State::Connected(_) if reply.generation != this.generation => {
+                warn!("Dropping reply to a request from a previous connection");
+                Ok(())
+            }
+            State::Connected(ref mut sock) => {
+                let reply = WorkerMessage::Final {
+                    client: reply.client,
+                    body: reply.body,
+                };
+
+                Pin::new(sock).start_send(reply.into())?;
+
+                // A worker that was busy with a request hasn't heard from the broker in a while
+                this.refresh(Instant::now());
+                Ok(())
+            }
+            State::Connecting(_) => {
+                warn!("Dropping reply while reconnecting to broker");
+                Ok(())
+            }
+        }
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        let this = &mut *self;
+
+        this.poll_commands(cx)?;
+
+        match this.state {
+            State::Connected(ref mut sock) => Pin::new(sock).poll_flush(cx),
+            State::Connecting(_) => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Self::Error>> {
+        let this = &mut *self;
+
+        // Let the broker know this worker is going away, once, even if closing takes several polls
+        if let State::Connected(_) = this.state {
+            if !this.disconnecting {
+                this.disconnecting = true;
+                this.commands.push_back(WorkerMessage::Disconnect.into());
+            }
+        }
+
+        this.poll_commands(cx)?;
+
+        match this.state {
+            State::Connected(ref mut sock) => {
+                if !this.commands.is_empty() {
+                    return Poll::Pending;
+                }
+
+                Pin::new(sock).poll_close(cx)
+            }
+            State::Connecting(_) => Poll::Ready(Ok(())),
+        }
+    }
+}
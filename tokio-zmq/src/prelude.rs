@@ -21,11 +21,12 @@
 
 use std::time::Duration;
 
-use futures::{Future, Stream};
+use futures::{future::BoxFuture, TryStream};
 
 pub use async_zmq_types::{
-    ControlHandler, Controllable, EndHandler, HasBuilder, IntoInnerSocket, SinkSocket,
-    SinkStreamSocket, StreamSocket, WithEndHandler,
+    codec::{Framed, WithCodec},
+    ControlHandler, Controllable, EndHandler, GracefulShutdown, HasBuilder, IntoInnerSocket,
+    SinkSocket, SinkStreamSocket, StreamSocket, WithEndHandler,
 };
 
 use crate::{async_types::TimeoutStream, error::Error};
@@ -35,50 +36,49 @@ use crate::{async_types::TimeoutStream, error::Error};
 /* ----------------------------------TRAITS---------------------------------- */
 
 /// This trait allows adding a timeout to any stream with Error = Error.
-pub trait WithTimeout: Stream<Error = Error> + Sized {
+pub trait WithTimeout: TryStream<Error = Error> + Sized {
     /// Add a timeout to a given stream.
     ///
     /// ### Example, using a Pull wrapper type
     /// ```rust
-    /// extern crate futures;
-    /// extern crate tokio_zmq;
-    /// extern crate zmq;
-    ///
     /// use std::{sync::Arc, time::Duration};
     ///
-    /// use futures::{Future, Stream};
-    /// use tokio_zmq::{prelude::*, Socket, Pull, Multipart};
+    /// use futures::TryStreamExt;
+    /// use tokio_zmq::{prelude::*, Error, Pull};
     ///
-    /// fn main() {
-    ///     let ctx = Arc::new(zmq::Context::new());
-    ///     let fut = Pull::builder(ctx)
+    /// async fn run(context: Arc<zmq::Context>) -> Result<(), Error> {
+    ///     let pull: Pull = Pull::builder(context)
     ///         .bind("tcp://*:5574")
     ///         .build()
-    ///         .and_then(|pull| {
-    ///             // Receive a Timeout after 30 seconds if the stream hasn't produced a value
-    ///             pull.stream()
-    ///                 .timeout(Duration::from_secs(30))
-    ///                 .for_each(|_| Ok(()))
-    ///         });
+    ///         .await?;
+    ///
+    ///     // Receive a Timeout after 30 seconds if the stream hasn't produced a value
+    ///     pull.stream()
+    ///         .timeout(Duration::from_secs(30))
+    ///         .try_for_each(|_| async { Ok(()) })
+    ///         .await
+    /// }
+    ///
+    /// fn main() {
+    ///     let context = Arc::new(zmq::Context::new());
+    ///     let fut = run(context);
     ///
-    ///     // tokio::run(fut.map(|_| ()).or_else(|e| {
-    ///     //     println!("Error: {}", e);
-    ///     //     Ok(())
-    ///     // }));
+    ///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+    ///     # let _ = fut;
     /// }
     /// ```
     fn timeout(self, duration: Duration) -> TimeoutStream<Self>;
 }
 
 pub trait Build<T>: Sized {
-    fn build(self) -> Box<dyn Future<Item = T, Error = Error> + Send>;
+    fn build(self) -> BoxFuture<'static, Result<T, Error>>;
 }
 
 /* ----------------------------------impls----------------------------------- */
 
 impl<T> WithTimeout for T
 where
-    T: Stream<Error = Error>,
+    T: TryStream<Error = Error>,
 {
     fn timeout(self, duration: Duration) -> TimeoutStream<Self> {
         TimeoutStream::new(self, duration)
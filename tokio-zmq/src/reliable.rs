@@ -0,0 +1,158 @@
+/*
+ * This file is part of Tokio ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Tokio ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Tokio ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Tokio ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! This module contains `ReliableReq`, a request client that recovers from servers that go away
+//! in the middle of a request, following the ZeroMQ guide's Lazy Pirate pattern.
+
+use std::time::Duration;
+
+use async_zmq_types::SockConfig;
+use futures::{future::Either, SinkExt, TryStreamExt};
+use log::warn;
+
+use crate::{
+    async_types::MultipartSinkStream,
+    error::Error,
+    prelude::{Build, SinkStreamSocket, WithTimeout},
+    Multipart, Req,
+};
+
+// How many multiparts the socket's sink can hold before it waits
+const BUFFER_SIZE: usize = 1;
+
+/// How long a request waits for its reply, unless the client is configured otherwise
+pub const REQUEST_TIMEOUT: Duration = Duration::from_millis(2500);
+
+/// How many times a request is resent, unless the client is configured otherwise
+pub const REQUEST_RETRIES: usize = 2;
+
+/// A Req client that retries requests which aren't answered in time
+///
+/// A plain Req socket waits forever for the reply to its last request, so a server that dies
+/// before replying leaves it stuck. `ReliableReq` gives each request a timeout instead. When the
+/// timeout passes, it closes the socket, builds a new one from the same configuration, and sends
+/// the request again, until it has been resent `retries` times. Replies to abandoned requests
+/// are thrown away with the socket they were sent to.
+///
+/// Retried requests may be handled more than once, so they should be safe to repeat.
+///
+/// ### Example
+/// ```rust
+/// use std::{sync::Arc, time::Duration};
+///
+/// use tokio_zmq::{prelude::*, reliable::ReliableReq, Error, Req};
+///
+/// async fn run(context: Arc<zmq::Context>) -> Result<(), Error> {
+///     let config = Req::builder(context).connect("tcp://localhost:5598");
+///
+///     let mut client = ReliableReq::new(config)
+///         .timeout(Duration::from_secs(1))
+///         .retries(3);
+///
+///     match client.request(zmq::Message::from("Hello").into()).await {
+///         Ok(reply) => println!("{:?}", reply.get(0).and_then(|msg| msg.as_str())),
+///         Err(Error::Unanswered(attempts)) => println!("No reply after {} attempts", attempts),
+///         Err(e) => return Err(e),
+///     }
+///
+///     Ok(())
+/// }
+///
+/// fn main() {
+///     let context = Arc::new(zmq::Context::new());
+///     let fut = run(context);
+///
+///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+///     # let _ = fut;
+/// }
+/// ```
+pub struct ReliableReq {
+    config: SockConfig<Req>,
+    timeout: Duration,
+    retries: usize,
+    sock: Option<MultipartSinkStream<Req>>,
+}
+
+impl ReliableReq {
+    /// Create a client that builds its sockets from `config`
+    ///
+    /// The sockets are built with a ZMQ_LINGER of zero, whatever `config` says, so abandoned
+    /// requests don't keep them open.
+    pub fn new(config: SockConfig<Req>) -> Self {
+        ReliableReq {
+            config: config.linger(Some(Duration::from_millis(0))),
+            timeout: REQUEST_TIMEOUT,
+            retries: REQUEST_RETRIES,
+            sock: None,
+        }
+    }
+
+    /// How long to wait for each reply
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// How many times to resend a request that wasn't answered in time
+    pub fn retries(mut self, retries: usize) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Send a request and wait for its reply
+    ///
+    /// If none of the attempts are answered, this resolves with `Error::Unanswered`, and the next
+    /// request is sent with a new socket. The same goes for requests that fail, or are dropped
+    /// before their reply arrives.
+    pub async fn request(&mut self, multipart: Multipart) -> Result<Multipart, Error> {
+        let attempts = self.retries + 1;
+
+        for attempt in 1..=attempts {
+            // The socket is only put back once it has its reply, since otherwise it's stuck
+            // waiting for one, and the next attempt has to start over with a new socket
+            let mut sock = match self.sock.take() {
+                Some(sock) => sock,
+                None => self.config.clone().build().await?.sink_stream(BUFFER_SIZE),
+            };
+
+            // The request is consumed by sending it, so each attempt sends a copy
+            sock.send(copy(&multipart)).await?;
+
+            match (&mut sock).timeout(self.timeout).try_next().await? {
+                Some(Either::Left(reply)) => {
+                    self.sock = Some(sock);
+                    return Ok(reply);
+                }
+                Some(Either::Right(_)) => {
+                    warn!("Request timed out, attempt {} of {}", attempt, attempts)
+                }
+                None => warn!("Socket closed, attempt {} of {}", attempt, attempts),
+            }
+        }
+
+        Err(Error::Unanswered(attempts))
+    }
+}
+
+fn copy(multipart: &Multipart) -> Multipart {
+    multipart
+        .iter()
+        .map(|msg| zmq::Message::from(&msg[..]))
+        .collect()
+}
@@ -20,7 +20,7 @@
 //! This module contains `SocketBuilder` and related types.
 
 use async_zmq_types::{IntoInnerSocket, PairConfig, SockConfig, SubConfig, UnPair};
-use futures::{future::lazy, Future};
+use futures::future::BoxFuture;
 
 use crate::{
     error::Error,
@@ -31,44 +31,47 @@ use crate::{
     },
 };
 
-impl<'a, T> Build<T> for SockConfig<'a, T>
+impl<T> Build<T> for SockConfig<T>
 where
     T: UnPair + IntoInnerSocket + From<Socket> + 'static,
 {
-    fn build(self) -> Box<dyn Future<Item = T, Error = Error> + Send> {
+    fn build(self) -> BoxFuture<'static, Result<T, Error>> {
+        let ipc_permissions = self.options.ipc_permissions;
         let res = self.do_build();
 
-        let fut = lazy(move || res)
-            .from_err()
-            .and_then(Socket::from_sock)
-            .map(T::from);
+        Box::pin(async move {
+            let mut sock = Socket::from_sock(res?)?;
+            sock.set_ipc_permissions(ipc_permissions);
 
-        Box::new(fut)
+            Ok(T::from(sock))
+        })
     }
 }
 
-impl<'a> Build<Sub> for SubConfig<'a> {
-    fn build(self) -> Box<dyn Future<Item = Sub, Error = Error> + Send> {
+impl Build<Sub> for SubConfig {
+    fn build(self) -> BoxFuture<'static, Result<Sub, Error>> {
+        let ipc_permissions = self.options.ipc_permissions;
         let sock = self.do_build();
 
-        let fut = lazy(move || sock)
-            .from_err()
-            .and_then(Socket::from_sock)
-            .map(Sub::from);
+        Box::pin(async move {
+            let mut sock = Socket::from_sock(sock?)?;
+            sock.set_ipc_permissions(ipc_permissions);
 
-        Box::new(fut)
+            Ok(Sub::from(sock))
+        })
     }
 }
 
-impl<'a> Build<Pair> for PairConfig<'a> {
-    fn build(self) -> Box<dyn Future<Item = Pair, Error = Error> + Send> {
+impl Build<Pair> for PairConfig {
+    fn build(self) -> BoxFuture<'static, Result<Pair, Error>> {
+        let ipc_permissions = self.options.ipc_permissions;
         let sock = self.do_build();
 
-        let fut = lazy(move || sock)
-            .from_err()
-            .and_then(Socket::from_sock)
-            .map(Pair::from);
+        Box::pin(async move {
+            let mut sock = Socket::from_sock(sock?)?;
+            sock.set_ipc_permissions(ipc_permissions);
 
-        Box::new(fut)
+            Ok(Pair::from(sock))
+        })
     }
 }
@@ -22,18 +22,28 @@
 pub mod config;
 pub mod types;
 
-use async_zmq_types::{InnerSocket, IntoInnerSocket, Multipart, SocketBuilder};
-use futures::{task::Task, Async};
-use mio::Ready;
-use std::{fmt, sync::Arc};
-use tokio_reactor::PollEvented;
-
-use zmq;
+#[cfg(feature = "draft")]
+use async_zmq_types::draft::{self, Group, Poller, RoutingId};
+use async_zmq_types::{
+    bind_endpoint, connect_endpoint, monitor::monitor_socket, unbind, HasBuilder, InnerSocket,
+    IntoInnerSocket, Multipart, SocketBuilder,
+};
+use futures::{
+    future::{ready, Ready},
+    ready,
+};
+use std::{
+    fmt,
+    sync::Arc,
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+use tokio::io::{unix::AsyncFd, Interest};
 
 use crate::{
     async_types::{
-        EventedFile, MultipartRequest, MultipartResponse, MultipartSink, MultipartSinkStream,
-        MultipartStream,
+        EventedFile, MonitorStream, MultipartRequest, MultipartRequestMut, MultipartResponse,
+        MultipartResponseMut, MultipartSink, MultipartSinkStream, MultipartStream,
     },
     error::Error,
     file::ZmqFile,
@@ -42,17 +52,22 @@ use crate::{
 /// Defines the raw Socket type. This type should never be interacted with directly, except to
 /// create new instances of wrapper types.
 pub struct Socket {
-    // Reads and Writes data
-    sock: zmq::Socket,
     // So we can hand out files to streams and sinks
+    //
+    // This is declared before `sock` so the file descriptor is deregistered from the reactor
+    // before ZeroMQ gets a chance to close it.
     file: EventedFile,
+    // Reads and Writes data
+    sock: zmq::Socket,
+    // Applied to the files of ipc endpoints bound after the socket was built
+    ipc_permissions: Option<u32>,
 }
 
 impl Socket {
     /// Start a new Socket Config builder
-    pub fn builder<T>(ctx: Arc<zmq::Context>) -> SocketBuilder<'static, T>
+    pub fn builder<T>(ctx: Arc<zmq::Context>) -> SocketBuilder<T>
     where
-        T: IntoInnerSocket,
+        T: HasBuilder,
     {
         SocketBuilder::new(ctx)
     }
@@ -67,76 +82,168 @@ impl Socket {
     /// This assumes that `sock` is already configured properly. Please don't call this directly
     /// unless you know what you're doing.
     pub fn from_sock_and_file(sock: zmq::Socket, file: EventedFile) -> Self {
-        Socket { sock, file }
+        Socket {
+            sock,
+            file,
+            ipc_permissions: None,
+        }
     }
 
     /// Create a new socket from a given Sock
     ///
     /// This assumes that `sock` is already configured properly. Please don't call this directly
     /// unless you know what you're doing.
+    ///
+    /// This registers the socket's file descriptor with the current Tokio reactor, so it must be
+    /// called from within a Tokio runtime.
     pub fn from_sock(sock: zmq::Socket) -> Result<Self, Error> {
-        let fd = sock.get_fd()?;
-        let file = PollEvented::new(ZmqFile::from_raw_fd(fd));
+        let file = match sock.get_fd() {
+            Ok(fd) => ZmqFile::from_raw_fd(fd),
+            // Draft sockets are thread-safe, and don't have a file descriptor of their own
+            #[cfg(feature = "draft")]
+            Err(zmq::Error::EINVAL) => ZmqFile::from_poller(Poller::new(&sock)?),
+            Err(e) => return Err(e.into()),
+        };
+        let file = AsyncFd::with_interest(file, Interest::READABLE)?;
+
+        Ok(Socket {
+            sock,
+            file,
+            ipc_permissions: None,
+        })
+    }
 
-        Ok(Socket { sock, file })
+    /// Carry the built socket's `ipc_permissions` option over to endpoints bound later on
+    pub(crate) fn set_ipc_permissions(&mut self, mode: Option<u32>) {
+        self.ipc_permissions = mode;
     }
 
-    pub(crate) fn send_msg(&self, msg: zmq::Message, flags: i32) -> zmq::Result<()> {
-        self.sock.send(msg, flags)
+    /// Send a single multipart message without giving up ownership of the socket
+    pub fn send_mut(&mut self, multipart: Multipart) -> MultipartRequestMut<'_> {
+        MultipartRequestMut::new(self, multipart)
     }
 
-    pub(crate) fn recv_msg(&self, msg: &mut zmq::Message) -> zmq::Result<()> {
-        self.sock.recv(msg, zmq::DONTWAIT)
+    /// Receive a single multipart message without giving up ownership of the socket
+    pub fn recv_mut(&mut self) -> MultipartResponseMut<'_> {
+        MultipartResponseMut::new(self)
     }
 
-    pub(crate) fn poll_read_ready(
-        &self,
-        mask: Ready,
-        task: Option<&Task>,
-    ) -> Result<Async<Ready>, Error> {
-        let _ = self.file.poll_read_ready(mask)?;
+    /// Start receiving the connection events ZeroMQ reports for this socket
+    ///
+    /// The events are delivered over an inproc PAIR socket, so `ctx` must be the context this
+    /// socket was created with. Like `from_sock`, this must be called from within a Tokio
+    /// runtime.
+    pub fn monitor(&self, ctx: &zmq::Context) -> Result<MonitorStream, Error> {
+        let pair = monitor_socket(ctx, &self.sock)?;
 
-        let events = self.sock.get_events()?;
+        Ok(MonitorStream::new(Socket::from_sock(pair)?))
+    }
 
-        if let Some(task) = task {
-            if events & zmq::POLLOUT == zmq::POLLOUT {
-                task.notify();
-            }
-        }
+    /// Bind to `endpoint`, returning the endpoint ZeroMQ resolved it to
+    fn bind_endpoint(&self, endpoint: &str) -> Result<String, Error> {
+        Ok(bind_endpoint(&self.sock, endpoint, self.ipc_permissions)?)
+    }
 
-        if events & zmq::POLLIN == zmq::POLLIN {
-            return Ok(Async::Ready(mask));
-        }
+    /// Start receiving messages that begin with `topic`, on a SUB socket, or pass them on, on an
+    /// XPUB socket with ZMQ_XPUB_MANUAL
+    pub(crate) fn subscribe(&self, topic: &[u8]) -> zmq::Result<()> {
+        self.sock.set_subscribe(topic)
+    }
 
-        self.file.clear_read_ready(mask)?;
-        Ok(Async::NotReady)
+    /// Undo a subscription made with `subscribe`
+    pub(crate) fn unsubscribe(&self, topic: &[u8]) -> zmq::Result<()> {
+        self.sock.set_unsubscribe(topic)
     }
 
-    pub(crate) fn poll_write_ready(&self, task: Option<&Task>) -> Result<Async<()>, Error> {
-        let _ = self.file.poll_write_ready()?;
+    /// Start receiving messages sent to `group`, on a DISH socket
+    #[cfg(feature = "draft")]
+    pub(crate) fn join(&self, group: &Group) -> zmq::Result<()> {
+        draft::join(&self.sock, group)
+    }
 
-        let events = self.sock.get_events()?;
+    /// Stop receiving messages sent to a group joined with `join`
+    #[cfg(feature = "draft")]
+    pub(crate) fn leave(&self, group: &Group) -> zmq::Result<()> {
+        draft::leave(&self.sock, group)
+    }
 
-        if let Some(task) = task {
-            if events & zmq::POLLIN == zmq::POLLIN {
-                task.notify();
-            }
-        }
+    /// Connect a PEER socket, returning the routing id of the new peer
+    #[cfg(feature = "draft")]
+    pub(crate) fn connect_peer(&self, endpoint: &str) -> zmq::Result<RoutingId> {
+        draft::connect_peer(&self.sock, endpoint)
+    }
 
-        if events & zmq::POLLOUT == zmq::POLLOUT {
-            return Ok(Async::Ready(()));
-        }
+    /// How long ZeroMQ holds on to unsent messages after the socket is closed, or `None` if it
+    /// waits forever
+    pub(crate) fn linger(&self) -> zmq::Result<Option<Duration>> {
+        let linger = self.sock.get_linger()?;
 
-        self.file.clear_write_ready()?;
-        Ok(Async::NotReady)
+        Ok(if linger < 0 {
+            None
+        } else {
+            Some(Duration::from_millis(linger as u64))
+        })
     }
 
-    pub(crate) fn clear_read_ready(&self, mask: Ready) -> Result<(), std::io::Error> {
-        self.file.clear_read_ready(mask)
+    pub(crate) fn send_front(&mut self, multipart: &mut Multipart, flags: i32) -> zmq::Result<()> {
+        multipart.send_front(&mut self.sock, flags)
     }
 
-    pub(crate) fn clear_write_ready(&self) -> Result<(), std::io::Error> {
-        self.file.clear_write_ready()
+    pub(crate) fn recv_msg(&self, msg: &mut zmq::Message) -> zmq::Result<()> {
+        self.sock.recv(msg, zmq::DONTWAIT)
+    }
+
+    /// Wait until ZeroMQ reports that a message can be received
+    ///
+    /// ZeroMQ's file descriptor is edge-triggered and shared between reading and writing, so
+    /// checking the socket's events may consume a notification meant for the writing half. If
+    /// that half is currently waiting, its waker is taken from `other` and woken so it can check
+    /// again.
+    pub(crate) fn poll_read_ready(
+        &self,
+        cx: &mut Context,
+        other: &mut Option<Waker>,
+    ) -> Poll<Result<(), Error>> {
+        self.poll_events(cx, zmq::POLLIN, zmq::POLLOUT, other)
+    }
+
+    /// Wait until ZeroMQ reports that a message can be sent
+    ///
+    /// See `poll_read_ready` for why `other` is needed.
+    pub(crate) fn poll_write_ready(
+        &self,
+        cx: &mut Context,
+        other: &mut Option<Waker>,
+    ) -> Poll<Result<(), Error>> {
+        self.poll_events(cx, zmq::POLLOUT, zmq::POLLIN, other)
+    }
+
+    fn poll_events(
+        &self,
+        cx: &mut Context,
+        wanted: zmq::PollEvents,
+        others: zmq::PollEvents,
+        other: &mut Option<Waker>,
+    ) -> Poll<Result<(), Error>> {
+        loop {
+            // ZeroMQ only signals its file descriptor as readable, no matter which events changed,
+            // and the signal is reset as soon as the events are read. Checking the events before
+            // waiting on the descriptor means a notification consumed elsewhere is never missed.
+            let events = self.sock.get_events()?;
+
+            if events & others == others {
+                if let Some(waker) = other.take() {
+                    waker.wake();
+                }
+            }
+
+            if events & wanted == wanted {
+                return Poll::Ready(Ok(()));
+            }
+
+            ready!(self.file.poll_read_ready(cx))?.clear_ready();
+            self.file.get_ref().drain();
+        }
     }
 }
 
@@ -152,6 +259,13 @@ where
 
     type SinkStream = MultipartSinkStream<T>;
 
+    type RequestMut<'a> = MultipartRequestMut<'a>;
+    type ResponseMut<'a> = MultipartResponseMut<'a>;
+
+    // Changing endpoints doesn't block, so these are ready immediately
+    type Bind = Ready<Result<String, Error>>;
+    type Endpoint = Ready<Result<(), Error>>;
+
     fn send(self, multipart: Multipart) -> Self::Request {
         MultipartRequest::new(self, multipart)
     }
@@ -160,6 +274,14 @@ where
         MultipartResponse::new(self)
     }
 
+    fn send_mut(&mut self, multipart: Multipart) -> Self::RequestMut<'_> {
+        Socket::send_mut(self, multipart)
+    }
+
+    fn recv_mut(&mut self) -> Self::ResponseMut<'_> {
+        Socket::recv_mut(self)
+    }
+
     fn stream(self) -> Self::Stream {
         MultipartStream::new(self)
     }
@@ -171,11 +293,27 @@ where
     fn sink_stream(self, buffer_size: usize) -> Self::SinkStream {
         MultipartSinkStream::new(buffer_size, self)
     }
+
+    fn bind(&self, endpoint: &str) -> Self::Bind {
+        ready(self.bind_endpoint(endpoint))
+    }
+
+    fn unbind(&self, endpoint: &str) -> Self::Endpoint {
+        ready(unbind(&self.sock, endpoint).map_err(Error::from))
+    }
+
+    fn connect(&self, endpoint: &str) -> Self::Endpoint {
+        ready(connect_endpoint(&self.sock, endpoint).map_err(Error::from))
+    }
+
+    fn disconnect(&self, endpoint: &str) -> Self::Endpoint {
+        ready(self.sock.disconnect(endpoint).map_err(Error::from))
+    }
 }
 
 impl From<(zmq::Socket, EventedFile)> for Socket {
     fn from((sock, file): (zmq::Socket, EventedFile)) -> Self {
-        Socket { sock, file }
+        Socket::from_sock_and_file(sock, file)
     }
 }
 
@@ -20,9 +20,17 @@
 //! This module defines all the socket wrapper types that can be used with Tokio.
 
 use async_zmq_derive::SocketWrapper;
+#[cfg(feature = "draft")]
+use async_zmq_types::draft::{Group, RoutingId};
+use async_zmq_types::subscription::{XpubStream, XsubSink};
+use futures::future::{ready, Ready};
 use zmq::SocketType::{self, DEALER, PAIR, PUB, PULL, PUSH, REP, REQ, ROUTER, SUB, XPUB, XSUB};
 
-use crate::{async_types::EventedFile, socket::Socket};
+use crate::{
+    async_types::{EventedFile, MultipartSinkStream},
+    error::Error,
+    socket::Socket,
+};
 
 // needed for derive
 type RawSocket = (zmq::Socket, EventedFile);
@@ -131,6 +139,54 @@ pub struct Sub {
     pub(crate) inner: Socket,
 }
 
+impl Sub {
+    /// Start receiving messages whose first frame begins with `topic`
+    ///
+    /// Subscriptions are counted, so subscribing to a topic twice takes two calls to
+    /// `unsubscribe` to stop receiving it. An empty topic matches every message. The change is
+    /// made right away, and the returned future is already resolved, like futures-zmq's is once
+    /// its poll thread has made it.
+    ///
+    /// ### Example
+    /// ```rust
+    /// use std::sync::Arc;
+    ///
+    /// use futures::TryStreamExt;
+    /// use tokio_zmq::{prelude::*, Error, Sub};
+    ///
+    /// async fn run(context: Arc<zmq::Context>) -> Result<(), Error> {
+    ///     let sub: Sub = Sub::builder(context)
+    ///         .connect("tcp://localhost:5569")
+    ///         .filter(b"prices.")
+    ///         .build()
+    ///         .await?;
+    ///
+    ///     sub.subscribe(b"trades.").await?;
+    ///     sub.unsubscribe(b"prices.").await?;
+    ///
+    ///     sub.stream()
+    ///         .try_for_each(|_multipart| async { Ok(()) })
+    ///         .await
+    /// }
+    ///
+    /// fn main() {
+    ///     let context = Arc::new(zmq::Context::new());
+    ///     let fut = run(context);
+    ///
+    ///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+    ///     # let _ = fut;
+    /// }
+    /// ```
+    pub fn subscribe(&self, topic: &[u8]) -> Ready<Result<(), Error>> {
+        ready(self.inner.subscribe(topic).map_err(Error::from))
+    }
+
+    /// Stop receiving messages for a subscription made with `filter` or `subscribe`
+    pub fn unsubscribe(&self, topic: &[u8]) -> Ready<Result<(), Error>> {
+        ready(self.inner.unsubscribe(topic).map_err(Error::from))
+    }
+}
+
 /* -------------------------------------------------------------------------- */
 
 /// The XPUB `SocketType` wrapper type
@@ -143,6 +199,64 @@ pub struct Xpub {
     pub(crate) inner: Socket,
 }
 
+impl Xpub {
+    /// Read the subscriptions sent by subscribers as `XpubEvent`s, while publishing multiparts
+    /// through the sink
+    ///
+    /// ### Example
+    /// ```rust
+    /// use std::sync::Arc;
+    ///
+    /// use futures::TryStreamExt;
+    /// use tokio_zmq::{
+    ///     prelude::*,
+    ///     subscription::{Subscription, XpubEvent},
+    ///     Error, Xpub,
+    /// };
+    ///
+    /// async fn run(context: Arc<zmq::Context>) -> Result<(), Error> {
+    ///     let xpub: Xpub = Xpub::builder(context)
+    ///         .bind("tcp://*:5570")
+    ///         .xpub_verbose(true)
+    ///         .build()
+    ///         .await?;
+    ///
+    ///     xpub.subscription_stream(25)
+    ///         .try_for_each(|event| async move {
+    ///             if let XpubEvent::Subscription(Subscription::Subscribe(topic)) = event {
+    ///                 println!("New subscriber for {:?}", topic);
+    ///             }
+    ///             Ok(())
+    ///         })
+    ///         .await
+    /// }
+    ///
+    /// fn main() {
+    ///     let context = Arc::new(zmq::Context::new());
+    ///     let fut = run(context);
+    ///
+    ///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+    ///     # let _ = fut;
+    /// }
+    /// ```
+    pub fn subscription_stream(self, buffer_size: usize) -> XpubStream<MultipartSinkStream<Xpub>> {
+        XpubStream::new(MultipartSinkStream::new(buffer_size, self.inner))
+    }
+
+    /// Apply a subscription, for sockets built with `xpub_manual`
+    ///
+    /// ZeroMQ applies it to the subscriber that sent the last subscription read from the socket,
+    /// so this should be called as soon as that subscription has been received.
+    pub fn subscribe(&self, topic: &[u8]) -> Ready<Result<(), Error>> {
+        ready(self.inner.subscribe(topic).map_err(Error::from))
+    }
+
+    /// Undo a subscription applied with `subscribe`, see `subscribe`
+    pub fn unsubscribe(&self, topic: &[u8]) -> Ready<Result<(), Error>> {
+        ready(self.inner.unsubscribe(topic).map_err(Error::from))
+    }
+}
+
 /* -------------------------------------------------------------------------- */
 
 /// The XSUB `SocketType` wrapper type
@@ -154,3 +268,203 @@ pub struct Xpub {
 pub struct Xsub {
     pub(crate) inner: Socket,
 }
+
+impl Xsub {
+    /// Send `Subscription`s upstream through the sink, alongside multiparts
+    pub fn subscription_sink(self, buffer_size: usize) -> XsubSink<MultipartSinkStream<Xsub>> {
+        XsubSink::new(MultipartSinkStream::new(buffer_size, self.inner))
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+
+/// The SERVER draft socket wrapper type
+///
+/// Server implements `StreamSocket` and `SinkSocket`. Each message it receives carries the routing
+/// id of the client that sent it, and each message it sends needs the routing id of the client
+/// it's for, see `RoutedMessage`.
+#[cfg(feature = "draft")]
+#[derive(Debug, SocketWrapper)]
+#[draft]
+#[stream]
+#[sink]
+pub struct Server {
+    pub(crate) inner: Socket,
+}
+
+/* -------------------------------------------------------------------------- */
+
+/// The CLIENT draft socket wrapper type
+///
+/// Client implements `StreamSocket` and `SinkSocket`, and sends single frames to the SERVER sockets
+/// it's connected to.
+#[cfg(feature = "draft")]
+#[derive(Debug, SocketWrapper)]
+#[draft]
+#[stream]
+#[sink]
+pub struct Client {
+    pub(crate) inner: Socket,
+}
+
+/* -------------------------------------------------------------------------- */
+
+/// The RADIO draft socket wrapper type
+///
+/// Radio implements `SinkSocket`. Each message it sends needs a group, see `GroupMessage`.
+#[cfg(feature = "draft")]
+#[derive(Debug, SocketWrapper)]
+#[draft]
+#[sink]
+pub struct Radio {
+    pub(crate) inner: Socket,
+}
+
+/* -------------------------------------------------------------------------- */
+
+/// The DISH draft socket wrapper type
+///
+/// Dish implements `StreamSocket`, and receives the messages sent to the groups it has joined.
+#[cfg(feature = "draft")]
+#[derive(Debug, SocketWrapper)]
+#[draft]
+#[stream]
+pub struct Dish {
+    pub(crate) inner: Socket,
+}
+
+#[cfg(feature = "draft")]
+impl Dish {
+    /// Start receiving messages sent to `group`
+    ///
+    /// ### Example
+    /// ```rust
+    /// use futures::TryStreamExt;
+    /// use tokio_zmq::{draft::{Group, GroupMessage}, prelude::*, Dish, Error};
+    ///
+    /// async fn run() -> Result<(), Error> {
+    ///     let dish: Dish = Dish::builder()
+    ///         .join(&Group::new("weather").unwrap())
+    ///         .bind("udp://*:5571")
+    ///         .build()
+    ///         .await?;
+    ///
+    ///     dish.join(&Group::new("alerts").unwrap()).await?;
+    ///
+    ///     dish.stream()
+    ///         .try_for_each(|multipart| async {
+    ///             if let Ok(msg) = GroupMessage::from_multipart(multipart) {
+    ///                 println!("{}: {:?}", msg.group, msg.body.as_str());
+    ///             }
+    ///             Ok(())
+    ///         })
+    ///         .await
+    /// }
+    ///
+    /// fn main() {
+    ///     let fut = run();
+    ///
+    ///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+    ///     # let _ = fut;
+    /// }
+    /// ```
+    pub fn join(&self, group: &Group) -> Ready<Result<(), Error>> {
+        ready(self.inner.join(group).map_err(Error::from))
+    }
+
+    /// Stop receiving messages sent to a group joined with `join`, or by the builder
+    pub fn leave(&self, group: &Group) -> Ready<Result<(), Error>> {
+        ready(self.inner.leave(group).map_err(Error::from))
+    }
+}
+
+/* -------------------------------------------------------------------------- */
+
+/// The SCATTER draft socket wrapper type
+///
+/// Scatter implements `SinkSocket`, and hands its messages to the GATHER sockets it's connected
+/// to in turn.
+#[cfg(feature = "draft")]
+#[derive(Debug, SocketWrapper)]
+#[draft]
+#[sink]
+pub struct Scatter {
+    pub(crate) inner: Socket,
+}
+
+/* -------------------------------------------------------------------------- */
+
+/// The GATHER draft socket wrapper type
+///
+/// Gather implements `StreamSocket`, and receives messages from the SCATTER sockets it's connected
+/// to.
+#[cfg(feature = "draft")]
+#[derive(Debug, SocketWrapper)]
+#[draft]
+#[stream]
+pub struct Gather {
+    pub(crate) inner: Socket,
+}
+
+/* -------------------------------------------------------------------------- */
+
+/// The CHANNEL draft socket wrapper type
+///
+/// Channel implements `StreamSocket` and `SinkSocket`, and, like PAIR, talks to a single peer.
+#[cfg(feature = "draft")]
+#[derive(Debug, SocketWrapper)]
+#[draft]
+#[stream]
+#[sink]
+pub struct Channel {
+    pub(crate) inner: Socket,
+}
+
+/* -------------------------------------------------------------------------- */
+
+/// The PEER draft socket wrapper type
+///
+/// Peer implements `StreamSocket` and `SinkSocket`. Like SERVER, each message carries the routing
+/// id of the peer it's from or for, see `RoutedMessage`, and peers are connected with
+/// `connect_peer`.
+#[cfg(feature = "draft")]
+#[derive(Debug, SocketWrapper)]
+#[draft]
+#[stream]
+#[sink]
+pub struct Peer {
+    pub(crate) inner: Socket,
+}
+
+#[cfg(feature = "draft")]
+impl Peer {
+    /// Connect to another PEER socket, returning the routing id to send it messages with
+    ///
+    /// ### Example
+    /// ```rust
+    /// use futures::SinkExt;
+    /// use tokio_zmq::{draft::RoutedMessage, prelude::*, Error, Peer};
+    ///
+    /// async fn run() -> Result<(), Error> {
+    ///     let peer: Peer = Peer::builder()
+    ///         .bind("tcp://*:5573")
+    ///         .build()
+    ///         .await?;
+    ///
+    ///     let routing_id = peer.connect_peer("tcp://localhost:5572").await?;
+    ///
+    ///     let hello = RoutedMessage::new(routing_id, zmq::Message::from("hello"));
+    ///     peer.sink(25).send(hello.into()).await
+    /// }
+    ///
+    /// fn main() {
+    ///     let fut = run();
+    ///
+    ///     // tokio::runtime::Runtime::new().unwrap().block_on(fut).unwrap();
+    ///     # let _ = fut;
+    /// }
+    /// ```
+    pub fn connect_peer(&self, endpoint: &str) -> Ready<Result<RoutingId, Error>> {
+        ready(self.inner.connect_peer(endpoint).map_err(Error::from))
+    }
+}
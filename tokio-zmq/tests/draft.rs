@@ -0,0 +1,179 @@
+/*
+ * This file is part of Tokio ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Tokio ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Tokio ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Tokio ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+// These tests need a libzmq built with the draft API, and only run with the `draft` feature.
+
+#![cfg(feature = "draft")]
+
+use std::time::Duration;
+
+use tokio::time::timeout;
+use tokio_zmq::{
+    draft::{Group, GroupMessage, RoutedMessage},
+    prelude::*,
+    Client, Dish, Gather, Peer, Radio, Scatter, Server,
+};
+
+fn message(s: &str) -> zmq::Message {
+    zmq::Message::from(s)
+}
+
+fn group(name: &str) -> Group {
+    Group::new(name).unwrap()
+}
+
+#[tokio::test]
+async fn servers_reply_by_routing_id() {
+    let mut server: Server = Server::builder()
+        .bind("inproc://draft-server")
+        .build()
+        .await
+        .unwrap();
+    let mut client: Client = Client::builder()
+        .connect("inproc://draft-server")
+        .build()
+        .await
+        .unwrap();
+
+    let exchange = async {
+        client.send_mut(message("ping").into()).await.unwrap();
+
+        let request = RoutedMessage::from_multipart(server.recv_mut().await.unwrap()).unwrap();
+        assert_eq!(request.body.as_str(), Some("ping"));
+        server
+            .send_mut(request.reply(message("pong")).into())
+            .await
+            .unwrap();
+
+        let reply = client.recv_mut().await.unwrap();
+        assert_eq!(reply.len(), 1);
+        assert_eq!(reply.get(0).and_then(|msg| msg.as_str()), Some("pong"));
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn dishes_only_receive_joined_groups() {
+    let mut dish: Dish = Dish::builder()
+        .join(&group("weather"))
+        .bind("inproc://draft-radio")
+        .build()
+        .await
+        .unwrap();
+    let mut radio: Radio = Radio::builder()
+        .connect("inproc://draft-radio")
+        .build()
+        .await
+        .unwrap();
+
+    let exchange = async {
+        // RADIO drops messages until the DISH's joins reach it, so keep publishing until one
+        // arrives
+        let update = loop {
+            let sports = GroupMessage::new(group("sports"), message("goal"));
+            radio.send_mut(sports.into()).await.unwrap();
+            let weather = GroupMessage::new(group("weather"), message("sunny"));
+            radio.send_mut(weather.into()).await.unwrap();
+
+            let recv = timeout(Duration::from_millis(50), dish.recv_mut());
+            if let Ok(multipart) = recv.await {
+                break GroupMessage::from_multipart(multipart.unwrap()).unwrap();
+            }
+        };
+
+        assert_eq!(update.group.as_str(), "weather");
+        assert_eq!(update.body.as_str(), Some("sunny"));
+
+        dish.leave(&group("weather")).await.unwrap();
+        dish.join(&group("sports")).await.unwrap();
+
+        let update = loop {
+            let sports = GroupMessage::new(group("sports"), message("goal"));
+            radio.send_mut(sports.into()).await.unwrap();
+
+            let recv = timeout(Duration::from_millis(50), dish.recv_mut());
+            if let Ok(multipart) = recv.await {
+                break GroupMessage::from_multipart(multipart.unwrap()).unwrap();
+            }
+        };
+
+        assert_eq!(update.group.as_str(), "sports");
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn scatter_reaches_gather() {
+    let mut gather: Gather = Gather::builder()
+        .bind("inproc://draft-scatter")
+        .build()
+        .await
+        .unwrap();
+    let mut scatter: Scatter = Scatter::builder()
+        .connect("inproc://draft-scatter")
+        .build()
+        .await
+        .unwrap();
+
+    let exchange = async {
+        scatter.send_mut(message("work").into()).await.unwrap();
+
+        let work = gather.recv_mut().await.unwrap();
+        assert_eq!(work.get(0).and_then(|msg| msg.as_str()), Some("work"));
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn peers_connect_with_routing_ids() {
+    let mut listener: Peer = Peer::builder()
+        .bind("inproc://draft-peer")
+        .build()
+        .await
+        .unwrap();
+    // Builders need an endpoint, this peer's real connection is made with connect_peer
+    let mut peer: Peer = Peer::builder()
+        .hello_msg(b"hello")
+        .bind("inproc://draft-peer-unused")
+        .build()
+        .await
+        .unwrap();
+
+    let exchange = async {
+        let routing_id = peer.connect_peer("inproc://draft-peer").await.unwrap();
+
+        // The hello message is delivered as soon as the peers are connected
+        let hello = RoutedMessage::from_multipart(listener.recv_mut().await.unwrap()).unwrap();
+        assert_eq!(hello.body.as_str(), Some("hello"));
+
+        listener
+            .send_mut(hello.reply(message("welcome")).into())
+            .await
+            .unwrap();
+
+        let reply = RoutedMessage::from_multipart(peer.recv_mut().await.unwrap()).unwrap();
+        assert_eq!(reply.routing_id, routing_id);
+        assert_eq!(reply.body.as_str(), Some("welcome"));
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
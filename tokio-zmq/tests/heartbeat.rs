@@ -0,0 +1,162 @@
+/*
+ * This file is part of Tokio ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Tokio ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Tokio ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Tokio ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use futures::{channel::mpsc, SinkExt, StreamExt, TryStreamExt};
+use tokio::time::timeout;
+use tokio_zmq::{
+    heartbeat::{Heartbeat, PeerEvent, PING, PONG},
+    prelude::*,
+    Dealer, Error, Multipart, PeerId, Router,
+};
+
+const INTERVAL: Duration = Duration::from_millis(50);
+
+fn message(s: &str) -> Multipart {
+    zmq::Message::from(s).into()
+}
+
+async fn router(ctx: &Arc<zmq::Context>, endpoint: &str) -> Heartbeat<Router> {
+    let router: Router = Router::builder(Arc::clone(ctx))
+        .bind(endpoint)
+        .build()
+        .await
+        .unwrap();
+
+    Heartbeat::new(router.sink_stream(25), INTERVAL)
+}
+
+async fn dealer(ctx: &Arc<zmq::Context>, endpoint: &str, id: &str) -> Dealer {
+    Dealer::builder(Arc::clone(ctx))
+        .identity(id.as_bytes())
+        .connect(endpoint)
+        .build()
+        .await
+        .unwrap()
+}
+
+// Keep a wrapped Dealer heartbeating, passing along what it hears
+fn drive(
+    heartbeat: Heartbeat<Dealer>,
+) -> (impl SinkExt<Multipart>, mpsc::UnboundedReceiver<PeerEvent>) {
+    let (sink, stream) = heartbeat.split();
+    let (tx, rx) = mpsc::unbounded();
+
+    tokio::spawn(async move {
+        let _ = stream
+            .try_for_each(|event| {
+                let _ = tx.unbounded_send(event);
+                async { Ok(()) }
+            })
+            .await;
+    });
+
+    (sink, rx)
+}
+
+#[tokio::test]
+async fn routers_track_their_dealers() {
+    let ctx = Arc::new(zmq::Context::new());
+    let endpoint = "inproc://heartbeat-track";
+
+    let mut router = router(&ctx, endpoint).await;
+
+    let a = Heartbeat::new(dealer(&ctx, endpoint, "a").await.sink_stream(25), INTERVAL);
+    let b = Heartbeat::new(dealer(&ctx, endpoint, "b").await.sink_stream(25), INTERVAL);
+
+    let (mut a_sink, mut a_events) = drive(a);
+    let (_b_sink, _b_events) = drive(b);
+
+    let exchange = async {
+        let mut up = Vec::new();
+        while up.len() < 2 {
+            match router.try_next().await.unwrap().unwrap() {
+                PeerEvent::PeerUp(peer) => up.push(peer),
+                other => panic!("Expected a peer to come up, got {:?}", other),
+            }
+        }
+        up.sort();
+        assert_eq!(up, vec![PeerId::from("a"), PeerId::from("b")]);
+
+        // Dealers see their Router come up when it answers their pings
+        match a_events.next().await.unwrap() {
+            PeerEvent::PeerUp(peer) => assert!(peer.as_bytes().is_empty()),
+            other => panic!("Expected the router to come up, got {:?}", other),
+        }
+
+        a_sink.send(message("hello")).await.ok().unwrap();
+        match router.try_next().await.unwrap().unwrap() {
+            PeerEvent::Message(multipart) => {
+                assert_eq!(multipart.get(0).map(|msg| &msg[..]), Some(&b"a"[..]));
+                assert_eq!(multipart.get(1).and_then(|msg| msg.as_str()), Some("hello"));
+            }
+            other => panic!("Expected a message, got {:?}", other),
+        }
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn quiet_peers_go_down() {
+    let ctx = Arc::new(zmq::Context::new());
+    let endpoint = "inproc://heartbeat-down";
+
+    let mut router = router(&ctx, endpoint).await;
+    let mut plain = dealer(&ctx, endpoint, "plain").await;
+
+    let exchange = async {
+        // Pings from peers that don't use Heartbeat are answered, and never delivered
+        plain
+            .send_mut(zmq::Message::from(PING).into())
+            .await
+            .unwrap();
+
+        match router.try_next().await.unwrap().unwrap() {
+            PeerEvent::PeerUp(peer) => assert_eq!(peer, PeerId::from("plain")),
+            other => panic!("Expected the peer to come up, got {:?}", other),
+        }
+
+        // Without more pings, the peer goes down after three intervals
+        let (event, pong) = futures::join!(router.try_next(), plain.recv_mut());
+
+        assert_eq!(pong.unwrap().get(0).map(|msg| &msg[..]), Some(PONG));
+        match event.unwrap().unwrap() {
+            PeerEvent::PeerDown(peer) => assert_eq!(peer, PeerId::from("plain")),
+            other => panic!("Expected the peer to go down, got {:?}", other),
+        }
+
+        assert_eq!(router.peers().count(), 0);
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn zero_liveness_is_rejected() {
+    let ctx = Arc::new(zmq::Context::new());
+    let router = router(&ctx, "inproc://heartbeat-liveness").await;
+
+    match router.liveness(0) {
+        Err(Error::Zmq(zmq::Error::EINVAL)) => (),
+        Err(e) => panic!("Expected EINVAL, got {}", e),
+        Ok(_) => panic!("Expected a liveness of zero to be rejected"),
+    }
+}
@@ -0,0 +1,294 @@
+/*
+ * This file is part of Tokio ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Tokio ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Tokio ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Tokio ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use futures::{StreamExt, TryStreamExt};
+use tokio::{
+    task::JoinHandle,
+    time::{sleep, timeout},
+};
+use tokio_zmq::{
+    mdp::{MdpBroker, MdpClient, MdpWorker},
+    prelude::*,
+    Dealer, Error, Multipart, Req, Router,
+};
+
+const HEARTBEAT: Duration = Duration::from_millis(50);
+
+fn message(s: &str) -> Multipart {
+    zmq::Message::from(s).into()
+}
+
+fn text(multipart: &Multipart) -> Option<&str> {
+    multipart.get(0).and_then(|msg| msg.as_str())
+}
+
+async fn broker(ctx: &Arc<zmq::Context>, endpoint: &str) -> JoinHandle<Result<(), Error>> {
+    // A broker that was just stopped may still hold its endpoint for a moment
+    let router: Router = loop {
+        let res = Router::builder(Arc::clone(ctx))
+            .bind(endpoint)
+            .build()
+            .await;
+
+        match res {
+            Ok(router) => break router,
+            Err(_) => sleep(Duration::from_millis(10)).await,
+        }
+    };
+
+    tokio::spawn(MdpBroker::new(router).heartbeat(HEARTBEAT).run())
+}
+
+fn echo_worker(ctx: &Arc<zmq::Context>, endpoint: &str, service: &str) -> JoinHandle<()> {
+    let worker = MdpWorker::new(Arc::clone(ctx), endpoint, service)
+        .heartbeat(HEARTBEAT)
+        .reconnect_delay(HEARTBEAT);
+
+    tokio::spawn(async move {
+        let (replies, requests) = worker.split();
+
+        requests
+            .map_ok(|request| {
+                let body = request
+                    .body()
+                    .iter()
+                    .map(|msg| zmq::Message::from(&msg[..]));
+                request.reply(body.collect::<Vec<_>>().into())
+            })
+            .forward(replies)
+            .await
+            .unwrap();
+    })
+}
+
+async fn client(ctx: &Arc<zmq::Context>, endpoint: &str) -> MdpClient {
+    let dealer: Dealer = Dealer::builder(Arc::clone(ctx))
+        .connect(endpoint)
+        .build()
+        .await
+        .unwrap();
+
+    MdpClient::new(dealer).timeout(Duration::from_secs(1))
+}
+
+#[tokio::test]
+async fn requests_reach_their_service() {
+    let ctx = Arc::new(zmq::Context::new());
+    let endpoint = "inproc://mdp-services";
+
+    broker(&ctx, endpoint).await;
+    echo_worker(&ctx, endpoint, "echo");
+    echo_worker(&ctx, endpoint, "shout");
+
+    let mut client = client(&ctx, endpoint).await;
+
+    let exchange = async {
+        for word in &["one", "two", "three"] {
+            let reply = client.request("echo", message(word)).await.unwrap();
+            assert_eq!(text(&reply), Some(*word));
+        }
+
+        let reply = client.request("shout", message("hey")).await.unwrap();
+        assert_eq!(text(&reply), Some("hey"));
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn req_clients_and_mmi() {
+    let ctx = Arc::new(zmq::Context::new());
+    let endpoint = "inproc://mdp-req";
+
+    broker(&ctx, endpoint).await;
+
+    let mut client = client(&ctx, endpoint).await;
+    let mut req: Req = Req::builder(Arc::clone(&ctx))
+        .connect(endpoint)
+        .build()
+        .await
+        .unwrap();
+
+    let exchange = async {
+        let reply = client
+            .request("mmi.service", message("echo"))
+            .await
+            .unwrap();
+        assert_eq!(text(&reply), Some("404"));
+
+        echo_worker(&ctx, endpoint, "echo");
+
+        // Requests wait for a worker to register
+        let reply = client.request("echo", message("first")).await.unwrap();
+        assert_eq!(text(&reply), Some("first"));
+
+        let reply = client
+            .request("mmi.service", message("echo"))
+            .await
+            .unwrap();
+        assert_eq!(text(&reply), Some("200"));
+
+        // A plain Req socket speaking MDP gets a single final reply
+        let mut request = message("req");
+        request.push_front(zmq::Message::from("echo"));
+        request.push_front(zmq::Message::from(&[0x01][..]));
+        request.push_front(zmq::Message::from("MDPC02"));
+        req.send_mut(request).await.unwrap();
+
+        let reply = req.recv_mut().await.unwrap();
+        let frames: Vec<_> = reply.iter().map(|msg| msg.to_vec()).collect();
+        assert_eq!(
+            frames,
+            vec![
+                b"MDPC02".to_vec(),
+                vec![0x03],
+                b"echo".to_vec(),
+                b"req".to_vec()
+            ]
+        );
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn idle_workers_stay_registered() {
+    let ctx = Arc::new(zmq::Context::new());
+    let endpoint = "inproc://mdp-idle";
+
+    broker(&ctx, endpoint).await;
+    echo_worker(&ctx, endpoint, "echo");
+
+    let mut client = client(&ctx, endpoint).await;
+
+    let exchange = async {
+        client.request("echo", message("warm up")).await.unwrap();
+
+        // Long enough for both sides to forget each other without heartbeats
+        sleep(HEARTBEAT * 10).await;
+
+        let reply = client
+            .request("mmi.service", message("echo"))
+            .await
+            .unwrap();
+        assert_eq!(text(&reply), Some("200"));
+
+        let reply = client.request("echo", message("still here")).await.unwrap();
+        assert_eq!(text(&reply), Some("still here"));
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn workers_reconnect_to_a_restarted_broker() {
+    let ctx = Arc::new(zmq::Context::new());
+    let endpoint = "inproc://mdp-restart";
+
+    let running = broker(&ctx, endpoint).await;
+    echo_worker(&ctx, endpoint, "echo");
+
+    let exchange = async {
+        let mut before = client(&ctx, endpoint).await;
+        let reply = before.request("echo", message("before")).await.unwrap();
+        assert_eq!(text(&reply), Some("before"));
+
+        running.abort();
+        let _ = running.await;
+
+        broker(&ctx, endpoint).await;
+
+        // The new broker has never heard of the worker, until it notices and reconnects
+        let mut after = client(&ctx, endpoint).await;
+        let reply = after.request("echo", message("after")).await.unwrap();
+        assert_eq!(text(&reply), Some("after"));
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn requests_time_out_without_workers() {
+    let ctx = Arc::new(zmq::Context::new());
+    let endpoint = "inproc://mdp-timeout";
+
+    broker(&ctx, endpoint).await;
+
+    let mut client = client(&ctx, endpoint).await;
+
+    match client.request("nobody", message("hello")).await {
+        Err(Error::Timeout) => (),
+        other => panic!("Expected a timeout, got {:?}", other.map(|m| m.len())),
+    }
+}
+
+#[tokio::test]
+async fn failed_connects_are_retried() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    // Not a transport ZeroMQ knows, so every attempt fails
+    let mut worker = MdpWorker::new(Arc::clone(&ctx), "bogus://mdp", "echo")
+        .reconnect_delay(Duration::from_millis(10));
+
+    let attempts = async {
+        for _ in 0..2 {
+            match worker.next().await {
+                Some(Err(_)) => (),
+                other => panic!("Expected a failed connect, got {:?}", other),
+            }
+        }
+    };
+
+    timeout(Duration::from_secs(5), attempts).await.unwrap();
+}
+
+#[tokio::test]
+async fn silent_busy_workers_are_forgotten() {
+    let ctx = Arc::new(zmq::Context::new());
+    let endpoint = "inproc://mdp-busy";
+
+    broker(&ctx, endpoint).await;
+
+    let mut client = client(&ctx, endpoint).await;
+
+    let exchange = async {
+        // Take a request, and then stop polling without replying or sending heartbeats
+        let mut worker = MdpWorker::new(Arc::clone(&ctx), endpoint, "stuck").heartbeat(HEARTBEAT);
+        let request = tokio::spawn(async move {
+            let request = worker.try_next().await;
+            (worker, request)
+        });
+
+        let _ = client.request("stuck", message("hello")).await;
+        let (_worker, request) = request.await.unwrap();
+        assert!(request.unwrap().is_some());
+
+        sleep(HEARTBEAT * 10).await;
+
+        let reply = client
+            .request("mmi.service", message("stuck"))
+            .await
+            .unwrap();
+        assert_eq!(text(&reply), Some("404"));
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
@@ -0,0 +1,140 @@
+/*
+ * This file is part of Tokio ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Tokio ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Tokio ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Tokio ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tokio::time::{sleep, timeout};
+use tokio_zmq::{prelude::*, reliable::ReliableReq, Error, Multipart, Rep, Req};
+
+fn message(s: &str) -> Multipart {
+    zmq::Message::from(s).into()
+}
+
+fn text(multipart: &Multipart) -> Option<&str> {
+    multipart.get(0).and_then(|msg| msg.as_str())
+}
+
+async fn rep(ctx: &Arc<zmq::Context>, endpoint: &str) -> Rep {
+    // A server that was just killed may still hold its endpoint for a moment
+    loop {
+        let res = Rep::builder(Arc::clone(ctx))
+            .linger(Some(Duration::from_millis(0)))
+            .bind(endpoint)
+            .build()
+            .await;
+
+        match res {
+            Ok(rep) => return rep,
+            Err(_) => sleep(Duration::from_millis(10)).await,
+        }
+    }
+}
+
+#[tokio::test]
+async fn requests_survive_a_server_restart() {
+    let ctx = Arc::new(zmq::Context::new());
+    let endpoint = "tcp://127.0.0.1:5630";
+
+    let mut client = ReliableReq::new(Req::builder(Arc::clone(&ctx)).connect(endpoint))
+        .timeout(Duration::from_millis(200))
+        .retries(5);
+
+    // Answers one request, then dies while handling the next
+    let mut server = rep(&ctx, endpoint).await;
+    let crashing = tokio::spawn(async move {
+        let request = server.recv_mut().await.unwrap();
+        server.send_mut(request).await.unwrap();
+
+        server.recv_mut().await.unwrap();
+    });
+
+    let exchange = async {
+        let reply = client.request(message("first")).await.unwrap();
+        assert_eq!(text(&reply), Some("first"));
+
+        let second = client.request(message("second"));
+
+        let restart = async {
+            crashing.await.unwrap();
+            sleep(Duration::from_millis(300)).await;
+
+            let mut server = rep(&ctx, endpoint).await;
+            let request = server.recv_mut().await.unwrap();
+            server.send_mut(request).await.unwrap();
+            server
+        };
+
+        let (reply, _server) = futures::join!(second, restart);
+        assert_eq!(text(&reply.unwrap()), Some("second"));
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn requests_give_up_after_their_retries() {
+    let ctx = Arc::new(zmq::Context::new());
+
+    let mut client = ReliableReq::new(Req::builder(ctx).connect("tcp://127.0.0.1:5631"))
+        .timeout(Duration::from_millis(50))
+        .retries(2);
+
+    let start = Instant::now();
+
+    match client.request(message("anyone?")).await {
+        Err(Error::Unanswered(3)) => (),
+        other => panic!(
+            "Expected three unanswered attempts, got {:?}",
+            other.map(|m| m.len())
+        ),
+    }
+
+    assert!(start.elapsed() >= Duration::from_millis(150));
+}
+
+#[tokio::test]
+async fn cancelled_requests_leave_the_client_usable() {
+    let ctx = Arc::new(zmq::Context::new());
+    let endpoint = "tcp://127.0.0.1:5632";
+
+    let mut client = ReliableReq::new(Req::builder(Arc::clone(&ctx)).connect(endpoint))
+        .timeout(Duration::from_secs(1))
+        .retries(0);
+
+    // Nobody is listening yet, so this is dropped while its socket waits for a reply
+    let cancelled = timeout(Duration::from_millis(50), client.request(message("first"))).await;
+    assert!(cancelled.is_err());
+
+    let mut server = rep(&ctx, endpoint).await;
+    let echo = tokio::spawn(async move {
+        let request = server.recv_mut().await.unwrap();
+        server.send_mut(request).await.unwrap();
+    });
+
+    let reply = timeout(Duration::from_secs(5), client.request(message("second")))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(text(&reply), Some("second"));
+
+    echo.await.unwrap();
+}
@@ -0,0 +1,137 @@
+/*
+ * This file is part of Tokio ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Tokio ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Tokio ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Tokio ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use tokio::time::timeout;
+use tokio_zmq::{prelude::*, Dealer, Multipart, Rep, Req, Router};
+
+fn multipart(frames: &[&str]) -> Multipart {
+    frames
+        .iter()
+        .map(|frame| zmq::Message::from(*frame))
+        .collect()
+}
+
+fn frames(multipart: &Multipart) -> Vec<String> {
+    multipart
+        .iter()
+        .map(|msg| String::from_utf8_lossy(&msg[..]).into_owned())
+        .collect()
+}
+
+async fn req_rep(ctx: &Arc<zmq::Context>, endpoint: &str) -> (Req, Rep) {
+    let rep: Rep = Rep::builder(Arc::clone(ctx))
+        .bind(endpoint)
+        .build()
+        .await
+        .unwrap();
+    let req: Req = Req::builder(Arc::clone(ctx))
+        .connect(endpoint)
+        .build()
+        .await
+        .unwrap();
+
+    (req, rep)
+}
+
+#[tokio::test]
+async fn borrowed_sockets_round_trip() {
+    let ctx = Arc::new(zmq::Context::new());
+    let (mut req, mut rep) = req_rep(&ctx, "inproc://send-recv-borrowed").await;
+
+    let exchange = async {
+        // The same sockets are used for every request, without handing them back and forth
+        for i in 0..3 {
+            let id = i.to_string();
+            req.send_mut(multipart(&["request", "", &id]))
+                .await
+                .unwrap();
+
+            let request = rep.recv_mut().await.unwrap();
+            assert_eq!(frames(&request), vec!["request", "", &id]);
+
+            rep.send_mut(multipart(&["reply", &id])).await.unwrap();
+
+            let reply = req.recv_mut().await.unwrap();
+            assert_eq!(frames(&reply), vec!["reply", &id]);
+        }
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn borrowing_and_consuming_calls_mix() {
+    let ctx = Arc::new(zmq::Context::new());
+    let (mut req, rep) = req_rep(&ctx, "inproc://send-recv-mixed").await;
+
+    let exchange = async {
+        req.send_mut(multipart(&["ping"])).await.unwrap();
+
+        let (request, rep) = rep.recv().await.unwrap();
+        assert_eq!(frames(&request), vec!["ping"]);
+        let mut rep = rep.send(multipart(&["pong"])).await.unwrap();
+
+        let reply = req.recv_mut().await.unwrap();
+        assert_eq!(frames(&reply), vec!["pong"]);
+
+        let req = req.send(multipart(&["ping again"])).await.unwrap();
+        let request = rep.recv_mut().await.unwrap();
+        assert_eq!(frames(&request), vec!["ping again"]);
+        rep.send_mut(multipart(&["pong again"])).await.unwrap();
+
+        let (reply, _req) = req.recv().await.unwrap();
+        assert_eq!(frames(&reply), vec!["pong again"]);
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn routers_receive_routing_ids() {
+    let ctx = Arc::new(zmq::Context::new());
+    let endpoint = "inproc://send-recv-router";
+
+    let mut router: Router = Router::builder(Arc::clone(&ctx))
+        .bind(endpoint)
+        .build()
+        .await
+        .unwrap();
+    let mut dealer: Dealer = Dealer::builder(Arc::clone(&ctx))
+        .identity(b"dealer")
+        .connect(endpoint)
+        .build()
+        .await
+        .unwrap();
+
+    let exchange = async {
+        dealer.send_mut(multipart(&["one", "two"])).await.unwrap();
+
+        let received = router.recv_mut().await.unwrap();
+        assert_eq!(frames(&received), vec!["dealer", "one", "two"]);
+
+        router.send_mut(received).await.unwrap();
+
+        let echoed = dealer.recv_mut().await.unwrap();
+        assert_eq!(frames(&echoed), vec!["one", "two"]);
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
@@ -0,0 +1,157 @@
+/*
+ * This file is part of Tokio ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Tokio ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Tokio ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Tokio ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use async_zmq_types::{
+    codec::{CodecSink, Encoder},
+    Multipart,
+};
+use futures::SinkExt;
+use tokio::time::{sleep, timeout};
+use tokio_zmq::{prelude::*, Pull, Push};
+
+fn message(i: usize) -> Multipart {
+    zmq::Message::from(&format!("{}", i)).into()
+}
+
+/// Sends a string as a single frame
+struct Text;
+
+impl Encoder<String> for Text {
+    type Error = std::convert::Infallible;
+
+    fn encode(&mut self, item: String) -> Result<Multipart, Self::Error> {
+        Ok(zmq::Message::from(item.as_str()).into())
+    }
+}
+
+// A bound Push socket without any peers can't send, so its sink holds on to everything it's fed
+async fn lonely_push(ctx: &Arc<zmq::Context>, endpoint: &str, linger: Option<Duration>) -> Push {
+    Push::builder(Arc::clone(ctx))
+        .bind(endpoint)
+        .linger(linger)
+        .build()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn close_flushes_once_a_peer_connects() {
+    let ctx = Arc::new(zmq::Context::new());
+    let push = lonely_push(&ctx, "inproc://shutdown-flush", None).await;
+
+    let mut sink = push.sink(10);
+    for i in 0..5 {
+        sink.feed(message(i)).await.unwrap();
+    }
+
+    let receiver = tokio::spawn(async move {
+        sleep(Duration::from_millis(100)).await;
+
+        let mut pull: Pull = Pull::builder(ctx)
+            .connect("inproc://shutdown-flush")
+            .build()
+            .await
+            .unwrap();
+
+        for _ in 0..5 {
+            pull.recv_mut().await.unwrap();
+        }
+    });
+
+    let report = sink.shutdown(Duration::from_secs(5)).await.unwrap();
+    assert!(
+        report.is_complete(),
+        "Undelivered: {}",
+        report.undelivered()
+    );
+
+    timeout(Duration::from_secs(5), receiver)
+        .await
+        .unwrap()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn shutdown_reports_what_timed_out() {
+    let ctx = Arc::new(zmq::Context::new());
+    let push = lonely_push(&ctx, "inproc://shutdown-timeout", None).await;
+
+    let mut sink = push.sink(10);
+    for i in 0..3 {
+        sink.feed(message(i)).await.unwrap();
+    }
+
+    let report = timeout(
+        Duration::from_secs(5),
+        sink.shutdown(Duration::from_millis(50)),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(report.undelivered(), 3);
+}
+
+#[tokio::test]
+async fn close_respects_linger() {
+    let ctx = Arc::new(zmq::Context::new());
+    let push = lonely_push(
+        &ctx,
+        "inproc://shutdown-linger",
+        Some(Duration::from_millis(0)),
+    )
+    .await;
+
+    let mut sink = CodecSink::new(push.sink(10), Text);
+    for i in 0..4 {
+        sink.feed(format!("{}", i)).await.unwrap();
+    }
+
+    let report = timeout(Duration::from_secs(5), sink.close())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(report.undelivered(), 4);
+}
+
+#[tokio::test]
+async fn sockets_close_like_their_sinks() {
+    let ctx = Arc::new(zmq::Context::new());
+    let mut push = lonely_push(&ctx, "inproc://shutdown-socket", None).await;
+    let mut pull: Pull = Pull::builder(Arc::clone(&ctx))
+        .connect("inproc://shutdown-socket")
+        .build()
+        .await
+        .unwrap();
+
+    push.send_mut(message(0)).await.unwrap();
+
+    let report = timeout(
+        Duration::from_secs(5),
+        push.shutdown(Duration::from_secs(1)),
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    assert!(report.is_complete());
+
+    let multipart = pull.recv_mut().await.unwrap();
+    assert_eq!(multipart.get(0).and_then(|msg| msg.as_str()), Some("0"));
+}
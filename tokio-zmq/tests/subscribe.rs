@@ -0,0 +1,172 @@
+/*
+ * This file is part of Tokio ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Tokio ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Tokio ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Tokio ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{sync::Arc, time::Duration};
+
+use futures::TryStreamExt;
+use tokio::time::timeout;
+use tokio_zmq::{prelude::*, Multipart, Pub, Sub};
+
+fn message(s: &str) -> Multipart {
+    zmq::Message::from(s).into()
+}
+
+fn text(multipart: &Multipart) -> String {
+    multipart
+        .get(0)
+        .and_then(|msg| msg.as_str())
+        .unwrap()
+        .to_owned()
+}
+
+async fn publisher(ctx: &Arc<zmq::Context>, endpoint: &str) -> Pub {
+    Pub::builder(Arc::clone(ctx))
+        .bind(endpoint)
+        .build()
+        .await
+        .unwrap()
+}
+
+async fn subscriber(ctx: &Arc<zmq::Context>, endpoint: &str, filter: &[u8]) -> Sub {
+    Sub::builder(Arc::clone(ctx))
+        .connect(endpoint)
+        .filter(filter)
+        .build()
+        .await
+        .unwrap()
+}
+
+// Publish a round of topics until `done` accepts what the subscriber receives
+//
+// Subscriptions reach the publisher asynchronously, so each round is tagged, letting `done`
+// tell when a change has taken effect.
+async fn publish_until<S, F>(publisher: &mut Pub, stream: &mut S, topics: &[&str], mut done: F)
+where
+    S: TryStreamExt<Ok = Multipart, Error = tokio_zmq::Error> + Unpin,
+    F: FnMut(&[String]) -> bool,
+{
+    for round in 0.. {
+        for topic in topics {
+            let msg = message(&format!("{} {}", topic, round));
+            publisher.send_mut(msg).await.unwrap();
+        }
+
+        // The last topic is always subscribed to, and marks the end of the round
+        let last = format!("{} {}", topics[topics.len() - 1], round);
+        let mut received = Vec::new();
+        loop {
+            let msg = text(&stream.try_next().await.unwrap().unwrap());
+            if msg.ends_with(&format!(" {}", round)) {
+                received.push(msg.clone());
+            }
+            if msg == last {
+                break;
+            }
+        }
+
+        if done(&received) {
+            return;
+        }
+    }
+}
+
+#[tokio::test]
+async fn subscriptions_change_while_streaming() {
+    let ctx = Arc::new(zmq::Context::new());
+    let endpoint = "inproc://subscribe-stream";
+
+    let mut publisher = publisher(&ctx, endpoint).await;
+    let sub = subscriber(&ctx, endpoint, b"last").await;
+
+    let exchange = async {
+        sub.subscribe(b"a").await.unwrap();
+        let mut stream = sub.stream();
+
+        publish_until(
+            &mut publisher,
+            &mut stream,
+            &["a", "b", "last"],
+            |received| received.len() == 2,
+        )
+        .await;
+
+        stream.subscribe(b"b").await.unwrap();
+        publish_until(
+            &mut publisher,
+            &mut stream,
+            &["a", "b", "last"],
+            |received| received.len() == 3,
+        )
+        .await;
+
+        stream.unsubscribe(b"a").await.unwrap();
+        publish_until(
+            &mut publisher,
+            &mut stream,
+            &["a", "b", "last"],
+            |received| received.len() == 2,
+        )
+        .await;
+
+        // Once the publisher has dropped a topic, it stays dropped
+        publish_until(
+            &mut publisher,
+            &mut stream,
+            &["a", "b", "last"],
+            |received| {
+                assert!(received[0].starts_with("b "), "{:?}", received);
+                assert_eq!(received.len(), 2);
+                true
+            },
+        )
+        .await;
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
+
+#[tokio::test]
+async fn subscriptions_are_counted() {
+    let ctx = Arc::new(zmq::Context::new());
+    let endpoint = "inproc://subscribe-counted";
+
+    let mut publisher = publisher(&ctx, endpoint).await;
+    let sub = subscriber(&ctx, endpoint, b"last").await;
+
+    let exchange = async {
+        sub.subscribe(b"a").await.unwrap();
+        sub.subscribe(b"a").await.unwrap();
+        sub.unsubscribe(b"a").await.unwrap();
+
+        let mut stream = sub.stream();
+
+        publish_until(&mut publisher, &mut stream, &["a", "last"], |received| {
+            received.len() == 2
+        })
+        .await;
+
+        stream.unsubscribe(b"a").await.unwrap();
+        publish_until(&mut publisher, &mut stream, &["a", "last"], |received| {
+            received.len() == 1
+        })
+        .await;
+    };
+
+    timeout(Duration::from_secs(5), exchange).await.unwrap();
+}
@@ -0,0 +1,177 @@
+/*
+ * This file is part of Tokio ZMQ.
+ *
+ * Copyright © 2019 Riley Trautman
+ *
+ * Tokio ZMQ is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * Tokio ZMQ is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with Tokio ZMQ.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{fs, sync::Arc, time::Duration};
+
+use tokio::time::timeout;
+use tokio_zmq::{
+    prelude::*,
+    zap::{Authenticator, ZapDomain, ZapHandler},
+    CurveKeyPair, Multipart, Rep, Req,
+};
+
+fn message(s: &str) -> Multipart {
+    zmq::Message::from(s).into()
+}
+
+async fn start(ctx: &Arc<zmq::Context>, authenticator: Authenticator) {
+    let handler = ZapHandler::bind(Arc::clone(ctx), authenticator)
+        .await
+        .unwrap();
+    tokio::spawn(handler.run());
+}
+
+/// Send a request from `req` and report whether `rep` received it
+async fn delivered(rep: &mut Rep, req: &mut Req) -> bool {
+    req.send_mut(message("ping")).await.unwrap();
+
+    match timeout(Duration::from_millis(500), rep.recv_mut()).await {
+        Ok(multipart) => {
+            let multipart = multipart.unwrap();
+            multipart.get(0).and_then(|msg| msg.as_str()) == Some("ping")
+        }
+        Err(_) => false,
+    }
+}
+
+#[tokio::test]
+async fn plain_passwords() {
+    let ctx = Arc::new(zmq::Context::new());
+    start(
+        &ctx,
+        Authenticator::new().domain("", ZapDomain::new().plain("admin", "secret")),
+    )
+    .await;
+
+    let mut rep: Rep = Rep::builder(Arc::clone(&ctx))
+        .plain_server()
+        .bind("tcp://127.0.0.1:5622")
+        .build()
+        .await
+        .unwrap();
+
+    let mut intruder: Req = Req::builder(Arc::clone(&ctx))
+        .plain_client("admin", "guess")
+        .linger(Some(Duration::from_millis(0)))
+        .connect("tcp://127.0.0.1:5622")
+        .build()
+        .await
+        .unwrap();
+    assert!(!delivered(&mut rep, &mut intruder).await);
+
+    let mut admin: Req = Req::builder(ctx)
+        .plain_client("admin", "secret")
+        .connect("tcp://127.0.0.1:5622")
+        .build()
+        .await
+        .unwrap();
+    assert!(delivered(&mut rep, &mut admin).await);
+}
+
+#[tokio::test]
+async fn curve_key_dir() {
+    let server_keys = CurveKeyPair::generate().unwrap();
+    let server_public = *server_keys.public();
+    let client_keys = CurveKeyPair::generate().unwrap();
+    let stranger_keys = CurveKeyPair::generate().unwrap();
+
+    let dir = std::env::temp_dir().join(format!("tokio-zmq-zap-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("client.key"),
+        format!(
+            "metadata\ncurve\n    public-key = \"{}\"\n",
+            client_keys.public().to_z85()
+        ),
+    )
+    .unwrap();
+
+    let domain = ZapDomain::new().curve_key_dir(&dir).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let ctx = Arc::new(zmq::Context::new());
+    start(&ctx, Authenticator::new().domain("", domain)).await;
+
+    let mut rep: Rep = Rep::builder(Arc::clone(&ctx))
+        .curve_server(server_keys)
+        .bind("tcp://127.0.0.1:5623")
+        .build()
+        .await
+        .unwrap();
+
+    let mut stranger: Req = Req::builder(Arc::clone(&ctx))
+        .curve_client(stranger_keys, server_public)
+        .linger(Some(Duration::from_millis(0)))
+        .connect("tcp://127.0.0.1:5623")
+        .build()
+        .await
+        .unwrap();
+    assert!(!delivered(&mut rep, &mut stranger).await);
+
+    let mut client: Req = Req::builder(ctx)
+        .curve_client(client_keys, server_public)
+        .connect("tcp://127.0.0.1:5623")
+        .build()
+        .await
+        .unwrap();
+    assert!(delivered(&mut rep, &mut client).await);
+}
+
+#[tokio::test]
+async fn ip_filters_per_domain() {
+    let loopback = "127.0.0.1".parse().unwrap();
+
+    let ctx = Arc::new(zmq::Context::new());
+    start(
+        &ctx,
+        Authenticator::new()
+            .domain("open", ZapDomain::new().allow(loopback))
+            .domain("closed", ZapDomain::new().deny(loopback)),
+    )
+    .await;
+
+    let mut closed: Rep = Rep::builder(Arc::clone(&ctx))
+        .zap_domain("closed")
+        .bind("tcp://127.0.0.1:5624")
+        .build()
+        .await
+        .unwrap();
+
+    let mut req: Req = Req::builder(Arc::clone(&ctx))
+        .linger(Some(Duration::from_millis(0)))
+        .connect("tcp://127.0.0.1:5624")
+        .build()
+        .await
+        .unwrap();
+    assert!(!delivered(&mut closed, &mut req).await);
+
+    let mut open: Rep = Rep::builder(Arc::clone(&ctx))
+        .zap_domain("open")
+        .bind("tcp://127.0.0.1:5625")
+        .build()
+        .await
+        .unwrap();
+
+    let mut req: Req = Req::builder(ctx)
+        .connect("tcp://127.0.0.1:5625")
+        .build()
+        .await
+        .unwrap();
+    assert!(delivered(&mut open, &mut req).await);
+}